@@ -0,0 +1,122 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+// Directories embedded into the release binary, their content hash
+// helps to identify which builtin theme a binary was built with.
+static EMBEDDED_DIRS: [&str; 3] = ["templates", "static", "locales"];
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+
+    println!("cargo:rustc-env=ZINE_GIT_REV={}", git_revision());
+    println!(
+        "cargo:rustc-env=ZINE_BUILD_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=ZINE_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=ZINE_GENKIT_VERSION={}",
+        locked_version(&manifest_dir.join("Cargo.lock"), "genkit")
+            .unwrap_or_else(|| String::from("unknown"))
+    );
+    println!(
+        "cargo:rustc-env=ZINE_TEMPLATE_HASH={:016x}",
+        embedded_hash(&manifest_dir)
+    );
+    println!("cargo:rustc-env=ZINE_BUILD_FEATURES={}", enabled_features());
+
+    for dir in EMBEDDED_DIRS {
+        println!("cargo:rerun-if-changed={dir}");
+    }
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+}
+
+// Get the short git revision, fallback to `unknown` if not in a git repository,
+// e.g. installed from crates.io.
+fn git_revision() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|rev| rev.trim().to_owned())
+        .filter(|rev| !rev.is_empty())
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+// The enabled cargo features joined by commas, which are read from the `CARGO_FEATURE_<NAME>`
+// variables, so a newly added feature is always listed.
+fn enabled_features() -> String {
+    let mut features = env::vars()
+        .filter_map(|(key, _)| {
+            let name = key.strip_prefix("CARGO_FEATURE_")?;
+            Some(name.to_lowercase().replace('_', "-"))
+        })
+        .filter(|name| name != "default")
+        .collect::<Vec<_>>();
+    features.sort();
+    features.join(",")
+}
+
+// Find the resolved version of `package` in the `Cargo.lock` file.
+fn locked_version(lock_file: &Path, package: &str) -> Option<String> {
+    let content = fs::read_to_string(lock_file).ok()?;
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == format!(r#"name = "{package}""#) {
+            return lines
+                .next()?
+                .trim()
+                .strip_prefix("version = ")
+                .map(|version| version.trim_matches('"').to_owned());
+        }
+    }
+    None
+}
+
+// FNV-1a hash of all embedded files, which is stable across Rust versions.
+fn embedded_hash(manifest_dir: &Path) -> u64 {
+    let mut files = vec![];
+    for dir in EMBEDDED_DIRS {
+        collect_files(&manifest_dir.join(dir), &mut files);
+    }
+    files.sort();
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for file in files {
+        let name = file.strip_prefix(manifest_dir).unwrap_or(&file);
+        let bytes = name
+            .to_string_lossy()
+            .replace('\\', "/")
+            .into_bytes()
+            .into_iter()
+            .chain(fs::read(&file).unwrap_or_default());
+        for byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -7,6 +7,16 @@ previous = Previous:
 
 next = Next:
 
+related-articles = Related articles
+
+reading-time = { $number } min read
+
+series = Series
+
+series-part = Part { $number }
+
+series-total = { $number } parts
+
 author-article-title = Published { $number } article(s)
 
 author-list = Authors list
@@ -20,3 +30,116 @@ editor = Editor
 topic-article-title = { $number } article(s)
 
 topic-list = Topic list
+
+subscribe-placeholder = Your email
+
+subscribe-button = Subscribe
+
+translated-by = Translated by
+
+continue-reading = Continue reading →
+
+whats-new = What's new
+
+whats-new-new = New
+
+whats-new-updated = Updated
+
+previous-page = ← Previous page
+
+next-page = Next page →
+
+download-issue = Download this issue
+
+serve-changes = Changes since the last build
+
+serve-changes-empty = No article changed since the last build.
+
+month-1 = January
+month-2 = February
+month-3 = March
+month-4 = April
+month-5 = May
+month-6 = June
+month-7 = July
+month-8 = August
+month-9 = September
+month-10 = October
+month-11 = November
+month-12 = December
+
+month-short-1 = Jan
+month-short-2 = Feb
+month-short-3 = Mar
+month-short-4 = Apr
+month-short-5 = May
+month-short-6 = Jun
+month-short-7 = Jul
+month-short-8 = Aug
+month-short-9 = Sep
+month-short-10 = Oct
+month-short-11 = Nov
+month-short-12 = Dec
+
+relative-today = today
+
+relative-yesterday = yesterday
+
+relative-days-ago = { $number } days ago
+
+relative-months-ago = { $number ->
+    [one] a month ago
+   *[other] { $number } months ago
+}
+
+relative-years-ago = { $number ->
+    [one] a year ago
+   *[other] { $number } years ago
+}
+
+relative-in-days = { $number ->
+    [one] tomorrow
+   *[other] in { $number } days
+}
+
+sponsored-by = Sponsored by
+
+badge-editor = Editor
+
+badge-articles = { $number }+ articles
+
+badge-since = Since { $number }
+
+colophon = Colophon
+
+colophon-typefaces = Typefaces
+
+colophon-tools = Tools
+
+colophon-print = Print
+
+download-vcard = Download vCard
+
+previously-in = Previously in
+
+tombstone-expired = Expired on
+
+tombstone-archive = View the archived version
+
+roundup-via = via
+
+events = Events
+
+events-upcoming = Upcoming events
+
+events-past = Past events
+
+events-subscribe = Subscribe to the calendar
+
+glossary = Glossary
+
+glossary-learn-more = Learn more
+
+not-found = The page you are looking for doesn't exist.
+
+not-found-home = Back to home
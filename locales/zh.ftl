@@ -7,6 +7,16 @@ previous = 上一篇：
 
 next = 下一篇：
 
+related-articles = 相关文章
+
+reading-time = 阅读约 { $number } 分钟
+
+series = 系列
+
+series-part = 第 { $number } 篇
+
+series-total = 共 { $number } 篇
+
 author-article-title = 已发布 { $number } 篇文章
 
 author-list = 作者列表
@@ -19,4 +29,108 @@ editor = 责任编辑
 
 topic-article-title = { $number } 篇文章
 
-topic-list = 话题列表
\ No newline at end of file
+topic-list = 话题列表
+
+subscribe-placeholder = 你的邮箱
+
+subscribe-button = 订阅
+
+translated-by = 译者
+
+continue-reading = 继续阅读 →
+
+whats-new = 最近更新
+
+whats-new-new = 新文章
+
+whats-new-updated = 已更新
+
+previous-page = ← 上一页
+
+next-page = 下一页 →
+
+download-issue = 下载本期
+
+serve-changes = 自上次构建以来的修改
+
+serve-changes-empty = 自上次构建以来没有文章修改。
+
+month-1 = 一月
+month-2 = 二月
+month-3 = 三月
+month-4 = 四月
+month-5 = 五月
+month-6 = 六月
+month-7 = 七月
+month-8 = 八月
+month-9 = 九月
+month-10 = 十月
+month-11 = 十一月
+month-12 = 十二月
+
+month-short-1 = 1月
+month-short-2 = 2月
+month-short-3 = 3月
+month-short-4 = 4月
+month-short-5 = 5月
+month-short-6 = 6月
+month-short-7 = 7月
+month-short-8 = 8月
+month-short-9 = 9月
+month-short-10 = 10月
+month-short-11 = 11月
+month-short-12 = 12月
+
+relative-today = 今天
+
+relative-yesterday = 昨天
+
+relative-days-ago = { $number } 天前
+
+relative-months-ago = { $number } 个月前
+
+relative-years-ago = { $number } 年前
+
+relative-in-days = { $number } 天后
+
+sponsored-by = 赞助商
+
+badge-editor = 编辑
+
+badge-articles = { $number }+ 篇文章
+
+badge-since = 始于 { $number } 年
+
+colophon = 版权页
+
+colophon-typefaces = 字体
+
+colophon-tools = 工具
+
+colophon-print = 印刷
+
+download-vcard = 下载名片
+
+previously-in = 往期回顾
+
+tombstone-expired = 下线日期：
+
+tombstone-archive = 查看存档版本
+
+roundup-via = 来源：
+
+events = 活动
+
+events-upcoming = 即将举行的活动
+
+events-past = 往期活动
+
+events-subscribe = 订阅日历
+
+glossary = 术语表
+
+glossary-learn-more = 了解更多
+
+not-found = 你访问的页面不存在。
+
+not-found-home = 返回首页
@@ -0,0 +1,199 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    html,
+    snapshot::{self, SnapshotManifest},
+    ZINE_FILE,
+};
+
+// The directory (relative to the zine root) to store the archived site generations.
+static ARCHIVE_DIR: &str = ".zine-cache/archive";
+// The directory (relative to the build directory) of archived site generations,
+// e.g. the generation 3 is served under `/v/3/`.
+static ARCHIVE_URL_DIR: &str = "v";
+// The files which change on every build by the build time,
+// they are skipped to tell whether the site is changed.
+static VOLATILE_FILES: &[&str] = &["feed.xml", "feeds.opml"];
+
+// An archived site generation.
+#[derive(Serialize, Deserialize)]
+struct Generation {
+    #[serde(flatten)]
+    manifest: SnapshotManifest,
+    // The file path and hash pairs to tell whether the site is changed, without
+    // the volatile files and with the build id removed from the file content.
+    #[serde(default)]
+    fingerprints: BTreeMap<String, String>,
+}
+
+/// Archive the site built in `dest` as a new generation if any file changed
+/// since the last generation, then restore all previous generations into `dest/v/<n>/`.
+///
+/// A generation only records the manifest of content hashes, each file content
+/// is stored once no matter how many generations share it. The content only
+/// differing in the `build_id` or the build time isn't a change.
+pub fn archive_site(
+    source: &Path,
+    dest: &Path,
+    site_url: &str,
+    build_id: Option<&str>,
+) -> Result<()> {
+    let archived_dir = dest.join(ARCHIVE_URL_DIR);
+    if archived_dir.exists() {
+        fs::remove_dir_all(&archived_dir)?;
+    }
+
+    let archive_dir = source.join(ARCHIVE_DIR);
+    let objects_dir = archive_dir.join("objects");
+    fs::create_dir_all(&objects_dir)?;
+
+    let mut generations = load_generations(&archive_dir)?;
+    let config = fs::read_to_string(source.join(ZINE_FILE))?;
+    let manifest = SnapshotManifest::new(dest, config)?;
+    let fingerprints = fingerprints(dest, &manifest.files, build_id)?;
+    let changed = generations
+        .last()
+        .map(|(_, last)| {
+            if last.fingerprints.is_empty() {
+                // The generation archived before the fingerprints.
+                last.manifest.files != manifest.files
+            } else {
+                last.fingerprints != fingerprints
+            }
+        })
+        .unwrap_or(true);
+    if changed {
+        for (path, hash) in &manifest.files {
+            let object = objects_dir.join(hash);
+            if !object.exists() {
+                fs::copy(dest.join(path), object)?;
+            }
+        }
+        let number = generations.last().map(|(n, _)| n + 1).unwrap_or(1);
+        let generation = Generation {
+            manifest,
+            fingerprints,
+        };
+        fs::write(
+            archive_dir.join(format!("{number}.json")),
+            serde_json::to_vec_pretty(&generation)?,
+        )?;
+        generations.push((number, generation));
+    }
+
+    // The latest generation is the current site itself.
+    generations.pop();
+    for (number, Generation { manifest, .. }) in generations {
+        let prefix = format!("/{ARCHIVE_URL_DIR}/{number}");
+        let generation_dir = archived_dir.join(number.to_string());
+        for (path, hash) in manifest.files {
+            let object = objects_dir.join(&hash);
+            let target = generation_dir.join(&path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if path.ends_with(".html") {
+                let html = fs::read_to_string(&object)
+                    .with_context(|| format!("Archived object of `{path}` is missing"))?;
+                fs::write(target, html::rebase_html_urls(&html, site_url, &prefix)?)?;
+            } else if fs::hard_link(&object, &target).is_err() {
+                // Hard link is unsupported by some file systems.
+                fs::copy(&object, &target)
+                    .with_context(|| format!("Archived object of `{path}` is missing"))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Get the fingerprints of the `files` in `dest`, see [`Generation::fingerprints`].
+fn fingerprints(
+    dest: &Path,
+    files: &BTreeMap<String, String>,
+    build_id: Option<&str>,
+) -> Result<BTreeMap<String, String>> {
+    let mut fingerprints = BTreeMap::new();
+    for (path, hash) in files {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        if VOLATILE_FILES.contains(&file_name) {
+            continue;
+        }
+        let hash = match build_id {
+            Some(build_id) => match String::from_utf8(fs::read(dest.join(path))?) {
+                Ok(content) if content.contains(build_id) => {
+                    snapshot::hash(content.replace(build_id, "").as_bytes())
+                }
+                _ => hash.clone(),
+            },
+            None => hash.clone(),
+        };
+        fingerprints.insert(path.clone(), hash);
+    }
+    Ok(fingerprints)
+}
+
+// Load the generation number and generation pairs, in ascending order.
+fn load_generations(archive_dir: &Path) -> Result<Vec<(usize, Generation)>> {
+    let mut generations = vec![];
+    for entry in fs::read_dir(archive_dir)? {
+        let path = entry?.path();
+        let Some(number) = path
+            .extension()
+            .filter(|ext| *ext == "json")
+            .and_then(|_| path.file_stem()?.to_str()?.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let manifest = serde_json::from_slice(&fs::read(&path)?)
+            .with_context(|| format!("Failed to parse `{}`", path.display()))?;
+        generations.push((number, manifest));
+    }
+    generations.sort_by_key(|(number, _)| *number);
+    Ok(generations)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, env, fs};
+
+    use crate::snapshot::SnapshotManifest;
+
+    use super::fingerprints;
+
+    #[test]
+    fn test_fingerprints() {
+        let build_dir = |build_id: &str, title: &str| {
+            let dir = env::temp_dir().join(format!("zine-archive-{build_id}"));
+            fs::create_dir_all(dir.join("@alice")).unwrap();
+            fs::write(
+                dir.join("index.html"),
+                format!(r#"<link href="/static/zine.css?v={build_id}"><h1>{title}</h1>"#),
+            )
+            .unwrap();
+            fs::write(
+                dir.join("feed.xml"),
+                format!("<updated>{build_id}</updated>"),
+            )
+            .unwrap();
+            fs::write(dir.join("@alice/feed.xml"), build_id).unwrap();
+            let manifest = SnapshotManifest::new(&dir, String::new()).unwrap();
+            fingerprints(&dir, &manifest.files, Some(build_id)).unwrap()
+        };
+
+        let first = build_dir("1700000000", "Zine");
+        assert_eq!(
+            first.keys().collect::<Vec<_>>(),
+            vec!["index.html"],
+            "the feeds are volatile"
+        );
+        assert_eq!(first, build_dir("1700000100", "Zine"));
+        assert_ne!(first, build_dir("1700000200", "Zine!"));
+        assert_eq!(
+            fingerprints(&env::temp_dir(), &BTreeMap::new(), None).unwrap(),
+            BTreeMap::new()
+        );
+    }
+}
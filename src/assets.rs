@@ -0,0 +1,464 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{anyhow, Result};
+use genkit::{current_mode, Mode};
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use serde_json::json;
+
+use crate::{
+    entity::{BuildConfig, MediaConfig, SandboxConfig},
+    exif, media, output,
+};
+
+// The Git LFS pointer file is always smaller than 1024 bytes.
+const LFS_POINTER_MAX_SIZE: u64 = 1024;
+const LFS_POINTER_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/v1";
+
+// The extension of the SCSS stylesheets, which are compiled to CSS.
+const SCSS_EXTENSION: &str = "scss";
+
+/// Copy the `static` directory of `source` into `dest`.
+///
+/// Symlinks are followed (but skipped in the sandbox mode), files matching the
+/// `static_exclude` patterns are ignored and files which are unchanged since
+/// the last build are skipped.
+///
+/// The `.scss` stylesheets are compiled to the `.css` files of the same name,
+/// the partials (e.g. `_colors.scss`) are only imported by other stylesheets.
+///
+/// The metadata of the JPEG and PNG images is stripped if `strip_metadata` of
+/// the media config is enabled, otherwise the images with the GPS location are warned.
+pub fn copy_static_dir(
+    source: &Path,
+    dest: &Path,
+    config: &BuildConfig,
+    media_config: &MediaConfig,
+    sandbox_config: &SandboxConfig,
+) -> Result<()> {
+    let static_dir = source.join("static");
+    if !static_dir.exists() {
+        return Ok(());
+    }
+
+    let mut files = vec![];
+    let mut stylesheets = vec![];
+    let mut walker = sandbox_config.walk_dir(&static_dir).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                if let Some(ancestor) = err.loop_ancestor() {
+                    println!(
+                        "Warning: symlink loop detected at `{}`, which points to `{}`.",
+                        err.path().unwrap_or(&static_dir).display(),
+                        ancestor.display()
+                    );
+                } else if matches!(
+                    err.io_error().map(io::Error::kind),
+                    Some(io::ErrorKind::NotFound)
+                ) {
+                    println!(
+                        "Warning: broken symlink `{}` in static directory.",
+                        err.path().unwrap_or(&static_dir).display()
+                    );
+                } else {
+                    return Err(err.into());
+                }
+                continue;
+            }
+        };
+
+        if entry.path_is_symlink() && !sandbox_config.follow_links() {
+            println!(
+                "Warning: the symlink `{}` in static directory is skipped in the sandbox mode.",
+                entry.path().display()
+            );
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(&static_dir)?;
+        if is_excluded(relative_path, &config.static_exclude) {
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+        let target = dest.join("static").join(relative_path);
+        if entry.file_type().is_dir() {
+            // Create directory eagerly to keep the empty directories.
+            if output::is_dir() {
+                fs::create_dir_all(&target)?;
+            }
+        } else if is_scss(relative_path) {
+            if !is_scss_partial(relative_path) {
+                stylesheets.push((entry.into_path(), target.with_extension("css")));
+            }
+        } else {
+            files.push((entry.into_path(), target));
+        }
+    }
+
+    check_static_files(&files, config, !media_config.strip_metadata);
+    for (stylesheet, target) in &stylesheets {
+        if files.iter().any(|(_, to)| to == target) {
+            println!(
+                "Warning: the static file `{}` is overwritten by the compiled `{}`.",
+                target.display(),
+                stylesheet.display()
+            );
+        }
+    }
+
+    let copied = AtomicUsize::new(0);
+    let skipped = AtomicUsize::new(0);
+    files.par_iter().try_for_each(|(from, to)| {
+        if media_config.strip_metadata && exif::is_supported(from) {
+            // The stripped image never has the same size, so it's always copied.
+            media::copy_stripped(from, to)?;
+            copied.fetch_add(1, Ordering::Relaxed);
+        } else if output::is_dir() && is_unchanged(from, to) {
+            skipped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            output::copy(from, to)?;
+            copied.fetch_add(1, Ordering::Relaxed);
+        }
+        anyhow::Ok(())
+    })?;
+    // The imported partials may change, so the stylesheets are always compiled.
+    let serve = matches!(current_mode(), Mode::Serve);
+    stylesheets
+        .par_iter()
+        .try_for_each(|(from, to)| compile_stylesheet(from, to, serve))?;
+
+    println!(
+        "Static files: {} copied, {} unchanged skipped, {} stylesheets compiled.",
+        copied.into_inner(),
+        skipped.into_inner(),
+        stylesheets.len()
+    );
+    Ok(())
+}
+
+/// Copy the `static_dir` of the theme package into the `dest_static_dir`,
+/// but never override the files of the project `static` directory in `source`.
+pub fn copy_theme_static_dir(
+    static_dir: &Path,
+    source: &Path,
+    dest_static_dir: &Path,
+    sandbox_config: &SandboxConfig,
+) -> Result<()> {
+    for entry in sandbox_config.walk_dir(static_dir) {
+        let entry = entry?;
+        // The symlinks are regular files only if followed.
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(static_dir)?;
+        if source.join("static").join(relative_path).exists() {
+            continue;
+        }
+        output::copy(entry.path(), &dest_static_dir.join(relative_path))?;
+    }
+    Ok(())
+}
+
+fn is_scss(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == SCSS_EXTENSION)
+}
+
+fn is_scss_partial(path: &Path) -> bool {
+    path.file_name()
+        .map_or(false, |name| name.to_string_lossy().starts_with('_'))
+}
+
+// Compile the SCSS `stylesheet` into the css file `to`.
+//
+// The css is compressed in build mode. In serve mode, the css is expanded
+// for debugging, along with a source map embedding the SCSS source.
+fn compile_stylesheet(stylesheet: &Path, to: &Path, serve: bool) -> Result<()> {
+    let style = if serve {
+        grass::OutputStyle::Expanded
+    } else {
+        grass::OutputStyle::Compressed
+    };
+    let mut css =
+        grass::from_path(stylesheet, &grass::Options::default().style(style)).map_err(|err| {
+            anyhow!(
+                "Failed to compile the stylesheet `{}`: {err}",
+                stylesheet.display()
+            )
+        })?;
+    if serve {
+        let css_name = to.file_name().unwrap_or_default().to_string_lossy();
+        let scss_name = stylesheet.file_name().unwrap_or_default().to_string_lossy();
+        let map_name = format!("{css_name}.map");
+        let scss = fs::read_to_string(stylesheet)?;
+        output::write(
+            &to.with_file_name(&map_name),
+            source_map(&css_name, &scss_name, &scss),
+        )?;
+        css.push_str(&format!("\n/*# sourceMappingURL={map_name} */\n"));
+    }
+    output::write(to, css)
+}
+
+// The source map of the compiled `css` file, which links to the `scss` source.
+//
+// grass doesn't track the source positions, so the map has no mappings,
+// the browser devtools show the embedded source of the stylesheet only.
+fn source_map(css: &str, scss: &str, content: &str) -> String {
+    json!({
+        "version": 3,
+        "file": css,
+        "sources": [scss],
+        "sourcesContent": [content],
+        "names": [],
+        "mappings": "",
+    })
+    .to_string()
+}
+
+// Warn about the Git LFS pointer files, large files and the images
+// with the GPS location if `check_location`, listing all offending paths.
+fn check_static_files(files: &[(PathBuf, PathBuf)], config: &BuildConfig, check_location: bool) {
+    let threshold = config.large_file_threshold_mb * 1024 * 1024;
+    let mut lfs_pointers = vec![];
+    let mut large_files = vec![];
+    let mut geotagged_images = vec![];
+    for (file, _) in files {
+        let Ok(metadata) = fs::metadata(file) else {
+            continue;
+        };
+        if metadata.len() < LFS_POINTER_MAX_SIZE && is_lfs_pointer(file) {
+            lfs_pointers.push(file);
+        } else if threshold > 0 && metadata.len() > threshold {
+            large_files.push((file, metadata.len()));
+        }
+        if check_location
+            && exif::is_supported(file)
+            && fs::read(file).map_or(false, |image| exif::has_location(&image))
+        {
+            geotagged_images.push(file);
+        }
+    }
+
+    if !lfs_pointers.is_empty() {
+        println!(
+            "Warning: the following static files are Git LFS pointers, \
+            please run `git lfs pull` to fetch the real content:"
+        );
+        for file in lfs_pointers {
+            println!("  - {}", file.display());
+        }
+    }
+    if !large_files.is_empty() {
+        println!(
+            "Warning: the following static files are larger than {}MB:",
+            config.large_file_threshold_mb
+        );
+        for (file, size) in large_files {
+            println!(
+                "  - {} ({:.1}MB)",
+                file.display(),
+                size as f64 / 1024.0 / 1024.0
+            );
+        }
+    }
+    if !geotagged_images.is_empty() {
+        println!(
+            "Warning: the following images contain the GPS location, \
+            please set `strip_metadata = true` in the `[media]` table to strip it:"
+        );
+        for file in geotagged_images {
+            println!("  - {}", file.display());
+        }
+    }
+}
+
+// Check whether the file is a Git LFS pointer file which isn't checked out.
+// See https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md
+fn is_lfs_pointer(file: &Path) -> bool {
+    fs::read(file)
+        .map(|content| content.starts_with(LFS_POINTER_PREFIX))
+        .unwrap_or_default()
+}
+
+// Whether the `to` file is identical to `from` since last copy.
+//
+// The copied file always has a newer modified time than the original file,
+// so the file is considered unchanged if the size is equal and it isn't older.
+fn is_unchanged(from: &Path, to: &Path) -> bool {
+    let (Ok(from_meta), Ok(to_meta)) = (fs::metadata(from), fs::metadata(to)) else {
+        return false;
+    };
+    match (from_meta.modified(), to_meta.modified()) {
+        (Ok(from_modified), Ok(to_modified)) => {
+            from_meta.len() == to_meta.len() && to_modified >= from_modified
+        }
+        _ => false,
+    }
+}
+
+// Check whether the relative `path` matches any of `patterns`.
+//
+// A pattern contains `/` is matched against the whole relative path,
+// otherwise, it is matched against the file name only.
+pub(crate) fn is_excluded(path: &Path, patterns: &[String]) -> bool {
+    if patterns.is_empty() || path.as_os_str().is_empty() {
+        return false;
+    }
+
+    let relative_path = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    patterns.iter().any(|pattern| {
+        if pattern.contains('/') {
+            wildcard_match(pattern.trim_start_matches('/'), &relative_path)
+        } else {
+            wildcard_match(pattern, &file_name)
+        }
+    })
+}
+
+// Match `text` with a simple glob `pattern`,
+// which supports `*` (any characters) and `?` (any single character).
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // The position of last `*` in pattern and the matched position in text.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(&'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use test_case::test_case;
+
+    use crate::entity::{BuildConfig, MediaConfig, SandboxConfig};
+
+    use super::{
+        copy_static_dir, copy_theme_static_dir, is_excluded, is_scss, is_scss_partial, source_map,
+        wildcard_match,
+    };
+
+    #[test_case("*.psd", "cover.psd")]
+    #[test_case("*", "cover.png")]
+    #[test_case("cover.*", "cover.png")]
+    #[test_case("c?ver.png", "cover.png")]
+    #[test_case("*.tar.*", "a.tar.gz")]
+    #[test_case("**", "")]
+    fn test_wildcard_match(pattern: &str, text: &str) {
+        assert!(wildcard_match(pattern, text));
+    }
+
+    #[test_case("*.psd", "cover.png")]
+    #[test_case("c?ver.png", "cver.png")]
+    #[test_case("cover", "cover.png")]
+    #[test_case("*.tar.*", "a.tar")]
+    fn test_wildcard_not_match(pattern: &str, text: &str) {
+        assert!(!wildcard_match(pattern, text));
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let patterns = vec![String::from("*.psd"), String::from("drafts/*")];
+        assert!(is_excluded(Path::new("images/cover.psd"), &patterns));
+        assert!(is_excluded(Path::new("drafts/a.png"), &patterns));
+        assert!(!is_excluded(Path::new("images/drafts/a.png"), &patterns));
+        assert!(!is_excluded(Path::new("images/cover.png"), &patterns));
+        assert!(!is_excluded(Path::new(""), &patterns));
+    }
+    #[test_case("css/main.scss", true, false)]
+    #[test_case("css/_colors.scss", true, true)]
+    #[test_case("css/main.css", false, false)]
+    fn test_scss(path: &str, scss: bool, partial: bool) {
+        assert_eq!(is_scss(Path::new(path)), scss);
+        assert_eq!(
+            is_scss(Path::new(path)) && is_scss_partial(Path::new(path)),
+            partial
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let map: serde_json::Value =
+            serde_json::from_str(&source_map("main.css", "main.scss", "a { b: c }")).unwrap();
+        assert_eq!(map["version"], 3);
+        assert_eq!(map["sources"][0], "main.scss");
+        assert_eq!(map["sourcesContent"][0], "a { b: c }");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_static_symlinks() {
+        let root = env::temp_dir().join("zine-static-symlinks");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("static")).unwrap();
+        fs::create_dir_all(root.join("theme/static")).unwrap();
+        fs::write(root.join("static/a.txt"), "a").unwrap();
+        fs::write(root.join("theme/static/b.txt"), "b").unwrap();
+        let outside = env::current_dir().unwrap().join("Cargo.toml");
+        std::os::unix::fs::symlink(&outside, root.join("static/secret.txt")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("theme/static/theme-secret.txt")).unwrap();
+
+        let mut enforced = SandboxConfig::default();
+        enforced.enforce();
+        for (sandbox_config, copied) in [(SandboxConfig::default(), true), (enforced, false)] {
+            let dest = root.join("build");
+            let _ = fs::remove_dir_all(&dest);
+            copy_static_dir(
+                &root,
+                &dest,
+                &BuildConfig::default(),
+                &MediaConfig::default(),
+                &sandbox_config,
+            )
+            .unwrap();
+            copy_theme_static_dir(
+                &root.join("theme/static"),
+                &root,
+                &dest.join("static"),
+                &sandbox_config,
+            )
+            .unwrap();
+
+            assert!(dest.join("static/a.txt").is_file());
+            assert!(dest.join("static/b.txt").is_file());
+            assert_eq!(dest.join("static/secret.txt").exists(), copied);
+            assert_eq!(dest.join("static/theme-secret.txt").exists(), copied);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+use crate::entity::Zine;
+
+// The file in the `.zine` directory to store the article states of last build and the changelog.
+static CHANGELOG_FILE: &str = "changelog.json";
+// The changelog file (relative to the zine root) in the previous versions.
+static LEGACY_CHANGELOG_FILE: &str = ".zine-cache/changelog.json";
+
+#[derive(Default, Serialize, Deserialize)]
+struct Changelog {
+    // The url path and state pairs of the articles in last build.
+    articles: BTreeMap<String, ArticleState>,
+    // The changelog entries, the latest first.
+    entries: Vec<ChangelogEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArticleState {
+    pub title: String,
+    #[serde(with = "genkit::helpers::serde_date")]
+    pub pub_date: Date,
+    #[serde(with = "genkit::helpers::serde_date::options")]
+    pub updated_date: Option<Date>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    New,
+    Updated,
+}
+
+/// A change of article between two builds.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    pub kind: ChangeKind,
+    pub title: String,
+    /// The url path of the article, e.g. `/issue-1/hello-zine`.
+    pub path: String,
+    /// The `pub_date` of a new article, or the `updated_date` of an updated article.
+    #[serde(with = "genkit::helpers::serde_date")]
+    pub date: Date,
+}
+
+/// Diff the current published articles of `zine` against the last build,
+/// then return the changelog, the latest first.
+///
+/// The article states and changelog are saved for the next build if `save` is true,
+/// otherwise the changes are only previewed.
+pub fn update_changelog(source: &Path, zine: &Zine, save: bool) -> Result<Vec<ChangelogEntry>> {
+    let file = source.join(crate::ZINE_STATE_DIR).join(CHANGELOG_FILE);
+    let legacy_file = source.join(LEGACY_CHANGELOG_FILE);
+    let previous_file = if file.exists() { &file } else { &legacy_file };
+    let current = article_states(zine);
+    let mut changelog = if previous_file.exists() {
+        let mut changelog: Changelog = serde_json::from_slice(&fs::read(previous_file)?)
+            .with_context(|| format!("Failed to parse `{}`", previous_file.display()))?;
+        let mut changes = diff_articles(&changelog.articles, &current);
+        changes.append(&mut changelog.entries);
+        changelog.entries = changes;
+        changelog
+    } else {
+        // Nothing is new in the first build.
+        Changelog::default()
+    };
+    changelog.articles = current;
+
+    if save {
+        fs::create_dir_all(source.join(crate::ZINE_STATE_DIR))?;
+        fs::write(&file, serde_json::to_vec_pretty(&changelog)?)?;
+    }
+    Ok(changelog.entries)
+}
+
+fn article_states(zine: &Zine) -> BTreeMap<String, ArticleState> {
+    zine.issues
+        .iter()
+        .flat_map(|issue| {
+            issue.articles().into_iter().map(|article| {
+                let path = article
+                    .meta
+                    .path
+                    .clone()
+                    .unwrap_or_else(|| format!("/{}/{}", issue.slug, article.meta.slug));
+                let state = ArticleState {
+                    title: article.meta.title.clone(),
+                    pub_date: article.meta.pub_date,
+                    updated_date: article.meta.updated_date,
+                };
+                (path, state)
+            })
+        })
+        .collect()
+}
+
+// An article is new if its path didn't exist in last build,
+// or updated if its `updated_date` changed.
+fn diff_articles(
+    previous: &BTreeMap<String, ArticleState>,
+    current: &BTreeMap<String, ArticleState>,
+) -> Vec<ChangelogEntry> {
+    let mut changes = current
+        .iter()
+        .filter_map(|(path, state)| {
+            let (kind, date) = match previous.get(path) {
+                None => (ChangeKind::New, state.pub_date),
+                Some(last) if state.updated_date != last.updated_date => {
+                    (ChangeKind::Updated, state.updated_date?)
+                }
+                Some(_) => return None,
+            };
+            Some(ChangelogEntry {
+                kind,
+                title: state.title.clone(),
+                path: path.clone(),
+                date,
+            })
+        })
+        .collect::<Vec<_>>();
+    changes.sort_by(|a, b| b.date.cmp(&a.date));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use time::{Date, Month};
+
+    use super::{diff_articles, ArticleState, ChangeKind};
+
+    fn date(month: u8) -> Date {
+        Date::from_calendar_date(2022, Month::try_from(month).unwrap(), 1).unwrap()
+    }
+
+    #[test]
+    fn test_diff_articles() {
+        let state = |updated_date| ArticleState {
+            title: String::from("Hello"),
+            pub_date: date(1),
+            updated_date,
+        };
+        let previous = BTreeMap::from([
+            (String::from("/a"), state(None)),
+            (String::from("/b"), state(None)),
+            (String::from("/c"), state(Some(date(2)))),
+        ]);
+        let current = BTreeMap::from([
+            (String::from("/a"), state(None)),
+            (String::from("/b"), state(Some(date(3)))),
+            (String::from("/c"), state(None)),
+            (String::from("/d"), state(None)),
+        ]);
+
+        let changes = diff_articles(&previous, &current);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "/b");
+        assert_eq!(changes[0].kind, ChangeKind::Updated);
+        assert_eq!(changes[0].date, date(3));
+        assert_eq!(changes[1].path, "/d");
+        assert_eq!(changes[1].kind, ChangeKind::New);
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+
+use parking_lot::{const_mutex, Mutex};
+use serde::Serialize;
+
+use crate::entity::Zine;
+
+// The url path and (title, markdown) pairs of the articles in the last rendering.
+static LAST_ARTICLES: Mutex<Option<BTreeMap<String, (String, String)>>> = const_mutex(None);
+
+// The max cells of the LCS table, the larger changes are diffed as a whole.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+// The unchanged words kept around each change.
+const CONTEXT_WORDS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// The change of an article since the last rendering in `zine serve` mode.
+#[derive(Debug, Serialize)]
+pub struct ArticleChange {
+    pub kind: ChangeKind,
+    pub title: String,
+    /// The url path of the article, e.g. `/issue-1/hello-zine`.
+    pub path: String,
+    /// The word diff of the changed article markdown, empty for the added or removed article.
+    pub diff: Vec<DiffSegment>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct DiffSegment {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Diff the articles of `zine` against the last call,
+/// nothing is changed in the first call.
+pub fn track_changes(zine: &Zine) -> Vec<ArticleChange> {
+    let current = article_markdowns(zine);
+    let Some(previous) = LAST_ARTICLES.lock().replace(current.clone()) else {
+        return Vec::new();
+    };
+
+    let mut changes = vec![];
+    for (path, (title, markdown)) in &current {
+        let (kind, diff) = match previous.get(path) {
+            None => (ChangeKind::Added, Vec::new()),
+            Some((_, old)) if old != markdown => (ChangeKind::Changed, word_diff(old, markdown)),
+            Some(_) => continue,
+        };
+        changes.push(ArticleChange {
+            kind,
+            title: title.clone(),
+            path: path.clone(),
+            diff,
+        });
+    }
+    for (path, (title, _)) in &previous {
+        if !current.contains_key(path) {
+            changes.push(ArticleChange {
+                kind: ChangeKind::Removed,
+                title: title.clone(),
+                path: path.clone(),
+                diff: Vec::new(),
+            });
+        }
+    }
+    changes
+}
+
+/// Print the `changes` in the `git diff --word-diff=plain` style.
+pub fn print_changes(changes: &[ArticleChange]) {
+    for change in changes {
+        println!("{:?}: {} ({})", change.kind, change.title, change.path);
+        if change.diff.is_empty() {
+            continue;
+        }
+        let line = change
+            .diff
+            .iter()
+            .map(|segment| match segment.kind {
+                DiffKind::Equal => segment.text.clone(),
+                DiffKind::Insert => format!("{{+{}+}}", segment.text),
+                DiffKind::Delete => format!("[-{}-]", segment.text),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("    {line}");
+    }
+}
+
+fn article_markdowns(zine: &Zine) -> BTreeMap<String, (String, String)> {
+    let mut articles = BTreeMap::new();
+    for issue in zine.issues.iter().filter(|issue| issue.need_publish()) {
+        for article in issue
+            .articles()
+            .into_iter()
+            .flat_map(|article| std::iter::once(article).chain(article.i18n.values()))
+        {
+            let path = article
+                .meta
+                .path
+                .clone()
+                .unwrap_or_else(|| format!("/{}/{}", issue.slug, article.meta.slug));
+            articles.insert(path, (article.meta.title.clone(), article.markdown.clone()));
+        }
+    }
+    articles
+}
+
+/// Diff the words of `old` and `new` text, the unchanged words far from
+/// the changes are collapsed into `…`.
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffSegment> {
+    let old = old.split_whitespace().collect::<Vec<_>>();
+    let new = new.split_whitespace().collect::<Vec<_>>();
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+
+    // Strip the common prefix and suffix, which are the most of words normally.
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    ops.extend(old[..prefix].iter().map(|word| (DiffKind::Equal, *word)));
+    ops.extend(lcs_diff(old_middle, new_middle));
+    ops.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|word| (DiffKind::Equal, *word)),
+    );
+
+    // Merge the words of the same kind into segments.
+    let mut segments: Vec<(DiffKind, Vec<&str>)> = vec![];
+    for (kind, word) in ops {
+        match segments.last_mut() {
+            Some((last, words)) if *last == kind => words.push(word),
+            _ => segments.push((kind, vec![word])),
+        }
+    }
+    let count = segments.len();
+    segments
+        .into_iter()
+        .enumerate()
+        .map(|(index, (kind, words))| {
+            let text = if kind == DiffKind::Equal && words.len() > CONTEXT_WORDS * 2 {
+                // Keep the context words next to the changes only.
+                let head = if index > 0 {
+                    words[..CONTEXT_WORDS].join(" ")
+                } else {
+                    String::new()
+                };
+                let tail = if index + 1 < count {
+                    words[words.len() - CONTEXT_WORDS..].join(" ")
+                } else {
+                    String::new()
+                };
+                format!("{head} … {tail}").trim().to_owned()
+            } else {
+                words.join(" ")
+            };
+            DiffSegment { kind, text }
+        })
+        .collect()
+}
+
+// Diff the words by the longest common subsequence.
+fn lcs_diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(DiffKind, &'a str)> {
+    if old.len() * new.len() > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|word| (DiffKind::Delete, *word))
+            .chain(new.iter().map(|word| (DiffKind::Insert, *word)))
+            .collect();
+    }
+
+    // The LCS length of `old[i..]` and `new[j..]`.
+    let width = new.len() + 1;
+    let mut table = vec![0_u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = if old[i] == new[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            ops.push((DiffKind::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            ops.push((DiffKind::Delete, old[i]));
+            i += 1;
+        } else {
+            ops.push((DiffKind::Insert, new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|word| (DiffKind::Delete, *word)));
+    ops.extend(new[j..].iter().map(|word| (DiffKind::Insert, *word)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{word_diff, DiffKind, DiffSegment};
+
+    fn segment(kind: DiffKind, text: &str) -> DiffSegment {
+        DiffSegment {
+            kind,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_word_diff() {
+        assert_eq!(
+            word_diff(
+                "Hello zine, a static site",
+                "Hello zine, the  static\nmagazine"
+            ),
+            vec![
+                segment(DiffKind::Equal, "Hello zine,"),
+                segment(DiffKind::Delete, "a"),
+                segment(DiffKind::Insert, "the"),
+                segment(DiffKind::Equal, "static"),
+                segment(DiffKind::Delete, "site"),
+                segment(DiffKind::Insert, "magazine"),
+            ]
+        );
+        assert_eq!(word_diff("", "New"), vec![segment(DiffKind::Insert, "New")]);
+        assert!(word_diff("Same", "Same")
+            .iter()
+            .all(|segment| segment.kind == DiffKind::Equal));
+    }
+
+    #[test]
+    fn test_word_diff_context() {
+        let old = (0..40).map(|i| i.to_string()).collect::<Vec<_>>().join(" ");
+        let new = old.replace(" 20 ", " twenty ");
+        let diff = word_diff(&old, &new);
+        assert_eq!(diff.len(), 4);
+        assert_eq!(diff[0].text, "… 12 13 14 15 16 17 18 19");
+        assert_eq!(diff[1], segment(DiffKind::Delete, "20"));
+        assert_eq!(diff[2], segment(DiffKind::Insert, "twenty"));
+        assert_eq!(diff[3].text, "21 22 23 24 25 26 27 28 …");
+    }
+}
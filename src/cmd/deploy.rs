@@ -0,0 +1,365 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    path::Path,
+};
+
+use anyhow::{bail, ensure, Context as _, Result};
+use clap::{Arg, ArgAction, Command};
+use genkit::Cmd;
+use s3::{bucket::Bucket, creds::Credentials, region::Region};
+
+use crate::{entity::Site, html, snapshot::SnapshotManifest, ZINE_FILE};
+
+// The name of remote manifest file, which records the hashes of deployed files.
+static REMOTE_MANIFEST_FILE: &str = ".zine-manifest.json";
+
+pub struct DeployCmd;
+
+#[async_trait::async_trait]
+impl Cmd for DeployCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("deploy")
+            .args([
+                Arg::new("target")
+                    .required(true)
+                    .help("The deploy target, e.g. `s3://bucket/prefix`."),
+                Arg::new("dest")
+                    .long("dest")
+                    .short('d')
+                    .default_value("build")
+                    .help("The build directory of the site."),
+                Arg::new("delete")
+                    .long("delete")
+                    .action(ArgAction::SetTrue)
+                    .help("Delete the remote files which no longer exist in the build."),
+                Arg::new("dry-run")
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue)
+                    .help("Print the changes without uploading."),
+            ])
+            .about("Upload the changed files of the built site to S3 compatible storage")
+            .after_help(
+                "The credentials are read from the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY \
+                environment variables or the AWS profile. Set AWS_REGION for the region, \
+                or S3_ENDPOINT for other S3 compatible services.",
+            )
+    }
+
+    async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> Result<()> {
+        let (source, zine) = crate::locate_root_zine_folder(env::current_dir()?)?
+            .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        let build_dir = source.join(arg_matches.get_one::<String>("dest").unwrap());
+        ensure!(
+            build_dir.exists(),
+            "`{}` not found, please run `zine build` first.",
+            build_dir.display()
+        );
+        upload(
+            &source,
+            &build_dir,
+            arg_matches.get_one::<String>("target").unwrap(),
+            arg_matches.get_flag("delete"),
+            arg_matches.get_flag("dry-run"),
+            zine.build_config.cache_bust.then_some(&zine.site),
+        )
+        .await
+    }
+}
+
+/// Upload the changed files of the `build_dir` to the S3 `target`, e.g. `s3://bucket/prefix`.
+///
+/// The remote files which no longer exist in the build are deleted if `delete`,
+/// nothing is uploaded or deleted if `dry_run`. The `cache_bust` site is present
+/// if the asset urls of the site are busted with the build id.
+pub async fn upload(
+    source: &Path,
+    build_dir: &Path,
+    target: &str,
+    delete: bool,
+    dry_run: bool,
+    cache_bust: Option<&Site>,
+) -> Result<()> {
+    let (bucket_name, prefix) = parse_s3_url(target)?;
+    let bucket = open_bucket(&bucket_name)?;
+    let remote_path = |path: &str| {
+        if prefix.is_empty() {
+            path.to_owned()
+        } else {
+            format!("{prefix}/{path}")
+        }
+    };
+
+    let config = fs::read_to_string(source.join(ZINE_FILE))?;
+    let local = SnapshotManifest::new(build_dir, config)?;
+    let remote = match bucket.get_object(remote_path(REMOTE_MANIFEST_FILE)).await {
+        Ok(response) if response.status_code() == 200 => {
+            serde_json::from_slice::<SnapshotManifest>(response.bytes())
+                .map(|manifest| manifest.files)
+                .unwrap_or_default()
+        }
+        // Deploy all files for the first time.
+        _ => BTreeMap::new(),
+    };
+    let (changed, deleted) = diff_files(&local.files, &remote);
+    let (uploaded, unchanged) = (changed.len(), local.files.len() - changed.len());
+    let busted = match cache_bust {
+        Some(site) => cache_busted_paths(build_dir, &local.files, site)?,
+        None => HashSet::new(),
+    };
+
+    for path in &changed {
+        println!("upload: {path}");
+        if dry_run {
+            continue;
+        }
+        let content = fs::read(build_dir.join(path))?;
+        let mut bucket = bucket.clone();
+        bucket.add_header("cache-control", cache_control(path, busted.contains(*path)));
+        bucket
+            .put_object_with_content_type(remote_path(path), &content, content_type(path))
+            .await
+            .with_context(|| format!("Failed to upload `{path}`"))?;
+    }
+    if delete {
+        for path in &deleted {
+            println!("delete: {path}");
+            if !dry_run {
+                bucket
+                    .delete_object(remote_path(path))
+                    .await
+                    .with_context(|| format!("Failed to delete `{path}`"))?;
+            }
+        }
+    }
+
+    if !dry_run {
+        // Upload the manifest at last, so an interrupted deploy would be retried.
+        let mut files = local.files.clone();
+        if !delete {
+            // The remote files are kept, so are their hashes.
+            files.extend(deleted.iter().filter_map(|path| {
+                remote
+                    .get(*path)
+                    .map(|hash| (path.to_string(), hash.clone()))
+            }));
+        }
+        let manifest = SnapshotManifest { files, ..local };
+        bucket
+            .put_object_with_content_type(
+                remote_path(REMOTE_MANIFEST_FILE),
+                &serde_json::to_vec_pretty(&manifest)?,
+                "application/json",
+            )
+            .await
+            .with_context(|| format!("Failed to upload `{REMOTE_MANIFEST_FILE}`"))?;
+    }
+
+    println!(
+        "{} files uploaded, {} files {}, {} files unchanged.",
+        uploaded,
+        deleted.len(),
+        if delete { "deleted" } else { "stale" },
+        unchanged
+    );
+    Ok(())
+}
+
+// Parse the `s3://bucket/prefix` url into the bucket name and prefix pair.
+fn parse_s3_url(url: &str) -> Result<(String, String)> {
+    let Some(path) = url.strip_prefix("s3://") else {
+        bail!("Unsupported deploy target `{url}`, expect `s3://bucket/prefix`");
+    };
+    let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+    ensure!(!bucket.is_empty(), "The bucket name is missing in `{url}`");
+    Ok((bucket.to_owned(), prefix.trim_matches('/').to_owned()))
+}
+
+fn open_bucket(name: &str) -> Result<Bucket> {
+    let region = match env::var("S3_ENDPOINT") {
+        Ok(endpoint) => Region::Custom {
+            region: env::var("AWS_REGION").unwrap_or_else(|_| String::from("auto")),
+            endpoint,
+        },
+        Err(_) => env::var("AWS_REGION")
+            .with_context(|| "Please set the AWS_REGION or S3_ENDPOINT environment variable")?
+            .parse()?,
+    };
+    let credentials = Credentials::default()?;
+    // Most S3 compatible services only support the path style url.
+    let bucket = if env::var_os("S3_ENDPOINT").is_some() {
+        Bucket::new(name, region, credentials)?.with_path_style()
+    } else {
+        Bucket::new(name, region, credentials)?
+    };
+    Ok(bucket)
+}
+
+// Get the changed paths to upload, and the remote paths which no longer exist.
+fn diff_files<'a>(
+    local: &'a BTreeMap<String, String>,
+    remote: &'a BTreeMap<String, String>,
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    let changed = local
+        .iter()
+        .filter(|(path, hash)| remote.get(*path) != Some(hash))
+        .map(|(path, _)| path.as_str())
+        .collect();
+    let deleted = remote
+        .keys()
+        .filter(|path| !local.contains_key(*path))
+        .map(String::as_str)
+        .collect();
+    (changed, deleted)
+}
+
+fn content_type(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "opml" => "text/x-opml",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "pdf" => "application/pdf",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+// Get the paths of the static files whose urls are busted with the build id
+// in the html files of `build_dir`, e.g. `/static/zine.css?v=3f2a9c1-1700000000`.
+fn cache_busted_paths(
+    build_dir: &Path,
+    files: &BTreeMap<String, String>,
+    site: &Site,
+) -> Result<HashSet<String>> {
+    let mut paths = HashSet::new();
+    for path in files.keys().filter(|path| path.ends_with(".html")) {
+        let content = fs::read_to_string(build_dir.join(path))?;
+        paths.extend(
+            html::cache_busted_urls(&content)?
+                .iter()
+                .filter_map(|url| static_path(url, site)),
+        );
+    }
+    Ok(paths)
+}
+
+// Get the file path of the static asset `url`, which is either root-relative,
+// under the site url or the cdn url.
+fn static_path(url: &str, site: &Site) -> Option<String> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let cdn = site.cdn.as_deref().map(|cdn| cdn.trim_end_matches('/'));
+    let path = match cdn.and_then(|cdn| url.strip_prefix(cdn)?.strip_prefix('/')) {
+        Some(path) => format!("static/{path}"),
+        None => url
+            .strip_prefix(site.url.trim_end_matches('/'))
+            .unwrap_or(url)
+            .strip_prefix('/')?
+            .to_owned(),
+    };
+    path.starts_with("static/").then_some(path)
+}
+
+// The static assets are cached for long if their urls are busted with the build id,
+// otherwise cached for a short time. The pages are always revalidated.
+fn cache_control(path: &str, busted: bool) -> &'static str {
+    if path.starts_with("static/") {
+        if busted {
+            "public, max-age=31536000, immutable"
+        } else {
+            "public, max-age=3600"
+        }
+    } else {
+        "public, max-age=0, must-revalidate"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use test_case::test_case;
+
+    use crate::entity::Site;
+
+    use super::{cache_control, content_type, diff_files, parse_s3_url, static_path};
+
+    #[test_case("s3://bucket", "bucket", ""; "no prefix")]
+    #[test_case("s3://bucket/", "bucket", ""; "empty prefix")]
+    #[test_case("s3://bucket/site/zine/", "bucket", "site/zine"; "prefix")]
+    fn test_parse_s3_url(url: &str, bucket: &str, prefix: &str) {
+        assert_eq!(
+            parse_s3_url(url).unwrap(),
+            (bucket.to_owned(), prefix.to_owned())
+        );
+    }
+
+    #[test_case("https://bucket"; "scheme")]
+    #[test_case("s3:///prefix"; "no bucket")]
+    fn test_parse_invalid_s3_url(url: &str) {
+        assert!(parse_s3_url(url).is_err());
+    }
+
+    #[test_case("index.html", "text/html; charset=utf-8")]
+    #[test_case("static/zine.CSS", "text/css; charset=utf-8")]
+    #[test_case("static/font", "application/octet-stream")]
+    fn test_content_type(path: &str, expected: &str) {
+        assert_eq!(content_type(path), expected);
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let files = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(path, hash)| (path.to_string(), hash.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let local = files(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let remote = files(&[("a", "1"), ("b", "0"), ("d", "4")]);
+        let (changed, deleted) = diff_files(&local, &remote);
+        assert_eq!(changed, vec!["b", "c"]);
+        assert_eq!(deleted, vec!["d"]);
+    }
+
+    #[test_case("/static/zine.css?v=1", Some("static/zine.css"); "root relative")]
+    #[test_case("https://zine.example.com/static/zine.js?v=1", Some("static/zine.js"); "site url")]
+    #[test_case("https://cdn.example.com/fonts/a.woff2?v=1", Some("static/fonts/a.woff2"); "cdn url")]
+    #[test_case("https://zine.example.com/issue-1/", None; "page")]
+    #[test_case("https://other.example.com/static/zine.css?v=1", None; "other site")]
+    fn test_static_path(url: &str, expected: Option<&str>) {
+        let site = Site {
+            url: String::from("https://zine.example.com"),
+            cdn: Some(String::from("https://cdn.example.com/")),
+            ..Default::default()
+        };
+        assert_eq!(static_path(url, &site).as_deref(), expected);
+    }
+
+    #[test_case("static/zine.css", true, "public, max-age=31536000, immutable")]
+    #[test_case("static/logo.png", false, "public, max-age=3600")]
+    #[test_case("index.html", false, "public, max-age=0, must-revalidate")]
+    fn test_cache_control(path: &str, busted: bool, expected: &str) {
+        assert_eq!(cache_control(path, busted), expected);
+    }
+}
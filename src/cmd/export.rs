@@ -0,0 +1,152 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+use clap::{Arg, Command};
+use genkit::{markdown, Cmd};
+
+use crate::{
+    crosspost::{self, CrossPost, Target},
+    data,
+    entity::{Article, ArticleKind, Entity, Zine},
+};
+
+pub struct ExportCmd;
+
+#[async_trait::async_trait]
+impl Cmd for ExportCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("export")
+            .args([
+                Arg::new("article")
+                    .required(true)
+                    .help("The url path of the article, e.g. `/issue-1/hello`"),
+                Arg::new("target")
+                    .long("target")
+                    .short('t')
+                    .value_parser(Target::NAMES)
+                    .default_value("devto")
+                    .help("The cross-posting platform"),
+                Arg::new("output")
+                    .long("output")
+                    .short('o')
+                    .help("The output file, default to `<slug>.<target>.md`"),
+            ])
+            .about("Export an article as a markdown file for cross-posting")
+            .after_help(
+                "The canonical url points to the original article, the images and links are \
+                rewritten to absolute urls, and the zine flavored blocks are downgraded to plain markdown.",
+            )
+    }
+
+    async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> Result<()> {
+        let (source, mut zine) = crate::locate_root_zine_folder(env::current_dir()?)?
+            .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        data::load();
+        zine.parse(&source)?;
+
+        let path = arg_matches.get_one::<String>("article").unwrap();
+        let target = Target::from_name(arg_matches.get_one::<String>("target").unwrap())?;
+        let (article_path, article) = find_article(&zine, path)
+            .with_context(|| format!("No published article found at `{path}`"))?;
+        let post = cross_post(&zine, &article_path, article)?;
+
+        let output = match arg_matches.get_one::<String>("output") {
+            Some(output) => PathBuf::from(output),
+            None => PathBuf::from(format!("{}.{}.md", article.meta.slug, target.name())),
+        };
+        fs::write(&output, post.render(target))?;
+        println!("Exported `{}` to {}", article.meta.title, output.display());
+        Ok(())
+    }
+}
+
+// Find the published article of the url `path` along with its normalized path.
+fn find_article<'a>(zine: &'a Zine, path: &str) -> Option<(String, &'a Article)> {
+    let path = format!("/{}", path.trim_matches('/'));
+    zine.issues
+        .iter()
+        .flat_map(|issue| {
+            issue.articles().into_iter().map(|article| {
+                let article_path = article
+                    .meta
+                    .path
+                    .clone()
+                    .unwrap_or_else(|| format!("/{}/{}", issue.slug, article.meta.slug));
+                (article_path, article)
+            })
+        })
+        .find(|(article_path, _)| article_path.trim_end_matches('/') == path)
+}
+
+fn cross_post(zine: &Zine, path: &str, article: &Article) -> Result<CrossPost> {
+    let site_url = zine.site.url.trim_end_matches('/');
+    let absolute_url = |url: &str| {
+        if url.starts_with('/') && !url.starts_with("//") {
+            format!("{site_url}{url}")
+        } else {
+            url.to_owned()
+        }
+    };
+
+    let mut content = article.markdown.clone();
+    if article.kind == ArticleKind::Roundup {
+        content.push_str("\n\n");
+        for link in &article.links {
+            content.push_str(&format!(
+                "- [{}]({})",
+                link.title.as_deref().unwrap_or(&link.url),
+                link.url
+            ));
+            if let Some(blurb) = &link.blurb {
+                content.push_str(&format!(" — {blurb}"));
+            }
+            if let Some(via) = &link.via {
+                content.push_str(&format!(" (via {via})"));
+            }
+            content.push('\n');
+        }
+    }
+
+    // The theme blocks and shortcodes are named by the templates, e.g. `blocks/recipe.jinja`.
+    let blocks = zine
+        .theme
+        .blocks
+        .keys()
+        .chain(zine.theme.shortcodes.keys())
+        .filter_map(|name| {
+            name.strip_prefix("blocks/")
+                .or_else(|| name.strip_prefix("shortcodes/"))?
+                .strip_suffix(".jinja")
+        })
+        .collect::<Vec<_>>();
+    let zine_data = data::read();
+    let resolve_code = |code: &str| {
+        if let Some(author_id) = code.strip_prefix('@') {
+            let author = zine_data.get_author_by_id(author_id)?;
+            let name = author.name.as_deref().unwrap_or(author_id);
+            Some(format!("[{name}]({site_url}/@{author_id})"))
+        } else if code.starts_with('/') {
+            let article = zine_data.get_article_by_path(code)?;
+            Some(format!("[{}]({site_url}{code})", article.title))
+        } else if let Some(topic) = code.strip_prefix('#') {
+            zine_data.is_valid_topic(topic).then(|| {
+                let slug = zine_data.get_topic_slug(topic);
+                format!("[#{topic}]({site_url}/topic/{slug})")
+            })
+        } else {
+            None
+        }
+    };
+
+    Ok(CrossPost {
+        title: article.meta.title.clone(),
+        description: markdown::extract_description(&article.markdown),
+        topics: article.topics.clone(),
+        canonical_url: article
+            .canonical()
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| format!("{site_url}{path}")),
+        cover_image: article.meta.cover.as_deref().map(absolute_url),
+        markdown: crosspost::portable_markdown(&content, site_url, &blocks, resolve_code)?,
+    })
+}
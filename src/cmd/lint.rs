@@ -0,0 +1,39 @@
+use std::env;
+
+use anyhow::{bail, Context as _, Result};
+use clap::Command;
+use genkit::Cmd;
+
+use crate::{data, entity::Entity, lint};
+
+pub struct LintCmd;
+
+#[async_trait::async_trait]
+impl Cmd for LintCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("lint")
+            .about("Check the broken internal links, missing static files, unknown author mentions and undeclared topics")
+            .after_help(
+                "The problems are reported with the file and line, the command exits with a non-zero code \
+                if any problem is found, which is suitable for CI.",
+            )
+    }
+
+    async fn on_execute(&self, _arg_matches: &clap::ArgMatches) -> Result<()> {
+        let (source, mut zine) = crate::locate_root_zine_folder(env::current_dir()?)?
+            .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        data::load();
+        zine.parse(&source)?;
+
+        let problems = lint::lint(&source, &zine)?;
+        if problems.is_empty() {
+            println!("No problems found.");
+            return Ok(());
+        }
+        for problem in &problems {
+            let file = problem.file.strip_prefix(&source).unwrap_or(&problem.file);
+            println!("{}:{}: {}", file.display(), problem.line, problem.message);
+        }
+        bail!("{} problem(s) found", problems.len());
+    }
+}
@@ -1,3 +1,19 @@
+#[cfg(feature = "s3")]
+pub mod deploy;
+pub mod export;
+pub mod lint;
 pub mod new;
+pub mod next_publish;
+pub mod publish;
+pub mod snapshot;
+pub mod version;
 
+#[cfg(feature = "s3")]
+pub use deploy::DeployCmd;
+pub use export::ExportCmd;
+pub use lint::LintCmd;
 pub use new::NewCmd;
+pub use next_publish::NextPublishCmd;
+pub use publish::PublishCmd;
+pub use snapshot::SnapshotCmd;
+pub use version::VersionCmd;
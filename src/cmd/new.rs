@@ -1,13 +1,30 @@
-use std::{borrow::Cow, env, fs, io::Write, path::PathBuf};
+use std::{
+    borrow::Cow,
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
 
-use anyhow::{Context as _, Ok, Result};
+use anyhow::{bail, Context as _, Ok, Result};
 use clap::{Arg, ArgAction, Command};
 use genkit::{helpers, Cmd};
-use minijinja::render;
+use minijinja::{context, render, Environment};
 use promptly::prompt_default;
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
+use toml_edit::{value, Document, InlineTable, Item, Table};
 
-use crate::{entity::Zine, ZINE_FILE};
+use crate::{
+    entity::{normalize_author_id, validate_author_id, Zine},
+    media, ZINE_FILE,
+};
+
+// The directory of the downloaded author avatars, relative to the zine root.
+static AVATAR_DIR: &str = "static/avatars";
+// The directory of the user scaffold templates, relative to the zine root.
+static SCAFFOLD_DIR: &str = "scaffolds";
+// The markdown content of the new articles without a user scaffold.
+static DEFAULT_ARTICLE_CONTENT: &str = "Hello Zine";
 
 static TEMPLATE_PROJECT_FILE: &str = r#"
 [site]
@@ -41,11 +58,14 @@ static TEMPLATE_ARTICLE: &str = r#"
 [[article]]
 file = "{{ file }}"
 title = "{{ title }}"
-author = "{{ author | lower }}"
-cover = ""
+author = "{{ author }}"
+cover = "{{ cover }}"
+{% if topics -%}
+topic = [{% for topic in topics %}"{{ topic }}"{% if not loop.last %}, {% endif %}{% endfor %}]
+{% endif -%}
 pub_date = "{{ pub_date }}"
 publish = true
-featured = true
+featured = {{ featured }}
 "#;
 
 pub struct NewCmd;
@@ -67,17 +87,42 @@ impl Cmd for NewCmd {
                     .action(ArgAction::SetTrue)
                     .conflicts_with("issue")
                     .help("New article."),
+                Arg::new("author")
+                    .long("author")
+                    .value_name("ID")
+                    .conflicts_with_all(["issue", "article"])
+                    .help("New author profile in the root zine.toml."),
+                Arg::new("from-template")
+                    .long("from-template")
+                    .value_name("NAME")
+                    .help(
+                        "New issue or article from the scaffold template `scaffolds/<NAME>.toml`.",
+                    ),
             ])
-            .about("New a Zine project, issue or article")
+            .about("New a Zine project, issue, article or author")
+            .after_help(
+                "The issue and article scaffolds are rendered from `scaffolds/issue.toml` and \
+                `scaffolds/article.toml` if present, or the templates given by `--from-template`. \
+                The optional `scaffolds/<NAME>.md` is the markdown content of the new article. \
+                The templates are minijinja templates, e.g. `title = \"{{ title }}\"`.",
+            )
     }
 
     async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> anyhow::Result<()> {
         let issue = arg_matches.get_flag("issue");
         let article = arg_matches.get_flag("article");
-        if issue {
-            new_zine_issue()?;
+        let template = arg_matches
+            .get_one::<String>("from-template")
+            .map(String::as_str);
+        if template.is_some() && !issue && !article {
+            bail!("`--from-template` requires `--issue` or `--article`");
+        }
+        if let Some(author) = arg_matches.get_one::<String>("author") {
+            new_author(author)?;
+        } else if issue {
+            new_zine_issue(template)?;
         } else if article {
-            new_article()?;
+            new_article(template)?;
         } else {
             new_zine_project(arg_matches.get_one("name").cloned())?
         }
@@ -86,6 +131,59 @@ impl Cmd for NewCmd {
     }
 }
 
+// The scaffold templates of a new issue or article.
+struct ScaffoldTemplate {
+    // The name of the template, for the error messages.
+    name: String,
+    // The issue `zine.toml` or the `[[article]]` table template.
+    toml: Cow<'static, str>,
+    // The markdown content template of the article.
+    markdown: Cow<'static, str>,
+}
+
+impl ScaffoldTemplate {
+    // Load the `scaffolds/<name>.toml` and the optional `scaffolds/<name>.md` templates,
+    // the `name` defaults to `kind` (`issue` or `article`) and falls back to the `builtin`
+    // template if the file is missing.
+    fn load(source: &Path, name: Option<&str>, kind: &str, builtin: &'static str) -> Result<Self> {
+        let scaffold_dir = source.join(SCAFFOLD_DIR);
+        let toml_file = scaffold_dir.join(format!("{}.toml", name.unwrap_or(kind)));
+        let toml = if toml_file.exists() {
+            Cow::Owned(fs::read_to_string(&toml_file)?)
+        } else if name.is_some() {
+            bail!("Scaffold template `{}` not found", toml_file.display());
+        } else {
+            Cow::Borrowed(builtin)
+        };
+        let markdown_file = toml_file.with_extension("md");
+        let markdown = if markdown_file.exists() {
+            Cow::Owned(fs::read_to_string(&markdown_file)?)
+        } else {
+            Cow::Borrowed(DEFAULT_ARTICLE_CONTENT)
+        };
+        Ok(ScaffoldTemplate {
+            name: name.unwrap_or(kind).to_owned(),
+            toml,
+            markdown,
+        })
+    }
+
+    // Render the toml and markdown templates with the `context`.
+    fn render(&self, context: minijinja::Value) -> Result<(String, String)> {
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        let render = |extension: &str, template: &str| {
+            env.render_named_str(
+                &format!("{}.{extension}", self.name),
+                template,
+                context.clone(),
+            )
+            .with_context(|| format!("Failed to render the scaffold `{}.{extension}`", self.name))
+        };
+        Ok((render("toml", &self.toml)?, render("md", &self.markdown)?))
+    }
+}
+
 struct ZineScaffold {
     source: PathBuf,
     author: String,
@@ -103,32 +201,31 @@ impl ZineScaffold {
         )?;
 
         // Create issue dir and issue zine.toml
-        self.create_issue()?;
+        self.create_issue(None)?;
         Ok(())
     }
 
-    // Create issue dir and issue zine.toml
-    fn create_issue(&self) -> Result<()> {
+    // Create issue dir and issue zine.toml from the scaffold `template`, see [`ScaffoldTemplate`].
+    fn create_issue(&self, template: Option<&str>) -> Result<()> {
+        let template =
+            ScaffoldTemplate::load(&self.source, template, "issue", TEMPLATE_ISSUE_FILE)?;
         let issue_dir = self
             .source
             .join(crate::ZINE_CONTENT_DIR)
             .join(self.issue_dir.as_ref());
         fs::create_dir_all(&issue_dir)?;
 
-        fs::write(
-            issue_dir.join(ZINE_FILE),
-            render!(
-                TEMPLATE_ISSUE_FILE,
-                slug => self.issue_dir,
-                number => self.issue_number,
-                title => self.issue_title,
-                pub_date => helpers::format_date(&OffsetDateTime::now_utc().date()),
-                author => self.author
-            ),
-        )?;
+        let (issue_file, first_article) = template.render(context! {
+            slug => self.issue_dir,
+            number => self.issue_number,
+            title => self.issue_title,
+            pub_date => helpers::format_date(&today()),
+            author => self.author
+        })?;
+        fs::write(issue_dir.join(ZINE_FILE), issue_file)?;
 
         // Create first article
-        fs::write(issue_dir.join("1-first.md"), "Hello Zine")?;
+        fs::write(issue_dir.join("1-first.md"), first_article)?;
         Ok(())
     }
 }
@@ -175,7 +272,7 @@ fn load_zine_project() -> Result<(PathBuf, Zine)> {
     Ok((source, zine))
 }
 
-pub fn new_zine_issue() -> Result<()> {
+pub fn new_zine_issue(template: Option<&str>) -> Result<()> {
     let (source, zine) = load_zine_project()?;
     let next_issue_number = zine.issues.len() + 1;
     let issue_dir = prompt_default(
@@ -196,12 +293,13 @@ pub fn new_zine_issue() -> Result<()> {
         issue_number,
         issue_title: issue_title.into(),
     };
-    scaffold.create_issue()?;
+    scaffold.create_issue(template)?;
     Ok(())
 }
 
-pub fn new_article() -> Result<()> {
+pub fn new_article(template: Option<&str>) -> Result<()> {
     let (source, zine) = load_zine_project()?;
+    let template = ScaffoldTemplate::load(&source, template, "article", TEMPLATE_ARTICLE)?;
     let latest_issue_number = zine.issues.len();
     let issue_number = prompt_default(
         "Which Issue do you want create a new article?",
@@ -213,24 +311,58 @@ pub fn new_article() -> Result<()> {
             "new-article.md".to_owned(),
         )?;
         let title = prompt_default("What is your article title?", "New Article".to_owned())?;
-        let author = git_user_name();
-
-        let issue_dir = source.join(crate::ZINE_CONTENT_DIR).join(&issue.dir);
-        // Write article file
-        fs::write(issue_dir.join(&article_file), "Hello Zine")?;
+        let author = prompt_author(&zine)?;
+        // Use the author's preferences as the defaults.
+        let author_config = zine
+            .authors
+            .iter()
+            .find(|(id, _)| normalize_author_id(id) == author)
+            .map(|(_, author)| author);
+        let topics = prompt_topics(
+            &zine,
+            author_config
+                .map(|author| author.default_topics.as_slice())
+                .unwrap_or_default(),
+        )?;
+        let cover = prompt_default(
+            "What is your article cover path? (optional)",
+            author_config
+                .and_then(|author| author.default_cover.clone())
+                .unwrap_or_default(),
+        )?;
+        let featured = prompt_default("Is it a featured article?", false)?;
 
-        // Append article to issue zine.toml
-        let article_content = render!(
-            TEMPLATE_ARTICLE,
+        let (article_content, markdown) = template.render(context! {
             title,
             author,
+            cover,
+            topics,
+            featured,
             file => article_file,
-            pub_date => helpers::format_date(&OffsetDateTime::now_utc().date()),
-        );
+            pub_date => helpers::format_date(&today()),
+            issue_number,
+            issue_slug => issue.slug,
+        })?;
+        let issue_dir = source.join(crate::ZINE_CONTENT_DIR).join(&issue.dir);
+        let article_path = issue_dir.join(&article_file);
+        // Write article file
+        fs::write(&article_path, markdown)?;
+
+        // Append article to issue zine.toml, separated by a blank line.
+        let article_content = format!("\n\n{}", article_content.trim_start());
         let mut issue_file = fs::OpenOptions::new()
             .append(true)
             .open(issue_dir.join(ZINE_FILE))?;
         issue_file.write_all(article_content.as_bytes())?;
+
+        if let Some(editor) = env::var_os("EDITOR").filter(|editor| !editor.is_empty()) {
+            if prompt_default("Open the article in $EDITOR?", true)? {
+                process::Command::new(editor)
+                    .arg(&article_path)
+                    .status()
+                    .with_context(|| "Failed to launch $EDITOR".to_string())?;
+            }
+        }
     } else {
         println!("Issue {} not found", issue_number);
     }
@@ -238,9 +370,257 @@ pub fn new_article() -> Result<()> {
     Ok(())
 }
 
+// The author profile collected by `zine new --author`.
+#[derive(Debug, Default)]
+struct AuthorProfile {
+    name: String,
+    bio: String,
+    avatar: String,
+    // The social link name and url pairs.
+    links: Vec<(String, String)>,
+}
+
+pub fn new_author(id: &str) -> Result<()> {
+    let (source, zine) = crate::locate_root_zine_folder(env::current_dir()?)?
+        .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+    let id = normalize_author_id(id);
+    validate_author_id(&id)?;
+    if zine
+        .authors
+        .keys()
+        .any(|author| normalize_author_id(author) == id)
+    {
+        bail!("Author `{id}` is already declared in [authors]");
+    }
+
+    let name = prompt_default("What is the author's name?", helpers::capitalize(&id))?;
+    let bio = prompt_default("What is the author's bio? (optional)", String::new())?;
+    let mut avatar = prompt_default(
+        "What is the author's avatar url or path? (optional)",
+        String::new(),
+    )?;
+    let links = prompt_default(
+        "What are the author's social links? (optional, e.g. github=https://github.com/zineland, twitter=...)",
+        String::new(),
+    )?;
+    if avatar.starts_with("http://") || avatar.starts_with("https://") {
+        if prompt_default("Download the avatar into the static directory?", true)? {
+            let file_name = match media::url_extension(&avatar) {
+                Some(ext) => format!("{id}.{ext}"),
+                None => id.clone(),
+            };
+            media::download(&avatar, &source.join(AVATAR_DIR).join(&file_name))?;
+            avatar = format!("/{AVATAR_DIR}/{file_name}");
+        }
+    }
+
+    let profile = AuthorProfile {
+        name,
+        bio,
+        avatar,
+        links: parse_links(&links),
+    };
+    let config_file = source.join(ZINE_FILE);
+    let config = insert_author(&fs::read_to_string(&config_file)?, &id, &profile)?;
+    fs::write(&config_file, config)?;
+    println!("Added author `{id}` into the [authors] table of `{ZINE_FILE}`.");
+    Ok(())
+}
+
+// Parse the comma separated `name=url` social links, the invalid ones are ignored.
+fn parse_links(input: &str) -> Vec<(String, String)> {
+    input
+        .split(',')
+        .filter_map(|link| {
+            let (name, url) = link.split_once('=')?;
+            let (name, url) = (name.trim(), url.trim());
+            (!name.is_empty() && !url.is_empty()).then(|| (name.to_owned(), url.to_owned()))
+        })
+        .collect()
+}
+
+// Insert the `[authors.<id>]` table of the `profile` into the root zine.toml `config`,
+// the existing formatting and comments are preserved.
+fn insert_author(config: &str, id: &str, profile: &AuthorProfile) -> Result<String> {
+    let mut document = config.parse::<Document>()?;
+    let mut table = Table::new();
+    table.insert("name", value(&profile.name));
+    for (key, field) in [("bio", &profile.bio), ("avatar", &profile.avatar)] {
+        if !field.is_empty() {
+            table.insert(key, value(field));
+        }
+    }
+    if !profile.links.is_empty() {
+        let mut links = InlineTable::new();
+        for (name, url) in &profile.links {
+            links.insert(name, url.into());
+        }
+        table.insert("links", value(links));
+    }
+
+    let authors = document.entry("authors").or_insert_with(|| {
+        // Declare the `[authors.<id>]` tables without an empty `[authors]` header.
+        let mut authors = Table::new();
+        authors.set_implicit(true);
+        Item::Table(authors)
+    });
+    match authors {
+        Item::Table(authors) => {
+            authors.insert(id, Item::Table(table));
+        }
+        Item::Value(toml_edit::Value::InlineTable(authors)) => {
+            authors.insert(id, toml_edit::Value::InlineTable(table.into_inline_table()));
+            authors.fmt();
+        }
+        _ => bail!("The `authors` of the root zine.toml isn't a table"),
+    }
+    Ok(document.to_string())
+}
+
+// Prompt for the article author until it is declared in the `[authors]` table.
+fn prompt_author(zine: &Zine) -> Result<String> {
+    let default_author = normalize_author_id(&git_user_name());
+    loop {
+        let author = normalize_author_id(&prompt_default(
+            "Who is the author of this article?",
+            default_author.clone(),
+        )?);
+        if zine.authors.is_empty()
+            || zine
+                .authors
+                .keys()
+                .any(|id| normalize_author_id(id) == author)
+        {
+            return Ok(author);
+        }
+        println!(
+            "Author `{author}` isn't declared in [authors], available authors: {}",
+            zine.authors.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+}
+
+// Prompt for the comma separated topics until all of them are declared in the `[topics]` table.
+fn prompt_topics(zine: &Zine, default_topics: &[String]) -> Result<Vec<String>> {
+    if zine.topics.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let available_topics = zine.topics.keys().cloned().collect::<Vec<_>>().join(", ");
+    loop {
+        let input = prompt_default(
+            format!("Which topics does this article belong to? ({available_topics})"),
+            default_topics.join(", "),
+        )?;
+        let topics = input
+            .split(',')
+            .map(str::trim)
+            .filter(|topic| !topic.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let unknown_topics = topics
+            .iter()
+            .filter(|topic| !zine.topics.contains_key(topic.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if unknown_topics.is_empty() {
+            return Ok(topics);
+        }
+        println!(
+            "Topic `{}` isn't declared in [topics]",
+            unknown_topics.join("`, `")
+        );
+    }
+}
+
+// The date of today in the local timezone, fallback to UTC if the local offset is unknown.
+fn today() -> Date {
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date()
+}
+
 fn git_user_name() -> String {
     helpers::run_command("git", &["config", "user.name"])
         .ok()
         .unwrap_or_default()
         .replace(' ', "_")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use minijinja::context;
+
+    use super::{insert_author, parse_links, AuthorProfile, ScaffoldTemplate, TEMPLATE_ARTICLE};
+
+    #[test]
+    fn test_parse_links() {
+        assert_eq!(
+            parse_links("github=https://github.com/alice, invalid,  blog = https://alice.dev "),
+            vec![
+                (
+                    String::from("github"),
+                    String::from("https://github.com/alice")
+                ),
+                (String::from("blog"), String::from("https://alice.dev")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insert_author() {
+        let profile = AuthorProfile {
+            name: String::from("Alice"),
+            avatar: String::from("/static/avatars/alice.png"),
+            links: vec![(
+                String::from("github"),
+                String::from("https://github.com/alice"),
+            )],
+            ..Default::default()
+        };
+        let config = "[site]\nname = \"Zine\"\n\n[authors]\n# The editor.\nbob = { name = \"Bob\" }\n\n[topics]\nrust = {}\n";
+        assert_eq!(
+            insert_author(config, "alice", &profile).unwrap(),
+            "[site]\nname = \"Zine\"\n\n[authors]\n# The editor.\nbob = { name = \"Bob\" }\n\n\
+            [authors.alice]\nname = \"Alice\"\navatar = \"/static/avatars/alice.png\"\n\
+            links = { github = \"https://github.com/alice\" }\n\n[topics]\nrust = {}\n"
+        );
+
+        let config = insert_author("[site]\nname = \"Zine\"\n", "alice", &profile).unwrap();
+        assert!(config.ends_with("\n[authors.alice]\nname = \"Alice\"\navatar = \"/static/avatars/alice.png\"\nlinks = { github = \"https://github.com/alice\" }\n"));
+        assert!(!config.contains("[authors]\n"));
+    }
+
+    #[test]
+    fn test_scaffold_template() {
+        let source = std::env::temp_dir().join("zine-scaffold-template");
+        fs::create_dir_all(source.join("scaffolds")).unwrap();
+        fs::write(
+            source.join("scaffolds/review.toml"),
+            "[[article]]\nfile = \"{{ file }}\"\ntitle = \"Review: {{ title }}\"\n",
+        )
+        .unwrap();
+        fs::write(
+            source.join("scaffolds/review.md"),
+            "# {{ title }}\n\n## Verdict\n",
+        )
+        .unwrap();
+
+        let template = ScaffoldTemplate::load(&source, Some("review"), "article", "").unwrap();
+        let (toml, markdown) = template
+            .render(context! { file => "dune.md", title => "Dune" })
+            .unwrap();
+        assert_eq!(
+            toml,
+            "[[article]]\nfile = \"dune.md\"\ntitle = \"Review: Dune\"\n"
+        );
+        assert_eq!(markdown, "# Dune\n\n## Verdict\n");
+
+        let template = ScaffoldTemplate::load(&source, None, "article", TEMPLATE_ARTICLE).unwrap();
+        assert_eq!(template.toml, TEMPLATE_ARTICLE);
+        assert_eq!(template.markdown, "Hello Zine");
+        assert!(ScaffoldTemplate::load(&source, Some("missing"), "article", "").is_err());
+    }
+}
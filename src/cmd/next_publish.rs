@@ -0,0 +1,58 @@
+use std::env;
+
+use anyhow::{Context as _, Result};
+use clap::{Arg, ArgAction, Command};
+use genkit::Cmd;
+use serde_json::json;
+use time::{format_description::well_known::Rfc3339, Time};
+
+use crate::engine;
+
+pub struct NextPublishCmd;
+
+#[async_trait::async_trait]
+impl Cmd for NextPublishCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("next-publish")
+            .args([Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the result in JSON format.")])
+            .about("Print the time when the built site changes next, e.g. an article expires")
+            .after_help(
+                "The site changes when an article or page expires, the announcement starts or ends, \
+                or an event is no longer upcoming. Print nothing (or `null` in JSON format) if nothing \
+                changes later. CI can schedule the next build at the printed time rather than polling.",
+            )
+    }
+
+    async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> Result<()> {
+        let (source, mut zine) = crate::locate_root_zine_folder(env::current_dir()?)?
+            .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        zine.parse_issue_from_dir(&source)?;
+
+        let today = engine::frozen_now().date();
+        let next = zine
+            .next_change_date(today)
+            .map(|(date, source)| {
+                // The site changes since the start of that day in UTC.
+                let time = date
+                    .with_time(Time::MIDNIGHT)
+                    .assume_utc()
+                    .format(&Rfc3339)?;
+                anyhow::Ok((time, source))
+            })
+            .transpose()?;
+
+        if arg_matches.get_flag("json") {
+            let output = match next {
+                Some((time, source)) => json!({ "next_publish": time, "source": source }),
+                None => json!({ "next_publish": null }),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        } else if let Some((time, _)) = next {
+            println!("{time}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    env, fs,
+    path::Path,
+    process::{self, Stdio},
+};
+
+use anyhow::{bail, ensure, Context as _, Result};
+use clap::{Arg, ArgAction, Command};
+use genkit::Cmd;
+
+use crate::{
+    engine,
+    entity::{DeployConfig, DeployProvider, Entity},
+    output,
+};
+
+// The git directory (relative to the zine root) to commit the GitHub Pages branch.
+static PAGES_GIT_DIR: &str = ".zine-cache/pages.git";
+
+pub struct PublishCmd;
+
+#[async_trait::async_trait]
+impl Cmd for PublishCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("publish")
+            .args([
+                Arg::new("dest")
+                    .long("dest")
+                    .short('d')
+                    .default_value("build")
+                    .help("The build directory of the site."),
+                Arg::new("skip-build")
+                    .long("skip-build")
+                    .action(ArgAction::SetTrue)
+                    .help("Publish the existing build without building the site again."),
+                Arg::new("message")
+                    .long("message")
+                    .short('m')
+                    .help("The commit message of the GitHub Pages branch."),
+                Arg::new("dry-run")
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue)
+                    .help("Print the changes of the existing build without building or publishing."),
+            ])
+            .about("Build the site and publish it to the target of the [deploy] table")
+            .after_help(
+                "The `github-pages` provider commits the build into the `branch` and pushes it \
+                to the git `remote`. The `s3` provider uploads the changed files like `zine deploy`. \
+                The `rsync` provider syncs the build to the `target`, e.g. `user@host:/var/www/zine` over SSH.",
+            )
+    }
+
+    async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> Result<()> {
+        let (source, zine) = crate::locate_root_zine_folder(env::current_dir()?)?
+            .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        let Some(mut config) = zine.deploy_config.clone() else {
+            bail!("The [deploy] table is missing in the root zine.toml");
+        };
+        config.parse(&source)?;
+
+        let dest = arg_matches.get_one::<String>("dest").unwrap();
+        // A dry run previews the changes of the existing build.
+        let dry_run = arg_matches.get_flag("dry-run");
+        if !arg_matches.get_flag("skip-build") && !dry_run {
+            build_site(&source, dest)?;
+        }
+        let build_dir = source.join(dest);
+        ensure!(
+            build_dir.exists(),
+            "`{}` not found, please run `zine build` first.",
+            build_dir.display()
+        );
+
+        match config.provider {
+            DeployProvider::GithubPages => {
+                let message = match arg_matches.get_one::<String>("message") {
+                    Some(message) => message.clone(),
+                    None => format!("Publish {}", zine.site.name),
+                };
+                publish_github_pages(&source, &build_dir, &config, &message, dry_run)?;
+            }
+            DeployProvider::S3 => {
+                #[cfg(feature = "s3")]
+                super::deploy::upload(
+                    &source,
+                    &build_dir,
+                    config.target.as_deref().unwrap_or_default(),
+                    config.delete,
+                    dry_run,
+                    zine.build_config.cache_bust.then_some(&zine.site),
+                )
+                .await?;
+                #[cfg(not(feature = "s3"))]
+                bail!("The `s3` provider of [deploy] requires the `s3` feature of zine");
+            }
+            DeployProvider::Rsync => {
+                let target = config.target.as_deref().unwrap_or_default();
+                run(process::Command::new("rsync").args(rsync_args(
+                    &build_dir,
+                    target,
+                    config.delete,
+                    dry_run,
+                )))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Build the site into the `dest` directory by the `zine build` command.
+//
+// The build variables set by the global arguments are cleared, so the
+// child builds the whole site into `dest`.
+fn build_site(source: &Path, dest: &str) -> Result<()> {
+    let zine = env::current_exe()?;
+    run(process::Command::new(zine)
+        .current_dir(source)
+        .env_remove(output::BUILD_ARCHIVE_ENV)
+        .env_remove(engine::BUILD_ONLY_ENV)
+        .env_remove(engine::BUILD_DRY_RUN_ENV)
+        .args(["build", ".", dest]))
+    .with_context(|| "Failed to build the site".to_string())
+}
+
+// Commit the `build_dir` into the GitHub Pages branch of a standalone git directory,
+// then push the branch to the remote.
+//
+// The existing branch is fetched first, so the history of the branch is kept.
+fn publish_github_pages(
+    source: &Path,
+    build_dir: &Path,
+    config: &DeployConfig,
+    message: &str,
+    dry_run: bool,
+) -> Result<()> {
+    let remote_url = output(
+        process::Command::new("git")
+            .args(["remote", "get-url", &config.remote])
+            .current_dir(source),
+    )
+    .with_context(|| format!("Failed to get the url of git remote `{}`", config.remote))?;
+
+    // The build directory is untouched in a dry run.
+    if !dry_run {
+        if let Some(cname) = &config.cname {
+            fs::write(build_dir.join("CNAME"), format!("{}\n", cname.trim()))?;
+        }
+        // Serve the files starting with underscores, e.g. `_headers`.
+        fs::write(build_dir.join(".nojekyll"), "")?;
+    }
+
+    let git_dir = source.join(PAGES_GIT_DIR);
+    if !git_dir.exists() {
+        run(process::Command::new("git")
+            .args(["init", "--quiet", "--bare"])
+            .arg(&git_dir))?;
+    }
+    let git = || {
+        let mut command = process::Command::new("git");
+        command
+            .arg("--git-dir")
+            .arg(&git_dir)
+            .arg("--work-tree")
+            .arg(build_dir);
+        command
+    };
+
+    let branch_ref = format!("refs/heads/{}", config.branch);
+    let fetched = git()
+        .args([
+            "fetch",
+            "--quiet",
+            "--update-head-ok",
+            "--depth",
+            "1",
+            &remote_url,
+        ])
+        .arg(format!("+{branch_ref}:{branch_ref}"))
+        .stderr(Stdio::null())
+        .status()?
+        .success();
+    if fetched {
+        // Base on the remote branch, while keeping the build files untouched.
+        run(git().args(["symbolic-ref", "HEAD", &branch_ref]))?;
+        run(git().args(["reset", "--quiet", "--mixed", &branch_ref]))?;
+    } else {
+        println!(
+            "The branch `{}` is missing in `{}`, creating it.",
+            config.branch, config.remote
+        );
+        // Start an orphan branch, drop the branch and index of the previous publish if any.
+        git().args(["update-ref", "-d", &branch_ref]).status()?;
+        run(git().args(["symbolic-ref", "HEAD", &branch_ref]))?;
+        fs::remove_file(git_dir.join("index")).ok();
+    }
+
+    run(git().args(["add", "--all"]))?;
+    let changes = output(git().args(["status", "--porcelain"]))?;
+    if changes.is_empty() {
+        println!("Nothing changed since the last publish.");
+        return Ok(());
+    }
+    println!("{changes}");
+    if dry_run {
+        return Ok(());
+    }
+
+    run(git().args(["commit", "--quiet", "--message", message]))?;
+    run(git()
+        .args(["push", "--quiet", &remote_url])
+        .arg(format!("{branch_ref}:{branch_ref}")))?;
+    println!(
+        "Published to the `{}` branch of `{}`.",
+        config.branch, config.remote
+    );
+    Ok(())
+}
+
+// The arguments of rsync to sync the contents of `build_dir` into the `target`.
+fn rsync_args(build_dir: &Path, target: &str, delete: bool, dry_run: bool) -> Vec<String> {
+    let mut args = vec![String::from("--archive"), String::from("--verbose")];
+    if delete {
+        args.push(String::from("--delete"));
+    }
+    if dry_run {
+        args.push(String::from("--dry-run"));
+    }
+    // The trailing slash syncs the directory contents rather than the directory itself.
+    args.push(format!(
+        "{}/",
+        build_dir.display().to_string().trim_end_matches('/')
+    ));
+    args.push(target.to_owned());
+    args
+}
+
+// Run the `command`, fail if it exits unsuccessfully.
+fn run(command: &mut process::Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run `{program}`"))?;
+    ensure!(status.success(), "`{program}` exited with {status}");
+    Ok(())
+}
+
+// Run the `command` and get the trimmed stdout, fail if it exits unsuccessfully.
+fn output(command: &mut process::Command) -> Result<String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run `{program}`"))?;
+    ensure!(
+        output.status.success(),
+        "`{program}` exited with {}",
+        output.status
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use test_case::test_case;
+
+    use super::rsync_args;
+
+    #[test_case("build", false, false, &["--archive", "--verbose", "build/", "host:/www"]; "default")]
+    #[test_case("build/", true, true, &["--archive", "--verbose", "--delete", "--dry-run", "build/", "host:/www"]; "delete and dry run")]
+    fn test_rsync_args(build_dir: &str, delete: bool, dry_run: bool, expected: &[&str]) {
+        assert_eq!(
+            rsync_args(Path::new(build_dir), "host:/www", delete, dry_run),
+            expected
+        );
+    }
+}
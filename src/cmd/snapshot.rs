@@ -0,0 +1,75 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{ensure, Context as _, Result};
+use clap::{Arg, Command};
+use genkit::Cmd;
+
+use crate::{
+    engine,
+    snapshot::{SnapshotManifest, MANIFEST_FILE},
+    ZINE_FILE,
+};
+
+pub struct SnapshotCmd;
+
+#[async_trait::async_trait]
+impl Cmd for SnapshotCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("snapshot")
+            .args([
+                Arg::new("dest")
+                    .long("dest")
+                    .short('d')
+                    .default_value("build")
+                    .help("The build directory of the site."),
+                Arg::new("output")
+                    .long("output")
+                    .short('o')
+                    .help("The output archive path, default to `snapshot-<date>-<hash>.tar`."),
+            ])
+            .about("Archive the built site with a manifest of content hashes")
+    }
+
+    async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> Result<()> {
+        let (source, _) = crate::locate_root_zine_folder(env::current_dir()?)?
+            .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        let build_dir = source.join(arg_matches.get_one::<String>("dest").unwrap());
+        ensure!(
+            build_dir.exists(),
+            "`{}` not found, please run `zine build` first.",
+            build_dir.display()
+        );
+
+        let config = fs::read_to_string(source.join(ZINE_FILE))?;
+        let manifest = SnapshotManifest::new(&build_dir, config)?;
+        let address = manifest.content_address();
+
+        let output = match arg_matches.get_one::<String>("output") {
+            Some(output) => PathBuf::from(output),
+            None => source.join(format!(
+                "snapshot-{}-{}.tar",
+                genkit::helpers::format_date(&engine::frozen_now().date()),
+                &address[..12]
+            )),
+        };
+
+        let mut archive = tar::Builder::new(fs::File::create(&output)?);
+        let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(&mut header, MANIFEST_FILE, manifest_json.as_slice())?;
+        for path in manifest.files.keys() {
+            archive.append_path_with_name(build_dir.join(path), format!("site/{path}"))?;
+        }
+        archive.finish()?;
+
+        println!(
+            "Snapshot {} files into `{}`, the content address is {address}.",
+            manifest.files.len(),
+            output.display()
+        );
+        Ok(())
+    }
+}
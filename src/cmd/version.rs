@@ -0,0 +1,60 @@
+use clap::{Arg, ArgAction, Command};
+use genkit::Cmd;
+
+// The short git revision this binary was built from.
+static GIT_REV: &str = env!("ZINE_GIT_REV");
+static BUILD_TARGET: &str = env!("ZINE_BUILD_TARGET");
+static BUILD_PROFILE: &str = env!("ZINE_BUILD_PROFILE");
+static GENKIT_VERSION: &str = env!("ZINE_GENKIT_VERSION");
+// The content hash of builtin templates, static and locale files.
+static TEMPLATE_HASH: &str = env!("ZINE_TEMPLATE_HASH");
+// The cargo features enabled in this build, joined by commas.
+static BUILD_FEATURES: &str = env!("ZINE_BUILD_FEATURES");
+
+pub struct VersionCmd;
+
+#[async_trait::async_trait]
+impl Cmd for VersionCmd {
+    fn on_init(&self) -> clap::Command {
+        Command::new("version")
+            .args([Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .action(ArgAction::SetTrue)
+                .help("Print the build information.")])
+            .about("Print the version of zine")
+    }
+
+    async fn on_execute(&self, arg_matches: &clap::ArgMatches) -> anyhow::Result<()> {
+        let version = clap::crate_version!();
+        if !arg_matches.get_flag("verbose") {
+            println!("zine {version} ({GIT_REV})");
+            return Ok(());
+        }
+
+        let features = enabled_features();
+        println!("zine {version}");
+        println!("git revision: {GIT_REV}");
+        println!("build target: {BUILD_TARGET}");
+        println!("build profile: {BUILD_PROFILE}");
+        println!("genkit version: {GENKIT_VERSION}");
+        println!(
+            "features: {}",
+            if features.is_empty() {
+                String::from("(none)")
+            } else {
+                features.join(", ")
+            }
+        );
+        println!("template hash: {TEMPLATE_HASH}");
+        Ok(())
+    }
+}
+
+// The cargo features enabled in this build.
+fn enabled_features() -> Vec<&'static str> {
+    BUILD_FEATURES
+        .split(',')
+        .filter(|feature| !feature.is_empty())
+        .collect()
+}
@@ -0,0 +1,277 @@
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::markdown;
+
+/// The built-in fenced blocks of zine, which no cross-posting platform understands.
+static BUILTIN_BLOCKS: &[&str] = &["urlpreview", "callout", "quote"];
+
+/// The maximum number of tags of a dev.to post.
+const DEVTO_MAX_TAGS: usize = 4;
+
+/// The cross-posting platform of the export bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Devto,
+    Medium,
+}
+
+impl Target {
+    /// All the targets, in the name of the `--target` option.
+    pub const NAMES: [&'static str; 2] = ["devto", "medium"];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "devto" => Ok(Target::Devto),
+            "medium" => Ok(Target::Medium),
+            _ => bail!("Unknown export target `{name}`, expected one of: devto, medium"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Devto => "devto",
+            Target::Medium => "medium",
+        }
+    }
+}
+
+/// An article ready to be cross-posted.
+#[derive(Debug)]
+pub struct CrossPost {
+    pub title: String,
+    pub description: String,
+    pub topics: Vec<String>,
+    /// The absolute url of the original article.
+    pub canonical_url: String,
+    /// The absolute cover image url, if any.
+    pub cover_image: Option<String>,
+    /// The portable markdown, see [`portable_markdown`].
+    pub markdown: String,
+}
+
+impl CrossPost {
+    /// Render the post in the markdown flavor of `target`.
+    pub fn render(&self, target: Target) -> String {
+        match target {
+            Target::Devto => {
+                let mut front_matter = vec![
+                    format!("title: {}", yaml_string(&self.title)),
+                    String::from("published: false"),
+                    format!("description: {}", yaml_string(&self.description)),
+                ];
+                let tags = devto_tags(&self.topics);
+                if !tags.is_empty() {
+                    front_matter.push(format!("tags: {}", tags.join(", ")));
+                }
+                front_matter.push(format!("canonical_url: {}", self.canonical_url));
+                if let Some(cover) = &self.cover_image {
+                    front_matter.push(format!("cover_image: {cover}"));
+                }
+                format!(
+                    "---\n{}\n---\n\n{}\n",
+                    front_matter.join("\n"),
+                    self.markdown.trim()
+                )
+            }
+            // Medium's importer doesn't support the front matter, the canonical link
+            // is set from the trailing note when importing the story.
+            Target::Medium => format!(
+                "# {}\n\n{}\n\n---\n\n*Originally published at <{}>.*\n",
+                self.title,
+                self.markdown.trim(),
+                self.canonical_url
+            ),
+        }
+    }
+}
+
+// Quote the `value` as a YAML string, a JSON string is a valid YAML string.
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+// The dev.to tags of `topics`, which are lowercase alphanumerics.
+fn devto_tags(topics: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for topic in topics {
+        let tag = topic
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags.truncate(DEVTO_MAX_TAGS);
+    tags
+}
+
+/// Make the zine flavored `markdown` portable to other platforms:
+///
+/// - the built-in and theme `blocks` fenced blocks are downgraded to plain markdown
+/// - the inline code resolved by `resolve_code`, e.g. `` `@alice` ``, are replaced with links
+/// - the root-relative image and link urls are rewritten to absolute urls of `site_url`
+pub fn portable_markdown(
+    markdown: &str,
+    site_url: &str,
+    blocks: &[&str],
+    resolve_code: impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let site_url = site_url.trim_end_matches('/');
+    let downgraded = markdown::replace_fenced_blocks(markdown, |name, _, body| {
+        if BUILTIN_BLOCKS.contains(&name) || blocks.contains(&name) {
+            Some(Ok(downgrade_block(name, body)))
+        } else {
+            None
+        }
+    })?;
+
+    let html_url = Regex::new(r#"(src|href)="(/[^/"][^"]*|/)""#)?;
+    let mut replacements: Vec<(Range<usize>, String)> = vec![];
+    for (event, range) in Parser::new(&downgraded).into_offset_iter() {
+        let source = &downgraded[range.clone()];
+        let replacement = match event {
+            Event::Code(code) => resolve_code(&code),
+            Event::Start(Tag::Image(_, url, _) | Tag::Link(_, url, _))
+                if is_root_relative(&url) =>
+            {
+                Some(source.replacen(&format!("({url}"), &format!("({site_url}{url}"), 1))
+            }
+            Event::Html(html) if html_url.is_match(&html) => Some(
+                html_url
+                    .replace_all(source, |caps: &regex::Captures| {
+                        format!(r#"{}="{site_url}{}""#, &caps[1], &caps[2])
+                    })
+                    .into_owned(),
+            ),
+            _ => None,
+        };
+        // Skip the nested events of a replaced link, e.g. the inline code of the link text.
+        let nested = matches!(replacements.last(), Some((last, _)) if last.end > range.start);
+        if let Some(replacement) = replacement.filter(|replacement| replacement != source) {
+            if !nested {
+                replacements.push((range, replacement));
+            }
+        }
+    }
+
+    let mut portable = String::new();
+    let mut offset = 0;
+    for (range, replacement) in replacements {
+        portable.push_str(&downgraded[offset..range.start]);
+        portable.push_str(&replacement);
+        offset = range.end;
+    }
+    portable.push_str(&downgraded[offset..]);
+    Ok(portable)
+}
+
+// Whether the `url` is relative to the site root, e.g. `/static/cover.png`.
+fn is_root_relative(url: &str) -> bool {
+    url.starts_with('/') && !url.starts_with("//")
+}
+
+// The `quote` block body, e.g. `content = "..."` and `author = "..."`.
+#[derive(Deserialize)]
+struct QuoteBlock {
+    content: String,
+    author: Option<String>,
+}
+
+// Downgrade the fenced block to the plain markdown.
+fn downgrade_block(name: &str, body: &str) -> String {
+    match name {
+        "urlpreview" => format!("<{}>", body.trim()),
+        "quote" => match toml::from_str::<QuoteBlock>(body) {
+            Ok(QuoteBlock { content, author }) => {
+                let mut quote = blockquote(&content);
+                if let Some(author) = author {
+                    quote.push_str(&format!("\n>\n> — {author}"));
+                }
+                quote
+            }
+            Err(_) => blockquote(body),
+        },
+        // The callout and theme blocks keep the body only.
+        _ => blockquote(body),
+    }
+}
+
+fn blockquote(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::from(">")
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{devto_tags, portable_markdown, CrossPost, Target};
+
+    fn resolve_code(code: &str) -> Option<String> {
+        (code == "@alice").then(|| String::from("[Alice](https://zine.example/@alice)"))
+    }
+
+    #[test_case("![cat](/static/cat.png)", "![cat](https://zine.example/static/cat.png)"; "image")]
+    #[test_case("[next](/issue-1/next \"Next\")", "[next](https://zine.example/issue-1/next \"Next\")"; "link")]
+    #[test_case("[cdn](//cdn.example.com/a.png)", "[cdn](//cdn.example.com/a.png)"; "protocol relative")]
+    #[test_case("<img src=\"/static/a.png\">", "<img src=\"https://zine.example/static/a.png\">"; "html")]
+    #[test_case("By `@alice` and `@bob`", "By [Alice](https://zine.example/@alice) and `@bob`"; "inline code")]
+    #[test_case("```rust\nlet a = \"/b\";\n```", "```rust\nlet a = \"/b\";\n```"; "code block")]
+    fn test_portable_markdown(markdown: &str, expected: &str) {
+        let portable =
+            portable_markdown(markdown, "https://zine.example/", &[], resolve_code).unwrap();
+        assert_eq!(portable, expected);
+    }
+
+    #[test]
+    fn test_downgrade_blocks() {
+        let markdown = "Hi\n\n```urlpreview\nhttps://example.com\n```\n\n```recipe servings=2\nEggs\n\nMilk\n```\n\n```quote\ncontent = \"Stay hungry\"\nauthor = \"Steve\"\n```\n";
+        let portable =
+            portable_markdown(markdown, "https://zine.example", &["recipe"], resolve_code).unwrap();
+        assert!(portable.contains("<https://example.com>"));
+        assert!(portable.contains("> Eggs\n>\n> Milk"));
+        assert!(portable.contains("> Stay hungry\n>\n> — Steve"));
+        assert!(!portable.contains("```"));
+    }
+
+    #[test]
+    fn test_devto_tags() {
+        let topics = ["Rust", "web-assembly", "rust", "c++", "go", "zig"].map(String::from);
+        assert_eq!(devto_tags(&topics), ["rust", "webassembly", "c", "go"]);
+    }
+
+    #[test]
+    fn test_render_devto() {
+        let post = CrossPost {
+            title: String::from("Hello \"zine\""),
+            description: String::from("A zine"),
+            topics: vec![String::from("rust")],
+            canonical_url: String::from("https://zine.example/issue-1/hello"),
+            cover_image: None,
+            markdown: String::from("Hello\n"),
+        };
+        assert_eq!(
+            post.render(Target::Devto),
+            "---\ntitle: \"Hello \\\"zine\\\"\"\npublished: false\ndescription: \"A zine\"\ntags: rust\ncanonical_url: https://zine.example/issue-1/hello\n---\n\nHello\n"
+        );
+        assert!(post
+            .render(Target::Medium)
+            .ends_with("*Originally published at <https://zine.example/issue-1/hello>.*\n"));
+    }
+}
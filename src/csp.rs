@@ -0,0 +1,360 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::Path,
+};
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lol_html::{element, text, HtmlRewriter, Settings};
+use parking_lot::{const_mutex, Mutex};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    entity::{CommentProvider, CspConfig, CspMode},
+    output,
+};
+
+// The policy of all pages rendered in this build, which is written into
+// the `_headers` file in the `headers` mode.
+static SITE_POLICY: Mutex<Option<Policy>> = const_mutex(None);
+
+/// The `_headers` file of the `headers` mode, relative to the build directory.
+pub static HEADERS_FILE: &str = "_headers";
+
+/// A content security policy, the directive and sources pairs.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Policy {
+    directives: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Policy {
+    // The baseline policy, all pages load the assets of this site.
+    fn baseline() -> Self {
+        let mut policy = Policy::default();
+        for directive in ["default-src", "script-src", "img-src", "base-uri"] {
+            policy.add(directive, "'self'");
+        }
+        // The templates use the inline style attributes.
+        policy.add("style-src", "'self'");
+        policy.add("style-src", "'unsafe-inline'");
+        policy.add("object-src", "'none'");
+        policy
+    }
+
+    pub fn add(&mut self, directive: &str, source: impl Into<String>) {
+        self.directives
+            .entry(directive.to_owned())
+            .or_default()
+            .insert(source.into());
+    }
+
+    pub fn merge(&mut self, other: Policy) {
+        for (directive, sources) in other.directives {
+            self.directives
+                .entry(directive)
+                .or_default()
+                .extend(sources);
+        }
+    }
+
+    /// Compute the policy from the sources the `html` actually loads,
+    /// along with the `extra` sources which can't be detected from the html.
+    pub fn from_html(html: &[u8], extra: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        let mut policy = Policy::baseline();
+        for (directive, sources) in extra {
+            for source in sources {
+                policy.add(directive, source.as_str());
+            }
+        }
+
+        let policy = RefCell::new(policy);
+        // Whether the current `<script>` is an inline script, rather than
+        // an external script or a data block such as JSON-LD.
+        let inline_script = Cell::new(false);
+        let mut script = String::new();
+        let mut rewriter = HtmlRewriter::new(
+            Settings {
+                element_content_handlers: vec![
+                    element!("script", |el| {
+                        let executable = el
+                            .get_attribute("type")
+                            .map(|kind| matches!(kind.as_str(), "" | "module" | "text/javascript"))
+                            .unwrap_or(true);
+                        inline_script.set(executable && !el.has_attribute("src"));
+                        if let Some(src) = el.get_attribute("src") {
+                            add_url(&mut policy.borrow_mut(), "script-src", &src);
+                        }
+                        Ok(())
+                    }),
+                    text!("script", |chunk| {
+                        if inline_script.get() {
+                            script.push_str(chunk.as_str());
+                            if chunk.last_in_text_node() {
+                                policy.borrow_mut().add("script-src", hash_source(&script));
+                                script.clear();
+                            }
+                        }
+                        Ok(())
+                    }),
+                    element!("*", |el| {
+                        // The inline event handlers require the `'unsafe-hashes'`.
+                        for attr in el.attributes() {
+                            if attr.name().starts_with("on") {
+                                let mut policy = policy.borrow_mut();
+                                policy.add("script-src", "'unsafe-hashes'");
+                                policy.add("script-src", hash_source(&attr.value()));
+                            }
+                        }
+                        Ok(())
+                    }),
+                    element!("link[rel=stylesheet][href]", |el| {
+                        if let Some(href) = el.get_attribute("href") {
+                            add_url(&mut policy.borrow_mut(), "style-src", &href);
+                        }
+                        Ok(())
+                    }),
+                    element!("img[src], video[poster]", |el| {
+                        let src = el
+                            .get_attribute("src")
+                            .or_else(|| el.get_attribute("poster"));
+                        if let Some(src) = src {
+                            add_url(&mut policy.borrow_mut(), "img-src", &src);
+                        }
+                        Ok(())
+                    }),
+                    element!("img[srcset], picture > source[srcset]", |el| {
+                        if let Some(srcset) = el.get_attribute("srcset") {
+                            for candidate in srcset.split(',') {
+                                if let Some(url) = candidate.split_whitespace().next() {
+                                    add_url(&mut policy.borrow_mut(), "img-src", url);
+                                }
+                            }
+                        }
+                        Ok(())
+                    }),
+                    element!(
+                        "audio[src], video[src], audio > source[src], video > source[src]",
+                        |el| {
+                            if let Some(src) = el.get_attribute("src") {
+                                add_url(&mut policy.borrow_mut(), "media-src", &src);
+                            }
+                            Ok(())
+                        }
+                    ),
+                    element!("iframe[src]", |el| {
+                        if let Some(src) = el.get_attribute("src") {
+                            add_url(&mut policy.borrow_mut(), "frame-src", &src);
+                        }
+                        Ok(())
+                    }),
+                    element!("form[action]", |el| {
+                        if let Some(action) = el.get_attribute("action") {
+                            add_url(&mut policy.borrow_mut(), "form-action", &action);
+                        }
+                        Ok(())
+                    }),
+                    // The comment embeds load the frames and more by scripts.
+                    element!("div.zine-comments[data-provider]", |el| {
+                        let provider = el
+                            .get_attribute("data-provider")
+                            .and_then(|name| CommentProvider::from_name(&name));
+                        if let Some(provider) = provider {
+                            let mut policy = policy.borrow_mut();
+                            for (directive, source) in provider.csp_sources() {
+                                policy.add(directive, *source);
+                            }
+                        }
+                        Ok(())
+                    }),
+                ],
+                ..Settings::default()
+            },
+            |_: &[u8]| {},
+        );
+        rewriter.write(html)?;
+        rewriter.end()?;
+        Ok(policy.into_inner())
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let directives = self
+            .directives
+            .iter()
+            .map(|(directive, sources)| {
+                let sources = sources.iter().map(String::as_str).collect::<Vec<_>>();
+                format!("{directive} {}", sources.join(" "))
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", directives.join("; "))
+    }
+}
+
+// Add the source of `url` into the `directive`, the relative urls are `'self'`.
+fn add_url(policy: &mut Policy, directive: &str, url: &str) {
+    if let Some(source) = url_source(url) {
+        policy.add(directive, source);
+    }
+}
+
+// Get the CSP source expression of the `url`, e.g. `https://cdn.example.com`.
+fn url_source(url: &str) -> Option<String> {
+    let url = url.trim();
+    if url.is_empty() || url.starts_with('#') || url.starts_with("javascript:") {
+        return None;
+    }
+    for scheme in ["data:", "blob:"] {
+        if url.starts_with(scheme) {
+            return Some(scheme.to_owned());
+        }
+    }
+    let (scheme, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        ("https://", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        ("http://", rest)
+    } else if let Some(rest) = url.strip_prefix("//") {
+        // The protocol-relative url.
+        ("", rest)
+    } else {
+        return Some(String::from("'self'"));
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    (!host.is_empty()).then(|| format!("{scheme}{host}"))
+}
+
+// The `'sha256-<base64>'` source of the inline `script`.
+fn hash_source(script: &str) -> String {
+    format!(
+        "'sha256-{}'",
+        STANDARD.encode(Sha256::digest(script.as_bytes()))
+    )
+}
+
+/// Apply the content security policy of `config` to the page `html`,
+/// which is either injected into the `<head>` as a meta tag, or merged
+/// into the site policy of the `_headers` file.
+pub fn apply(html: Vec<u8>, config: &CspConfig) -> Result<Vec<u8>> {
+    let policy = Policy::from_html(&html, &config.extra)?;
+    match config.mode {
+        CspMode::Meta => {
+            let meta = format!(
+                r#"<meta http-equiv="Content-Security-Policy" content="{}">"#,
+                policy.to_string().replace('"', "&quot;")
+            );
+            let mut output = vec![];
+            let mut rewriter = HtmlRewriter::new(
+                Settings {
+                    element_content_handlers: vec![element!("head", |el| {
+                        el.prepend(&meta, lol_html::html_content::ContentType::Html);
+                        Ok(())
+                    })],
+                    ..Settings::default()
+                },
+                |chunk: &[u8]| output.extend_from_slice(chunk),
+            );
+            rewriter.write(&html)?;
+            rewriter.end()?;
+            Ok(output)
+        }
+        CspMode::Headers => {
+            SITE_POLICY
+                .lock()
+                .get_or_insert_with(Policy::default)
+                .merge(policy);
+            Ok(html)
+        }
+    }
+}
+
+/// Write the site policy of all rendered pages into the `_headers` file of `dest`,
+/// in the format of Netlify and Cloudflare Pages.
+pub fn write_headers(dest: &Path) -> Result<()> {
+    let Some(policy) = SITE_POLICY.lock().take() else {
+        return Ok(());
+    };
+    output::write(
+        &dest.join(HEADERS_FILE),
+        format!("/*\n  Content-Security-Policy: {policy}\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use test_case::test_case;
+
+    use crate::entity::{CommentProvider, CommentsConfig};
+
+    use super::{url_source, Policy};
+
+    #[test_case("/static/zine.js", Some("'self'"))]
+    #[test_case("https://cdn.example.com/zine.js?v=1", Some("https://cdn.example.com"))]
+    #[test_case("//giscus.app/client.js", Some("giscus.app"))]
+    #[test_case("data:image/png;base64,AAAA", Some("data:"))]
+    #[test_case("#top", None)]
+    fn test_url_source(url: &str, expected: Option<&str>) {
+        assert_eq!(url_source(url).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_policy_from_html() {
+        let html = r#"<html><head>
+            <script src="https://giscus.app/client.js"></script>
+            <script type="application/ld+json">{}</script>
+            <script>alert(1)</script>
+            </head><body>
+            <img src="https://images.example.com/cat.png">
+            <iframe src="https://www.youtube.com/embed/1"></iframe>
+            <button onclick="this.remove()">×</button>
+            </body></html>"#;
+        let extra = BTreeMap::from([(
+            String::from("connect-src"),
+            vec![String::from("https://plausible.io")],
+        )]);
+        let policy = Policy::from_html(html.as_bytes(), &extra)
+            .unwrap()
+            .to_string();
+        assert!(policy.contains("connect-src https://plausible.io;"));
+        assert!(policy.contains("frame-src https://www.youtube.com;"));
+        assert!(policy.contains("img-src 'self' https://images.example.com;"));
+        assert!(policy.contains("script-src 'self' 'sha256-"));
+        assert!(policy.contains("'unsafe-hashes' https://giscus.app;"));
+        // The inline script and the event handler, the JSON-LD block isn't hashed.
+        assert_eq!(policy.matches("'sha256-").count(), 2);
+    }
+
+    #[test_case(CommentProvider::Giscus, "frame-src https://giscus.app;")]
+    #[test_case(CommentProvider::Utterances, "frame-src https://utteranc.es;")]
+    #[test_case(CommentProvider::Disqus, "frame-src https://disqus.com;")]
+    fn test_comment_provider_policy(provider: CommentProvider, expected: &str) {
+        let config = CommentsConfig {
+            provider,
+            repo: Some(String::from("zineland/zine")),
+            repo_id: Some(String::from("R_kgDOxxxx")),
+            category: Some(String::from("Comments")),
+            category_id: Some(String::from("DIC_kwDOxxxx")),
+            mapping: String::from("pathname"),
+            label: None,
+            theme: None,
+            shortname: Some(String::from("zine")),
+        };
+        let html = format!(
+            "<html><head></head><body>{}</body></html>",
+            config.render_embed("en").unwrap()
+        );
+        let policy = Policy::from_html(html.as_bytes(), &BTreeMap::new())
+            .unwrap()
+            .to_string();
+        assert!(policy.contains(expected));
+
+        // The pages without comments are unaffected.
+        let policy = Policy::from_html(b"<html></html>", &BTreeMap::new())
+            .unwrap()
+            .to_string();
+        assert!(!policy.contains("frame-src"));
+    }
+}
@@ -1,36 +1,124 @@
-use once_cell::sync::OnceCell;
-use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::{
+    collections::HashMap,
+    mem,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
-use crate::entity::{Author, MetaArticle, Site, Theme};
+use parking_lot::{const_mutex, const_rwlock, Mutex, MutexGuard, RwLock};
+use serde_json::Value;
 
-static ZINE_DATA: OnceCell<RwLock<ZineData>> = OnceCell::new();
+use crate::entity::{
+    normalize_author_id, ArticleSource, Author, BuildConfig, GlossaryTerm, ImagesConfig, Issue,
+    LintConfig, MetaArticle, Preprocessor, RelatedArticle, SandboxConfig, SeriesNavigation, Site,
+    SlugConfig, SocialCardsConfig, Theme, Topic,
+};
+
+// The latest immutable snapshot of the zine data, which is replaced as a whole
+// by the writers, so the readers never block each other or the writers.
+static ZINE_DATA: RwLock<Option<Arc<ZineData>>> = const_rwlock(None);
+// Serialize the writers, so no write is lost between the snapshot copy and publish.
+static WRITER: Mutex<()> = const_mutex(());
 
 pub fn load() {
-    ZINE_DATA.get_or_init(|| RwLock::new(ZineData::default()));
+    ZINE_DATA.write().get_or_insert_with(Default::default);
+}
+
+/// Get the current snapshot of the zine data.
+///
+/// The snapshot is immutable and cheap to clone, the later writes are invisible to it.
+/// Take one snapshot for a whole rendering loop, rather than calling this repeatedly.
+pub fn read() -> Arc<ZineData> {
+    ZINE_DATA
+        .read()
+        .clone()
+        .expect("The zine data should be loaded before reading")
+}
+
+/// Get a writable copy of the current snapshot, which is published
+/// as the new snapshot after the returned writer dropped.
+pub fn write() -> ZineDataWriter {
+    let guard = WRITER.lock();
+    ZineDataWriter {
+        data: ZineData::clone(&read()),
+        _guard: guard,
+    }
+}
+
+/// The writable copy of the zine data, see [`write`].
+pub struct ZineDataWriter {
+    data: ZineData,
+    _guard: MutexGuard<'static, ()>,
 }
 
-pub fn read() -> RwLockReadGuard<'static, ZineData> {
-    ZINE_DATA.get().unwrap().read()
+impl Deref for ZineDataWriter {
+    type Target = ZineData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl DerefMut for ZineDataWriter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
 }
 
-pub fn write() -> RwLockWriteGuard<'static, ZineData> {
-    ZINE_DATA.get().unwrap().write()
+impl Drop for ZineDataWriter {
+    fn drop(&mut self) {
+        let data = mem::take(&mut self.data);
+        *ZINE_DATA.write() = Some(Arc::new(data));
+    }
 }
 
-#[derive(Debug, Default)]
+// The large lists are shared by `Arc`, so copying the data for writes is cheap.
+#[derive(Debug, Default, Clone)]
 pub struct ZineData {
-    authors: Vec<Author>,
+    authors: Arc<Vec<Author>>,
     // Issue slug and article pair list.
-    articles: Vec<(String, MetaArticle)>,
+    articles: Arc<Vec<(String, ArticleSource)>>,
+    // The published issue list.
+    issues: Arc<Vec<Issue>>,
     // The topic name list.
     topics: Vec<String>,
+    // The parsed topic list.
+    topic_entities: Arc<Vec<Topic>>,
     site: Site,
     theme: Theme,
+    build_config: BuildConfig,
+    lint_config: LintConfig,
+    slug_config: SlugConfig,
+    sandbox_config: SandboxConfig,
+    markdown_preprocessors: Vec<Preprocessor>,
+    number_headings: bool,
+    toc: bool,
+    toc_depth: Option<usize>,
+    glossary: Arc<Vec<GlossaryTerm>>,
+    images_config: Option<ImagesConfig>,
+    social_cards_config: Option<SocialCardsConfig>,
+    // The external article data keyed by the article path without slashes,
+    // see [`ZineData::merge_article_data`].
+    article_data: Arc<HashMap<String, Value>>,
+    // The related articles keyed by the article path without slashes.
+    related_articles: Arc<HashMap<String, Vec<RelatedArticle>>>,
+    // The series navigations keyed by the article path without slashes.
+    series_navigations: Arc<HashMap<String, SeriesNavigation>>,
 }
 
 impl ZineData {
     pub fn set_authors(&mut self, authors: Vec<Author>) -> &mut Self {
-        self.authors = authors;
+        self.authors = Arc::new(authors);
+        self
+    }
+
+    pub fn set_issues(&mut self, issues: Vec<Issue>) -> &mut Self {
+        self.issues = Arc::new(issues);
+        self
+    }
+
+    pub fn set_topic_entities(&mut self, topics: Vec<Topic>) -> &mut Self {
+        self.topic_entities = Arc::new(topics);
         self
     }
 
@@ -39,8 +127,8 @@ impl ZineData {
         self
     }
 
-    pub fn set_articles(&mut self, articles: Vec<(String, MetaArticle)>) -> &mut Self {
-        self.articles = articles;
+    pub fn set_articles(&mut self, articles: Vec<(String, ArticleSource)>) -> &mut Self {
+        self.articles = Arc::new(articles);
         self
     }
 
@@ -54,29 +142,197 @@ impl ZineData {
         self
     }
 
+    pub fn set_build_config(&mut self, build_config: BuildConfig) -> &mut Self {
+        self.build_config = build_config;
+        self
+    }
+
+    pub fn set_lint_config(&mut self, lint_config: LintConfig) -> &mut Self {
+        self.lint_config = lint_config;
+        self
+    }
+
+    pub fn set_slug_config(&mut self, slug_config: SlugConfig) -> &mut Self {
+        self.slug_config = slug_config;
+        self
+    }
+
+    pub fn set_sandbox_config(&mut self, sandbox_config: SandboxConfig) -> &mut Self {
+        self.sandbox_config = sandbox_config;
+        self
+    }
+
+    pub fn set_markdown_preprocessors(&mut self, preprocessors: Vec<Preprocessor>) -> &mut Self {
+        self.markdown_preprocessors = preprocessors;
+        self
+    }
+
+    pub fn set_number_headings(&mut self, number_headings: bool) -> &mut Self {
+        self.number_headings = number_headings;
+        self
+    }
+
+    pub fn set_toc(&mut self, toc: bool) -> &mut Self {
+        self.toc = toc;
+        self
+    }
+
+    pub fn set_toc_depth(&mut self, toc_depth: Option<usize>) -> &mut Self {
+        self.toc_depth = toc_depth;
+        self
+    }
+
+    pub fn set_glossary(&mut self, glossary: Vec<GlossaryTerm>) -> &mut Self {
+        self.glossary = Arc::new(glossary);
+        self
+    }
+
+    pub fn set_images_config(&mut self, images_config: Option<ImagesConfig>) -> &mut Self {
+        self.images_config = images_config;
+        self
+    }
+
+    pub fn set_social_cards_config(
+        &mut self,
+        social_cards_config: Option<SocialCardsConfig>,
+    ) -> &mut Self {
+        self.social_cards_config = social_cards_config;
+        self
+    }
+
+    /// Replace the related articles, keyed by the article path.
+    pub fn set_related_articles(
+        &mut self,
+        related_articles: HashMap<String, Vec<RelatedArticle>>,
+    ) -> &mut Self {
+        self.related_articles = Arc::new(
+            related_articles
+                .into_iter()
+                .map(|(path, articles)| (path.trim_matches('/').to_owned(), articles))
+                .collect(),
+        );
+        self
+    }
+
+    /// Replace the series navigations, keyed by the article path.
+    pub fn set_series_navigations(
+        &mut self,
+        series_navigations: HashMap<String, SeriesNavigation>,
+    ) -> &mut Self {
+        self.series_navigations = Arc::new(
+            series_navigations
+                .into_iter()
+                .map(|(path, navigation)| (path.trim_matches('/').to_owned(), navigation))
+                .collect(),
+        );
+        self
+    }
+
+    /// Replace the external article data with the merged `data` list.
+    pub fn set_article_data(&mut self, data: Vec<Value>) -> &mut Self {
+        self.article_data = Default::default();
+        for data in data {
+            self.merge_article_data(data);
+        }
+        self
+    }
+
+    /// Merge the external article data, a JSON object keyed by the article path,
+    /// e.g. `{"/issue-1/foo": {"likes": 3}}` of the `[build] article_data` files.
+    ///
+    /// This is the only merge point of the external article data, which is
+    /// exposed as `article_data` in the article template context.
+    /// The objects of the same article are merged deeply, the later values take precedence.
+    pub fn merge_article_data(&mut self, data: Value) -> &mut Self {
+        if let Value::Object(map) = data {
+            let article_data = Arc::make_mut(&mut self.article_data);
+            for (path, value) in map {
+                let key = path.trim_matches('/').to_owned();
+                match article_data.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        article_data.insert(key, value);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Register a new topic if it doesn't exist.
+    pub fn add_topic(&mut self, topic: &str) {
+        if !self.is_valid_topic(topic) {
+            self.topics.push(topic.to_owned());
+        }
+    }
+
     pub fn get_authors(&self) -> Vec<&Author> {
         self.authors.iter().by_ref().collect()
     }
 
     pub fn get_author_by_id(&self, author_id: &str) -> Option<&Author> {
-        self.authors
-            .iter()
-            .find(|author| author.id.eq_ignore_ascii_case(author_id))
+        let author_id = normalize_author_id(author_id);
+        self.authors.iter().find(|author| author.id == author_id)
     }
 
     pub fn get_article_by_path(&self, article_path: &str) -> Option<MetaArticle> {
-        self.articles
+        self.get_article(article_path)
+            .map(|article| article.meta.clone())
+    }
+
+    /// Get the article by its `path`, or `/<issue slug>/<article slug>`.
+    pub fn get_article(&self, article_path: &str) -> Option<&ArticleSource> {
+        self.articles.iter().find_map(|(issue_slug, article)| {
+            if article.meta.path.as_deref() == Some(article_path)
+                || format!("/{}/{}", issue_slug, article.meta.slug) == article_path
+            {
+                Some(article)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Get the published issue by its number or slug.
+    pub fn get_issue(&self, number_or_slug: &str) -> Option<&Issue> {
+        self.issues.iter().find(|issue| {
+            issue.slug == number_or_slug || number_or_slug.parse::<u32>().ok() == Some(issue.number)
+        })
+    }
+
+    pub fn get_topic(&self, id: &str) -> Option<&Topic> {
+        self.topic_entities
             .iter()
-            .find_map(|(issue_slug, article)| {
-                if article.path.as_deref() == Some(article_path)
-                    || format!("/{}/{}", issue_slug, article.slug) == article_path
-                {
-                    Some(article)
-                } else {
-                    None
-                }
-            })
-            .cloned()
+            .find(|topic| topic.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Find the article by `path` if any, otherwise by issue slug and article slug.
+    /// If the issue slug is unknown, the article slug must be unique among all issues.
+    pub fn find_article(
+        &self,
+        issue_slug: Option<&str>,
+        slug: &str,
+        path: Option<&str>,
+    ) -> Option<&ArticleSource> {
+        if let Some(path) = path {
+            return self
+                .articles
+                .iter()
+                .find(|(_, article)| article.meta.path.as_deref() == Some(path))
+                .map(|(_, article)| article);
+        }
+
+        let mut articles = self
+            .articles
+            .iter()
+            .filter(|(article_issue_slug, article)| {
+                article.meta.slug == slug
+                    && issue_slug.map_or(true, |issue_slug| issue_slug == article_issue_slug)
+            });
+        match (articles.next(), articles.next()) {
+            (Some((_, article)), None) => Some(article),
+            _ => None,
+        }
     }
 
     pub fn get_site(&self) -> &Site {
@@ -87,7 +343,124 @@ impl ZineData {
         &self.theme
     }
 
+    pub fn get_build_config(&self) -> &BuildConfig {
+        &self.build_config
+    }
+
+    pub fn get_lint_config(&self) -> &LintConfig {
+        &self.lint_config
+    }
+
+    pub fn get_slug_config(&self) -> &SlugConfig {
+        &self.slug_config
+    }
+
+    pub fn get_sandbox_config(&self) -> &SandboxConfig {
+        &self.sandbox_config
+    }
+
+    /// Get the external article data of the article `path`, see [`ZineData::merge_article_data`].
+    pub fn get_article_data(&self, path: &str) -> Option<&Value> {
+        self.article_data.get(path.trim_matches('/'))
+    }
+
+    /// Get the related articles of the article `path`, see [`crate::entity::RelatedConfig`].
+    pub fn get_related_articles(&self, path: &str) -> &[RelatedArticle] {
+        self.related_articles
+            .get(path.trim_matches('/'))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get the series navigation of the article `path`, if it's a part of a series.
+    pub fn get_series_navigation(&self, path: &str) -> Option<&SeriesNavigation> {
+        self.series_navigations.get(path.trim_matches('/'))
+    }
+
+    pub fn get_markdown_preprocessors(&self) -> &[Preprocessor] {
+        &self.markdown_preprocessors
+    }
+
+    pub fn get_number_headings(&self) -> bool {
+        self.number_headings
+    }
+
+    pub fn get_toc(&self) -> bool {
+        self.toc
+    }
+
+    pub fn get_toc_depth(&self) -> Option<usize> {
+        self.toc_depth
+    }
+
+    pub fn get_glossary(&self) -> &[GlossaryTerm] {
+        &self.glossary
+    }
+
+    pub fn get_images_config(&self) -> Option<&ImagesConfig> {
+        self.images_config.as_ref()
+    }
+
+    pub fn get_social_cards_config(&self) -> Option<&SocialCardsConfig> {
+        self.social_cards_config.as_ref()
+    }
+
+    /// Get the url slug of `topic`, which is used in the `/topic/<slug>` url.
+    pub fn get_topic_slug(&self, topic: &str) -> String {
+        self.slug_config
+            .normalize(&self.site.slug_strategy.apply(&topic.to_lowercase()))
+    }
+
+    pub fn get_topics(&self) -> &[String] {
+        &self.topics
+    }
+
     pub fn is_valid_topic(&self, topic: &str) -> bool {
         self.topics.iter().any(|t| t.eq_ignore_ascii_case(topic))
     }
 }
+
+// Merge the JSON `value` into `target`, the objects are merged recursively.
+fn merge_json(target: &mut Value, value: Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(map)) => {
+            for (key, value) in map {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::ZineData;
+
+    #[test]
+    fn test_merge_article_data() {
+        let mut data = ZineData::default();
+        data.merge_article_data(json!({
+            "/issue-1/foo": {"likes": 1, "reactions": {"heart": 2}},
+            "issue-1/bar/": {"likes": 5},
+        }))
+        .merge_article_data(json!({
+            "issue-1/foo": {"likes": 3, "reactions": {"rocket": 1}},
+        }));
+        assert_eq!(
+            data.get_article_data("/issue-1/foo"),
+            Some(&json!({"likes": 3, "reactions": {"heart": 2, "rocket": 1}}))
+        );
+        assert_eq!(
+            data.get_article_data("/issue-1/bar"),
+            Some(&json!({"likes": 5}))
+        );
+        assert_eq!(data.get_article_data("/issue-1/baz"), None);
+    }
+}
@@ -0,0 +1,148 @@
+use time::{Date, Month};
+
+/// The default date format, e.g. `2023-01-02`.
+pub static DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Parse the `YYYY-MM-DD` date of templates.
+pub fn parse_date(date: &str) -> Option<Date> {
+    let mut parts = date.trim().splitn(3, '-');
+    let mut next = |len: usize| {
+        parts
+            .next()
+            .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|part| part.parse::<u16>().ok())
+    };
+    let (year, month, day) = (next(4)?, next(2)?, next(2)?);
+    let month = Month::try_from(u8::try_from(month).ok()?).ok()?;
+    Date::from_calendar_date(year.into(), month, u8::try_from(day).ok()?).ok()
+}
+
+/// Format the `date` with the strftime-like `format`, the supported specifiers are:
+///
+/// - `%Y`: the full year, e.g. `2023`
+/// - `%y`: the last two digits of year, e.g. `23`
+/// - `%m`: the zero-padded month, e.g. `01`
+/// - `%d`: the zero-padded day, e.g. `02`
+/// - `%e`: the day without padding, e.g. `2`
+/// - `%B`: the month name, e.g. `January`
+/// - `%b`: the abbreviated month name, e.g. `Jan`
+/// - `%j`: the zero-padded day of year, e.g. `002`
+/// - `%%`: a literal `%`
+///
+/// The month names are localized by `month_name(month, abbreviated)`.
+pub fn format_date(date: Date, format: &str, month_name: impl Fn(u8, bool) -> String) -> String {
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        let month = u8::from(date.month());
+        match chars.next() {
+            Some('Y') => formatted.push_str(&date.year().to_string()),
+            Some('y') => formatted.push_str(&format!("{:02}", date.year().rem_euclid(100))),
+            Some('m') => formatted.push_str(&format!("{month:02}")),
+            Some('d') => formatted.push_str(&format!("{:02}", date.day())),
+            Some('e') => formatted.push_str(&date.day().to_string()),
+            Some('B') => formatted.push_str(&month_name(month, false)),
+            Some('b') => formatted.push_str(&month_name(month, true)),
+            Some('j') => formatted.push_str(&format!("{:03}", date.ordinal())),
+            Some('%') => formatted.push('%'),
+            // Keep the unsupported specifiers as is.
+            Some(other) => {
+                formatted.push('%');
+                formatted.push(other);
+            }
+            None => formatted.push('%'),
+        }
+    }
+    formatted
+}
+
+/// The relative distance of a date from today, see [`relative_date`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum RelativeDate {
+    Today,
+    Yesterday,
+    DaysAgo(i64),
+    MonthsAgo(i64),
+    YearsAgo(i64),
+    /// A future date, in days.
+    InDays(i64),
+}
+
+impl RelativeDate {
+    /// The fluent key and number pair to localize this relative date.
+    pub fn fluent_key(&self) -> (&'static str, Option<i64>) {
+        match self {
+            RelativeDate::Today => ("relative-today", None),
+            RelativeDate::Yesterday => ("relative-yesterday", None),
+            RelativeDate::DaysAgo(days) => ("relative-days-ago", Some(*days)),
+            RelativeDate::MonthsAgo(months) => ("relative-months-ago", Some(*months)),
+            RelativeDate::YearsAgo(years) => ("relative-years-ago", Some(*years)),
+            RelativeDate::InDays(days) => ("relative-in-days", Some(*days)),
+        }
+    }
+}
+
+/// Get the distance of `date` from `today`, e.g. 3 days ago.
+pub fn relative_date(date: Date, today: Date) -> RelativeDate {
+    let days = (today - date).whole_days();
+    match days {
+        i64::MIN..=-1 => RelativeDate::InDays(-days),
+        0 => RelativeDate::Today,
+        1 => RelativeDate::Yesterday,
+        2..=29 => RelativeDate::DaysAgo(days),
+        30..=364 => RelativeDate::MonthsAgo(days / 30),
+        _ => RelativeDate::YearsAgo(days / 365),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+    use time::{Date, Month};
+
+    use super::{format_date, parse_date, relative_date, RelativeDate};
+
+    fn month_name(month: u8, abbreviated: bool) -> String {
+        let name = ["January", "February", "March"][month as usize - 1];
+        if abbreviated {
+            name[..3].to_owned()
+        } else {
+            name.to_owned()
+        }
+    }
+
+    #[test_case("%Y-%m-%d", "2023-01-02")]
+    #[test_case("%B %e, %Y", "January 2, 2023")]
+    #[test_case("%d %b %y", "02 Jan 23")]
+    #[test_case("%Y年%m月%d日", "2023年01月02日")]
+    #[test_case("%j %% %Q", "002 % %Q")]
+    fn test_format_date(format: &str, expected: &str) {
+        let date = Date::from_calendar_date(2023, Month::January, 2).unwrap();
+        assert_eq!(format_date(date, format, month_name), expected);
+    }
+
+    #[test_case("2023-01-02", Some((2023, Month::January, 2)))]
+    #[test_case("2023-1-2", None)]
+    #[test_case("yesterday", None)]
+    fn test_parse_date(date: &str, expected: Option<(i32, Month, u8)>) {
+        let expected =
+            expected.map(|(year, month, day)| Date::from_calendar_date(year, month, day).unwrap());
+        assert_eq!(parse_date(date), expected);
+    }
+
+    #[test_case(0, RelativeDate::Today)]
+    #[test_case(1, RelativeDate::Yesterday)]
+    #[test_case(3, RelativeDate::DaysAgo(3))]
+    #[test_case(65, RelativeDate::MonthsAgo(2))]
+    #[test_case(800, RelativeDate::YearsAgo(2))]
+    #[test_case(-5, RelativeDate::InDays(5))]
+    fn test_relative_date(days_ago: i64, expected: RelativeDate) {
+        let today = Date::from_calendar_date(2023, Month::June, 1).unwrap();
+        let date = today - time::Duration::days(days_ago);
+        assert_eq!(relative_date(date, today), expected);
+    }
+}
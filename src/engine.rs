@@ -1,7 +1,24 @@
-use std::{collections::HashMap, env, fs, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+};
 
-use crate::{data, html::rewrite_html_base_url, locales::FluentLoader, Zine};
-use genkit::{current_mode, helpers::copy_dir, Context, Entity, Generator, Mode};
+use crate::{
+    archive, assets, changelog, changes, csp, data, date,
+    entity::{FormLabels, MetaArticle, SandboxConfig, Site},
+    export,
+    html::{self, rewrite_html_base_url, truncate_html},
+    images,
+    locales::FluentLoader,
+    media, output, permalink, snapshot, social_card,
+    watch::{self, Rebuild},
+    Zine,
+};
+use genkit::{current_mode, html::Meta, Context, Entity, Generator, Mode};
 
 use anyhow::{Context as _, Result};
 use http::Uri;
@@ -10,20 +27,58 @@ use once_cell::sync::OnceCell;
 use parking_lot::RwLock;
 use serde::Serialize;
 use serde_json::Value;
+use time::{
+    format_description,
+    format_description::well_known::{Rfc2822, Rfc3339},
+    Date, OffsetDateTime,
+};
+
+// The build id of the site, see [`build_id`].
+static BUILD_ID: OnceCell<String> = OnceCell::new();
+// Whether the global render thread pool is built, see [`init_thread_pool`].
+static THREAD_POOL: OnceCell<()> = OnceCell::new();
+
+// The environment variable to build only one issue (slug or number)
+// into an existing build directory, which is set by the global `--only` argument.
+pub static BUILD_ONLY_ENV: &str = "ZINE_BUILD_ONLY";
+// The environment variable to print the output paths of a build
+// with their sources without writing anything,
+// which is set by the `--dry-run` argument of `zine build`.
+pub static BUILD_DRY_RUN_ENV: &str = "ZINE_BUILD_DRY_RUN";
+// The environment variable of the render threads, which is set by the global
+// `--threads` argument and overrides the `threads` of `[build]` table.
+pub static THREADS_ENV: &str = "ZINE_THREADS";
+// The environment variable to render the draft articles, which is set by the global
+// `--drafts` argument and only takes effect in `zine serve` mode.
+pub static DRAFTS_ENV: &str = "ZINE_DRAFTS";
+
+/// The directory of the draft articles, see [`drafts_mode`].
+pub static DRAFTS_DIR: &str = "drafts";
+
+/// Whether to render the draft articles under the unlisted `/drafts/` prefix,
+/// which only happens in `zine serve --drafts`.
+pub fn drafts_mode() -> bool {
+    matches!(current_mode(), Mode::Serve) && env::var_os(DRAFTS_ENV).is_some()
+}
 
 pub fn render(
     env: &Environment,
     template: &str,
     context: Context,
     dest: impl AsRef<Path>,
+) -> Result<()> {
+    render_file(env, template, context, dest.as_ref().join("index.html"))
+}
+
+/// Render the `template` into the `dest` file, e.g. `404.html`.
+pub fn render_file(
+    env: &Environment,
+    template: &str,
+    context: Context,
+    dest: impl AsRef<Path>,
 ) -> Result<()> {
     let mut buf = vec![];
-    let dest = dest.as_ref().join("index.html");
-    if let Some(parent_dir) = dest.parent() {
-        if !parent_dir.exists() {
-            fs::create_dir_all(parent_dir)?;
-        }
-    }
+    let dest = dest.as_ref();
 
     let site = context.get("site").cloned();
     env.get_template(template)?
@@ -48,17 +103,111 @@ pub fn render(
             }
         }
 
-        let html = rewrite_html_base_url(&buf, site_url, cdn_url)?;
-        fs::write(dest, html)?;
+        let zine_data = data::read();
+        let build_config = zine_data.get_build_config();
+        let cache_bust_version = build_config
+            .cache_bust
+            .then(|| BUILD_ID.get().cloned())
+            .flatten();
+        let mut html =
+            rewrite_html_base_url(&buf, site_url, cdn_url, cache_bust_version.as_deref())?;
+        // The policy is computed from the final html.
+        if let Some(csp) = build_config.csp.as_ref() {
+            html = csp::apply(html, csp)?;
+        }
+        output::write(dest, html)?;
         return Ok(());
     }
 
-    fs::write(dest, buf)?;
+    output::write(dest, buf)?;
     Ok(())
 }
 
-// Render Atom feed
-fn render_atom_feed(
+// The fluent loaders of the site locale and the `[site.i18n]` locales,
+// the loader of a page is selected by the `site.locale` of its context.
+struct FluentLoaders {
+    default: FluentLoader,
+    localized: HashMap<String, FluentLoader>,
+}
+
+impl FluentLoaders {
+    fn new(source: &Path, site: &Site, sandbox_config: &SandboxConfig) -> Self {
+        FluentLoaders {
+            default: FluentLoader::new(source, &site.locale, sandbox_config),
+            localized: site
+                .i18n
+                .keys()
+                .map(|locale| {
+                    (
+                        locale.clone(),
+                        FluentLoader::new(source, locale, sandbox_config),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn get(&self, state: &minijinja::State) -> &FluentLoader {
+        state
+            .lookup("site")
+            .and_then(|site| site.get_attr("locale").ok())
+            .and_then(|locale| self.localized.get(locale.as_str()?))
+            .unwrap_or(&self.default)
+    }
+}
+
+// Build the global render thread pool before any parallel parsing or rendering.
+// It is built once per process, the thread count changes require a restart in
+// `zine serve` mode.
+fn init_thread_pool(threads: Option<usize>) -> Result<()> {
+    THREAD_POOL.get_or_try_init(|| build_thread_pool(threads))?;
+    Ok(())
+}
+
+fn build_thread_pool(threads: Option<usize>) -> Result<()> {
+    let threads = match env::var(THREADS_ENV) {
+        Ok(threads) => Some(
+            threads
+                .parse::<usize>()
+                .with_context(|| format!("Invalid render threads `{threads}`"))?,
+        ),
+        Err(_) => threads,
+    };
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Failed to build the render thread pool")?;
+    }
+    Ok(())
+}
+
+/// Render the tombstone page of an expired article or page into `dest`,
+/// which links to the `archive_url` as the canonical url.
+pub fn render_tombstone(
+    env: &Environment,
+    mut context: Context,
+    title: &str,
+    expire_date: Date,
+    archive_url: &str,
+    dest: impl AsRef<Path>,
+) -> Result<()> {
+    context.insert("page_type", "tombstone");
+    context.insert("canonical_url", archive_url);
+    context.insert("noindex", &true);
+    context.insert(
+        "tombstone",
+        &context! {
+            title,
+            expire_date => genkit::helpers::format_date(&expire_date),
+            archive_url,
+        },
+    );
+    render(env, "tombstone.jinja", context, dest)
+}
+
+/// Render the Atom feed `feed.xml` into the `dest` directory.
+pub fn render_atom_feed(
     env: &Environment,
     context: impl Serialize,
     dest: impl AsRef<Path>,
@@ -71,7 +220,23 @@ fn render_atom_feed(
     template
         .render_to_write(context, &mut buf)
         .expect("Render feed.jinja failed.");
-    fs::write(dest, buf).expect("Write feed.xml failed");
+    output::write(&dest, buf).expect("Write feed.xml failed");
+    Ok(())
+}
+
+// Render the JSON Feed feed.json
+fn render_json_feed(
+    env: &Environment,
+    context: impl Serialize,
+    dest: impl AsRef<Path>,
+) -> Result<()> {
+    let dest = dest.as_ref().join("feed.json");
+    let template = env.get_template("feed.json.jinja")?;
+    let mut buf = vec![];
+    template
+        .render_to_write(context, &mut buf)
+        .expect("Render feed.json.jinja failed.");
+    output::write(&dest, buf).expect("Write feed.json failed");
     Ok(())
 }
 
@@ -87,10 +252,78 @@ fn render_sitemap(
     template
         .render_to_write(context, &mut buf)
         .expect("Render sitemap.jinja failed.");
-    fs::write(dest, buf).expect("Write sitemap.xml failed");
+    output::write(&dest, buf).expect("Write sitemap.xml failed");
     Ok(())
 }
 
+// Render feeds.opml
+fn render_opml(env: &Environment, context: impl Serialize, dest: impl AsRef<Path>) -> Result<()> {
+    let dest = dest.as_ref().join("feeds.opml");
+    let template = env.get_template("opml.jinja")?;
+    let mut buf = vec![];
+    template
+        .render_to_write(context, &mut buf)
+        .expect("Render opml.jinja failed.");
+    output::write(&dest, buf).expect("Write feeds.opml failed");
+    Ok(())
+}
+
+// Render the `404.html` page from the `pages/404.md` if present, otherwise the default message.
+fn render_not_found(
+    env: &Environment,
+    mut context: Context,
+    zine: &Zine,
+    dest: &Path,
+) -> Result<()> {
+    context.insert("site", &zine.site);
+    context.insert("page_type", "not-found");
+    context.insert("noindex", &true);
+    if let Some(page) = &zine.not_found_page {
+        context.insert(
+            "meta",
+            &Meta {
+                title: Cow::Owned(page.title()),
+                description: Cow::Owned(genkit::markdown::extract_description(&page.markdown)),
+                url: None,
+                image: None,
+            },
+        );
+        context.insert("page", page);
+    }
+    render_file(env, "404.jinja", context, dest.join("404.html"))
+}
+
+// Render the "What's new" page and feed, the changelog is only saved in build mode
+// and never in a dry run.
+fn render_whats_new(
+    env: &Environment,
+    mut context: Context,
+    zine: &Zine,
+    source: &Path,
+    dest: &Path,
+) -> Result<()> {
+    let changelog = changelog::update_changelog(
+        source,
+        zine,
+        matches!(current_mode(), Mode::Build) && !output::is_dry_run(),
+    )?;
+    let dest = dest.join("whats-new");
+    context.insert("site", &zine.site);
+    context.insert("entries", &changelog);
+    render(env, "whats-new.jinja", context, &dest)?;
+    render_atom_feed(
+        env,
+        context! {
+            site => &zine.site,
+            entries => &zine.changelog_feed_entries(&changelog, 20),
+            generator_version => env!("CARGO_PKG_VERSION"),
+            feed_title => format!("{} - What's new", zine.site.name),
+            feed_path => "/whats-new/feed.xml",
+        },
+        &dest,
+    )
+}
+
 pub struct ZineGenerator;
 
 impl Generator for ZineGenerator {
@@ -98,8 +331,14 @@ impl Generator for ZineGenerator {
 
     fn on_load(&self, source: &std::path::Path) -> Result<Self::Entity> {
         data::load();
+        // Print the output paths only, e.g. `zine build --dry-run`, which is
+        // known before parsing to leave the state files untouched.
+        output::set_dry_run(
+            env::var_os(BUILD_DRY_RUN_ENV).is_some() && matches!(current_mode(), Mode::Build),
+        );
         let (_source, zine) = crate::locate_root_zine_folder(std::fs::canonicalize(source)?)?
             .with_context(|| "Failed to find the root zine.toml file".to_string())?;
+        init_thread_pool(zine.build_config.threads)?;
         Ok(zine)
     }
 
@@ -108,7 +347,7 @@ impl Generator for ZineGenerator {
     }
 
     fn get_markdown_config(&self, zine: &Self::Entity) -> Option<genkit::entity::MarkdownConfig> {
-        Some(zine.markdown_config.clone())
+        Some(zine.markdown_config.inner.clone())
     }
 
     fn on_extend_environment<'a>(
@@ -126,6 +365,24 @@ impl Generator for ZineGenerator {
             "zine_version",
             option_env!("CARGO_PKG_VERSION").unwrap_or("(Unknown Cargo package version)"),
         );
+        env.add_global("site_facts", zine.site_facts());
+        if let Some(announcement) = zine.site.active_announcement(frozen_now().date()) {
+            env.add_global(
+                "announcement",
+                context! {
+                    text => &announcement.text,
+                    dismissible => announcement.dismissible,
+                    // A stable id to remember the dismissed announcement.
+                    id => &snapshot::hash(announcement.text.as_bytes())[..12],
+                },
+            );
+        }
+        env.add_global(
+            "build_id",
+            BUILD_ID
+                .get_or_init(|| build_id(source, &zine.sandbox_config))
+                .as_str(),
+        );
         env.add_global(
             "live_reload",
             matches!(genkit::current_mode(), genkit::Mode::Serve),
@@ -140,11 +397,44 @@ impl Generator for ZineGenerator {
                 ),
                 ("_macros.jinja", include_str!("../templates/_macros.jinja")),
                 ("_meta.jinja", include_str!("../templates/_meta.jinja")),
+                (
+                    "_pagination.jinja",
+                    include_str!("../templates/_pagination.jinja"),
+                ),
+                (
+                    "_signature.jinja",
+                    include_str!("../templates/_signature.jinja"),
+                ),
+                (
+                    "_sponsors.jinja",
+                    include_str!("../templates/_sponsors.jinja"),
+                ),
+                (
+                    "_roundup.jinja",
+                    include_str!("../templates/_roundup.jinja"),
+                ),
                 ("base.jinja", include_str!("../templates/base.jinja")),
                 ("index.jinja", include_str!("../templates/index.jinja")),
                 ("issue.jinja", include_str!("../templates/issue.jinja")),
                 ("article.jinja", include_str!("../templates/article.jinja")),
+                (
+                    "article-wide.jinja",
+                    include_str!("../templates/article-wide.jinja"),
+                ),
+                (
+                    "article-photo-essay.jinja",
+                    include_str!("../templates/article-photo-essay.jinja"),
+                ),
                 ("author.jinja", include_str!("../templates/author.jinja")),
+                (
+                    "colophon.jinja",
+                    include_str!("../templates/colophon.jinja"),
+                ),
+                ("events.jinja", include_str!("../templates/events.jinja")),
+                (
+                    "glossary.jinja",
+                    include_str!("../templates/glossary.jinja"),
+                ),
                 (
                     "author-list.jinja",
                     include_str!("../templates/author-list.jinja"),
@@ -154,15 +444,43 @@ impl Generator for ZineGenerator {
                     "topic-list.jinja",
                     include_str!("../templates/topic-list.jinja"),
                 ),
+                ("series.jinja", include_str!("../templates/series.jinja")),
                 ("page.jinja", include_str!("../templates/page.jinja")),
+                ("404.jinja", include_str!("../templates/404.jinja")),
+                (
+                    "tombstone.jinja",
+                    include_str!("../templates/tombstone.jinja"),
+                ),
+                (
+                    "whats-new.jinja",
+                    include_str!("../templates/whats-new.jinja"),
+                ),
+                ("changes.jinja", include_str!("../templates/changes.jinja")),
                 ("feed.jinja", include_str!("../templates/feed.jinja")),
+                (
+                    "feed.json.jinja",
+                    include_str!("../templates/feed.json.jinja"),
+                ),
                 ("sitemap.jinja", include_str!("../templates/sitemap.jinja")),
+                ("opml.jinja", include_str!("../templates/opml.jinja")),
             ];
             for (name, template) in templates {
                 env.add_template(name, template).unwrap();
             }
         }
 
+        // The templates of the theme package override the builtin templates.
+        if let Some(package) = &zine.theme.loaded_package {
+            for (name, template) in &package.templates {
+                env.add_template(name, template).unwrap_or_else(|err| {
+                    panic!(
+                        "Cannot add template `{name}` of theme `{}`: {err}",
+                        package.manifest.name
+                    )
+                });
+            }
+        }
+
         // Dynamically add templates.
         if let Some(head_template) = &zine.theme.head_template {
             env.add_template("head_template.jinja", head_template)
@@ -176,14 +494,113 @@ impl Generator for ZineGenerator {
             env.add_template("article_extend_template.jinja", article_extend_template)
                 .expect("Cannot add article_extend_template");
         }
+        for (name, template) in &zine.theme.blocks {
+            env.add_template(name, template)
+                .unwrap_or_else(|err| panic!("Cannot add block template `{name}`: {err}"));
+        }
+        for (name, template) in &zine.theme.shortcodes {
+            env.add_template(name, template)
+                .unwrap_or_else(|err| panic!("Cannot add shortcode template `{name}`: {err}"));
+        }
 
-        env.add_function("load_json", load_json);
+        env.add_filter("topic_slug", |topic: &str| {
+            data::read().get_topic_slug(topic)
+        });
+        env.add_function("now", now_function);
+        let sandbox_config = zine.sandbox_config.clone();
+        if !sandbox_config.trusted {
+            env.set_recursion_limit(sandbox_config.recursion_limit);
+            env.set_fuel(Some(sandbox_config.fuel));
+        }
+        let root = source.to_path_buf();
+        env.add_function("load_json", move |filename: &str| {
+            load_json(&root, &sandbox_config, filename)
+        });
         env.add_function("get_entity", get_entity);
         env.add_function("get_author", get_author_function);
-        let fluent_loader = FluentLoader::new(source, &zine.site.locale);
-        env.add_function("fluent", move |key: &str, number: Option<i64>| -> String {
-            fluent_loader.format(key, number)
+        env.add_function("get_issue", get_issue_function);
+        env.add_function("get_topic", get_topic_function);
+        env.add_function("get_article", get_article_function);
+        env.add_function("excerpt_html", excerpt_html_function);
+        env.add_function("render_markdown", render_markdown_function);
+        let images_config = zine.images_config.clone();
+        env.add_function("responsive_image", move |url: Option<&str>| {
+            let image = images_config
+                .as_ref()
+                .zip(url)
+                .and_then(|(config, url)| images::responsive_image(url, config));
+            JinjaValue::from_serializable(&image)
+        });
+        let fluent_loaders = Arc::new(FluentLoaders::new(source, &zine.site, &zine.sandbox_config));
+        let fluent_loader = &fluent_loaders.default;
+        let subscribe_form = zine
+            .forms_config
+            .as_ref()
+            .map(|forms_config| {
+                forms_config.render_subscribe_form(&FormLabels {
+                    placeholder: fluent_loader.format("subscribe-placeholder", None),
+                    button: fluent_loader.format("subscribe-button", None),
+                })
+            })
+            .transpose()
+            .expect("Render subscribe form failed")
+            .unwrap_or_default();
+        env.add_function("subscribe_form", move || {
+            JinjaValue::from_safe_string(subscribe_form.clone())
         });
+        let comments_embed = zine
+            .comments_config
+            .as_ref()
+            .map(|comments_config| comments_config.render_embed(&zine.site.locale))
+            .transpose()
+            .expect("Render comments embed failed")
+            .unwrap_or_default();
+        env.add_function("comments_embed", move || {
+            JinjaValue::from_safe_string(comments_embed.clone())
+        });
+        let date_format = zine
+            .site
+            .date_format
+            .clone()
+            .unwrap_or_else(|| date::DEFAULT_DATE_FORMAT.to_owned());
+        let loaders = fluent_loaders.clone();
+        env.add_filter(
+            "format_date",
+            move |state: &minijinja::State,
+                  value: &str,
+                  format: Option<&str>|
+                  -> Result<String, JinjaError> {
+                let date = parse_date_value(value)?;
+                let loader = loaders.get(state);
+                Ok(date::format_date(
+                    date,
+                    format.unwrap_or(&date_format),
+                    |month, abbreviated| {
+                        let key = if abbreviated { "month-short" } else { "month" };
+                        loader.format(&format!("{key}-{month}"), None)
+                    },
+                ))
+            },
+        );
+        let loaders = fluent_loaders.clone();
+        env.add_filter(
+            "relative_date",
+            move |state: &minijinja::State, value: &str| -> Result<String, JinjaError> {
+                let date = parse_date_value(value)?;
+                let (key, number) = date::relative_date(date, frozen_now().date()).fluent_key();
+                Ok(loaders.get(state).format(key, number))
+            },
+        );
+        env.add_function(
+            "fluent",
+            move |state: &minijinja::State, key: &str, number: Option<i64>| -> String {
+                fluent_loaders.get(state).format(key, number)
+            },
+        );
+        env.add_global(
+            "site_alternates",
+            JinjaValue::from_serializable(&zine.site.alternates()),
+        );
         env
     }
 
@@ -195,16 +612,105 @@ impl Generator for ZineGenerator {
         source: &Path,
         dest: &Path,
     ) -> Result<()> {
-        zine.render(env, context, dest)?;
+        // Partial build for quick fixes, e.g. `zine build --only issue-5`.
+        let mut build_only = env::var(BUILD_ONLY_ENV)
+            .ok()
+            .filter(|_| matches!(current_mode(), Mode::Build))
+            .map(|issue| vec![issue]);
+        // Record the output paths only, see `on_load`.
+        let dry_run = output::is_dry_run();
+        // Rebuild only what the changed files affect in `zine serve` mode, and skip
+        // the rebuilding triggered by the ignored files, such as the editor swap files.
+        let mut static_only = false;
+        if matches!(current_mode(), Mode::Serve) {
+            let issues = zine
+                .issues
+                .iter()
+                .map(|issue| (issue.dir.as_str(), issue.slug.as_str()))
+                .collect::<Vec<_>>();
+            match watch::rebuild(source, dest, &zine.build_config.watch_ignore, &issues) {
+                Rebuild::Nothing => return Ok(()),
+                Rebuild::Static => static_only = true,
+                Rebuild::Issues(slugs) => build_only = Some(slugs.into_iter().collect()),
+                Rebuild::Full => {}
+            }
+        }
+        if zine.build_config.permalink_registry && matches!(current_mode(), Mode::Build) && !dry_run
+        {
+            permalink::check_registry(source, zine)?;
+        }
+        if dry_run {
+            output::init_dry_run(dest);
+        } else {
+            // A partial build always writes into the existing dest directory.
+            output::init(
+                dest,
+                build_only.is_none() && matches!(current_mode(), Mode::Build),
+            )?;
+        }
+        if static_only {
+            copy_static_assets(source, dest, zine)?;
+            return output::finish();
+        }
+        images::reset_variants();
+        if zine.build_config.serve_changes && matches!(current_mode(), Mode::Serve) {
+            let changes = changes::track_changes(zine);
+            changes::print_changes(&changes);
+            let mut context = context.clone();
+            context.insert("site", &zine.site);
+            context.insert("changes", &changes);
+            context.insert("noindex", &true);
+            render(env, "changes.jinja", context, dest.join("_changes"))?;
+        }
+        if zine.site.whats_new && build_only.is_none() {
+            render_whats_new(env, context.clone(), zine, source, dest)?;
+        }
+        if let Some(issues) = build_only.as_deref() {
+            for issue in issues {
+                zine.render_only(env, context.clone(), dest, issue)?;
+            }
+        } else {
+            zine.render(env, context.clone(), dest)?;
+            // Render the localized sites under the `/<locale>/` roots.
+            for locale in zine.site.i18n.keys() {
+                if let Some(localized) = zine.localized(locale) {
+                    localized.render(env, context.clone(), &dest.join(locale))?;
+                }
+            }
+            if !zine.site.i18n.is_empty() {
+                // Restore the render data of the original site.
+                zine.prepare_render_data();
+            }
+            render_not_found(env, context.clone(), zine, dest)?;
+        }
+        // The variants are registered while rendering, including a partial build.
+        if let Some(images_config) = &zine.images_config {
+            images::write_variants(dest, images_config)?;
+        }
+        if let Some(social_cards_config) = &zine.social_cards_config {
+            social_card::write_cards(source, dest, social_cards_config)?;
+        }
+        export::export(zine, dest)?;
+        let feed_entries = zine.latest_feed_entries(zine.site.feeds.entries);
         render_atom_feed(
             env,
             context! {
                 site => &zine.site,
-                entries => &zine.latest_feed_entries(20),
+                entries => &feed_entries,
                 generator_version => env!("CARGO_PKG_VERSION"),
             },
             dest,
         )?;
+        if zine.site.json_feed {
+            render_json_feed(
+                env,
+                context! {
+                    site => &zine.site,
+                    entries => &feed_entries,
+                },
+                dest,
+            )?;
+        }
         render_sitemap(
             env,
             context! {
@@ -214,9 +720,265 @@ impl Generator for ZineGenerator {
             dest,
         )?;
 
-        copy_static_assets(source, dest)?;
-        Ok(())
+        render_opml(
+            env,
+            context! {
+                site => &zine.site,
+                feeds => &zine.feed_links(),
+                // OPML 2.0 requires the RFC 822 date, e.g. `Mon, 16 Oct 2026 10:00:00 +0000`.
+                date_created => frozen_now().format(&Rfc2822)?,
+            },
+            dest,
+        )?;
+
+        // The static assets are unchanged in a partial build.
+        if build_only.is_none() {
+            copy_static_assets(source, dest, zine)?;
+            // The site policy is incomplete in a partial build.
+            csp::write_headers(dest)?;
+            if zine.site.versioned_archive && matches!(current_mode(), Mode::Build) && !dry_run {
+                if output::is_dir() {
+                    archive::archive_site(
+                        source,
+                        dest,
+                        &zine.site.url,
+                        BUILD_ID.get().map(String::as_str),
+                    )?;
+                } else {
+                    println!("Warning: the versioned archive is skipped when building into an archive file.");
+                }
+            }
+        }
+        if let Some(paths) = output::recorded_paths() {
+            print_recorded_paths(zine, dest, &paths);
+        }
+        output::finish()
+    }
+}
+
+// Print the recorded paths of the dry run with the sources which generate them,
+// the source of a path is looked up from its nearest listed ancestor.
+fn print_recorded_paths(zine: &Zine, dest: &Path, paths: &[PathBuf]) {
+    let sources = zine.output_paths().into_iter().collect::<HashMap<_, _>>();
+    let paths = paths
+        .iter()
+        .map(|path| {
+            // The same form as the output paths, e.g. `/issue-1/index.html`.
+            let path = path
+                .components()
+                .map(|component| format!("/{}", component.as_os_str().to_string_lossy()))
+                .collect::<String>();
+            let page = path.strip_suffix("/index.html");
+            let source = match page {
+                // Only the home page itself is generated from the root.
+                Some("") => sources.get("/"),
+                _ => Path::new(page.unwrap_or(&path))
+                    .ancestors()
+                    .filter_map(|ancestor| ancestor.to_str())
+                    .filter(|ancestor| *ancestor != "/")
+                    .find_map(|ancestor| sources.get(ancestor)),
+            };
+            (path, source)
+        })
+        .collect::<Vec<_>>();
+    let width = paths.iter().map(|(path, _)| path.len()).max().unwrap_or(0);
+    for (path, source) in &paths {
+        match source {
+            Some(source) => println!("{path:width$}  <- {source}"),
+            None => println!("{path}"),
+        }
+    }
+    println!(
+        "{} files would be written in `{}`.",
+        paths.len(),
+        dest.display()
+    );
+}
+
+// Parse the `YYYY-MM-DD` date value of the date filters.
+fn parse_date_value(value: &str) -> Result<time::Date, JinjaError> {
+    date::parse_date(value).ok_or_else(|| {
+        JinjaError::new(
+            ErrorKind::InvalidOperation,
+            format!("invalid date `{value}`, expect `YYYY-MM-DD`"),
+        )
+    })
+}
+
+/// Get the current time, which is frozen to the `SOURCE_DATE_EPOCH`
+/// environment variable if present.
+pub fn frozen_now() -> OffsetDateTime {
+    env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| OffsetDateTime::from_unix_timestamp(epoch).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc)
+}
+
+// Get the build id of the site, which is the short git revision of the `source`
+// and the build timestamp, e.g. `3f2a9c1-1700000000`.
+//
+// The git revision is omitted if the `source` isn't a git repository,
+// or in the sandbox mode which never runs the external commands.
+fn build_id(source: &Path, sandbox_config: &SandboxConfig) -> String {
+    let timestamp = frozen_now().unix_timestamp();
+    if sandbox_config.enforced {
+        return timestamp.to_string();
+    }
+    let rev = process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(source)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .filter(|rev| !rev.is_empty());
+    match rev {
+        Some(rev) => format!("{rev}-{timestamp}"),
+        None => timestamp.to_string(),
+    }
+}
+
+// Get the current time in RFC 3339 format, or in the optional
+// time format description, e.g. `now("[year]")`.
+//
+// The time is frozen to the `SOURCE_DATE_EPOCH` environment variable if present,
+// which is useful for reproducible builds.
+fn now_function(format: Option<&str>) -> Result<String, JinjaError> {
+    let now = frozen_now();
+
+    let formatted = if let Some(format) = format {
+        let description = format_description::parse(format).map_err(|err| {
+            JinjaError::new(ErrorKind::InvalidOperation, "invalid time format").with_source(err)
+        })?;
+        now.format(&description)
+    } else {
+        now.format(&Rfc3339)
+    };
+    formatted.map_err(|err| {
+        JinjaError::new(ErrorKind::InvalidOperation, "failed to format time").with_source(err)
+    })
+}
+
+// Render the markdown `text` with the same pipeline of articles, including the
+// theme blocks, the zine code blocks and mentions, e.g. `render_markdown(sponsor.blurb)`.
+// The undefined or none `text` is rendered as empty.
+fn render_markdown_function(
+    state: &minijinja::State,
+    text: Option<&str>,
+) -> Result<JinjaValue, JinjaError> {
+    let Some(text) = text else {
+        return Ok(JinjaValue::from_safe_string(String::new()));
+    };
+    let to_jinja_error = |err: anyhow::Error| {
+        JinjaError::new(
+            ErrorKind::InvalidOperation,
+            format!("`render_markdown` failed: {err:#}"),
+        )
+    };
+    let expanded = crate::markdown::replace_fenced_blocks(text, |name, options, body| {
+        render_fenced_block(state.env(), name, options, body, None)
+    })
+    .map_err(to_jinja_error)?;
+    let (html, _) = genkit::markdown::render_html_with_toc(&expanded);
+    let mut html = html::fill_image_alt(&html).map_err(to_jinja_error)?;
+    if let Some(images_config) = data::read().get_images_config() {
+        html = html::responsive_images(&html, |src| images::responsive_image(src, images_config))
+            .map_err(to_jinja_error)?;
+    }
+    Ok(JinjaValue::from_safe_string(html))
+}
+
+/// Render the fenced block `name` by the `blocks/<name>.jinja` template, or fall back to
+/// the user shortcode `shortcodes/<name>.jinja` template, e.g. ```` ```youtube, id: abc ````.
+///
+/// The shortcode templates get the options as variables too, e.g. `{{ id }}`.
+/// Return `None` if there is no such template, then the block is kept as is.
+pub fn render_fenced_block(
+    env: &Environment,
+    name: &str,
+    options: &BTreeMap<String, String>,
+    body: &str,
+    article: Option<&MetaArticle>,
+) -> Option<Result<String>> {
+    if !crate::markdown::is_block_name(name) {
+        return None;
+    }
+    if let Ok(template) = env.get_template(&format!("blocks/{name}.jinja")) {
+        return Some(
+            template
+                .render(context! { body, options, article })
+                .with_context(|| format!("Failed to render the `{name}` block")),
+        );
     }
+
+    let template = env.get_template(&format!("shortcodes/{name}.jinja")).ok()?;
+    let mut variables = options
+        .iter()
+        .map(|(key, value)| (key.as_str(), JinjaValue::from(value.as_str())))
+        .collect::<BTreeMap<_, _>>();
+    variables.insert("body", JinjaValue::from(body));
+    variables.insert("options", JinjaValue::from_serializable(options));
+    variables.insert("article", JinjaValue::from_serializable(&article));
+    Some(
+        template
+            .render(variables)
+            .with_context(|| format!("Failed to render the `{name}` shortcode")),
+    )
+}
+
+// Get the rendered HTML of the first `paragraphs` (default 2) paragraphs of the article,
+// e.g. `excerpt_html(article, 3)`.
+fn excerpt_html_function(
+    state: &minijinja::State,
+    article: JinjaValue,
+    paragraphs: Option<usize>,
+) -> Result<JinjaValue, JinjaError> {
+    let attr = |value: &JinjaValue, name: &str| {
+        value
+            .get_attr(name)
+            .ok()
+            .and_then(|value| value.as_str().map(ToOwned::to_owned))
+    };
+    // The article reference of author and topic pages is an article and issue slug pair.
+    let (article, issue_slug) = match article.get_attr("article") {
+        Ok(inner) if !inner.is_undefined() => {
+            let issue_slug = attr(&article, "issue_slug");
+            (inner, issue_slug)
+        }
+        // The issue slug is only known in the issue page.
+        _ => (
+            article,
+            state.lookup("issue").and_then(|issue| attr(&issue, "slug")),
+        ),
+    };
+    let slug = attr(&article, "slug").ok_or_else(|| {
+        JinjaError::new(
+            ErrorKind::InvalidOperation,
+            "`excerpt_html` requires an article argument",
+        )
+    })?;
+    let path = attr(&article, "path");
+
+    let article = data::read()
+        .find_article(issue_slug.as_deref(), &slug, path.as_deref())
+        .cloned()
+        .ok_or_else(|| {
+            JinjaError::new(
+                ErrorKind::InvalidOperation,
+                format!("article `{slug}` not found for `excerpt_html`"),
+            )
+        })?;
+    let html = article.render_html(state.env()).map_err(|err| {
+        JinjaError::new(
+            ErrorKind::InvalidOperation,
+            format!("`excerpt_html` failed: {err:#}"),
+        )
+    })?;
+    Ok(JinjaValue::from_safe_string(truncate_html(
+        &html,
+        paragraphs.unwrap_or(2),
+    )))
 }
 
 fn get_author_function(id: &str) -> JinjaValue {
@@ -225,6 +987,26 @@ fn get_author_function(id: &str) -> JinjaValue {
     JinjaValue::from_serializable(&author)
 }
 
+// Get the issue by number or slug, e.g. `get_issue(3)` or `get_issue("issue-3")`.
+fn get_issue_function(number_or_slug: JinjaValue) -> JinjaValue {
+    let data = data::read();
+    let issue = data.get_issue(&number_or_slug.to_string());
+    JinjaValue::from_serializable(&issue)
+}
+
+fn get_topic_function(id: &str) -> JinjaValue {
+    let data = data::read();
+    let topic = data.get_topic(id);
+    JinjaValue::from_serializable(&topic)
+}
+
+// Get the article by path, e.g. `get_article("/issue-3/hello")`.
+fn get_article_function(path: &str) -> JinjaValue {
+    let data = data::read();
+    let article = data.get_article(path);
+    JinjaValue::from_serializable(&article)
+}
+
 fn get_entity(name: &str) -> Result<JinjaValue, JinjaError> {
     match name {
         "authors" => {
@@ -240,19 +1022,32 @@ fn get_entity(name: &str) -> Result<JinjaValue, JinjaError> {
 
 static DATA_JSON: OnceCell<RwLock<HashMap<String, JinjaValue>>> = OnceCell::new();
 
-fn load_json(filename: &str) -> Result<JinjaValue, JinjaError> {
+// Load the json file relative to the project `root` directory.
+fn load_json(
+    root: &Path,
+    sandbox_config: &SandboxConfig,
+    filename: &str,
+) -> Result<JinjaValue, JinjaError> {
+    if sandbox_config.enforced {
+        return Err(JinjaError::new(
+            ErrorKind::InvalidOperation,
+            "`load_json` is disabled in the sandbox mode",
+        ));
+    }
     let data = DATA_JSON.get_or_init(|| RwLock::new(HashMap::new()));
     if let Some(value) = { data.read().get(filename).cloned() } {
         return Ok(value);
     }
 
-    let mut path = env::current_dir().unwrap();
-    for segment in filename.split('/') {
-        if segment.starts_with('.') || segment.contains('\\') {
-            return Err(JinjaError::new(ErrorKind::InvalidOperation, "bad filename"));
-        }
-        path.push(segment);
+    if filename
+        .split('/')
+        .any(|segment| segment.starts_with('.') || segment.contains('\\'))
+    {
+        return Err(JinjaError::new(ErrorKind::InvalidOperation, "bad filename"));
     }
+    let path = sandbox_config
+        .resolve_path(root, filename)
+        .map_err(|err| JinjaError::new(ErrorKind::InvalidOperation, err.to_string()))?;
     println!("Loading json data from {}", path.display());
 
     let contents = fs::read(&path).map_err(|err| {
@@ -266,22 +1061,82 @@ fn load_json(filename: &str) -> Result<JinjaValue, JinjaError> {
     Ok(value)
 }
 
-fn copy_static_assets(source: &Path, dest: &Path) -> Result<()> {
-    let static_dir = source.join("static");
-    if static_dir.exists() {
-        copy_dir(&static_dir, dest)?;
+fn copy_static_assets(source: &Path, dest: &Path, zine: &Zine) -> Result<()> {
+    assets::copy_static_dir(
+        source,
+        dest,
+        &zine.build_config,
+        &zine.media_config,
+        &zine.sandbox_config,
+    )?;
+    if zine.media_config.localize_remote {
+        media::copy_localized_media(source, dest, zine.media_config.strip_metadata)?;
     }
 
     // Copy builtin static files into dest static dir.
     let dest_static_dir = dest.join("static");
-    #[allow(clippy::needless_borrow)]
-    fs::create_dir_all(&dest_static_dir)?;
 
     #[cfg(not(debug_assertions))]
-    include_dir::include_dir!("static").extract(dest_static_dir)?;
+    extract_builtin_static(&BUILTIN_STATIC_DIR, &dest_static_dir)?;
     // Alwasy copy static directory in debug mode.
     #[cfg(debug_assertions)]
-    copy_dir(Path::new("static"), dest)?;
+    output::copy_dir(Path::new("static"), &dest_static_dir)?;
+
+    // The static files of the theme package override the builtin ones,
+    // but never the files of the project `static` directory.
+    if let Some(static_dir) = zine
+        .theme
+        .loaded_package
+        .as_ref()
+        .and_then(|package| package.static_dir.as_ref())
+    {
+        assets::copy_theme_static_dir(static_dir, source, &dest_static_dir, &zine.sandbox_config)?;
+    }
+
+    Ok(())
+}
+
+/// Whether the `path` (relative to the `static` directory) is a builtin static file.
+pub fn is_builtin_static(path: &str) -> bool {
+    #[cfg(not(debug_assertions))]
+    {
+        BUILTIN_STATIC_DIR.get_file(path).is_some()
+    }
+    // The static directory is copied from the working directory in debug mode.
+    #[cfg(debug_assertions)]
+    {
+        Path::new("static").join(path).is_file()
+    }
+}
 
+#[cfg(not(debug_assertions))]
+static BUILTIN_STATIC_DIR: include_dir::Dir = include_dir::include_dir!("static");
+
+#[cfg(not(debug_assertions))]
+fn extract_builtin_static(dir: &include_dir::Dir, dest_static_dir: &Path) -> Result<()> {
+    for file in dir.files() {
+        output::write(&dest_static_dir.join(file.path()), file.contents())?;
+    }
+    for dir in dir.dirs() {
+        extract_builtin_static(dir, dest_static_dir)?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::entity::SandboxConfig;
+
+    use super::build_id;
+
+    #[test]
+    fn test_build_id_in_sandbox() {
+        let mut sandbox_config = SandboxConfig::default();
+        sandbox_config.enforce();
+        // No git revision since the git command is never run.
+        let build_id = build_id(&env::current_dir().unwrap(), &sandbox_config);
+        assert!(build_id.parse::<i64>().is_ok());
+    }
+}
@@ -1,16 +1,24 @@
-use std::{borrow::Cow, collections::HashMap, fs, path::Path};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{ensure, Context as _, Result};
+use anyhow::{bail, ensure, Context as _, Result};
 use genkit::{current_mode, Mode};
-use genkit::{html::Meta, markdown, Context};
+use genkit::{helpers, html::Meta, markdown, Context};
 use minijinja::Environment;
 use rayon::prelude::{ParallelBridge, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use time::Date;
 
-use crate::{data, engine, i18n};
+use crate::{
+    data, engine, html, i18n,
+    social_card::{self, SocialCard},
+};
 
-use super::{AuthorId, Entity};
+use super::{author::AuthorSignature, Author, AuthorId, Entity};
 
 /// The Meta info of Article.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,11 +35,57 @@ pub struct MetaArticle {
     /// The author id of this article.
     /// An article can has zero, one or multiple authors.
     pub author: Option<AuthorId>,
+    /// The translator id of this article, only meaningful in the `i18n` translations.
+    pub translator: Option<AuthorId>,
     pub cover: Option<String>,
+    /// The OpenGraph and Twitter card image, fallback to the generated social card
+    /// if the `[social_cards]` is enabled, otherwise the cover.
+    pub social_image: Option<String>,
+    /// The id of the series this article belongs to, declared in `[series]`
+    /// of the root `zine.toml`.
+    pub series: Option<String>,
     /// The publish date. Format like YYYY-MM-DD.
     #[serde(with = "genkit::helpers::serde_date")]
     #[serde(default = "MetaArticle::default_pub_date")]
     pub pub_date: Date,
+    /// The last significant update date, e.g. a correction. Format like YYYY-MM-DD.
+    #[serde(default, with = "genkit::helpers::serde_date::options")]
+    pub updated_date: Option<Date>,
+    /// The teaser markdown before the `<!-- more -->` marker, if any.
+    #[serde(skip_deserializing, default)]
+    pub teaser: Option<String>,
+    /// The word count of the content, each CJK character counts as a word.
+    #[serde(skip_deserializing, default)]
+    pub word_count: usize,
+    /// The estimated reading time in minutes.
+    #[serde(skip_deserializing, default)]
+    pub reading_time: usize,
+    /// Keep this article out of the search engines, the sitemap and the search indexes,
+    /// see [`MetaArticle::visibility`].
+    #[serde(default)]
+    pub noindex: bool,
+    /// Keep this article out of the feeds, see [`MetaArticle::visibility`].
+    #[serde(default)]
+    pub exclude_from_feed: bool,
+    /// The expiry date, the article is excluded from the builds since this date,
+    /// e.g. the time-limited announcements. Format like YYYY-MM-DD.
+    #[serde(default, with = "genkit::helpers::serde_date::options")]
+    pub expire_date: Option<Date>,
+    /// The archive url of the expired article, a tombstone page linking to it
+    /// is rendered in place of the article rather than removing the article.
+    pub archive_url: Option<String>,
+}
+
+/// Where an article is visible besides its own page, resolved from
+/// the `noindex` and `exclude_from_feed` flags of the article.
+///
+/// All outputs should check the visibility rather than the flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Visibility {
+    /// Listed in the atom feeds.
+    pub feed: bool,
+    /// Listed in the sitemap and the search indexes, and indexed by the search engines.
+    pub index: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -41,8 +95,8 @@ pub struct Article {
     /// The article's markdown content.
     #[serde(default, skip_serializing)]
     pub markdown: String,
-    /// The optional topics of this article.
-    #[serde(default)]
+    /// The optional topics of this article, either a single topic or a topic list.
+    #[serde(default, deserialize_with = "deserialize_topics")]
     #[serde(rename(deserialize = "topic"))]
     pub topics: Vec<String>,
     /// Whether the article is an featured article.
@@ -54,11 +108,95 @@ pub struct Article {
     /// generate HTML file in this mode.
     #[serde(default)]
     publish: bool,
+    /// Whether the article is a draft, which is never published regardless of `publish`,
+    /// even in `zine serve` mode. Use `zine serve --drafts` to preview the drafts
+    /// under the unlisted `/drafts/` prefix.
+    #[serde(default)]
+    pub draft: bool,
+    /// The reading layout of this article.
+    #[serde(default)]
+    pub layout: ArticleLayout,
+    /// The kind of this article, see [`ArticleKind`].
+    #[serde(default)]
+    pub kind: ArticleKind,
+    /// The links of the roundup article, declared in `[[article.link]]` tables.
+    #[serde(default, rename = "link")]
+    pub links: Vec<RoundupLink>,
     /// The canonical link of this article.
     /// See issue: https://github.com/zineland/zine/issues/141
     canonical: Option<String>,
+    /// Whether number the headings of this article,
+    /// fallback to the `number_headings` of the `[markdown]` table if missing.
+    #[serde(default, skip_serializing)]
+    number_headings: Option<bool>,
+    /// Whether render the ToC of this article,
+    /// fallback to the `toc` of the `[markdown]` table if missing.
+    #[serde(default, skip_serializing)]
+    toc: Option<bool>,
+    /// The deepest heading level listed in the ToC of this article,
+    /// fallback to the `toc_depth` of the `[markdown]` table if missing.
+    #[serde(default, skip_serializing)]
+    toc_depth: Option<usize>,
+    /// Whether link the glossary terms in this article, see [`super::link_terms`].
+    #[serde(default = "Article::default_glossary", skip_serializing)]
+    glossary: bool,
     #[serde(default, skip_serializing)]
     pub i18n: HashMap<String, Article>,
+    /// Whether the `expire_date` has passed, resolved in parsing.
+    #[serde(skip)]
+    expired: bool,
+}
+
+/// The reading layout of an article, each layout has its own template.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArticleLayout {
+    #[default]
+    Default,
+    /// A wider column for long-form essays, tables and code.
+    Wide,
+    /// Full-width images with a narrow text column, for photography features.
+    PhotoEssay,
+}
+
+impl ArticleLayout {
+    fn template_name(&self) -> &'static str {
+        match self {
+            ArticleLayout::Default => "article.jinja",
+            ArticleLayout::Wide => "article-wide.jinja",
+            ArticleLayout::PhotoEssay => "article-photo-essay.jinja",
+        }
+    }
+}
+
+/// The kind of an article.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArticleKind {
+    #[default]
+    Default,
+    /// A link roundup, the structured `links` are rendered after the markdown content.
+    Roundup,
+}
+
+/// A link of the roundup article, e.g.
+///
+/// ```toml
+/// [[article.link]]
+/// url = "https://blog.rust-lang.org/2023/06/01/Rust-1.70.0.html"
+/// title = "Announcing Rust 1.70.0"
+/// blurb = "The sparse protocol is the default now."
+/// via = "This Week in Rust"
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RoundupLink {
+    pub url: String,
+    /// The link title, the link is rendered as an url preview card if missing.
+    pub title: Option<String>,
+    /// A short comment of the link (markdown format).
+    pub blurb: Option<String>,
+    /// Where the link is found, e.g. a newsletter or a person.
+    pub via: Option<String>,
 }
 
 /// The translation info of an article.
@@ -66,10 +204,14 @@ pub struct Article {
 struct Translations<'a> {
     // The locale name.
     name: &'static str,
+    // The `hreflang` of locale.
+    hreflang: String,
     // Article slug.
     slug: &'a String,
     // Article path.
     path: &'a Option<String>,
+    // The translator of this translation.
+    translator: &'a Option<AuthorId>,
 }
 
 impl MetaArticle {
@@ -84,6 +226,26 @@ impl MetaArticle {
     fn is_default_pub_date(&self) -> bool {
         self.pub_date == Date::MIN
     }
+
+    /// Resolve the visibility of this article.
+    pub fn visibility(&self) -> Visibility {
+        Visibility {
+            feed: !self.exclude_from_feed,
+            index: !self.noindex,
+        }
+    }
+
+    /// Check whether the article is expired at `today`.
+    pub fn is_expired(&self, today: Date) -> bool {
+        self.expire_date.is_some_and(|date| date <= today)
+    }
+
+    /// The last modified date, which is the `updated_date` or `pub_date`.
+    pub fn last_modified(&self) -> Date {
+        self.updated_date
+            .unwrap_or(self.pub_date)
+            .max(self.pub_date)
+    }
 }
 
 impl std::fmt::Debug for Article {
@@ -96,23 +258,171 @@ impl std::fmt::Debug for Article {
     }
 }
 
+/// The article fields shared in the zine data, which are looked up by the
+/// `get_article` and `excerpt_html` template functions.
+#[derive(Clone, Debug, Serialize)]
+pub struct ArticleSource {
+    #[serde(flatten)]
+    pub meta: MetaArticle,
+    pub topics: Vec<String>,
+    #[serde(skip)]
+    pub markdown: String,
+    #[serde(skip)]
+    glossary: bool,
+}
+
+impl ArticleSource {
+    /// Render the markdown into html with the same pipeline of the article page.
+    pub fn render_html(&self, env: &Environment) -> Result<String> {
+        render_article_html(env, &self.markdown, &self.meta, self.glossary).map(|(html, _)| html)
+    }
+}
+
 impl Article {
-    /// Check whether `author` name is the author of this article.
+    /// The fields of this article shared in the zine data.
+    pub fn source(&self) -> ArticleSource {
+        ArticleSource {
+            meta: self.meta.clone(),
+            topics: self.topics.clone(),
+            markdown: self.markdown.clone(),
+            glossary: self.glossary,
+        }
+    }
+
+    fn default_glossary() -> bool {
+        true
+    }
+
+    /// Check whether `author` name is the author or translator of this article.
     pub fn is_author(&self, author: &str) -> bool {
-        self.meta
-            .author
-            .as_ref()
-            .map(|inner| inner.is_author(author))
-            .unwrap_or_default()
+        [&self.meta.author, &self.meta.translator]
+            .into_iter()
+            .flatten()
+            .any(|inner| inner.is_author(author))
     }
 
     /// Check whether the article need publish.
     ///
-    /// The article need publish in any of two conditions:
-    /// - the publish property is true
+    /// The article isn't a draft and need publish in any of two conditions:
+    /// - the publish property is true and the article isn't expired
     /// - in `zine serve` mode
     pub fn need_publish(&self) -> bool {
-        self.publish || matches!(current_mode(), Mode::Serve)
+        !self.draft && ((self.publish && !self.expired) || matches!(current_mode(), Mode::Serve))
+    }
+
+    /// The archive url of the tombstone page, if this published article
+    /// is expired and has an `archive_url`.
+    pub fn tombstone_url(&self) -> Option<&str> {
+        // A published article doesn't need publish only if it's expired.
+        self.meta
+            .archive_url
+            .as_deref()
+            .filter(|_| self.publish && !self.draft && !self.need_publish())
+    }
+
+    /// The canonical link of this article, if any.
+    pub fn canonical(&self) -> Option<&str> {
+        self.canonical.as_deref().filter(|url| !url.is_empty())
+    }
+
+    // Check the roundup article has links, and all links are absolute urls.
+    fn validate_links(&self) -> Result<()> {
+        ensure!(
+            self.kind != ArticleKind::Roundup || !self.links.is_empty(),
+            "the roundup article `{}` has no links, which are declared in `[[article.link]]` tables",
+            self.meta.title
+        );
+        if let Some(link) = self
+            .links
+            .iter()
+            .find(|link| !link.url.starts_with("http://") && !link.url.starts_with("https://"))
+        {
+            bail!(
+                "the link `{}` of article `{}` isn't an absolute url",
+                link.url,
+                self.meta.title
+            );
+        }
+        Ok(())
+    }
+
+    // Apply the `default_topics` and `default_cover` of the authors
+    // if this article omits them.
+    fn apply_author_defaults(&mut self) {
+        let Some(author) = self.meta.author.as_ref() else {
+            return;
+        };
+        let zine_data = data::read();
+        let authors = author
+            .ids()
+            .into_iter()
+            .filter_map(|id| zine_data.get_author_by_id(id))
+            .collect::<Vec<_>>();
+        if self.topics.is_empty() {
+            for topic in authors.iter().flat_map(|author| &author.default_topics) {
+                if !self.topics.contains(topic) {
+                    self.topics.push(topic.clone());
+                }
+            }
+        }
+        if self.meta.has_empty_cover() {
+            if let Some(cover) = authors
+                .iter()
+                .find_map(|author| author.default_cover.as_ref())
+            {
+                self.meta.cover = Some(cover.clone());
+            }
+        }
+    }
+
+    // The OpenGraph image of this article, see [`MetaArticle::social_image`].
+    fn social_image(&self, context: &Context) -> Option<Cow<'_, str>> {
+        if let Some(social_image) = self.meta.social_image.as_deref() {
+            return Some(Cow::Borrowed(social_image));
+        }
+        let zine_data = data::read();
+        let Some(config) = zine_data.get_social_cards_config() else {
+            return self.meta.cover.as_deref().map(Cow::Borrowed);
+        };
+        let authors = self
+            .meta
+            .author
+            .iter()
+            .flat_map(|author| author.ids())
+            .map(|id| match zine_data.get_author_by_id(id) {
+                Some(Author {
+                    name: Some(name), ..
+                }) => name.clone(),
+                _ => helpers::capitalize(id),
+            })
+            .collect::<Vec<_>>();
+        let card = SocialCard {
+            title: self.meta.title.clone(),
+            authors: authors.join(", "),
+            issue: context
+                .get("issue")
+                .and_then(|issue| issue.get("title"))
+                .and_then(|title| title.as_str())
+                .unwrap_or_default()
+                .to_owned(),
+            site: zine_data.get_site().name.clone(),
+        };
+        Some(Cow::Owned(social_card::card_url(card, config)))
+    }
+
+    // The signature blocks of the authors of this article.
+    fn author_signatures(&self) -> Vec<AuthorSignature> {
+        let Some(author) = self.meta.author.as_ref() else {
+            return Vec::new();
+        };
+        let zine_data = data::read();
+        let site_url = zine_data.get_site().url.trim_end_matches('/');
+        author
+            .ids()
+            .into_iter()
+            .filter_map(|id| zine_data.get_author_by_id(id))
+            .map(|author| author.signature(site_url))
+            .collect()
     }
 
     fn get_translations(&self) -> Vec<Translations<'_>> {
@@ -122,8 +432,10 @@ impl Article {
             .map(|(locale, article)| Translations {
                 name: i18n::get_locale_name(locale)
                     .unwrap_or_else(|| panic!("Currently, we don't support locale: `{locale}`")),
+                hreflang: i18n::hreflang(locale),
                 slug: &article.meta.slug,
                 path: &article.meta.path,
+                translator: &article.meta.translator,
             })
             .collect::<Vec<_>>();
 
@@ -135,28 +447,104 @@ impl Article {
                 name: i18n::get_locale_name(&site.locale).unwrap_or_else(|| {
                     panic!("Currently, we don't support locale: `{}`", site.locale)
                 }),
+                hreflang: i18n::hreflang(&site.locale),
                 slug: &self.meta.slug,
                 path: &self.meta.path,
+                translator: &self.meta.translator,
             });
             translations.sort_by_key(|t| t.name);
         }
         translations
     }
 
+    // Check all topics are declared in the root `zine.toml`.
+    fn validate_topics(&self) -> Result<()> {
+        let zine_data = data::read();
+        let invalid_topics = self
+            .topics
+            .iter()
+            .filter(|topic| !zine_data.is_valid_topic(topic))
+            .collect::<Vec<_>>();
+        if invalid_topics.is_empty() {
+            return Ok(());
+        }
+
+        let build_config = zine_data.get_build_config();
+        if build_config.auto_register_topics && matches!(current_mode(), Mode::Serve) {
+            let mut zine_data = data::write();
+            invalid_topics
+                .into_iter()
+                .for_each(|topic| zine_data.add_topic(topic));
+            return Ok(());
+        }
+
+        for topic in invalid_topics {
+            let mut message = format!(
+                "the topic `{topic}` of article `{}` is invalid, please declare it in the root `zine.toml`",
+                self.meta.title
+            );
+            if let Some(suggestion) = suggest_topic(topic, zine_data.get_topics()) {
+                message.push_str(&format!(", did you mean `{suggestion}`?"));
+            }
+            ensure!(!build_config.invalid_topic.is_error(), "{message}");
+            println!("Warning: {message}");
+        }
+        Ok(())
+    }
+
     fn parse(&mut self, source: &Path) -> Result<()> {
-        let file_path = source.join(&self.meta.file);
-        self.markdown = fs::read_to_string(&file_path).with_context(|| {
+        let zine_data = data::read();
+        let file_path = zine_data
+            .get_sandbox_config()
+            .resolve_project_path(source, &self.meta.file)?;
+        let markdown = fs::read_to_string(&file_path).with_context(|| {
             format!("Failed to read markdown file of `{}`", file_path.display())
         })?;
+        self.markdown =
+            super::preprocess(zine_data.get_markdown_preprocessors(), markdown, &file_path)?;
+        self.meta.teaser = crate::markdown::split_teaser(&self.markdown).map(ToOwned::to_owned);
+        let stats = crate::markdown::reading_stats(&crate::markdown::plain_text(&self.markdown));
+        self.meta.word_count = stats.word_count;
+        self.meta.reading_time = stats.reading_time;
+        self.expired = self.meta.is_expired(engine::frozen_now().date());
+        let lint_config = zine_data.get_lint_config();
+        // The drafts are unfinished by definition.
+        if !self.draft {
+            lint_config.check_markers(&file_path, &self.markdown)?;
+        }
+        lint_config.check_raw_html_tags(
+            &self.meta.title,
+            &crate::markdown::raw_html_tags(&self.markdown),
+        )?;
+        if lint_config.require_image_alt {
+            if let Some(url) = crate::markdown::images_without_alt(&self.markdown).first() {
+                bail!(
+                    "the image `{url}` of article `{}` has no alt text, which is required by `require_image_alt` of [lint]",
+                    self.meta.title
+                );
+            }
+        }
+
+        self.validate_links()?;
 
+        if let Some(author) = self.meta.author.as_mut() {
+            author.normalize();
+        }
+        if let Some(translator) = self.meta.translator.as_mut() {
+            translator.normalize();
+        }
         // Fallback to file name if no slug specified.
         if self.meta.path.is_none() && self.meta.slug.is_empty() {
-            self.meta.slug = self.meta.file.replace(".md", "")
+            self.meta.slug = zine_data
+                .get_site()
+                .slug_strategy
+                .apply(&self.meta.file.replace(".md", ""));
         }
+        super::slug::normalize_slug("article", &mut self.meta.slug);
+        self.apply_author_defaults();
         // Fallback to the default placeholder image if the cover is missing.
         if self.meta.has_empty_cover() {
-            let data = data::read();
-            self.meta.cover = data.get_theme().default_cover.clone();
+            self.meta.cover = zine_data.get_theme().default_cover.clone();
         }
         // Ensure the path starts with / if exists.
         if matches!(self.meta.path.as_ref(), Some(path) if !path.starts_with('/')) {
@@ -189,29 +577,139 @@ impl Article {
                         Cow::Owned(format!("{}/{}", issue_slug, self.meta.slug))
                     },
                 ),
-                image: self.meta.cover.as_deref().map(Cow::Borrowed),
+                image: self.social_image(&context),
             },
         );
         context.insert("page_type", "article");
         context.insert("article", &self);
         context.insert("canonical_url", &self.canonical);
+        if !self.meta.visibility().index {
+            context.insert("noindex", &true);
+        }
+        context.insert("signatures", &self.author_signatures());
+        if let Some(url) = self.meta.path.clone().or_else(|| {
+            context
+                .get("issue")
+                .and_then(|issue| issue.get("slug"))
+                .and_then(|v| v.as_str())
+                .map(|issue_slug| format!("{issue_slug}/{}", self.meta.slug))
+        }) {
+            let zine_data = data::read();
+            context.insert("article_data", &zine_data.get_article_data(&url));
+            context.insert("related_articles", zine_data.get_related_articles(&url));
+            context.insert("series", &zine_data.get_series_navigation(&url));
+        }
 
-        let (html, toc) = markdown::render_html_with_toc(&self.markdown);
+        let (mut html, mut toc) =
+            render_article_html(env, &self.markdown, &self.meta, self.glossary)?;
+        let zine_data = data::read();
+        if let Some(items) = toc.as_array_mut() {
+            if !self.toc.unwrap_or_else(|| zine_data.get_toc()) {
+                items.clear();
+            } else if let Some(depth) = self.toc_depth.or_else(|| zine_data.get_toc_depth()) {
+                retain_toc_depth(items, depth);
+            }
+        }
+        if self
+            .number_headings
+            .unwrap_or_else(|| zine_data.get_number_headings())
+        {
+            // The numbers are prefixed after rendering, so the heading anchors keep stable.
+            let (numbered, numbers) = html::number_headings(&html)?;
+            html = numbered;
+            for item in toc.as_array_mut().into_iter().flatten() {
+                let number = item
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .and_then(|id| numbers.get(id));
+                if let (Some(number), Some(item)) = (number.cloned(), item.as_object_mut()) {
+                    let title = item.get("title").and_then(|title| title.as_str());
+                    let title = format!("{number} {}", title.unwrap_or_default());
+                    item.insert("title".into(), title.into());
+                    item.insert("number".into(), number.into());
+                }
+            }
+        }
+        if self.meta.teaser.is_some() {
+            // Replace the `<!-- more -->` marker with a jump anchor.
+            html = html
+                .lines()
+                .map(|line| {
+                    if crate::markdown::is_more_marker(line) {
+                        r#"<span id="more"></span>"#
+                    } else {
+                        line
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
         context.insert("html", &html);
         context.insert("toc", &toc);
 
+        // Fallback to the default layout if the template is missing.
+        let template = match self.layout.template_name() {
+            template if env.get_template(template).is_ok() => template,
+            template => {
+                println!(
+                    "Warning: the layout template `{template}` of article `{}` is missing, fallback to the default layout.",
+                    self.meta.title
+                );
+                ArticleLayout::Default.template_name()
+            }
+        };
+        engine::render(env, template, context, self.output_dir(dest))
+    }
+
+    // The output directory of this article in the issue directory `dest`.
+    fn output_dir(&self, dest: &Path) -> PathBuf {
         if let Some(path) = self.meta.path.as_ref() {
             let mut dest = dest.to_path_buf();
             dest.pop();
-            engine::render(
+            dest.join(path.trim_start_matches('/'))
+        } else {
+            dest.join(&self.meta.slug)
+        }
+    }
+
+    /// Render the tombstone pages of this expired article and its translations, if any.
+    pub fn render_tombstones(
+        &self,
+        env: &Environment,
+        context: Context,
+        dest: &Path,
+    ) -> Result<()> {
+        if self.tombstone_url().is_none() {
+            return Ok(());
+        }
+        // The translations inherit the `expire_date` and `archive_url` in parsing.
+        for article in std::iter::once(self).chain(self.i18n.values()) {
+            let (Some(expire_date), Some(archive_url)) = (
+                article.meta.expire_date,
+                article.meta.archive_url.as_deref(),
+            ) else {
+                continue;
+            };
+            let mut context = context.clone();
+            context.insert(
+                "meta",
+                &Meta {
+                    title: Cow::Borrowed(&article.meta.title),
+                    description: Cow::Borrowed(""),
+                    url: None,
+                    image: None,
+                },
+            );
+            engine::render_tombstone(
                 env,
-                "article.jinja",
                 context,
-                dest.join(path.trim_start_matches('/')),
-            )
-        } else {
-            engine::render(env, "article.jinja", context, dest.join(&self.meta.slug))
+                &article.meta.title,
+                expire_date,
+                archive_url,
+                article.output_dir(dest),
+            )?;
         }
+        Ok(())
     }
 }
 
@@ -223,17 +721,7 @@ impl Entity for Article {
             "`pub_date` is required for article `{}`",
             self.meta.title
         );
-        {
-            let zine_data = data::read();
-            self.topics.iter().for_each(|topic| {
-                if !zine_data.is_valid_topic(topic) {
-                    println!(
-                        "Warning: the topic `{}` is invalid, please declare it in the root `zine.toml`",
-                        topic
-                    )
-                }
-            });
-        }
+        self.validate_topics()?;
 
         for article in self.i18n.values_mut() {
             // Extend topics from the origin article
@@ -244,6 +732,22 @@ impl Entity for Article {
             if article.meta.has_empty_cover() {
                 article.meta.cover = self.meta.cover.clone();
             }
+            if article.layout == ArticleLayout::Default {
+                article.layout = self.layout;
+            }
+            if article.kind == ArticleKind::Default && article.links.is_empty() {
+                article.kind = self.kind;
+                article.links = self.links.clone();
+            }
+            // The translations are never more visible than the origin article.
+            article.meta.noindex |= self.meta.noindex;
+            article.meta.exclude_from_feed |= self.meta.exclude_from_feed;
+            if article.meta.expire_date.is_none() {
+                article.meta.expire_date = self.meta.expire_date;
+            }
+            if article.meta.archive_url.is_none() {
+                article.meta.archive_url = self.meta.archive_url.clone();
+            }
             // Fallback to original article date if the `pub_date` is missing
             if article.meta.is_default_pub_date() {
                 article.meta.pub_date = self.meta.pub_date;
@@ -264,3 +768,175 @@ impl Entity for Article {
         Ok(())
     }
 }
+
+fn deserialize_topics<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Topics {
+        One(String),
+        List(Vec<String>),
+    }
+
+    Ok(match Topics::deserialize(deserializer)? {
+        Topics::One(topic) => vec![topic],
+        Topics::List(topics) => topics,
+    })
+}
+
+// Render the article `markdown` into the html and the ToC, the glossary terms are linked
+// if `glossary` is true, the fenced blocks are rendered by the block and shortcode templates,
+// then the image alts and the responsive images are filled.
+fn render_article_html(
+    env: &Environment,
+    markdown: &str,
+    meta: &MetaArticle,
+    glossary: bool,
+) -> Result<(String, serde_json::Value)> {
+    let zine_data = data::read();
+    let markdown = if glossary && !zine_data.get_glossary().is_empty() {
+        Cow::Owned(super::link_terms(markdown, zine_data.get_glossary()))
+    } else {
+        Cow::Borrowed(markdown)
+    };
+    let expanded = crate::markdown::replace_fenced_blocks(&markdown, |name, options, body| {
+        engine::render_fenced_block(env, name, options, body, Some(meta))
+            .map(|rendered| rendered.with_context(|| format!("In the article `{}`", meta.title)))
+    })?;
+    let (html, toc) = markdown::render_html_with_toc(&expanded);
+    let mut html = html::fill_image_alt(&html)?;
+    if let Some(images_config) = zine_data.get_images_config() {
+        html = html::responsive_images(&html, |src| {
+            crate::images::responsive_image(src, images_config)
+        })?;
+    }
+    Ok((html, serde_json::to_value(toc)?))
+}
+
+// Retain the ToC items whose heading level is not deeper than `depth`.
+fn retain_toc_depth(items: &mut Vec<serde_json::Value>, depth: usize) {
+    items.retain(|item| {
+        item.get("depth")
+            .and_then(|level| level.as_u64())
+            .map_or(true, |level| level as usize <= depth)
+    });
+}
+
+// Find the most similar declared topic of the invalid `topic`, if any.
+fn suggest_topic<'a>(topic: &str, topics: &'a [String]) -> Option<&'a str> {
+    let topic = topic.to_lowercase();
+    // Allow one typo in every three characters.
+    let max_distance = (topic.chars().count() / 3).max(1);
+    topics
+        .iter()
+        .map(|candidate| (edit_distance(&topic, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+// The Levenshtein distance of two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if a_char == *b_char {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde_json::json;
+    use test_case::test_case;
+
+    use crate::{
+        data,
+        entity::SandboxConfig,
+        fixture::{self, ArticleBuilder},
+    };
+
+    use super::{retain_toc_depth, suggest_topic, ArticleKind};
+
+    #[test_case("rustlag", Some("rustlang"))]
+    #[test_case("RustLang", Some("rustlang"))]
+    #[test_case("go", Some("Go"))]
+    #[test_case("gp", Some("Go"))]
+    #[test_case("python", None)]
+    fn test_suggest_topic(topic: &str, suggestion: Option<&str>) {
+        let topics = vec![String::from("rustlang"), String::from("Go")];
+        assert_eq!(suggest_topic(topic, &topics), suggestion);
+    }
+
+    #[test_case(json!([{ "url": "https://zine.example", "via": "Alice" }]), true; "ok")]
+    #[test_case(json!([]), false; "no links")]
+    #[test_case(json!([{ "url": "/issue-1/hello" }]), false; "relative url")]
+    fn test_roundup_links(links: serde_json::Value, valid: bool) {
+        let article = ArticleBuilder::new("links", "Weekly links")
+            .set("kind", "roundup")
+            .set("link", links)
+            .build();
+        assert_eq!(article.kind, ArticleKind::Roundup);
+        assert_eq!(article.validate_links().is_ok(), valid);
+    }
+
+    #[test]
+    fn test_article_file_outside_project() {
+        let _data = fixture::lock();
+        let mut sandbox_config = SandboxConfig::default();
+        sandbox_config.enforce();
+        data::load();
+        data::write().set_sandbox_config(sandbox_config);
+
+        let mut article = ArticleBuilder::new("passwd", "Passwd")
+            .set("file", "../../etc/passwd")
+            .build();
+        let err = article.parse(&env::temp_dir()).unwrap_err();
+        assert!(err.to_string().contains("disallowed by [sandbox]"));
+    }
+
+    #[test]
+    fn test_draft_never_published() {
+        let article = ArticleBuilder::new("hello", "Hello").build();
+        assert!(article.need_publish());
+
+        let draft = ArticleBuilder::new("hello", "Hello")
+            .set("draft", true)
+            .set("archive_url", "https://archive.example/hello")
+            .build();
+        assert!(!draft.need_publish());
+        assert_eq!(draft.tombstone_url(), None);
+    }
+
+    #[test_case(1, &["intro"])]
+    #[test_case(2, &["intro", "usage"])]
+    #[test_case(6, &["intro", "usage", "options"])]
+    fn test_retain_toc_depth(depth: usize, expected: &[&str]) {
+        let toc = json!([
+            { "depth": 1, "id": "intro", "title": "Intro" },
+            { "depth": 2, "id": "usage", "title": "Usage" },
+            { "depth": 3, "id": "options", "title": "Options" },
+        ]);
+        let mut items = toc.as_array().cloned().unwrap();
+        retain_toc_depth(&mut items, depth);
+        let ids = items
+            .iter()
+            .filter_map(|item| item["id"].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+    }
+}
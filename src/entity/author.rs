@@ -1,11 +1,13 @@
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, collections::BTreeMap, path::Path};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use genkit::{html::Meta, markdown, Context, Entity};
 use minijinja::Environment;
 use serde::{de, ser::SerializeSeq, Deserialize, Serialize};
 
-use crate::engine;
+use crate::{data, engine, output};
+
+use super::{Badge, Pagination};
 
 /// AuthorId represents a single author or multiple co-authors.
 /// Declared in `[[article]]` table.
@@ -35,22 +37,203 @@ pub struct Author {
     #[serde(default)]
     /// Whether the author is a team account.
     pub team: bool,
+    /// The social links of author, the name and url pairs,
+    /// e.g. `links = { github = "https://github.com/zineland" }`.
+    #[serde(default)]
+    pub links: BTreeMap<String, String>,
+    /// The default topics of this author's articles, applied to the new
+    /// article scaffolds and the articles without topics.
+    #[serde(default, skip_serializing)]
+    pub default_topics: Vec<String>,
+    /// The default cover of this author's articles, applied to the new
+    /// article scaffolds and the articles without cover.
+    #[serde(default, skip_serializing)]
+    pub default_cover: Option<String>,
+    /// The organization of the author, e.g. `Rust Foundation`.
+    pub org: Option<String>,
+    /// Whether to publish the contact card of the author, which is the
+    /// `/@<id>/vcard.vcf` file and the hCard markup of the author page.
+    #[serde(default)]
+    pub public_contact: bool,
+    /// The contribution badges computed from the articles, see [`super::BadgeConfig`].
+    #[serde(skip_deserializing)]
+    pub badges: Vec<Badge>,
+}
+
+/// The signature block of an author, rendered at the end of article.
+///
+/// All urls are absolute, so the block is still valid outside of this site, such as in an email.
+#[derive(Debug, Serialize)]
+pub struct AuthorSignature {
+    pub id: String,
+    pub name: String,
+    pub avatar: Option<String>,
+    /// The plain text excerpt of the author's bio.
+    pub bio_excerpt: Option<String>,
+    /// The absolute url of the author page.
+    pub url: String,
+    pub links: BTreeMap<String, String>,
 }
 
 impl AuthorId {
+    /// Get all the author ids.
+    pub fn ids(&self) -> Vec<&str> {
+        match self {
+            Self::One(author_id) => vec![author_id.as_str()],
+            Self::List(authors) => authors.iter().map(String::as_str).collect(),
+        }
+    }
+
     pub fn is_author(&self, id: &str) -> bool {
+        let id = normalize_author_id(id);
         match self {
-            Self::One(author_id) => author_id.eq_ignore_ascii_case(id),
+            Self::One(author_id) => normalize_author_id(author_id) == id,
             Self::List(authors) => authors
                 .iter()
-                .any(|author_id| author_id.eq_ignore_ascii_case(id)),
+                .any(|author_id| normalize_author_id(author_id) == id),
+        }
+    }
+
+    /// Normalize all the author ids, see [`normalize_author_id`].
+    pub fn normalize(&mut self) {
+        match self {
+            Self::One(author_id) => *author_id = normalize_author_id(author_id),
+            Self::List(authors) => {
+                authors
+                    .iter_mut()
+                    .for_each(|author_id| *author_id = normalize_author_id(author_id));
+                // Avoid author duplication after normalization.
+                let mut seen = Vec::with_capacity(authors.len());
+                authors.retain(|author_id| {
+                    if seen.contains(author_id) {
+                        false
+                    } else {
+                        seen.push(author_id.clone());
+                        true
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl Author {
+    /// Assemble the signature block of this author.
+    pub fn signature(&self, site_url: &str) -> AuthorSignature {
+        let absolute_url = |url: &str| {
+            if url.starts_with('/') {
+                format!("{site_url}{url}")
+            } else {
+                url.to_owned()
+            }
+        };
+        AuthorSignature {
+            id: self.id.clone(),
+            name: self.name.clone().unwrap_or_else(|| self.id.clone()),
+            avatar: self.avatar.as_deref().map(absolute_url),
+            bio_excerpt: self
+                .bio
+                .as_ref()
+                .map(|bio| markdown::extract_description(bio))
+                .filter(|excerpt| !excerpt.is_empty()),
+            url: format!("{site_url}/@{}", self.id),
+            links: self
+                .links
+                .iter()
+                .map(|(name, url)| (name.clone(), absolute_url(url)))
+                .collect(),
+        }
+    }
+
+    /// Generate the vCard 4.0 of this author, all urls are absolute.
+    pub fn vcard(&self, site_url: &str) -> String {
+        let signature = self.signature(site_url);
+        let mut lines = vec![
+            String::from("BEGIN:VCARD"),
+            String::from("VERSION:4.0"),
+            format!("FN:{}", escape_text(&signature.name)),
+            format!("NICKNAME:{}", escape_text(&signature.id)),
+            format!("URL:{}", signature.url),
+        ];
+        if let Some(org) = self.org.as_deref() {
+            lines.push(format!("ORG:{}", escape_text(org)));
+        }
+        if let Some(avatar) = signature.avatar.as_deref() {
+            lines.push(format!("PHOTO:{avatar}"));
+        }
+        if let Some(bio) = signature.bio_excerpt.as_deref() {
+            lines.push(format!("NOTE:{}", escape_text(bio)));
         }
+        for (name, url) in &signature.links {
+            lines.push(format!("URL;TYPE={}:{url}", escape_text(name)));
+        }
+        lines.push(String::from("END:VCARD"));
+        lines
+            .iter()
+            .map(|line| fold_content_line(line))
+            .collect::<Vec<_>>()
+            .join("")
+    }
+}
+
+// Escape the text value of vCard and iCalendar.
+pub(super) fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+// Fold the vCard or iCalendar content line into 75 octets lines, each line ends with CRLF.
+pub(super) fn fold_content_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            // The leading space is counted.
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
     }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Normalize the author id by trimming and lowercasing it.
+///
+/// The normalized id is used to match authors everywhere,
+/// such as the `@id` mention and the `/@id` author page url.
+pub fn normalize_author_id(id: &str) -> String {
+    id.trim().to_lowercase()
+}
+
+/// Validate the normalized author id, which must be a valid url path segment.
+pub fn validate_author_id(id: &str) -> Result<()> {
+    if id.is_empty() {
+        bail!("The author id can't be empty");
+    }
+    if let Some(ch) = id
+        .chars()
+        .find(|ch| !(ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.')))
+    {
+        let suggestion = id
+            .split(|ch: char| !(ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.')))
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        bail!(
+            "Invalid character `{ch}` in author id `{id}`, only letters, digits, `-`, `_` and `.` are allowed. Try `{suggestion}` instead."
+        );
+    }
+    Ok(())
 }
 
 impl Entity for Author {
     fn render(&self, env: &Environment, mut context: Context, dest: &Path) -> anyhow::Result<()> {
-        let slug = format!("@{}", self.id.to_lowercase());
+        let slug = format!("@{}", self.id);
         context.insert(
             "meta",
             &Meta {
@@ -66,7 +249,24 @@ impl Entity for Author {
             },
         );
         context.insert("author", &self);
-        engine::render(env, "author.jinja", context, dest.join(slug))?;
+        // The paginated pages are rendered into `@<id>/page/<n>`.
+        let page_dir = context
+            .get("pagination")
+            .and_then(|pagination| pagination.get("current"))
+            .and_then(|current| current.as_u64())
+            .and_then(|current| Pagination::page_dir(current as usize));
+        let dest = match page_dir {
+            Some(page_dir) => dest.join(&slug).join(page_dir),
+            None => dest.join(&slug),
+        };
+        if self.public_contact && page_dir.is_none() {
+            let site_url = data::read().get_site().url.clone();
+            output::write(
+                &dest.join("vcard.vcf"),
+                self.vcard(site_url.trim_end_matches('/')),
+            )?;
+        }
+        engine::render(env, "author.jinja", context, dest)?;
         Ok(())
     }
 }
@@ -131,7 +331,9 @@ impl<'de> de::Visitor<'de> for AuthorNameVisitor {
 
 #[cfg(test)]
 mod tests {
-    use super::AuthorId;
+    use test_case::test_case;
+
+    use super::{normalize_author_id, validate_author_id, Author, AuthorId};
 
     #[test]
     fn test_author_name() {
@@ -167,4 +369,50 @@ mod tests {
         assert!(!a.is_author("John"));
         assert_eq!("[\"Alice\",\"Bob\"]", serde_json::to_string(&a).unwrap());
     }
+
+    #[test_case(" Alice ", "alice", true)]
+    #[test_case("John_Doe", "john_doe", true)]
+    #[test_case("张三", "张三", true)]
+    #[test_case("John Doe", "john doe", false)]
+    #[test_case("a/b", "a/b", false)]
+    #[test_case("  ", "", false)]
+    fn test_normalize_author_id(id: &str, normalized: &str, valid: bool) {
+        assert_eq!(normalize_author_id(id), normalized);
+        assert_eq!(validate_author_id(normalized).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_normalize_author() {
+        let mut a = AuthorId::List(vec![String::from("Alice"), String::from("alice ")]);
+        a.normalize();
+        assert!(matches!(a, AuthorId::List(names) if names == vec![String::from("alice")]));
+    }
+
+    #[test]
+    fn test_author_vcard() {
+        let mut author: Author = serde_json::from_str(
+            r#"{
+                "name": "Doe, John",
+                "avatar": "/static/john.png",
+                "org": "Zine; Inc.",
+                "links": { "github": "https://github.com/zineland/zine/tree/main/docs/authors/john-doe" }
+            }"#,
+        )
+        .unwrap();
+        author.id = String::from("john");
+
+        let vcard = author.vcard("https://zine.example");
+        let lines = vcard.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], "BEGIN:VCARD");
+        assert!(lines.contains(&"FN:Doe\\, John"));
+        assert!(lines.contains(&"URL:https://zine.example/@john"));
+        assert!(lines.contains(&"ORG:Zine\\; Inc."));
+        assert!(lines.contains(&"PHOTO:https://zine.example/static/john.png"));
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(vcard.ends_with("END:VCARD\r\n"));
+        // The folded lines are unfolded by removing the CRLF and the leading space.
+        assert!(vcard.replace("\r\n ", "").contains(
+            "URL;TYPE=github:https://github.com/zineland/zine/tree/main/docs/authors/john-doe"
+        ));
+    }
 }
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+/// The author badges config, declared in `[site.badges]` table.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BadgeConfig {
+    /// The article count thresholds, the highest reached one is
+    /// awarded as a badge, e.g. `10+ articles`.
+    #[serde(default = "default_article_thresholds")]
+    pub articles: Vec<usize>,
+    /// Whether to award the `since <year>` badge of the first article.
+    #[serde(default = "default_true")]
+    pub since: bool,
+    /// Whether to award the `editor` badge.
+    #[serde(default = "default_true")]
+    pub editor: bool,
+}
+
+impl Default for BadgeConfig {
+    fn default() -> Self {
+        Self {
+            articles: default_article_thresholds(),
+            since: true,
+            editor: true,
+        }
+    }
+}
+
+fn default_article_thresholds() -> Vec<usize> {
+    vec![10, 50, 100]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BadgeKind {
+    Articles,
+    Since,
+    Editor,
+}
+
+/// A contribution badge of an author, computed from the articles.
+///
+/// Render it in templates with `fluent(badge.fluent_key, badge.number)`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Badge {
+    pub kind: BadgeKind,
+    /// The article threshold or the year.
+    pub number: Option<i64>,
+    pub fluent_key: &'static str,
+}
+
+impl BadgeConfig {
+    /// Compute the badges of an author with `article_count` articles,
+    /// the first one is published in `first_year`.
+    pub fn badges(
+        &self,
+        article_count: usize,
+        first_year: Option<i32>,
+        editor: bool,
+    ) -> Vec<Badge> {
+        let mut badges = vec![];
+        if self.editor && editor {
+            badges.push(Badge {
+                kind: BadgeKind::Editor,
+                number: None,
+                fluent_key: "badge-editor",
+            });
+        }
+        if let Some(threshold) = self
+            .articles
+            .iter()
+            .filter(|threshold| **threshold > 0 && **threshold <= article_count)
+            .max()
+        {
+            badges.push(Badge {
+                kind: BadgeKind::Articles,
+                number: Some(*threshold as i64),
+                fluent_key: "badge-articles",
+            });
+        }
+        if let Some(year) = first_year.filter(|_| self.since) {
+            badges.push(Badge {
+                kind: BadgeKind::Since,
+                number: Some(year.into()),
+                fluent_key: "badge-since",
+            });
+        }
+        badges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{BadgeConfig, BadgeKind};
+
+    #[test_case(3, None, false, &[])]
+    #[test_case(12, Some(2021), false, &[(BadgeKind::Articles, Some(10)), (BadgeKind::Since, Some(2021))])]
+    #[test_case(120, None, true, &[(BadgeKind::Editor, None), (BadgeKind::Articles, Some(100))])]
+    fn test_badges(
+        article_count: usize,
+        first_year: Option<i32>,
+        editor: bool,
+        expected: &[(BadgeKind, Option<i64>)],
+    ) {
+        let badges = BadgeConfig::default()
+            .badges(article_count, first_year, editor)
+            .into_iter()
+            .map(|badge| (badge.kind, badge.number))
+            .collect::<Vec<_>>();
+        assert_eq!(badges, expected);
+    }
+}
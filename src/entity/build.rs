@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// The build config, declared in the `[build]` table of the root `zine.toml`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BuildConfig {
+    /// Glob patterns of static files which shouldn't be copied
+    /// into the build directory, e.g. `["*.psd"]`.
+    #[serde(default)]
+    pub static_exclude: Vec<String>,
+    /// Warn about the static files larger than this size (in megabytes).
+    /// Default to 10MB, set to 0 to disable the warning.
+    #[serde(default = "BuildConfig::default_large_file_threshold_mb")]
+    pub large_file_threshold_mb: u64,
+    /// The severity of undeclared topics in articles.
+    #[serde(default)]
+    pub invalid_topic: Severity,
+    /// Append the build id as a `?v=` query string to static asset urls,
+    /// to bust the browser and CDN caches after each deployment.
+    #[serde(default)]
+    pub cache_bust: bool,
+    /// Register the undeclared topics automatically in `zine serve` mode,
+    /// rather than reporting them.
+    #[serde(default)]
+    pub auto_register_topics: bool,
+    /// JSON files of external article data keyed by the article path,
+    /// e.g. `{"/issue-1/foo": {"likes": 3}}` produced by a backend.
+    /// They are merged in order and exposed as `article_data` in the article templates.
+    #[serde(default)]
+    pub article_data: Vec<String>,
+    /// Glob patterns of source files which don't trigger a rebuild in `zine serve` mode,
+    /// e.g. `["drafts/*", "*.psd"]`. The hidden files, editor temporary files
+    /// and the dest directory are always ignored.
+    #[serde(default)]
+    pub watch_ignore: Vec<String>,
+    /// Print the word diff of the changed articles after each rebuild in `zine serve` mode,
+    /// which is also available at the `/_changes/` page.
+    #[serde(default)]
+    pub serve_changes: bool,
+    /// The exporters of the search integration files, declared in `[[build.exporter]]` tables.
+    #[serde(default, rename = "exporter")]
+    pub exporters: Vec<ExporterConfig>,
+    /// The threads of the render thread pool, default to the CPU count.
+    /// Overridden by the global `--threads` argument.
+    pub threads: Option<usize>,
+    /// Render the issues one by one rather than in parallel to reduce the peak memory,
+    /// the articles of each issue are still rendered in parallel.
+    /// Useful for the builds on small CI runners.
+    #[serde(default)]
+    pub low_memory: bool,
+    /// The content security policy computed from the build outputs, declared in `[build.csp]` table.
+    pub csp: Option<CspConfig>,
+    /// Register every published url in the `.zine/permalinks.json`,
+    /// then fail the build if a registered url disappears, unless it's redirected
+    /// in the `[redirects]` table.
+    #[serde(default)]
+    pub permalink_registry: bool,
+}
+
+/// The exporter to write the articles into an integration-specific file, e.g.
+///
+/// ```toml
+/// [[build.exporter]]
+/// format = "algolia"
+/// output = "search/algolia.json"
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExporterConfig {
+    pub format: ExportFormat,
+    /// The output path relative to the build directory.
+    pub output: String,
+    /// The max characters of the article content in each record, default to 2000.
+    #[serde(default = "ExporterConfig::default_content_length")]
+    pub content_length: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A JSON array of Algolia records.
+    Algolia,
+    /// The NDJSON documents of Meilisearch.
+    Meilisearch,
+    /// The JSONL documents of Typesense, along with the collection
+    /// schema in `<output stem>.schema.json`.
+    Typesense,
+}
+
+impl ExporterConfig {
+    fn default_content_length() -> usize {
+        2000
+    }
+}
+
+/// The content security policy config, the policy of each page is computed
+/// from the scripts, styles, images, media, embeds and forms it loads, e.g.
+///
+/// ```toml
+/// [build.csp]
+/// mode = "headers"
+/// extra = { connect-src = ["https://plausible.io"] }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct CspConfig {
+    #[serde(default)]
+    pub mode: CspMode,
+    /// The extra sources of the directives which can't be detected from the html,
+    /// such as the `connect-src` of analytics scripts or the fonts of stylesheets.
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CspMode {
+    /// A `<meta http-equiv="Content-Security-Policy">` tag in each page.
+    #[default]
+    Meta,
+    /// The site-wide policy of all pages in the `_headers` file,
+    /// which is supported by Netlify and Cloudflare Pages.
+    Headers,
+}
+
+/// The severity of a validation issue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Print a warning and continue.
+    #[default]
+    Warn,
+    /// Abort the build.
+    Error,
+    /// Abort the build in CI (the `CI` environment variable is set), otherwise warn.
+    Ci,
+}
+
+impl Severity {
+    pub fn is_error(&self) -> bool {
+        match self {
+            Severity::Warn => false,
+            Severity::Error => true,
+            Severity::Ci => std::env::var_os("CI").is_some(),
+        }
+    }
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            static_exclude: Vec::new(),
+            large_file_threshold_mb: Self::default_large_file_threshold_mb(),
+            invalid_topic: Severity::default(),
+            cache_bust: false,
+            auto_register_topics: false,
+            article_data: Vec::new(),
+            watch_ignore: Vec::new(),
+            serve_changes: false,
+            exporters: Vec::new(),
+            threads: None,
+            low_memory: false,
+            csp: None,
+            permalink_registry: false,
+        }
+    }
+}
+
+impl BuildConfig {
+    fn default_large_file_threshold_mb() -> u64 {
+        10
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// The colophon of the site or an issue, declared in `[site.colophon]`
+/// or the `[colophon]` table of issue `zine.toml`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Colophon {
+    /// The typefaces, e.g. `["Inter", "Source Serif"]`.
+    pub typefaces: Vec<String>,
+    /// The tools to make the issue, e.g. `["zine", "Figma"]`.
+    pub tools: Vec<String>,
+    /// The print-run information, e.g. `First printing, 500 copies`.
+    pub print: Option<String>,
+    /// The International Standard Serial Number, e.g. `1234-5678`.
+    pub issn: Option<String>,
+    /// The additional notes (markdown format).
+    pub notes: Option<String>,
+}
@@ -0,0 +1,239 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::html::escape_attr;
+
+use super::Entity;
+
+/// The comment system config, declared in the `[comments]` table of the root `zine.toml`:
+///
+/// ```toml
+/// [comments]
+/// provider = "giscus"
+/// repo = "zineland/zine"
+/// repo_id = "R_kgDOxxxx"
+/// category = "Comments"
+/// category_id = "DIC_kwDOxxxx"
+/// ```
+///
+/// The embed snippet is rendered at the bottom of each article page.
+/// The comment iframes are created by scripts, so the provider origins are added
+/// into the content security policy of the page if `[build.csp]` is enabled,
+/// see [`CommentProvider::csp_sources`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct CommentsConfig {
+    pub provider: CommentProvider,
+    /// The GitHub repository of giscus and utterances, e.g. `owner/repo`.
+    pub repo: Option<String>,
+    /// The repository id of giscus.
+    pub repo_id: Option<String>,
+    /// The discussion category name of giscus.
+    pub category: Option<String>,
+    /// The discussion category id of giscus.
+    pub category_id: Option<String>,
+    /// How to map the pages to the discussions (giscus) or issues (utterances).
+    /// Default to `pathname`.
+    #[serde(default = "CommentsConfig::default_mapping")]
+    pub mapping: String,
+    /// The label of the issues created by utterances.
+    pub label: Option<String>,
+    /// The widget theme, default to `preferred_color_scheme` of giscus
+    /// and `github-light` of utterances.
+    pub theme: Option<String>,
+    /// The site shortname of disqus.
+    pub shortname: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentProvider {
+    Giscus,
+    Utterances,
+    Disqus,
+}
+
+impl CommentProvider {
+    const ALL: [CommentProvider; 3] = [
+        CommentProvider::Giscus,
+        CommentProvider::Utterances,
+        CommentProvider::Disqus,
+    ];
+
+    /// The provider name, the same as the `provider` of `[comments]`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommentProvider::Giscus => "giscus",
+            CommentProvider::Utterances => "utterances",
+            CommentProvider::Disqus => "disqus",
+        }
+    }
+
+    /// Find the provider of the `name`, see [`Self::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|provider| provider.name() == name)
+    }
+
+    /// The CSP directive and source pairs of what the embed loads by scripts,
+    /// which can't be detected from the page html.
+    pub fn csp_sources(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            CommentProvider::Giscus => &[("frame-src", "https://giscus.app")],
+            CommentProvider::Utterances => &[("frame-src", "https://utteranc.es")],
+            // The embed script is loaded from the `<shortname>.disqus.com`.
+            CommentProvider::Disqus => &[
+                ("script-src", "https://*.disqus.com"),
+                ("script-src", "https://*.disquscdn.com"),
+                ("style-src", "https://*.disquscdn.com"),
+                ("img-src", "https://*.disqus.com"),
+                ("img-src", "https://*.disquscdn.com"),
+                ("connect-src", "https://*.disqus.com"),
+                ("frame-src", "https://disqus.com"),
+            ],
+        }
+    }
+}
+
+impl CommentsConfig {
+    fn default_mapping() -> String {
+        String::from("pathname")
+    }
+
+    // The required fields of the provider, along with the values.
+    fn required_fields(&self) -> Vec<(&'static str, Option<&str>)> {
+        match self.provider {
+            CommentProvider::Giscus => vec![
+                ("repo", self.repo.as_deref()),
+                ("repo_id", self.repo_id.as_deref()),
+                ("category", self.category.as_deref()),
+                ("category_id", self.category_id.as_deref()),
+            ],
+            CommentProvider::Utterances => vec![("repo", self.repo.as_deref())],
+            CommentProvider::Disqus => vec![("shortname", self.shortname.as_deref())],
+        }
+    }
+
+    /// Render the embed snippet HTML, the `locale` is the language of giscus.
+    pub fn render_embed(&self, locale: &str) -> Result<String> {
+        let field = |value: &Option<String>| escape_attr(value.as_deref().unwrap_or_default());
+        let mut html = String::new();
+        writeln!(
+            &mut html,
+            r#"<div class="zine-comments" data-provider="{}">"#,
+            self.provider.name()
+        )?;
+        match self.provider {
+            CommentProvider::Giscus => {
+                writeln!(
+                    &mut html,
+                    r#"<script src="https://giscus.app/client.js" data-repo="{}" data-repo-id="{}" data-category="{}" data-category-id="{}" data-mapping="{}" data-reactions-enabled="1" data-input-position="bottom" data-theme="{}" data-lang="{}" data-loading="lazy" crossorigin="anonymous" async></script>"#,
+                    field(&self.repo),
+                    field(&self.repo_id),
+                    field(&self.category),
+                    field(&self.category_id),
+                    escape_attr(&self.mapping),
+                    escape_attr(self.theme.as_deref().unwrap_or("preferred_color_scheme")),
+                    escape_attr(locale),
+                )?;
+            }
+            CommentProvider::Utterances => {
+                write!(
+                    &mut html,
+                    r#"<script src="https://utteranc.es/client.js" repo="{}" issue-term="{}" theme="{}""#,
+                    field(&self.repo),
+                    escape_attr(&self.mapping),
+                    escape_attr(self.theme.as_deref().unwrap_or("github-light")),
+                )?;
+                if let Some(label) = &self.label {
+                    write!(&mut html, r#" label="{}""#, escape_attr(label))?;
+                }
+                writeln!(&mut html, r#" crossorigin="anonymous" async></script>"#)?;
+            }
+            CommentProvider::Disqus => {
+                writeln!(&mut html, r#"<div id="disqus_thread"></div>"#)?;
+                writeln!(
+                    &mut html,
+                    "<script>var disqus_config = function () {{ this.page.url = location.origin + location.pathname; this.page.identifier = location.pathname; }};\
+                    (function () {{ var s = document.createElement('script'); s.src = 'https://{}.disqus.com/embed.js'; s.setAttribute('data-timestamp', +new Date()); (document.head || document.body).appendChild(s); }})();</script>",
+                    // The shortname is a part of the script url, only the subdomain characters are kept.
+                    self.shortname
+                        .as_deref()
+                        .unwrap_or_default()
+                        .chars()
+                        .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '-')
+                        .collect::<String>(),
+                )?;
+            }
+        }
+        writeln!(&mut html, "</div>")?;
+        Ok(html)
+    }
+}
+
+impl Entity for CommentsConfig {
+    fn parse(&mut self, _source: &Path) -> Result<()> {
+        for (name, value) in self.required_fields() {
+            if !matches!(value, Some(value) if !value.trim().is_empty()) {
+                bail!(
+                    "`{name}` is required in [comments] for {:?} provider",
+                    self.provider
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::entity::Entity;
+
+    use super::{CommentProvider, CommentsConfig};
+
+    #[test]
+    fn test_render_embed() {
+        let mut config = CommentsConfig {
+            provider: CommentProvider::Utterances,
+            repo: Some(String::from("zineland/zine")),
+            repo_id: None,
+            category: None,
+            category_id: None,
+            mapping: CommentsConfig::default_mapping(),
+            label: Some(String::from("comment")),
+            theme: None,
+            shortname: None,
+        };
+        config.parse(Path::new("")).unwrap();
+        let html = config.render_embed("en").unwrap();
+        assert!(html.contains(
+            r#"repo="zineland/zine" issue-term="pathname" theme="github-light" label="comment""#
+        ));
+
+        config.provider = CommentProvider::Giscus;
+        assert!(config.parse(Path::new("")).is_err());
+
+        config.provider = CommentProvider::Disqus;
+        config.shortname = Some(String::from("zine'blog"));
+        let html = config.render_embed("en").unwrap();
+        assert!(html.contains("https://zineblog.disqus.com/embed.js"));
+        assert!(html.contains(r#"<div class="zine-comments" data-provider="disqus">"#));
+    }
+
+    #[test]
+    fn test_provider_name() {
+        for provider in CommentProvider::ALL {
+            let name = provider.name();
+            assert_eq!(CommentProvider::from_name(name), Some(provider));
+            assert_eq!(
+                serde_json::from_value::<CommentProvider>(name.into()).unwrap(),
+                provider
+            );
+        }
+        assert_eq!(CommentProvider::from_name("facebook"), None);
+    }
+}
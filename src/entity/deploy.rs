@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use super::Entity;
+
+/// The deploy config of `zine publish`, declared in the `[deploy]` table of the root `zine.toml`:
+///
+/// ```toml
+/// [deploy]
+/// provider = "github-pages"
+/// cname = "zine.example"
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeployConfig {
+    pub provider: DeployProvider,
+    /// The branch of the GitHub Pages. Default to `gh-pages`.
+    #[serde(default = "DeployConfig::default_branch")]
+    pub branch: String,
+    /// The git remote to push the GitHub Pages branch. Default to `origin`.
+    #[serde(default = "DeployConfig::default_remote")]
+    pub remote: String,
+    /// The custom domain of the GitHub Pages, written into the `CNAME` file.
+    pub cname: Option<String>,
+    /// The deploy target of S3 (e.g. `s3://bucket/prefix`) and rsync (e.g. `user@host:/var/www/zine`).
+    pub target: Option<String>,
+    /// Delete the remote files which no longer exist in the build, for S3 and rsync.
+    #[serde(default)]
+    pub delete: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeployProvider {
+    GithubPages,
+    S3,
+    Rsync,
+}
+
+impl DeployConfig {
+    fn default_branch() -> String {
+        String::from("gh-pages")
+    }
+
+    fn default_remote() -> String {
+        String::from("origin")
+    }
+}
+
+impl Entity for DeployConfig {
+    fn parse(&mut self, _source: &Path) -> Result<()> {
+        match self.provider {
+            DeployProvider::GithubPages => {
+                if self.branch.trim().is_empty() {
+                    bail!("`branch` of [deploy] must not be empty");
+                }
+            }
+            DeployProvider::S3 | DeployProvider::Rsync => {
+                if !matches!(self.target.as_deref(), Some(target) if !target.is_empty()) {
+                    bail!(
+                        "`target` is required in [deploy] for {:?} provider",
+                        self.provider
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use crate::markdown;
+
+use super::{
+    author::{escape_text, fold_content_line},
+    Site,
+};
+
+/// An event or announcement, declared in the `[[event]]` array of the root `zine.toml`,
+/// e.g. a community meetup.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub title: String,
+    /// The event date. Format like YYYY-MM-DD.
+    #[serde(with = "genkit::helpers::serde_date")]
+    pub date: Date,
+    /// The url of the event, e.g. the registration page.
+    pub url: Option<String>,
+    /// The event description (markdown format).
+    pub description: Option<String>,
+}
+
+impl Event {
+    /// Whether the event is today or in the future.
+    pub fn is_upcoming(&self, today: Date) -> bool {
+        self.date >= today
+    }
+
+    // The unique id of the event in the calendar, which is stable across builds,
+    // e.g. `20230102-rust-meetup@zine.example`.
+    fn uid(&self, host: &str) -> String {
+        let slug = self
+            .title
+            .to_lowercase()
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        format!("{}-{slug}@{host}", ics_date(self.date))
+    }
+}
+
+/// Render the `events` into an iCalendar feed of the `site`,
+/// each event is an all-day event stamped at `now`.
+pub fn render_calendar(events: &[Event], site: &Site, now: OffsetDateTime) -> String {
+    let host = site
+        .url
+        .split("://")
+        .last()
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or_default();
+    let now = now.to_offset(UtcOffset::UTC);
+    let stamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        ics_date(now.date()),
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        format!("PRODID:-//zine//{}//EN", escape_text(&site.name)),
+        String::from("CALSCALE:GREGORIAN"),
+        format!("X-WR-CALNAME:{}", escape_text(&site.name)),
+    ];
+    for event in events {
+        let start = ics_date(event.date);
+        let end = event
+            .date
+            .next_day()
+            .map(ics_date)
+            .unwrap_or_else(|| start.clone());
+        lines.extend([
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}", event.uid(host)),
+            format!("DTSTAMP:{stamp}"),
+            format!("DTSTART;VALUE=DATE:{start}"),
+            format!("DTEND;VALUE=DATE:{end}"),
+            format!("SUMMARY:{}", escape_text(&event.title)),
+        ]);
+        if let Some(description) = &event.description {
+            let description = markdown::plain_text(description);
+            lines.push(format!("DESCRIPTION:{}", escape_text(description.trim())));
+        }
+        if let Some(url) = &event.url {
+            lines.push(format!("URL:{url}"));
+        }
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    lines.iter().map(|line| fold_content_line(line)).collect()
+}
+
+// The iCalendar DATE value, e.g. `20230102`.
+fn ics_date(date: Date) -> String {
+    format!(
+        "{:04}{:02}{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month};
+
+    use crate::entity::Site;
+
+    use super::{render_calendar, Event};
+
+    #[test]
+    fn test_render_calendar() {
+        let site = Site {
+            url: String::from("https://zine.example/"),
+            name: String::from("Zine, Weekly"),
+            ..Default::default()
+        };
+        let events = [Event {
+            title: String::from("Rust Meetup #3"),
+            date: Date::from_calendar_date(2023, Month::January, 31).unwrap(),
+            url: Some(String::from("https://meetup.example/3")),
+            description: Some(String::from("Talks; **pizza**")),
+        }];
+        let now = Date::from_calendar_date(2023, Month::January, 2)
+            .unwrap()
+            .with_hms(3, 4, 5)
+            .unwrap()
+            .assume_utc();
+        let calendar = render_calendar(&events, &site, now);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.contains("X-WR-CALNAME:Zine\\, Weekly\r\n"));
+        assert!(calendar.contains("UID:20230131-rust-meetup-3@zine.example\r\n"));
+        assert!(calendar.contains("DTSTAMP:20230102T030405Z\r\n"));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20230131\r\nDTEND;VALUE=DATE:20230201\r\n"));
+        assert!(calendar.contains("DESCRIPTION:Talks\\; pizza\r\n"));
+        assert!(calendar.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    }
+}
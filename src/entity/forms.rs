@@ -0,0 +1,170 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::html::escape_attr;
+
+use super::Entity;
+
+/// The newsletter form config, declared in the `[forms]` table of the root `zine.toml`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormsConfig {
+    pub provider: FormProvider,
+    /// The form id of formspree, or the username of buttondown.
+    pub id: Option<String>,
+    /// The form action url, required by mailchimp and custom provider.
+    pub endpoint: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormProvider {
+    Formspree,
+    Buttondown,
+    Mailchimp,
+    Custom,
+}
+
+/// The localized text of the subscribe form.
+pub struct FormLabels {
+    pub placeholder: String,
+    pub button: String,
+}
+
+impl FormsConfig {
+    fn action_url(&self) -> Option<String> {
+        match self.provider {
+            FormProvider::Formspree => self
+                .id
+                .as_ref()
+                .map(|id| format!("https://formspree.io/f/{id}")),
+            FormProvider::Buttondown => self
+                .id
+                .as_ref()
+                .map(|id| format!("https://buttondown.email/api/emails/embed-subscribe/{id}")),
+            FormProvider::Mailchimp | FormProvider::Custom => self.endpoint.clone(),
+        }
+    }
+
+    // The name of the honeypot field, bots would fill it but humans never see it.
+    fn honeypot_name(&self) -> String {
+        match self.provider {
+            // Mailchimp's honeypot field is named `b_{u}_{id}`.
+            FormProvider::Mailchimp => {
+                let endpoint = self.endpoint.as_deref().unwrap_or_default();
+                let query = endpoint.split_once('?').map(|(_, q)| q).unwrap_or_default();
+                let param = |name: &str| {
+                    query
+                        .split('&')
+                        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                        .unwrap_or_default()
+                };
+                format!("b_{}_{}", param("u"), param("id"))
+            }
+            _ => String::from("_gotcha"),
+        }
+    }
+
+    fn email_name(&self) -> &'static str {
+        match self.provider {
+            FormProvider::Mailchimp => "EMAIL",
+            _ => "email",
+        }
+    }
+
+    /// Render the subscribe form HTML.
+    pub fn render_subscribe_form(&self, labels: &FormLabels) -> Result<String> {
+        let mut html = String::new();
+        writeln!(
+            &mut html,
+            r#"<form class="zine-subscribe-form" action="{}" method="post" target="_blank">"#,
+            escape_attr(&self.action_url().unwrap_or_default()),
+        )?;
+        writeln!(
+            &mut html,
+            r#"<input type="email" name="{}" placeholder="{}" required>"#,
+            self.email_name(),
+            escape_attr(&labels.placeholder),
+        )?;
+        writeln!(
+            &mut html,
+            r#"<div style="position: absolute; left: -5000px;" aria-hidden="true"><input type="text" name="{}" tabindex="-1" autocomplete="off" value=""></div>"#,
+            escape_attr(&self.honeypot_name()),
+        )?;
+        writeln!(
+            &mut html,
+            r#"<button type="submit">{}</button>"#,
+            escape_attr(&labels.button),
+        )?;
+        writeln!(&mut html, "</form>")?;
+        Ok(html)
+    }
+}
+
+impl Entity for FormsConfig {
+    fn parse(&mut self, _source: &Path) -> Result<()> {
+        if self.action_url().is_none() {
+            match self.provider {
+                FormProvider::Formspree | FormProvider::Buttondown => {
+                    bail!(
+                        "`id` is required in [forms] for {:?} provider",
+                        self.provider
+                    )
+                }
+                FormProvider::Mailchimp | FormProvider::Custom => {
+                    bail!(
+                        "`endpoint` is required in [forms] for {:?} provider",
+                        self.provider
+                    )
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Escape the `value` of an attribute or a text node.
+#[cfg(test)]
+mod tests {
+    use super::{FormLabels, FormProvider, FormsConfig};
+
+    #[test]
+    fn test_honeypot_name() {
+        let config = FormsConfig {
+            provider: FormProvider::Mailchimp,
+            id: None,
+            endpoint: Some(String::from(
+                "https://example.us1.list-manage.com/subscribe/post?u=abc&id=123",
+            )),
+        };
+        assert_eq!(config.honeypot_name(), "b_abc_123");
+
+        let config = FormsConfig {
+            provider: FormProvider::Formspree,
+            id: Some(String::from("xyz")),
+            endpoint: None,
+        };
+        assert_eq!(config.honeypot_name(), "_gotcha");
+        assert_eq!(
+            config.action_url().as_deref(),
+            Some("https://formspree.io/f/xyz")
+        );
+    }
+
+    #[test]
+    fn test_escape_labels() {
+        let config = FormsConfig {
+            provider: FormProvider::Buttondown,
+            id: Some(String::from("zine")),
+            endpoint: None,
+        };
+        let labels = FormLabels {
+            placeholder: String::from(r#"Your "email""#),
+            button: String::from("<b>Subscribe</b>"),
+        };
+        let html = config.render_subscribe_form(&labels).unwrap();
+        assert!(html.contains(r#"placeholder="Your &quot;email&quot;""#));
+        assert!(html.contains("&lt;b&gt;Subscribe&lt;/b&gt;</button>"));
+    }
+}
@@ -0,0 +1,186 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context as _, Result};
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{html::escape_attr, markdown};
+
+use super::{SandboxConfig, SlugConfig};
+
+/// The glossary file in the root directory.
+pub static GLOSSARY_FILE: &str = "glossary.toml";
+
+/// A glossary term, declared in the `[[term]]` array of the root `glossary.toml`:
+///
+/// ```toml
+/// [[term]]
+/// term = "WASI"
+/// definition = "The WebAssembly System Interface."
+/// link = "https://wasi.dev"
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+    pub term: String,
+    /// The term definition (markdown format).
+    pub definition: String,
+    /// The url to learn more about the term, if any.
+    pub link: Option<String>,
+    /// The anchor of the term in the `/glossary/` page, resolved in loading.
+    #[serde(skip_deserializing)]
+    pub slug: String,
+}
+
+#[derive(Deserialize)]
+struct GlossaryFile {
+    #[serde(default, rename = "term")]
+    terms: Vec<GlossaryTerm>,
+}
+
+/// Load the glossary terms of the root `source` directory, sorted alphabetically.
+/// Return an empty list if the `glossary.toml` is missing.
+pub fn load_glossary(
+    source: &Path,
+    slug_config: &SlugConfig,
+    sandbox_config: &SandboxConfig,
+) -> Result<Vec<GlossaryTerm>> {
+    if !source.join(GLOSSARY_FILE).exists() {
+        return Ok(vec![]);
+    }
+    let file = sandbox_config.resolve_project_path(source, GLOSSARY_FILE)?;
+    let content = fs::read_to_string(&file)?;
+    let mut terms = toml::from_str::<GlossaryFile>(&content)
+        .with_context(|| format!("Failed to parse `{}`", file.display()))?
+        .terms;
+    for term in &mut terms {
+        term.slug = slug_config.normalize(&term.term.to_lowercase());
+    }
+    terms.sort_by_key(|term| term.term.to_lowercase());
+    Ok(terms)
+}
+
+/// Link the first occurrence of each glossary term in `markdown` to the `/glossary/` page,
+/// the terms are matched as whole words case-insensitively.
+///
+/// The terms in the headings, links, images, code and html are never linked.
+pub fn link_terms(markdown: &str, terms: &[GlossaryTerm]) -> String {
+    // Match the longer terms first, e.g. `Rust Foundation` rather than `Rust`.
+    let mut patterns = terms
+        .iter()
+        .filter(|term| !term.term.trim().is_empty())
+        .filter_map(|term| {
+            let pattern = format!(r"\b{}\b", regex::escape(term.term.trim()));
+            let regex = RegexBuilder::new(&pattern)
+                .case_insensitive(true)
+                .build()
+                .ok()?;
+            Some((regex, term))
+        })
+        .collect::<Vec<(Regex, &GlossaryTerm)>>();
+    patterns.sort_by_key(|(_, term)| std::cmp::Reverse(term.term.len()));
+
+    let mut linked = vec![false; patterns.len()];
+    // The nesting depth of the elements whose text is never linked.
+    let mut skipped = 0;
+    let mut replacements = vec![];
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(..) | Tag::Link(..) | Tag::Image(..) | Tag::CodeBlock(_)) => {
+                skipped += 1
+            }
+            Event::End(Tag::Heading(..) | Tag::Link(..) | Tag::Image(..) | Tag::CodeBlock(_)) => {
+                skipped -= 1
+            }
+            Event::Text(_) if skipped == 0 => {
+                let text = &markdown[range.clone()];
+                let mut matches = vec![];
+                for (index, (regex, term)) in patterns.iter().enumerate() {
+                    if linked[index] {
+                        continue;
+                    }
+                    let found = regex.find_iter(text).find(|found| {
+                        matches
+                            .iter()
+                            .all(|(start, end, _)| found.end() <= *start || found.start() >= *end)
+                    });
+                    if let Some(found) = found {
+                        linked[index] = true;
+                        matches.push((found.start(), found.end(), *term));
+                    }
+                }
+                replacements.extend(matches.into_iter().map(|(start, end, term)| {
+                    let text = &text[start..end];
+                    (
+                        range.start + start..range.start + end,
+                        term_link(text, term),
+                    )
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut linked_markdown = String::with_capacity(markdown.len());
+    let mut offset = 0;
+    for (range, link) in replacements {
+        linked_markdown.push_str(&markdown[offset..range.start]);
+        linked_markdown.push_str(&link);
+        offset = range.end;
+    }
+    linked_markdown.push_str(&markdown[offset..]);
+    linked_markdown
+}
+
+// The inline html link of the `text` to the glossary `term`,
+// the plain text definition is shown as the tooltip.
+fn term_link(text: &str, term: &GlossaryTerm) -> String {
+    let definition = markdown::plain_text(&term.definition)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        r##"<a class="zine-glossary-term" href="/glossary/#{}" title="{}">{text}</a>"##,
+        escape_attr(&term.slug),
+        escape_attr(&definition),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{link_terms, GlossaryTerm};
+
+    fn term(term: &str, slug: &str) -> GlossaryTerm {
+        GlossaryTerm {
+            term: String::from(term),
+            definition: String::from("The definition."),
+            link: None,
+            slug: String::from(slug),
+        }
+    }
+
+    #[test_case("WASI and wasi", r##"<a class="zine-glossary-term" href="/glossary/#wasi" title="The definition.">WASI</a> and wasi"##; "first occurrence")]
+    #[test_case("the wasi-sdk", r##"the <a class="zine-glossary-term" href="/glossary/#wasi" title="The definition.">wasi</a>-sdk"##; "case insensitive")]
+    #[test_case("WASIX", "WASIX"; "whole word")]
+    #[test_case("# WASI\n\nWASI", "# WASI\n\n<a class=\"zine-glossary-term\" href=\"/glossary/#wasi\" title=\"The definition.\">WASI</a>"; "heading")]
+    #[test_case("[WASI](https://wasi.dev) `WASI`\n\n```\nWASI\n```", "[WASI](https://wasi.dev) `WASI`\n\n```\nWASI\n```"; "link and code")]
+    fn test_link_terms(markdown: &str, expected: &str) {
+        assert_eq!(link_terms(markdown, &[term("WASI", "wasi")]), expected);
+    }
+
+    #[test]
+    fn test_link_longer_terms_first() {
+        let terms = [
+            term("Rust", "rust"),
+            term("Rust Foundation", "rust-foundation"),
+        ];
+        let linked = link_terms("The Rust Foundation loves Rust.", &terms);
+        assert!(linked.contains(
+            r##"href="/glossary/#rust-foundation" title="The definition.">Rust Foundation</a>"##
+        ));
+        assert!(linked.ends_with(r##"href="/glossary/#rust" title="The definition.">Rust</a>."##));
+    }
+}
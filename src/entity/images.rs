@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Result};
+use serde::Deserialize;
+
+use super::SandboxConfig;
+
+/// The responsive images config, declared in the `[images]` table of the root `zine.toml`.
+///
+/// The local article covers and inline images are resized into `widths` at build time,
+/// and rendered with the `srcset` and `sizes` attributes.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ImagesConfig {
+    /// The widths (in pixels) to resize into, the widths not smaller than
+    /// the original image are skipped. Default to `[480, 960, 1440]`.
+    #[serde(default = "ImagesConfig::default_widths")]
+    pub widths: Vec<u32>,
+    /// The extra formats to convert into, e.g. `["avif", "webp"]`, which are rendered
+    /// as the `<source>` of a `<picture>` in this order. Default to `["webp"]`.
+    #[serde(default = "ImagesConfig::default_formats")]
+    pub formats: Vec<ImageFormat>,
+    /// The `sizes` attribute of the responsive images.
+    #[serde(default = "ImagesConfig::default_sizes")]
+    pub sizes: String,
+    /// The encoding quality of the lossy formats, from 1 to 100. Default to 80.
+    #[serde(default = "ImagesConfig::default_quality")]
+    pub quality: u8,
+    /// The project root directory, which the image urls are resolved against.
+    #[serde(skip)]
+    pub root: PathBuf,
+    /// The sandbox config of the project, which the image files are resolved with.
+    #[serde(skip)]
+    pub sandbox_config: SandboxConfig,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Webp,
+    Avif,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "webp",
+            ImageFormat::Avif => "avif",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Avif => "image/avif",
+        }
+    }
+}
+
+impl ImagesConfig {
+    fn default_widths() -> Vec<u32> {
+        vec![480, 960, 1440]
+    }
+
+    fn default_formats() -> Vec<ImageFormat> {
+        vec![ImageFormat::Webp]
+    }
+
+    fn default_sizes() -> String {
+        String::from("(max-width: 768px) 100vw, 768px")
+    }
+
+    fn default_quality() -> u8 {
+        80
+    }
+
+    /// Check the config and resolve the images in the project `source` directory.
+    pub(super) fn parse(&mut self, source: &Path, sandbox_config: &SandboxConfig) -> Result<()> {
+        ensure!(
+            (1..=100).contains(&self.quality),
+            "The `quality` of [images] must be from 1 to 100, got {}",
+            self.quality
+        );
+        self.widths.retain(|width| *width > 0);
+        self.widths.sort_unstable();
+        self.widths.dedup();
+        if cfg!(not(feature = "avif")) && self.formats.contains(&ImageFormat::Avif) {
+            println!("Warning: the `avif` format of [images] requires the `avif` feature of zine, skipped.");
+            self.formats.retain(|format| *format != ImageFormat::Avif);
+        }
+        self.root = source.to_path_buf();
+        self.sandbox_config = sandbox_config.clone();
+        Ok(())
+    }
+}
@@ -1,10 +1,18 @@
-use std::{borrow::Cow, fs, path::Path};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context as _, Result};
+use anyhow::{bail, Context as _, Result};
 use genkit::{html::Meta, markdown, Context};
 use minijinja::Environment;
 use rayon::{
-    prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    prelude::{
+        IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator,
+        ParallelIterator,
+    },
     slice::ParallelSliceMut,
 };
 use serde::{Deserialize, Serialize};
@@ -12,9 +20,9 @@ use time::Date;
 
 use genkit::{current_mode, Mode};
 
-use crate::engine;
+use crate::{data, engine, i18n, media, output};
 
-use super::{article::Article, Entity};
+use super::{article::Article, Colophon, Entity, Pagination, SandboxConfig, ThemeOverride};
 
 /// The issue entity config.
 /// It parsed from issue directory's `zine.toml`.
@@ -29,7 +37,20 @@ pub struct Issue {
     /// The optional introduction for this issue (parsed from convention intro.md file).
     #[serde(skip)]
     pub intro: Option<String>,
+    /// The short summary of this issue on the home page,
+    /// fallback to the first sentences of the intro.
+    pub summary: Option<String>,
     cover: Option<String>,
+    /// How many articles in each page of the issue page,
+    /// which overrides the `page_size` of `[site]` table.
+    #[serde(default, skip_serializing)]
+    page_size: Option<usize>,
+    /// The accent color of this issue, which overrides the theme's
+    /// primary color in issue and article pages.
+    pub accent_color: Option<String>,
+    /// The theme values overridden in the issue and article pages, declared in `[theme]` table.
+    #[serde(default, skip_serializing)]
+    theme: Option<ThemeOverride>,
     /// Default cover for each article in this issue.
     /// The global `default_cover` in [theme] section will be overrided.
     #[serde(skip_serializing)]
@@ -41,6 +62,13 @@ pub struct Issue {
     /// Whether to publish the whole issue.
     #[serde(default)]
     publish: bool,
+    /// Whether to build this unpublished issue into an unlisted
+    /// preview url, such as `/preview/<token>/issue-5/`.
+    #[serde(default, skip_serializing)]
+    pub preview: bool,
+    /// The token of preview url, persisted in `.zine/preview-tokens.json`.
+    #[serde(skip)]
+    pub preview_token: Option<String>,
     /// The path of issue diretory.
     #[serde(skip_deserializing)]
     pub dir: String,
@@ -49,7 +77,84 @@ pub struct Issue {
     /// single article page.
     #[serde(skip_serializing, default)]
     #[serde(rename(deserialize = "article"))]
-    articles: Vec<Article>,
+    pub(crate) articles: Vec<Article>,
+    /// The locale and translated issue pairs, declared in `[i18n.<locale>]` tables.
+    #[serde(default)]
+    pub i18n: BTreeMap<String, IssueTranslation>,
+    /// The alternative outputs of this issue, such as the PDF or EPUB files
+    /// in the `downloads` directory of the issue, e.g. `content/issue-1/downloads/issue-1.pdf`.
+    #[serde(skip_deserializing)]
+    pub downloads: Vec<IssueDownload>,
+    /// The sponsors of this issue, declared in `[[sponsor]]` tables.
+    #[serde(default)]
+    #[serde(rename(deserialize = "sponsor"))]
+    pub sponsors: Vec<IssueSponsor>,
+    /// The colophon of this issue, declared in `[colophon]` table.
+    pub colophon: Option<Colophon>,
+}
+
+/// A sponsor or partner of an issue, shown in the issue and its article pages.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssueSponsor {
+    pub name: String,
+    /// The logo url, either a remote url or a local file path,
+    /// such as `/static/sponsors/acme.png`.
+    pub logo: Option<String>,
+    /// The link of the sponsor.
+    pub url: Option<String>,
+    /// A short introduction of the sponsor (markdown format).
+    pub blurb: Option<String>,
+}
+
+/// The summary of an issue, exposed as `previous_issue` to the pages of
+/// the next issue for the "Previously in ..." teasers.
+#[derive(Clone, Debug, Serialize)]
+pub struct IssueRetrospective {
+    pub number: u32,
+    pub title: String,
+    pub slug: String,
+    /// The count of the published articles.
+    pub article_count: usize,
+    /// The most used topic ids of the articles, in descending order of usage.
+    pub top_topics: Vec<String>,
+}
+
+/// A downloadable alternative output of an issue.
+#[derive(Clone, Debug, Serialize)]
+pub struct IssueDownload {
+    /// The file name.
+    pub name: String,
+    /// The lowercase file extension, e.g. `pdf` or `epub`.
+    pub format: String,
+    /// The url of the file, e.g. `/issue-1/downloads/issue-1.pdf`.
+    pub url: String,
+    /// The file size in bytes.
+    pub size: u64,
+    #[serde(skip)]
+    source: PathBuf,
+}
+
+/// The locale-specific metadata of an issue.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssueTranslation {
+    pub title: String,
+    /// The intro markdown file of this locale, default to `intro.<locale>.md`.
+    #[serde(default, rename = "intro", skip_serializing)]
+    intro_file: Option<String>,
+    #[serde(skip)]
+    intro: Option<String>,
+    /// The slug of the translated issue page, default to `<issue slug>/<locale>`.
+    #[serde(default)]
+    pub slug: String,
+}
+
+/// The translation link of an issue page.
+#[derive(Serialize)]
+struct IssueTranslationLink<'a> {
+    // The locale name.
+    name: &'static str,
+    // The issue page slug.
+    slug: &'a str,
 }
 
 impl std::fmt::Debug for Issue {
@@ -60,12 +165,21 @@ impl std::fmt::Debug for Issue {
             .field("title", &self.title)
             .field("intro", &self.intro.is_some())
             .field("cover", &self.cover)
+            .field("accent_color", &self.accent_color)
+            .field("theme", &self.theme)
             .field("dir", &self.dir)
             .field("articles", &self.articles)
+            .field("i18n", &self.i18n)
+            .field("downloads", &self.downloads)
+            .field("sponsors", &self.sponsors)
+            .field("colophon", &self.colophon)
             .finish()
     }
 }
 
+// The directory of the issue downloads, both in the issue directory and the issue page.
+const DOWNLOADS_DIR: &str = "downloads";
+
 impl Issue {
     /// Check whether the issue need publish.
     ///
@@ -76,16 +190,100 @@ impl Issue {
         self.publish || matches!(current_mode(), Mode::Serve)
     }
 
+    // Render the issue pages of the `articles` into `dir` whose url path is `slug`,
+    // the page `n` is rendered into `<dir>/page/<n>` if paginated.
+    fn render_pages(
+        &self,
+        env: &Environment,
+        context: &Context,
+        articles: &[&Article],
+        slug: &str,
+        dir: &Path,
+    ) -> Result<()> {
+        let page_size = self
+            .page_size
+            .unwrap_or_else(|| data::read().get_site().page_size);
+        for (articles, pagination) in Pagination::paginate(articles, page_size, &format!("/{slug}"))
+        {
+            let mut context = context.clone();
+            context.insert("articles", &articles);
+            context.insert("pagination", &pagination);
+            let dir = match Pagination::page_dir(pagination.current) {
+                Some(page_dir) => dir.join(page_dir),
+                None => dir.to_owned(),
+            };
+            engine::render(env, "issue.jinja", context, dir)?;
+        }
+        Ok(())
+    }
+
+    // Collect the downloadable files of the `dir`, sorted by name.
+    fn collect_downloads(&self, dir: &Path) -> Result<Vec<IssueDownload>> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut downloads = vec![];
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let format = Path::new(&name)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            downloads.push(IssueDownload {
+                url: download_url(&self.slug, &name),
+                name,
+                format,
+                size: entry.metadata()?.len(),
+                source: entry.path(),
+            });
+        }
+        downloads.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(downloads)
+    }
+
     // Get the description of this issue.
     // Mainly for html meta description tag.
     fn description(&self) -> String {
-        if let Some(intro) = self.intro.as_ref() {
+        if let Some(summary) = self.summary.as_ref().filter(|summary| !summary.is_empty()) {
+            summary.clone()
+        } else if let Some(intro) = self.intro.as_ref() {
             markdown::extract_description(intro)
         } else {
             String::default()
         }
     }
 
+    fn get_translations(&self) -> Vec<IssueTranslationLink<'_>> {
+        if self.i18n.is_empty() {
+            return vec![];
+        }
+
+        let site_locale = data::read().get_site().locale.clone();
+        let mut translations = self
+            .i18n
+            .iter()
+            .filter_map(|(locale, translation)| {
+                Some(IssueTranslationLink {
+                    name: i18n::get_locale_name(locale)?,
+                    slug: &translation.slug,
+                })
+            })
+            .collect::<Vec<_>>();
+        // Add default locale.
+        if let Some(name) = i18n::get_locale_name(&site_locale) {
+            translations.push(IssueTranslationLink {
+                name,
+                slug: &self.slug,
+            });
+        }
+        translations.sort_by_key(|t| t.name);
+        translations
+    }
+
     fn sibling_articles(&self, current: usize) -> (Option<&Article>, Option<&Article>) {
         if current == 0 {
             return (None, self.articles.get(current + 1));
@@ -104,6 +302,45 @@ impl Issue {
             .collect()
     }
 
+    /// The home page summary of this issue, which is the `summary` or
+    /// the first `sentences` of the intro.
+    pub fn summary(&self, sentences: usize) -> Option<String> {
+        if let Some(summary) = self.summary.as_ref().filter(|summary| !summary.is_empty()) {
+            return Some(summary.clone());
+        }
+        let intro = self.intro.as_deref()?;
+        Some(crate::markdown::first_sentences(
+            &crate::markdown::plain_text(intro),
+            sentences,
+        ))
+        .filter(|summary| !summary.is_empty())
+    }
+
+    /// Get the latest `limit` published articles which aren't featured.
+    pub fn recent_articles(&self, limit: usize) -> Vec<&Article> {
+        let mut articles = self
+            .articles()
+            .into_iter()
+            .filter(|article| !article.featured)
+            .collect::<Vec<_>>();
+        articles.sort_by(|a, b| b.meta.pub_date.cmp(&a.meta.pub_date));
+        articles.truncate(limit);
+        articles
+    }
+
+    /// Download the remote covers of this issue and its articles,
+    /// then rewrite them to the local paths.
+    pub(super) fn localize_media(&mut self, source: &Path) {
+        media::localize(source, &mut self.cover);
+        self.articles.par_iter_mut().for_each(|article| {
+            media::localize(source, &mut article.meta.cover);
+            article
+                .i18n
+                .values_mut()
+                .for_each(|article| media::localize(source, &mut article.meta.cover));
+        });
+    }
+
     /// Get all articles need published.
     ///
     /// See [`Article::need_publish()`](super::Article::need_publish)
@@ -114,23 +351,100 @@ impl Issue {
             .filter(|article| issue_need_publish && article.need_publish())
             .collect()
     }
+
+    /// Replace the title, intro and articles with their `locale` translations if any
+    /// for the localized site, the slugs and paths are kept.
+    pub(super) fn localize(&mut self, locale: &str) {
+        if let Some(translation) = self.i18n.get(locale) {
+            self.title = translation.title.clone();
+            if translation.intro.is_some() {
+                self.intro = translation.intro.clone();
+            }
+        }
+        // The localized site replaces the translated issue pages.
+        self.i18n.clear();
+        for article in &mut self.articles {
+            if let Some(mut translation) = article.i18n.remove(locale) {
+                translation.meta.slug = article.meta.slug.clone();
+                translation.meta.path = article.meta.path.clone();
+                translation.featured = article.featured;
+                *article = translation;
+            }
+            article.i18n.clear();
+        }
+    }
+
+    /// Summarize this issue with its `limit` most used topics.
+    pub fn retrospective(&self, limit: usize) -> IssueRetrospective {
+        let articles = self.articles();
+        let mut topics = BTreeMap::<&str, usize>::new();
+        for topic in articles.iter().flat_map(|article| &article.topics) {
+            *topics.entry(topic.as_str()).or_default() += 1;
+        }
+        let mut topics = topics.into_iter().collect::<Vec<_>>();
+        // The stable sort keeps the topics of same usage in alphabetical order.
+        topics.sort_by(|a, b| b.1.cmp(&a.1));
+        IssueRetrospective {
+            number: self.number,
+            title: self.title.clone(),
+            slug: self.slug.clone(),
+            article_count: articles.len(),
+            top_topics: topics
+                .into_iter()
+                .take(limit)
+                .map(|(topic, _)| topic.to_owned())
+                .collect(),
+        }
+    }
+}
+
+impl Issue {
+    // Check the local logo files of sponsors exist, the logo path is relative
+    // to the zine root directory, e.g. `/static/sponsors/acme.png`.
+    fn check_sponsor_logos(&self, source: &Path, sandbox_config: &SandboxConfig) -> Result<()> {
+        for sponsor in &self.sponsors {
+            let Some(logo) = sponsor.logo.as_deref() else {
+                continue;
+            };
+            if logo.starts_with("http://") || logo.starts_with("https://") {
+                continue;
+            }
+            let path = source.join(logo.trim_start_matches('/'));
+            if !path.is_file() {
+                bail!(
+                    "The logo `{logo}` of sponsor `{}` in issue `{}` doesn't exist: {}",
+                    sponsor.name,
+                    self.title,
+                    path.display()
+                );
+            }
+            sandbox_config.resolve_project_path(source, logo.trim_start_matches('/'))?;
+        }
+        Ok(())
+    }
 }
 
 impl Entity for Issue {
     fn parse(&mut self, source: &Path) -> Result<()> {
         // Fallback to path if no slug specified.
         if self.slug.is_empty() {
-            self.slug = self.dir.clone();
+            self.slug = data::read().get_site().slug_strategy.apply(&self.dir);
         }
+        super::slug::normalize_slug("issue", &mut self.slug);
 
         let dir = source.join(crate::ZINE_CONTENT_DIR).join(&self.dir);
+        let zine_data = data::read();
+        let lint_config = zine_data.get_lint_config();
         // Parse intro file
         let intro_path = dir.join(crate::ZINE_INTRO_FILE);
         if intro_path.exists() {
-            self.intro =
-                Some(fs::read_to_string(&intro_path).with_context(|| {
-                    format!("Failed to read intro from {}", intro_path.display())
-                })?);
+            let intro_path = zine_data
+                .get_sandbox_config()
+                .resolve_project_path(&dir, crate::ZINE_INTRO_FILE)?;
+            let intro = fs::read_to_string(&intro_path)
+                .with_context(|| format!("Failed to read intro from {}", intro_path.display()))?;
+            lint_config.check_markers(&intro_path, &intro)?;
+            self.intro = Some(intro);
         }
 
         // Sort all articles by pub_date.
@@ -151,17 +465,78 @@ impl Entity for Issue {
                 .for_each(|article| article.meta.cover = Some(default_cover.to_owned()))
         }
 
+        if let Some(theme) = self.theme.as_mut() {
+            theme.parse(source, zine_data.get_sandbox_config())?;
+        }
         self.articles.parse(&dir)?;
+        self.downloads = self.collect_downloads(&dir.join(DOWNLOADS_DIR))?;
+        self.check_sponsor_logos(source, zine_data.get_sandbox_config())?;
+
+        for (locale, translation) in self.i18n.iter_mut() {
+            if i18n::get_locale_name(locale).is_none() {
+                bail!(
+                    "Currently, we don't support locale: `{locale}` of issue `{}`",
+                    self.title
+                );
+            }
+            if translation.slug.is_empty() {
+                translation.slug = format!("{}/{}", self.slug, locale);
+            }
+
+            let intro_file = match translation.intro_file.as_ref() {
+                Some(intro_file) => intro_file.clone(),
+                None => format!("intro.{locale}.md"),
+            };
+            if translation.intro_file.is_some() || dir.join(&intro_file).exists() {
+                let intro_path = zine_data
+                    .get_sandbox_config()
+                    .resolve_project_path(&dir, &intro_file)?;
+                let intro = fs::read_to_string(&intro_path).with_context(|| {
+                    format!("Failed to read intro from {}", intro_path.display())
+                })?;
+                lint_config.check_markers(&intro_path, &intro)?;
+                translation.intro = Some(intro);
+            }
+        }
         Ok(())
     }
 
     fn render(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
         if !self.need_publish() {
+            if let (true, Some(token)) = (self.preview, self.preview_token.as_ref()) {
+                // Render the preview issue under the tokenized directory,
+                // all links prefixed with issue slug will point to preview pages.
+                let mut issue = self.clone();
+                issue.slug = format!("preview/{}/{}", token, self.slug);
+                issue.i18n.values_mut().for_each(|translation| {
+                    translation.slug = format!("preview/{}/{}", token, translation.slug);
+                });
+                issue.publish = true;
+                issue.downloads.iter_mut().for_each(|download| {
+                    download.url = download_url(&issue.slug, &download.name);
+                });
+                println!(
+                    "Preview of issue `{}` is available at: /{}/",
+                    self.title, issue.slug
+                );
+                context.insert("noindex", &true);
+                return issue.render(env, context, dest);
+            }
             return Ok(());
         }
 
         let issue_dir = dest.join(&self.slug);
+        for download in &self.downloads {
+            output::copy(
+                &download.source,
+                &issue_dir.join(DOWNLOADS_DIR).join(&download.name),
+            )?;
+        }
         context.insert("issue", &self);
+        if let Some(theme) = self.theme.as_ref() {
+            // Shadow the global `theme` in the pages of this issue.
+            context.insert("theme", &data::read().get_theme().with_override(theme));
+        }
 
         let articles = self
             .articles
@@ -184,8 +559,27 @@ impl Entity for Issue {
                     .render(env, context, &dest)
                     .expect("Render article failed.");
             });
+        if engine::drafts_mode() {
+            // The drafts aren't listed anywhere, nor indexed.
+            let drafts_dir = dest.join(engine::DRAFTS_DIR).join(&self.slug);
+            let mut context = context.clone();
+            context.insert("noindex", &true);
+            for article in self.articles.iter().filter(|article| article.draft) {
+                println!(
+                    "Draft `{}` is available at: /{}/{}/{}/",
+                    article.meta.title,
+                    engine::DRAFTS_DIR,
+                    self.slug,
+                    article.meta.slug
+                );
+                article.render(env, context.clone(), &drafts_dir)?;
+            }
+        }
+        // Render the tombstones of the expired articles.
+        for article in &self.articles {
+            article.render_tombstones(env, context.clone(), &issue_dir)?;
+        }
 
-        context.insert("articles", &articles);
         context.insert(
             "meta",
             &Meta {
@@ -196,7 +590,114 @@ impl Entity for Issue {
             },
         );
         context.insert("intro", &self.intro);
-        engine::render(env, "issue.jinja", context, issue_dir)?;
+        context.insert("i18n", &self.get_translations());
+        self.render_pages(env, &context, &articles, &self.slug, &issue_dir)?;
+
+        // Render the translated issue pages, the article links still point to
+        // the original issue slug.
+        for (locale, translation) in &self.i18n {
+            let mut context = context.clone();
+            let mut issue = self.clone();
+            issue.title = translation.title.clone();
+            let localized_articles = articles
+                .iter()
+                .map(|article| article.i18n.get(locale).unwrap_or(*article))
+                .collect::<Vec<_>>();
+            let intro = translation.intro.as_ref().or(self.intro.as_ref());
+
+            context.insert("issue", &issue);
+            context.insert("locale", locale);
+            context.insert(
+                "meta",
+                &Meta {
+                    title: Cow::Borrowed(&translation.title),
+                    description: Cow::Owned(
+                        intro
+                            .map(|intro| markdown::extract_description(intro))
+                            .unwrap_or_default(),
+                    ),
+                    url: Some(Cow::Borrowed(&translation.slug)),
+                    image: self.cover.as_deref().map(Cow::Borrowed),
+                },
+            );
+            context.insert("intro", &intro);
+            let dir = dest.join(&translation.slug);
+            self.render_pages(env, &context, &localized_articles, &translation.slug, &dir)?;
+        }
         Ok(())
     }
 }
+
+// The url of the download file `name` of the issue `slug`.
+fn download_url(slug: &str, name: &str) -> String {
+    format!("/{slug}/{DOWNLOADS_DIR}/{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use crate::{
+        data,
+        entity::{Entity, SandboxConfig},
+        fixture::{self, IssueBuilder},
+    };
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sponsor_logo_outside_project() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("static")).unwrap();
+        std::os::unix::fs::symlink(
+            env::current_dir().unwrap().join("Cargo.toml"),
+            root.join("static/logo.png"),
+        )
+        .unwrap();
+        let issue = IssueBuilder::new(1, "issue-1")
+            .set(
+                "sponsor",
+                json!([{ "name": "Acme", "logo": "/static/logo.png" }]),
+            )
+            .build();
+        assert!(issue
+            .check_sponsor_logos(root, &SandboxConfig::default())
+            .is_ok());
+
+        let mut sandbox_config = SandboxConfig::default();
+        sandbox_config.enforce();
+        assert!(issue.check_sponsor_logos(root, &sandbox_config).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_localized_intro_outside_project() {
+        let _data = fixture::lock();
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("content/issue-1")).unwrap();
+        std::os::unix::fs::symlink(
+            env::current_dir().unwrap().join("Cargo.toml"),
+            root.join("content/issue-1/intro.zh.md"),
+        )
+        .unwrap();
+        let issue = IssueBuilder::new(1, "issue-1")
+            .set("i18n", json!({ "zh": { "title": "第一期" } }))
+            .build();
+
+        data::load();
+        data::write().set_sandbox_config(SandboxConfig::default());
+        let mut trusted = issue.clone();
+        trusted.parse(root).unwrap();
+        assert!(trusted.i18n["zh"].intro.is_some());
+
+        let mut sandbox_config = SandboxConfig::default();
+        sandbox_config.enforce();
+        data::write().set_sandbox_config(sandbox_config);
+        let err = issue.clone().parse(root).unwrap_err();
+        assert!(err.to_string().contains("disallowed by [sandbox]"));
+    }
+}
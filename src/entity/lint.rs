@@ -0,0 +1,252 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use anyhow::{ensure, Result};
+use serde::Deserialize;
+
+use super::build::Severity;
+
+/// The lint config, declared in the `[lint]` table of the root `zine.toml`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LintConfig {
+    /// Fail the build if any image in the article markdown lacks alt text.
+    #[serde(default)]
+    pub require_image_alt: bool,
+    /// Report the article pairs whose text similarity (from 0.0 to 1.0) exceeds
+    /// this threshold, e.g. `0.9`, which are likely the same piece included twice.
+    #[serde(default)]
+    pub duplicate_threshold: Option<f64>,
+    /// The severity of the near-identical articles.
+    #[serde(default)]
+    pub duplicate_articles: Severity,
+    /// Report the raw HTML tags in the article markdown with this severity,
+    /// except the `allowed_html_tags`. No check if not set.
+    #[serde(default)]
+    pub raw_html: Option<Severity>,
+    /// The raw HTML tags allowed in the article markdown, e.g. `["details", "summary"]`.
+    #[serde(default)]
+    pub allowed_html_tags: Vec<String>,
+    /// The raw HTML tags which always abort the build, e.g. `["script", "style"]`.
+    #[serde(default)]
+    pub denied_html_tags: Vec<String>,
+    /// Report the translations older than their source article with this severity,
+    /// by the `updated_date` or `pub_date`. No check if not set.
+    #[serde(default)]
+    pub stale_translation: Option<Severity>,
+    /// Report the canonical urls which no longer resolve with this severity,
+    /// which is checked by HTTP requests in `zine build` only. No check if not set.
+    #[serde(default)]
+    pub stale_canonical: Option<Severity>,
+    /// Report the unfinished `markers` in the articles and intros with this severity,
+    /// the code is excluded. No check if not set.
+    #[serde(default)]
+    pub unfinished_markers: Option<Severity>,
+    /// The unfinished markers, matched case-insensitively as whole words.
+    /// Default to `["TODO", "FIXME", "XXX", "lorem ipsum"]`.
+    #[serde(default = "LintConfig::default_markers")]
+    pub markers: Vec<String>,
+    /// The markdown files allowed to contain the markers, relative to the `content`
+    /// directory, e.g. `["issue-3/todo-apps.md"]`.
+    #[serde(default)]
+    pub markers_allowed: Vec<String>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            require_image_alt: false,
+            duplicate_threshold: None,
+            duplicate_articles: Severity::default(),
+            raw_html: None,
+            allowed_html_tags: Vec::new(),
+            denied_html_tags: Vec::new(),
+            stale_translation: None,
+            stale_canonical: None,
+            unfinished_markers: None,
+            markers: Self::default_markers(),
+            markers_allowed: Vec::new(),
+        }
+    }
+}
+
+impl LintConfig {
+    fn default_markers() -> Vec<String> {
+        ["TODO", "FIXME", "XXX", "lorem ipsum"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    /// Check the unfinished markers in the `markdown` of `file`.
+    pub fn check_markers(&self, file: &Path, markdown: &str) -> Result<()> {
+        let Some(severity) = self.unfinished_markers else {
+            return Ok(());
+        };
+        if self
+            .markers_allowed
+            .iter()
+            .any(|allowed| file.ends_with(allowed))
+        {
+            return Ok(());
+        }
+        let found = crate::markdown::find_markers(markdown, &self.markers);
+        if found.is_empty() {
+            return Ok(());
+        }
+        let message = found
+            .iter()
+            .map(|(line, marker)| format!("{}:{line}: found `{marker}`", file.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        ensure!(
+            !severity.is_error(),
+            "{message}\nPlease remove the unfinished markers, or add the file to `markers_allowed` of [lint]"
+        );
+        println!("Warning: {message}");
+        Ok(())
+    }
+
+    /// Check the raw HTML `tags` of an article titled `title`.
+    pub fn check_raw_html_tags(&self, title: &str, tags: &[String]) -> Result<()> {
+        let contains =
+            |list: &[String], tag: &str| list.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        let denied = tags
+            .iter()
+            .filter(|tag| contains(&self.denied_html_tags, tag))
+            .map(|tag| format!("<{tag}>"))
+            .collect::<Vec<_>>();
+        ensure!(
+            denied.is_empty(),
+            "the article `{title}` contains raw HTML {}, which is denied by `denied_html_tags` of [lint]",
+            denied.join(", ")
+        );
+
+        let Some(severity) = self.raw_html else {
+            return Ok(());
+        };
+        let disallowed = tags
+            .iter()
+            .filter(|tag| !contains(&self.allowed_html_tags, tag))
+            .map(|tag| format!("<{tag}>"))
+            .collect::<Vec<_>>();
+        if disallowed.is_empty() {
+            return Ok(());
+        }
+        let message = format!(
+            "the article `{title}` contains raw HTML {}, which may render differently between `zine serve` and `zine build`, add them to `allowed_html_tags` of [lint] if it is intended",
+            disallowed.join(", ")
+        );
+        ensure!(!severity.is_error(), "{message}");
+        println!("Warning: {message}");
+        Ok(())
+    }
+}
+
+// The timeout of each canonical url request.
+#[cfg(feature = "url-check")]
+const URL_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Check the `url` still resolves, return the failure reason if not.
+#[cfg(feature = "url-check")]
+pub fn check_url(url: &str) -> Result<(), String> {
+    let agent = ureq::AgentBuilder::new().timeout(URL_CHECK_TIMEOUT).build();
+    let response = match agent.head(url).call() {
+        // Some servers don't support the HEAD method.
+        Err(ureq::Error::Status(405, _)) => agent.get(url).call(),
+        response => response,
+    };
+    match response {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(status, _)) => Err(format!("HTTP {status}")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// A pair of near-identical articles.
+#[derive(Debug, PartialEq)]
+pub struct DuplicatePair<'a> {
+    pub first: &'a str,
+    pub second: &'a str,
+    /// The text similarity, from 0.0 to 1.0.
+    pub similarity: f64,
+}
+
+// The characters of each shingle, which works for the languages without spaces too.
+const SHINGLE_SIZE: usize = 5;
+
+/// Find the pairs of `texts` (a name and plain text pair) whose similarity
+/// exceeds the `threshold`, ordered by the similarity descending.
+pub fn find_duplicates<'a>(
+    texts: &'a [(String, String)],
+    threshold: f64,
+) -> Vec<DuplicatePair<'a>> {
+    let shingles = texts
+        .iter()
+        .map(|(_, text)| shingles(text))
+        .collect::<Vec<_>>();
+    let mut pairs = vec![];
+    for i in 0..texts.len() {
+        for j in i + 1..texts.len() {
+            let similarity = jaccard(&shingles[i], &shingles[j]);
+            if similarity >= threshold {
+                pairs.push(DuplicatePair {
+                    first: &texts[i].0,
+                    second: &texts[j].0,
+                    similarity,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    pairs
+}
+
+// The hashes of the overlapped character shingles of the normalized `text`.
+fn shingles(text: &str) -> HashSet<u64> {
+    let chars = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    chars
+        .windows(SHINGLE_SIZE)
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let intersection = a.intersection(b).count();
+    intersection as f64 / (a.len() + b.len() - intersection) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_duplicates;
+
+    #[test]
+    fn test_find_duplicates() {
+        let texts = [
+            ("issue-1/rust", "Rust is a language empowering everyone to build reliable and efficient software."),
+            ("issue-2/rust", "Rust is a language empowering everyone to build reliable and efficient software!"),
+            ("issue-2/go", "Go is an open source programming language that makes it simple to build secure, scalable systems."),
+            ("issue-3/empty", ""),
+        ]
+        .map(|(name, text)| (name.to_owned(), text.to_owned()));
+        let pairs = find_duplicates(&texts, 0.9);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(
+            (pairs[0].first, pairs[0].second),
+            ("issue-1/rust", "issue-2/rust")
+        );
+        assert!(pairs[0].similarity > 0.99);
+    }
+}
@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+/// The media config, declared in the `[media]` table of the root `zine.toml`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct MediaConfig {
+    /// Whether to download the remote covers and avatars at build time,
+    /// then self-host them in the `/static/media` directory.
+    #[serde(default)]
+    pub localize_remote: bool,
+    /// Whether to strip the EXIF metadata (e.g. the GPS location) of the JPEG
+    /// and PNG images copied into the output, including the localized media.
+    #[serde(default)]
+    pub strip_metadata: bool,
+}
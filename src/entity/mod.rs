@@ -1,21 +1,57 @@
 mod article;
 mod author;
+mod badge;
+mod build;
+mod colophon;
+mod comments;
+mod deploy;
+mod event;
+mod forms;
+mod glossary;
+mod images;
 mod issue;
+mod lint;
 mod list;
+mod media;
 mod page;
+mod pagination;
+mod preprocess;
+mod related;
+mod sandbox;
+mod series;
 mod site;
+mod slug;
+mod social_card;
 mod theme;
 mod topic;
 mod zine;
 
 pub use genkit::Entity;
 
-pub use article::{Article, MetaArticle};
-pub use author::{Author, AuthorId};
+pub use article::{Article, ArticleKind, ArticleSource, MetaArticle};
+pub use author::{normalize_author_id, validate_author_id, Author, AuthorId};
+pub use badge::{Badge, BadgeConfig};
+pub use build::{BuildConfig, CspConfig, CspMode, ExportFormat, ExporterConfig};
+pub use colophon::Colophon;
+pub use comments::{CommentProvider, CommentsConfig};
+pub use deploy::{DeployConfig, DeployProvider};
+pub use event::{render_calendar, Event};
+pub use forms::{FormLabels, FormsConfig};
+pub use glossary::{link_terms, load_glossary, GlossaryTerm, GLOSSARY_FILE};
+pub use images::{ImageFormat, ImagesConfig};
 pub use issue::Issue;
+pub use lint::LintConfig;
 pub use list::List;
+pub use media::MediaConfig;
 pub use page::Page;
-pub use site::Site;
-pub use theme::Theme;
+pub use pagination::Pagination;
+pub use preprocess::{preprocess, Preprocessor, ZineMarkdownConfig};
+pub use related::{RelatedArticle, RelatedConfig};
+pub use sandbox::{SandboxConfig, SANDBOX_ENV};
+pub use series::{Series, SeriesNavigation, SeriesPart};
+pub use site::{FeedConfig, Site, SiteAlternate, SiteTranslation};
+pub use slug::{SlugConfig, SlugStrategy};
+pub use social_card::SocialCardsConfig;
+pub use theme::{Theme, ThemeManifest, ThemeOverride, ThemePackage};
 pub use topic::Topic;
 pub use zine::Zine;
@@ -7,8 +7,9 @@ use anyhow::Result;
 use minijinja::Environment;
 use serde::{Deserialize, Serialize};
 
-use crate::engine;
-use genkit::{html::Meta, markdown, Context};
+use crate::{date, engine};
+use genkit::{current_mode, html::Meta, markdown, Context, Mode};
+use time::Date;
 
 use super::Entity;
 
@@ -25,7 +26,28 @@ impl Page {
         self.file_path.to_str().unwrap().replace(".md", "")
     }
 
-    fn title(&self) -> String {
+    /// The expiry date declared by the `<!-- expire_date: YYYY-MM-DD -->` line,
+    /// the page is excluded from the builds since this date.
+    pub fn expire_date(&self) -> Option<Date> {
+        crate::markdown::comment_directive(&self.markdown, "expire_date").and_then(date::parse_date)
+    }
+
+    /// The archive url declared by the `<!-- archive_url: URL -->` line, a tombstone page
+    /// linking to it is rendered in place of the expired page rather than removing the page.
+    pub fn archive_url(&self) -> Option<&str> {
+        crate::markdown::comment_directive(&self.markdown, "archive_url")
+    }
+
+    /// Check whether the page need publish, which is false if it's expired
+    /// except in `zine serve` mode.
+    pub fn need_publish(&self) -> bool {
+        let today = engine::frozen_now().date();
+        !self.expire_date().is_some_and(|date| date <= today)
+            || matches!(current_mode(), Mode::Serve)
+    }
+
+    /// The title of the first heading.
+    pub fn title(&self) -> String {
         let prefix = &['#', ' '];
         self.markdown
             .lines()
@@ -51,6 +73,20 @@ impl Entity for Page {
                 image: None,
             },
         );
+        if !self.need_publish() {
+            if let (Some(expire_date), Some(archive_url)) = (self.expire_date(), self.archive_url())
+            {
+                engine::render_tombstone(
+                    env,
+                    context,
+                    &self.title(),
+                    expire_date,
+                    archive_url,
+                    dest.join(self.slug()),
+                )?;
+            }
+            return Ok(());
+        }
         context.insert("page", &self);
         engine::render(env, "page.jinja", context, dest.join(self.slug()))?;
         Ok(())
@@ -90,4 +126,20 @@ mod tests {
 
         assert_eq!("Title", page.title());
     }
+
+    #[test]
+    fn test_page_expiry() {
+        let page = Page {
+            markdown: String::from(
+                "<!-- expire_date: 2024-01-31 -->\n<!-- archive_url: https://archive.example/event -->\n# Event",
+            ),
+            file_path: PathBuf::from("event.md"),
+        };
+        assert_eq!(
+            page.expire_date(),
+            time::Date::from_calendar_date(2024, time::Month::January, 31).ok()
+        );
+        assert_eq!(page.archive_url(), Some("https://archive.example/event"));
+        assert_eq!(page.title(), "Event");
+    }
 }
@@ -0,0 +1,100 @@
+use serde::Serialize;
+
+/// The pagination info of a paginated page, inserted as the `pagination` template context.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Pagination {
+    /// The current page number, starting from 1.
+    pub current: usize,
+    /// The total page count.
+    pub total: usize,
+    /// The count of the items in the previous pages, e.g. to number the items across pages.
+    pub offset: usize,
+    /// The url of previous page, if any.
+    pub prev_url: Option<String>,
+    /// The url of next page, if any.
+    pub next_url: Option<String>,
+}
+
+impl Pagination {
+    /// Split `items` into pages of `page_size` items, along with the pagination info of each page.
+    ///
+    /// The first page is served at `base_url`, the others are served at `<base_url>/page/<n>`,
+    /// see [`Pagination::page_dir`]. All items are in one page if `page_size` is 0.
+    pub fn paginate<'a, T>(
+        items: &'a [T],
+        page_size: usize,
+        base_url: &str,
+    ) -> Vec<(&'a [T], Pagination)> {
+        if page_size == 0 || items.len() <= page_size {
+            return vec![(items, Pagination::new(1, 1, 0, base_url))];
+        }
+
+        let chunks = items.chunks(page_size).collect::<Vec<_>>();
+        let total = chunks.len();
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let pagination = Pagination::new(index + 1, total, index * page_size, base_url);
+                (chunk, pagination)
+            })
+            .collect()
+    }
+
+    fn new(current: usize, total: usize, offset: usize, base_url: &str) -> Self {
+        let page_url = |number: usize| match Self::page_dir(number) {
+            Some(dir) => format!("{base_url}/{dir}"),
+            None => base_url.to_owned(),
+        };
+        Pagination {
+            current,
+            total,
+            offset,
+            prev_url: (current > 1).then(|| page_url(current - 1)),
+            next_url: (current < total).then(|| page_url(current + 1)),
+        }
+    }
+
+    /// The directory of page `number` relative to the first page, `None` for the first page.
+    pub fn page_dir(number: usize) -> Option<String> {
+        (number > 1).then(|| format!("page/{number}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::Pagination;
+
+    #[test_case(0, 5, 1; "disabled")]
+    #[test_case(10, 5, 1; "one page")]
+    #[test_case(2, 5, 3; "three pages")]
+    #[test_case(5, 5, 1; "exact")]
+    #[test_case(2, 0, 1; "empty")]
+    fn test_paginate(page_size: usize, count: usize, total: usize) {
+        let items = (0..count).collect::<Vec<_>>();
+        let pages = Pagination::paginate(&items, page_size, "/@alice");
+        assert_eq!(pages.len(), total);
+        assert_eq!(
+            pages.iter().map(|(chunk, _)| chunk.len()).sum::<usize>(),
+            count
+        );
+        assert!(pages
+            .iter()
+            .all(|(_, pagination)| pagination.total == total));
+    }
+
+    #[test]
+    fn test_pagination_urls() {
+        let items = [1, 2, 3, 4, 5];
+        let pages = Pagination::paginate(&items, 2, "/@alice");
+        assert_eq!(pages[0].1.prev_url, None);
+        assert_eq!(pages[0].1.next_url.as_deref(), Some("/@alice/page/2"));
+        assert_eq!(pages[1].1.prev_url.as_deref(), Some("/@alice"));
+        assert_eq!(pages[1].1.next_url.as_deref(), Some("/@alice/page/3"));
+        assert_eq!(pages[2].1.prev_url.as_deref(), Some("/@alice/page/2"));
+        assert_eq!(pages[2].1.next_url, None);
+        assert_eq!(pages[2].1.offset, 4);
+    }
+}
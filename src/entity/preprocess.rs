@@ -0,0 +1,178 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, ensure, Context as _, Result};
+use genkit::entity::MarkdownConfig;
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer};
+
+/// The markdown config, declared in the `[markdown]` table of the root `zine.toml`.
+///
+/// The genkit's [`MarkdownConfig`] is extended with the zine's own fields.
+#[derive(Clone, Deserialize)]
+pub struct ZineMarkdownConfig {
+    #[serde(flatten)]
+    pub inner: MarkdownConfig,
+    /// The pre-processors applied to the article markdown in order,
+    /// declared in the `[[markdown.preprocess]]` tables.
+    #[serde(default)]
+    pub preprocess: Vec<Preprocessor>,
+    /// Whether prefix the article headings and the ToC entries with the hierarchical
+    /// numbers, e.g. `2.1.3`, which can be overridden by the `number_headings` of articles.
+    #[serde(default)]
+    pub number_headings: bool,
+    /// Whether render the ToC of the articles, which can be overridden by the `toc` of articles.
+    /// Default to `true`.
+    #[serde(default = "ZineMarkdownConfig::default_toc")]
+    pub toc: bool,
+    /// The deepest heading level listed in the ToC, e.g. `3` for `<h1>` to `<h3>`,
+    /// which can be overridden by the `toc_depth` of articles. All levels are listed if missing.
+    pub toc_depth: Option<usize>,
+}
+
+impl ZineMarkdownConfig {
+    fn default_toc() -> bool {
+        true
+    }
+}
+
+impl Default for ZineMarkdownConfig {
+    fn default() -> Self {
+        Self {
+            inner: MarkdownConfig::default(),
+            preprocess: Vec::new(),
+            number_headings: false,
+            toc: Self::default_toc(),
+            toc_depth: None,
+        }
+    }
+}
+
+/// A markdown pre-processor, either a regex replacement:
+///
+/// ```toml
+/// [[markdown.preprocess]]
+/// regex = '\{\{<\s*youtube (\w+)\s*>\}\}'
+/// replace = '<iframe src="https://www.youtube.com/embed/$1"></iframe>'
+/// ```
+///
+/// or an external command filter, which reads the markdown from stdin
+/// and writes the processed markdown to stdout:
+///
+/// ```toml
+/// [[markdown.preprocess]]
+/// command = ["python3", "scripts/shortcodes.py"]
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Preprocessor {
+    Regex {
+        #[serde(deserialize_with = "deserialize_regex")]
+        regex: Regex,
+        /// The replacement, `$1` or `${name}` refers to the capture group.
+        replace: String,
+    },
+    Command {
+        /// The program and its arguments, run in the root directory.
+        command: Vec<String>,
+        #[serde(skip)]
+        root: PathBuf,
+    },
+}
+
+impl Preprocessor {
+    /// Set the root directory to run the external commands.
+    pub(super) fn set_root(&mut self, source: &Path) {
+        if let Preprocessor::Command { root, .. } = self {
+            *root = source.to_owned();
+        }
+    }
+
+    /// Apply this pre-processor to the `markdown` of `file`.
+    pub fn apply(&self, markdown: String, file: &Path) -> Result<String> {
+        match self {
+            Preprocessor::Regex { regex, replace } => {
+                Ok(regex.replace_all(&markdown, replace.as_str()).into_owned())
+            }
+            Preprocessor::Command { command, root } => {
+                let Some((program, args)) = command.split_first() else {
+                    bail!("The `command` of [[markdown.preprocess]] is empty");
+                };
+                let mut child = Command::new(program)
+                    .args(args)
+                    .current_dir(root)
+                    .env("ZINE_MARKDOWN_FILE", file)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .with_context(|| format!("Failed to run markdown pre-processor `{program}`"))?;
+                // Write in a standalone thread to avoid the deadlock of a full stdout pipe.
+                let mut stdin = child.stdin.take().expect("stdin is piped");
+                let writer = std::thread::spawn(move || stdin.write_all(markdown.as_bytes()));
+                let output = child.wait_with_output()?;
+                writer
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("markdown pre-processor writer panicked"))??;
+                ensure!(
+                    output.status.success(),
+                    "Markdown pre-processor `{program}` failed on `{}`: {}",
+                    file.display(),
+                    output.status
+                );
+                Ok(String::from_utf8(output.stdout)?)
+            }
+        }
+    }
+}
+
+/// Apply all the `preprocessors` to the `markdown` of `file` in order.
+pub fn preprocess(preprocessors: &[Preprocessor], markdown: String, file: &Path) -> Result<String> {
+    preprocessors
+        .iter()
+        .try_fold(markdown, |markdown, preprocessor| {
+            preprocessor.apply(markdown, file)
+        })
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use regex::Regex;
+
+    use super::{preprocess, Preprocessor};
+
+    #[test]
+    fn test_regex_preprocess() {
+        let preprocessors = [
+            Preprocessor::Regex {
+                regex: Regex::new(r"\{\{<\s*youtube (\w+)\s*>\}\}").unwrap(),
+                replace: String::from("[video](https://youtu.be/$1)"),
+            },
+            Preprocessor::Regex {
+                regex: Regex::new(r"\[video\]").unwrap(),
+                replace: String::from("[Video]"),
+            },
+        ];
+        assert_eq!(
+            preprocess(
+                &preprocessors,
+                String::from("Watch {{< youtube abc123 >}} now."),
+                Path::new("a.md")
+            )
+            .unwrap(),
+            "Watch [Video](https://youtu.be/abc123) now."
+        );
+    }
+}
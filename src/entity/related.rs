@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+use super::Article;
+
+/// The related articles config, declared in the `[related]` table of the root `zine.toml`:
+///
+/// ```toml
+/// [related]
+/// count = 4
+/// topic_weight = 3
+/// author_weight = 2
+/// issue_weight = 1
+/// ```
+///
+/// The score of a related article is the weighted sum of the shared topics,
+/// the shared authors and whether it's in the same issue.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RelatedConfig {
+    /// The max count of the related articles of each article, `0` to disable. Default to 3.
+    #[serde(default = "RelatedConfig::default_count")]
+    pub count: usize,
+    /// The weight of each shared topic. Default to 3.
+    #[serde(default = "RelatedConfig::default_topic_weight")]
+    pub topic_weight: u32,
+    /// The weight of each shared author. Default to 2.
+    #[serde(default = "RelatedConfig::default_author_weight")]
+    pub author_weight: u32,
+    /// The weight of being in the same issue. Default to 1.
+    #[serde(default = "RelatedConfig::default_issue_weight")]
+    pub issue_weight: u32,
+}
+
+/// A related article, rendered as the `related_articles` of the article page.
+#[derive(Clone, Debug, Serialize)]
+pub struct RelatedArticle {
+    pub title: String,
+    pub url: String,
+    pub cover: Option<String>,
+    #[serde(with = "genkit::helpers::serde_date")]
+    pub pub_date: Date,
+    pub topics: Vec<String>,
+    pub issue_title: String,
+    pub score: u32,
+}
+
+impl Default for RelatedConfig {
+    fn default() -> Self {
+        Self {
+            count: Self::default_count(),
+            topic_weight: Self::default_topic_weight(),
+            author_weight: Self::default_author_weight(),
+            issue_weight: Self::default_issue_weight(),
+        }
+    }
+}
+
+impl RelatedConfig {
+    fn default_count() -> usize {
+        3
+    }
+
+    fn default_topic_weight() -> u32 {
+        3
+    }
+
+    fn default_author_weight() -> u32 {
+        2
+    }
+
+    fn default_issue_weight() -> u32 {
+        1
+    }
+
+    /// The relevance score of the `other` article to the `article`, `0` means unrelated.
+    pub fn score(&self, article: &Article, other: &Article, same_issue: bool) -> u32 {
+        let shared_topics = article
+            .topics
+            .iter()
+            .filter(|topic| other.topics.contains(topic))
+            .count() as u32;
+        let shared_authors = match (&article.meta.author, &other.meta.author) {
+            (Some(author), Some(other_author)) => author
+                .ids()
+                .into_iter()
+                .filter(|id| other_author.is_author(id))
+                .count() as u32,
+            _ => 0,
+        };
+        shared_topics * self.topic_weight
+            + shared_authors * self.author_weight
+            + u32::from(same_issue) * self.issue_weight
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context as _, Result};
+use serde::Deserialize;
+use walkdir::WalkDir;
+
+/// The environment variable of the sandbox mode, which is set by the global
+/// `--sandbox` argument, see [`SandboxConfig::enforce`].
+pub static SANDBOX_ENV: &str = "ZINE_SANDBOX";
+
+/// The template sandbox config, declared in the `[sandbox]` table of the root `zine.toml`.
+///
+/// The sandbox limits what the (maybe third-party) templates can do. The templates
+/// are trusted by default, set `trusted = false` to opt in the limits, which are
+/// always enforced by the `--sandbox` argument.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SandboxConfig {
+    /// Whether the templates are trusted, default `true`.
+    #[serde(default = "SandboxConfig::default_trusted")]
+    pub trusted: bool,
+    /// The maximum nesting depth of includes, extends and macro calls.
+    #[serde(default = "SandboxConfig::default_recursion_limit")]
+    pub recursion_limit: usize,
+    /// The maximum number of instructions to render a single template,
+    /// which caps the render time of runaway loops.
+    #[serde(default = "SandboxConfig::default_fuel")]
+    pub fuel: u64,
+    /// Whether the sandbox is enforced by the sandbox mode, which the project can't opt out.
+    #[serde(skip)]
+    pub enforced: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            trusted: Self::default_trusted(),
+            recursion_limit: Self::default_recursion_limit(),
+            fuel: Self::default_fuel(),
+            enforced: false,
+        }
+    }
+}
+
+impl SandboxConfig {
+    fn default_trusted() -> bool {
+        true
+    }
+
+    fn default_recursion_limit() -> usize {
+        100
+    }
+
+    fn default_fuel() -> u64 {
+        50_000_000
+    }
+
+    /// Whether to build in the sandbox mode, which is turned on by the `--sandbox` argument.
+    pub fn is_sandbox_mode() -> bool {
+        env::var_os(SANDBOX_ENV).is_some()
+    }
+
+    /// Enforce the sandbox to build the untrusted projects, e.g. on a multi-tenant hosting:
+    /// the `trusted` is ignored, the template functions reading files are disabled and
+    /// the project files must not link to outside of the project.
+    pub fn enforce(&mut self) {
+        self.trusted = false;
+        self.enforced = true;
+    }
+
+    /// Resolve the relative `path` in the `root` directory.
+    ///
+    /// Unless trusted, the resolved path must not escape the `root` directory,
+    /// either by absolute path, `..` components or symlinks.
+    pub fn resolve_path(&self, root: &Path, path: &str) -> Result<PathBuf> {
+        let resolved = root.join(path);
+        if self.trusted {
+            return Ok(resolved);
+        }
+
+        if Path::new(path)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            bail!("The path `{path}` is outside of the project directory, which is disallowed by [sandbox]");
+        }
+        // Resolve the symlinks.
+        let canonical_root = root.canonicalize()?;
+        let canonical_path = resolved
+            .canonicalize()
+            .with_context(|| format!("Failed to read `{}`", resolved.display()))?;
+        ensure!(
+            canonical_path.starts_with(&canonical_root),
+            "The path `{path}` links to outside of the project directory, which is disallowed by [sandbox]"
+        );
+        Ok(canonical_path)
+    }
+
+    /// Resolve the relative `path` of a project file in the `root` directory,
+    /// e.g. an article, a font or a logo file.
+    ///
+    /// The project files are only limited in the sandbox mode, where the resolved
+    /// path must not escape the `root` directory, see [`Self::resolve_path`].
+    pub fn resolve_project_path(&self, root: &Path, path: &str) -> Result<PathBuf> {
+        if self.enforced {
+            self.resolve_path(root, path)
+        } else {
+            Ok(root.join(path))
+        }
+    }
+
+    /// Whether to follow the symlinks when walking the project directories,
+    /// the symlinks may link to outside of the project so they are skipped
+    /// in the sandbox mode.
+    pub fn follow_links(&self) -> bool {
+        !self.enforced
+    }
+
+    /// Walk the project directory `dir`, including `dir` itself, following
+    /// the symlinks unless in the sandbox mode, see [`Self::follow_links`].
+    pub fn walk_dir(&self, dir: &Path) -> WalkDir {
+        WalkDir::new(dir)
+            .follow_links(self.follow_links())
+            .follow_root_links(self.follow_links())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use tempfile::tempdir;
+    use test_case::test_case;
+
+    use super::SandboxConfig;
+
+    #[test_case("Cargo.toml", true)]
+    #[test_case("./src/main.rs", true)]
+    #[test_case("../Cargo.toml", false)]
+    #[test_case("src/../../Cargo.toml", false)]
+    #[test_case("/etc/passwd", false)]
+    fn test_resolve_path(path: &str, allowed: bool) {
+        let root = env::current_dir().unwrap();
+        let sandbox = SandboxConfig {
+            trusted: false,
+            ..Default::default()
+        };
+        assert_eq!(sandbox.resolve_path(&root, path).is_ok(), allowed);
+
+        let trusted = SandboxConfig::default();
+        assert!(trusted.resolve_path(&root, path).is_ok());
+
+        let mut enforced = SandboxConfig::default();
+        enforced.enforce();
+        assert_eq!(enforced.resolve_path(&root, path).is_ok(), allowed);
+    }
+
+    #[cfg(unix)]
+    #[test_case("Cargo.toml", true)]
+    #[test_case("../Cargo.toml", false)]
+    #[test_case("/etc/passwd", false)]
+    #[test_case("outside.toml", false; "symlink")]
+    fn test_resolve_project_path(path: &str, allowed: bool) {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        std::os::unix::fs::symlink(
+            env::current_dir().unwrap().join("Cargo.toml"),
+            root.join("outside.toml"),
+        )
+        .unwrap();
+
+        // The project files are only limited in the sandbox mode.
+        let untrusted = SandboxConfig {
+            trusted: false,
+            ..Default::default()
+        };
+        assert!(untrusted.resolve_project_path(root, path).is_ok());
+        assert!(untrusted.follow_links());
+
+        let mut enforced = SandboxConfig::default();
+        enforced.enforce();
+        assert_eq!(enforced.resolve_project_path(root, path).is_ok(), allowed);
+        assert!(!enforced.follow_links());
+    }
+}
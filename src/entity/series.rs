@@ -0,0 +1,115 @@
+use std::{borrow::Cow, path::Path};
+
+use anyhow::Result;
+use minijinja::Environment;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+use crate::{data, engine};
+
+use super::Entity;
+use genkit::{html::Meta, Context};
+
+/// A multi-part article series, declared in the `[series.<id>]` table of the root `zine.toml`:
+///
+/// ```toml
+/// [series.async-rust]
+/// name = "Async Rust from scratch"
+/// description = "Build an async runtime step by step."
+/// ```
+///
+/// The articles join the series by the `series = "async-rust"` field, the parts
+/// are ordered by the publish date, even across issues.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Series {
+    #[serde(skip_deserializing, default)]
+    pub id: String,
+    /// The url slug, which is derived from the id like the topics.
+    #[serde(skip_deserializing, default)]
+    pub slug: String,
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// A published part of a series.
+#[derive(Clone, Debug, Serialize)]
+pub struct SeriesPart {
+    pub title: String,
+    pub url: String,
+    #[serde(with = "genkit::helpers::serde_date")]
+    pub pub_date: Date,
+    pub issue_title: String,
+    /// The part number, starting from 1.
+    pub part: usize,
+}
+
+/// The series navigation of an article, rendered as the `series` of the article page.
+#[derive(Clone, Debug, Serialize)]
+pub struct SeriesNavigation {
+    pub id: String,
+    pub name: String,
+    /// The url of the series landing page, e.g. `/series/async-rust`.
+    pub url: String,
+    /// The part number of the article, starting from 1.
+    pub part: usize,
+    pub total: usize,
+    pub parts: Vec<SeriesPart>,
+    pub previous: Option<SeriesPart>,
+    pub next: Option<SeriesPart>,
+}
+
+impl Series {
+    /// The display name of the series, fallback to the id.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+
+    /// The url of the series landing page.
+    pub fn url(&self) -> String {
+        format!("/series/{}", self.slug)
+    }
+
+    /// The navigation of the `index`th part of the `parts`.
+    pub fn navigation(&self, parts: &[SeriesPart], index: usize) -> SeriesNavigation {
+        SeriesNavigation {
+            id: self.id.clone(),
+            name: self.name().to_owned(),
+            url: self.url(),
+            part: index + 1,
+            total: parts.len(),
+            parts: parts.to_vec(),
+            previous: index.checked_sub(1).map(|index| parts[index].clone()),
+            next: parts.get(index + 1).cloned(),
+        }
+    }
+}
+
+impl Entity for Series {
+    fn parse(&mut self, _source: &Path) -> Result<()> {
+        self.slug = data::read()
+            .get_site()
+            .slug_strategy
+            .apply(&self.id.to_lowercase());
+        super::slug::normalize_slug("series", &mut self.slug);
+        // Fallback to capitalized id if missing.
+        if self.name.is_none() {
+            self.name = Some(genkit::helpers::capitalize(&self.id));
+        }
+        Ok(())
+    }
+
+    fn render(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
+        let url = self.url();
+        context.insert(
+            "meta",
+            &Meta {
+                title: Cow::Borrowed(self.name()),
+                description: Cow::Borrowed(self.description.as_deref().unwrap_or_default()),
+                url: Some(Cow::Borrowed(&url[1..])),
+                image: None,
+            },
+        );
+        context.insert("series", &self);
+        engine::render(env, "series.jinja", context, dest.join(&self.slug))
+    }
+}
@@ -1,4 +1,12 @@
+use std::collections::BTreeMap;
+
+use semver::Version;
 use serde::{Deserialize, Serialize};
+use time::Date;
+
+use crate::i18n;
+
+use super::{BadgeConfig, Colophon, SlugStrategy};
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Site {
@@ -15,17 +23,324 @@ pub struct Site {
     /// Default to 'en'.
     #[serde(default = "default_locale")]
     pub locale: String,
+    /// Keep the previous builds under `/v/<n>/`, so readers can view
+    /// an issue exactly as it appeared at publication time.
+    #[serde(default)]
+    pub versioned_archive: bool,
+    /// Generate the "What's new" page and feed of new and updated articles
+    /// since the previous builds, under `/whats-new/`.
+    #[serde(default)]
+    pub whats_new: bool,
+    /// Generate the JSON Feed `/feed.json` alongside the Atom feed.
+    #[serde(default)]
+    pub json_feed: bool,
+    /// How many articles in each page of the issue, topic and author pages,
+    /// default to 0 (no pagination).
+    #[serde(default)]
+    pub page_size: usize,
+    /// How many articles in each page of the author page, fallback to `page_size` if 0.
+    #[serde(default)]
+    pub author_page_size: usize,
+    /// How many recent non-featured articles of each issue on the home page, default to 0.
+    #[serde(default)]
+    pub home_recent_articles: usize,
+    /// How many sentences of the issue intro as the issue summary on the home page,
+    /// if the issue has no `summary`. Default to 2, set to 0 to disable.
+    #[serde(default = "default_summary_sentences")]
+    pub home_summary_sentences: usize,
+    /// The strategy to derive the url slugs from the file names and topic ids,
+    /// one of `keep` (default), `slugify`, `transliterate` and `pinyin`.
+    #[serde(default, skip_serializing)]
+    pub slug_strategy: SlugStrategy,
+    /// The strftime-like format of the `format_date` filter, e.g. `%B %e, %Y`.
+    /// Default to `%Y-%m-%d`.
+    pub date_format: Option<String>,
+    /// The author contribution badges, declared in `[site.badges]`.
+    /// No badges are awarded if missing.
+    #[serde(default, skip_serializing)]
+    pub badges: Option<BadgeConfig>,
+    /// The entry limits of the atom feeds, declared in `[site.feeds]`.
+    #[serde(default, skip_serializing)]
+    pub feeds: FeedConfig,
+    /// The colophon of the site, rendered in the `/colophon/` page.
+    pub colophon: Option<Colophon>,
+    /// The minimum zine version to build this site, e.g. `0.14`.
+    pub min_zine_version: Option<String>,
+    /// The announcement bar on top of every page, declared in `[site.announcement]`.
+    /// Only the active announcement is exposed to templates, see [`Site::active_announcement`].
+    #[serde(default, skip_serializing)]
+    pub announcement: Option<Announcement>,
+    #[serde(rename(deserialize = "menu"))]
+    #[serde(default)]
+    pub menus: Vec<Menu>,
+    /// The localized sites, declared in `[site.i18n.<locale>]` tables,
+    /// each of which renders the whole site under the `/<locale>/` root.
+    #[serde(default, skip_serializing)]
+    pub i18n: BTreeMap<String, SiteTranslation>,
+}
+
+/// A localized site, declared in `[site.i18n.<locale>]` table.
+///
+/// The issues and articles of the localized site use their `[i18n.<locale>]`
+/// translations if any, otherwise the original ones.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SiteTranslation {
+    /// The site name of this locale, fallback to the original name.
+    pub name: Option<String>,
+    /// The site description of this locale, fallback to the original description.
+    pub description: Option<String>,
+    /// The menus of this locale, fallback to the original menus.
     #[serde(rename(deserialize = "menu"))]
     #[serde(default)]
     pub menus: Vec<Menu>,
 }
 
+/// The root url of the original or a localized site, rendered as
+/// the `<link rel="alternate" hreflang>` tags of every page.
+#[derive(Clone, Debug, Serialize)]
+pub struct SiteAlternate {
+    pub hreflang: String,
+    pub url: String,
+}
+
+/// A time-limited notice of the site, such as a call for submissions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Announcement {
+    /// The markdown text of the announcement.
+    pub text: String,
+    /// The first date to show the announcement, format like YYYY-MM-DD.
+    #[serde(default)]
+    #[serde(with = "genkit::helpers::serde_date::options")]
+    pub start_date: Option<Date>,
+    /// The last date to show the announcement, format like YYYY-MM-DD.
+    #[serde(default)]
+    #[serde(with = "genkit::helpers::serde_date::options")]
+    pub end_date: Option<Date>,
+    /// Whether readers can close the announcement, which is remembered by the browser.
+    #[serde(default)]
+    pub dismissible: bool,
+}
+
+impl Announcement {
+    /// Check whether the `today` is within the date window, both ends are inclusive.
+    pub fn is_active(&self, today: Date) -> bool {
+        self.start_date.map_or(true, |start| start <= today)
+            && self.end_date.map_or(true, |end| today <= end)
+    }
+}
+
+impl Site {
+    /// Get the localized site of `locale` under the `/<locale>/` root,
+    /// `None` if the `locale` isn't declared in `[site.i18n]`.
+    pub fn localized(&self, locale: &str) -> Option<Site> {
+        let translation = self.i18n.get(locale)?;
+        let url = self.url.trim_end_matches('/');
+        Some(Site {
+            url: format!("{url}/{locale}"),
+            // The static assets are shared with the original site.
+            cdn: Some(self.cdn.clone().unwrap_or_else(|| format!("{url}/static"))),
+            name: translation
+                .name
+                .clone()
+                .unwrap_or_else(|| self.name.clone()),
+            description: translation
+                .description
+                .clone()
+                .or_else(|| self.description.clone()),
+            locale: locale.to_owned(),
+            menus: if translation.menus.is_empty() {
+                self.menus.clone()
+            } else {
+                translation.menus.clone()
+            },
+            i18n: BTreeMap::new(),
+            ..self.clone()
+        })
+    }
+
+    /// Get the root urls of the original site and the localized sites,
+    /// empty if there is no localized site.
+    pub fn alternates(&self) -> Vec<SiteAlternate> {
+        if self.i18n.is_empty() {
+            return Vec::new();
+        }
+        let url = self.url.trim_end_matches('/');
+        let mut alternates = vec![
+            SiteAlternate {
+                hreflang: i18n::hreflang(&self.locale),
+                url: url.to_owned(),
+            },
+            SiteAlternate {
+                hreflang: String::from("x-default"),
+                url: url.to_owned(),
+            },
+        ];
+        alternates.extend(self.i18n.keys().map(|locale| SiteAlternate {
+            hreflang: i18n::hreflang(locale),
+            url: format!("{url}/{locale}"),
+        }));
+        alternates
+    }
+
+    /// Get the announcement if it is active `today`.
+    pub fn active_announcement(&self, today: Date) -> Option<&Announcement> {
+        self.announcement
+            .as_ref()
+            .filter(|announcement| announcement.is_active(today))
+    }
+}
+
+/// The entry limits of the atom feeds, declared in `[site.feeds]` table.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// How many latest articles in the site feed `/feed.xml`, default to 20.
+    #[serde(default = "default_feed_entries")]
+    pub entries: usize,
+    /// How many latest articles in each topic feed `/topic/<id>/feed.xml`,
+    /// default to 20, set to 0 to disable.
+    #[serde(default = "default_feed_entries")]
+    pub topic_entries: usize,
+    /// How many latest articles in each author feed `/@<id>/feed.xml`,
+    /// default to 20, set to 0 to disable.
+    #[serde(default = "default_feed_entries")]
+    pub author_entries: usize,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            entries: default_feed_entries(),
+            topic_entries: default_feed_entries(),
+            author_entries: default_feed_entries(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Menu {
     pub name: String,
     pub url: String,
 }
 
+/// Check whether the `version` is the `min` version or later, both are
+/// semantic versions like `0.14`, `0.14.1` or `0.15.0-beta.1`.
+///
+/// A pre-release precedes its release, so `0.14.0-beta.1` is not `0.14` or later.
+///
+/// Return `None` if any version is invalid.
+pub fn version_at_least(version: &str, min: &str) -> Option<bool> {
+    fn parse(version: &str) -> Option<Version> {
+        let version = version.trim().trim_start_matches('v');
+        // Pad the missing parts with zero, e.g. `0.14` is `0.14.0`.
+        let (core, suffix) = version.split_at(version.find(['-', '+']).unwrap_or(version.len()));
+        let padding = ".0".repeat(3usize.saturating_sub(core.split('.').count()));
+        Version::parse(&format!("{core}{padding}{suffix}")).ok()
+    }
+
+    Some(parse(version)? >= parse(min)?)
+}
+
 fn default_locale() -> String {
     "en".to_owned()
 }
+
+fn default_summary_sentences() -> usize {
+    2
+}
+
+fn default_feed_entries() -> usize {
+    20
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+    use time::{Date, Month};
+
+    use super::{version_at_least, Announcement, Site, SiteTranslation};
+
+    fn date(day: u8) -> Option<Date> {
+        Some(Date::from_calendar_date(2023, Month::March, day).unwrap())
+    }
+
+    #[test_case(None, None, 1, true)]
+    #[test_case(date(5), None, 4, false)]
+    #[test_case(date(5), date(10), 5, true)]
+    #[test_case(date(5), date(10), 10, true)]
+    #[test_case(date(5), date(10), 11, false)]
+    #[test_case(None, date(10), 1, true)]
+    fn test_announcement_is_active(
+        start_date: Option<Date>,
+        end_date: Option<Date>,
+        today: u8,
+        expected: bool,
+    ) {
+        let announcement = Announcement {
+            text: String::from("Call for submissions"),
+            start_date,
+            end_date,
+            dismissible: false,
+        };
+        assert_eq!(announcement.is_active(date(today).unwrap()), expected);
+    }
+
+    #[test_case("0.14.0", "0.14", Some(true))]
+    #[test_case("0.14.1", "0.14", Some(true))]
+    #[test_case("0.13.9", "0.14", Some(false))]
+    #[test_case("1.0.0", "0.14.2", Some(true))]
+    #[test_case("0.14.0-beta.1", "v0.14", Some(false))]
+    #[test_case("0.14.0-beta.1", "0.13", Some(true))]
+    #[test_case("0.14.0-beta.2", "0.14.0-beta.1", Some(true))]
+    #[test_case("0.14.0+build.5", "0.14", Some(true))]
+    #[test_case("0.14.0.1", "0.14", None)]
+    #[test_case("0.14.0", "latest", None)]
+    fn test_version_at_least(version: &str, min: &str, expected: Option<bool>) {
+        assert_eq!(version_at_least(version, min), expected);
+    }
+
+    #[test]
+    fn test_localized_site() {
+        let mut site = Site {
+            url: String::from("https://zine.example/"),
+            name: String::from("Rust Magazine"),
+            description: Some(String::from("A magazine")),
+            locale: String::from("en"),
+            ..Default::default()
+        };
+        assert!(site.alternates().is_empty());
+        site.i18n.insert(
+            String::from("zh_CN"),
+            SiteTranslation {
+                name: Some(String::from("Rust 杂志")),
+                ..Default::default()
+            },
+        );
+
+        let localized = site.localized("zh_CN").unwrap();
+        assert_eq!(localized.url, "https://zine.example/zh_CN");
+        assert_eq!(
+            localized.cdn.as_deref(),
+            Some("https://zine.example/static")
+        );
+        assert_eq!(localized.name, "Rust 杂志");
+        assert_eq!(localized.description.as_deref(), Some("A magazine"));
+        assert_eq!(localized.locale, "zh_CN");
+        assert!(localized.i18n.is_empty());
+        assert!(site.localized("fr").is_none());
+
+        let alternates = site
+            .alternates()
+            .into_iter()
+            .map(|alternate| (alternate.hreflang, alternate.url))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            alternates,
+            [
+                ("en", "https://zine.example"),
+                ("x-default", "https://zine.example"),
+                ("zh-CN", "https://zine.example/zh_CN"),
+            ]
+            .map(|(hreflang, url)| (hreflang.to_owned(), url.to_owned()))
+        );
+    }
+}
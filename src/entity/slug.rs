@@ -0,0 +1,191 @@
+use deunicode::deunicode;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use pinyin::ToPinyin;
+use serde::Deserialize;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::data;
+
+// The (kind, original slug, normalized slug) list changed by the slug policy.
+static SLUG_CHANGES: OnceCell<Mutex<Vec<(&'static str, String, String)>>> = OnceCell::new();
+
+/// The url slug policy, declared in the `[slug]` table of the root `zine.toml`.
+///
+/// The policy applies to the slugs of issues, articles and topics.
+/// The author ids are always lowercased and can't contain spaces.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SlugConfig {
+    /// Lowercase the slugs.
+    #[serde(default)]
+    pub lowercase: bool,
+    /// Replace the whitespaces in slugs with `-`.
+    #[serde(default)]
+    pub replace_spaces: bool,
+    /// Strip the diacritics in slugs, e.g. `café` -> `cafe`.
+    #[serde(default)]
+    pub strip_diacritics: bool,
+}
+
+impl SlugConfig {
+    /// Normalize the `slug` with this policy.
+    pub fn normalize(&self, slug: &str) -> String {
+        let mut slug = slug.to_owned();
+        if self.strip_diacritics {
+            slug = slug
+                .nfd()
+                .filter(|ch| !is_combining_mark(*ch))
+                .nfc()
+                .collect();
+        }
+        if self.lowercase {
+            slug = slug.to_lowercase();
+        }
+        if self.replace_spaces {
+            slug = slug.split_whitespace().collect::<Vec<_>>().join("-");
+        }
+        slug
+    }
+}
+
+/// The strategy to derive the url slugs from the file names, issue directories
+/// and topic ids, declared as the `slug_strategy` of `[site]`.
+///
+/// The explicit `slug` of issues and articles is kept as is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlugStrategy {
+    /// Keep the names verbatim.
+    #[default]
+    Keep,
+    /// Lowercase the names and join the words with `-`, e.g. `Hello World!` -> `hello-world`.
+    Slugify,
+    /// Transliterate the names into ASCII then slugify, e.g. `Привет мир` -> `privet-mir`.
+    Transliterate,
+    /// Like `transliterate`, but the Chinese characters are converted into the pinyin,
+    /// e.g. `中文标题` -> `zhong-wen-biao-ti`.
+    Pinyin,
+}
+
+impl SlugStrategy {
+    /// Derive the slug from the `name`, each `/` separated segment is converted separately.
+    ///
+    /// The segment is kept if nothing is left after the conversion, e.g. an emoji.
+    pub fn apply(&self, name: &str) -> String {
+        if *self == SlugStrategy::Keep {
+            return name.to_owned();
+        }
+        name.split('/')
+            .map(|segment| {
+                let converted = match self {
+                    SlugStrategy::Keep | SlugStrategy::Slugify => slugify(segment),
+                    SlugStrategy::Transliterate => slugify(&deunicode(segment)),
+                    SlugStrategy::Pinyin => {
+                        let mut text = String::new();
+                        for ch in segment.chars() {
+                            match ch.to_pinyin() {
+                                Some(pinyin) => {
+                                    text.push(' ');
+                                    text.push_str(pinyin.plain());
+                                    text.push(' ');
+                                }
+                                None => text.push(ch),
+                            }
+                        }
+                        slugify(&deunicode(&text))
+                    }
+                };
+                if converted.is_empty() {
+                    segment.to_owned()
+                } else {
+                    converted
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+// Lowercase the `text` and join the alphanumeric words with `-`.
+fn slugify(text: &str) -> String {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Normalize the `slug` of `kind` (issue, article or topic) with the slug policy,
+/// the changed slug is recorded for the migration report.
+pub(super) fn normalize_slug(kind: &'static str, slug: &mut String) {
+    let normalized = data::read().get_slug_config().normalize(slug);
+    if normalized != *slug {
+        SLUG_CHANGES
+            .get_or_init(|| Mutex::new(Vec::new()))
+            .lock()
+            .push((
+                kind,
+                std::mem::replace(slug, normalized.clone()),
+                normalized,
+            ));
+    }
+}
+
+/// Print the slugs changed by the slug policy, so that redirects can be added
+/// for the old urls.
+pub(super) fn report_slug_changes() {
+    let Some(changes) = SLUG_CHANGES.get() else {
+        return;
+    };
+    let mut changes = std::mem::take(&mut *changes.lock());
+    if changes.is_empty() {
+        return;
+    }
+
+    changes.sort();
+    println!(
+        "Warning: {} url(s) changed by the [slug] policy, consider adding redirects for the old urls:",
+        changes.len()
+    );
+    for (kind, original, normalized) in changes {
+        println!("  {kind} `{original}` -> `{normalized}`");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{SlugConfig, SlugStrategy};
+
+    #[test_case("Hello World", "Hello World"; "disabled")]
+    fn test_normalize_disabled(slug: &str, expected: &str) {
+        assert_eq!(SlugConfig::default().normalize(slug), expected);
+    }
+
+    #[test_case("Hello  World", "hello-world")]
+    #[test_case("Café Crème", "cafe-creme")]
+    #[test_case("中文 标题", "中文-标题")]
+    #[test_case("already-ok", "already-ok")]
+    fn test_normalize(slug: &str, expected: &str) {
+        let config = SlugConfig {
+            lowercase: true,
+            replace_spaces: true,
+            strip_diacritics: true,
+        };
+        assert_eq!(config.normalize(slug), expected);
+    }
+
+    #[test_case(SlugStrategy::Keep, "Hello World!", "Hello World!")]
+    #[test_case(SlugStrategy::Slugify, "Hello  World!", "hello-world")]
+    #[test_case(SlugStrategy::Slugify, "中文 标题", "中文-标题")]
+    #[test_case(SlugStrategy::Slugify, "issue-1/Hello World", "issue-1/hello-world"; "slugify segments")]
+    #[test_case(SlugStrategy::Transliterate, "Привет мир", "privet-mir")]
+    #[test_case(SlugStrategy::Transliterate, "Café Crème", "cafe-creme")]
+    #[test_case(SlugStrategy::Pinyin, "中文标题", "zhong-wen-biao-ti")]
+    #[test_case(SlugStrategy::Pinyin, "Rust 中文", "rust-zhong-wen")]
+    #[test_case(SlugStrategy::Slugify, "🦀", "🦀"; "keep empty conversion")]
+    fn test_slug_strategy(strategy: SlugStrategy, name: &str, expected: &str) {
+        assert_eq!(strategy.apply(name), expected);
+    }
+}
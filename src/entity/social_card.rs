@@ -0,0 +1,81 @@
+use std::{fs, path::Path, sync::Arc};
+
+use anyhow::{bail, Context as _, Result};
+use serde::Deserialize;
+
+use super::SandboxConfig;
+
+/// The social cards config, declared in the `[social_cards]` table of the root `zine.toml`:
+///
+/// ```toml
+/// [social_cards]
+/// font = "static/fonts/Inter-Bold.ttf"
+/// background = "#1e293b"
+/// ```
+///
+/// A card PNG with the title, authors and issue name is generated for each article
+/// without a `social_image`, as the OpenGraph and Twitter card image.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SocialCardsConfig {
+    /// The TTF or OTF font file to draw the text, relative to the zine root.
+    pub font: String,
+    /// The background color, default to the theme primary color.
+    pub background: Option<String>,
+    /// The text color. Default to `#ffffff`.
+    #[serde(default = "SocialCardsConfig::default_text_color")]
+    pub text_color: String,
+    /// The font file content, loaded in parsing.
+    #[serde(skip)]
+    pub font_data: Arc<Vec<u8>>,
+}
+
+impl SocialCardsConfig {
+    fn default_text_color() -> String {
+        String::from("#ffffff")
+    }
+
+    /// Load the font file in the project `source` directory and check the colors.
+    pub(super) fn parse(&mut self, source: &Path, sandbox_config: &SandboxConfig) -> Result<()> {
+        let font_file = sandbox_config.resolve_project_path(source, &self.font)?;
+        self.font_data = Arc::new(fs::read(&font_file).with_context(|| {
+            format!(
+                "Failed to read the font of [social_cards]: `{}`",
+                font_file.display()
+            )
+        })?);
+        for color in [self.background.as_deref(), Some(self.text_color.as_str())]
+            .into_iter()
+            .flatten()
+        {
+            if crate::social_card::parse_color(color).is_none() {
+                bail!("Invalid color of [social_cards]: `{color}`, expect like `#1e293b`");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::entity::SandboxConfig;
+
+    use super::SocialCardsConfig;
+
+    #[test]
+    fn test_font_outside_project() {
+        let mut config = SocialCardsConfig {
+            font: String::from("../Cargo.toml"),
+            background: None,
+            text_color: SocialCardsConfig::default_text_color(),
+            font_data: Default::default(),
+        };
+        let source = env::current_dir().unwrap().join("src");
+        assert!(config.parse(&source, &SandboxConfig::default()).is_ok());
+
+        let mut sandbox_config = SandboxConfig::default();
+        sandbox_config.enforce();
+        assert!(config.parse(&source, &sandbox_config).is_err());
+    }
+}
@@ -1,9 +1,21 @@
-use std::{fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
 
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use super::Entity;
+use crate::snapshot;
+
+use super::{site, Entity, SandboxConfig};
+
+/// The manifest file of a theme package.
+pub static THEME_MANIFEST_FILE: &str = "theme.toml";
+// The directory (relative to the zine root) to clone the git theme packages.
+static THEME_CACHE_DIR: &str = ".zine-cache/themes";
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
@@ -23,6 +35,10 @@ pub struct Theme {
     // The background image url.
     #[serde(default)]
     pub background_image: Option<String>,
+    // Whether the theme (usually the custom head template) provides a dark mode,
+    // exposed to client scripts in `window.zineTheme`.
+    #[serde(default)]
+    pub dark_mode: bool,
     // The extra head template path, will be parsed to html.
     pub head_template: Option<String>,
     // The custom footer template path, will be parsed to html.
@@ -34,6 +50,73 @@ pub struct Theme {
     pub default_cover: Option<String>,
     #[serde(skip_serializing)]
     pub default_avatar: Option<String>,
+    // The fenced block templates in the `blocks` directory, keyed by the template name,
+    // e.g. `blocks/recipe.jinja` renders the ```` ```recipe ```` blocks of articles.
+    #[serde(skip)]
+    pub blocks: BTreeMap<String, String>,
+    // The user shortcode templates in the `templates/shortcodes` directory, keyed by the
+    // template name, e.g. `shortcodes/youtube.jinja` renders ```` ```youtube, id: abc ````.
+    #[serde(skip)]
+    pub shortcodes: BTreeMap<String, String>,
+    // The theme package, either a directory relative to the project or a git url,
+    // e.g. `https://github.com/zineland/paper.git#v1`, see [`ThemePackage`].
+    #[serde(skip_serializing)]
+    pub package: Option<String>,
+    // The loaded theme package.
+    #[serde(skip)]
+    pub loaded_package: Option<ThemePackage>,
+}
+
+/// A theme package, which is a directory with the `theme.toml` manifest, the `templates`
+/// directory overriding the builtin templates, and the `static` directory.
+#[derive(Clone, Debug)]
+pub struct ThemePackage {
+    pub manifest: ThemeManifest,
+    /// The templates keyed by the path relative to the `templates` directory,
+    /// e.g. `article.jinja` and `blocks/recipe.jinja`.
+    pub templates: BTreeMap<String, String>,
+    /// The `static` directory of the package if present, the project's static files
+    /// of the same path take precedence.
+    pub static_dir: Option<PathBuf>,
+}
+
+/// The `theme.toml` manifest of a theme package.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ThemeManifest {
+    pub name: String,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// The minimum zine version to use this theme, e.g. `0.16`.
+    pub min_zine_version: Option<String>,
+}
+
+/// The theme values overridden by the `[theme]` table of an issue's `zine.toml`,
+/// which are applied only to the pages of that issue, e.g. a special issue.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ThemeOverride {
+    pub primary_color: Option<String>,
+    pub main_color: Option<String>,
+    pub link_color: Option<String>,
+    pub secondary_color: Option<String>,
+    pub background_image: Option<String>,
+    /// The extra head template path, which replaces the site's head template.
+    pub head_template: Option<String>,
+}
+
+impl ThemeOverride {
+    /// Read the head template in the project `source` directory to html.
+    pub(super) fn parse(&mut self, source: &Path, sandbox_config: &SandboxConfig) -> Result<()> {
+        if let Some(head_template) = self.head_template.as_ref() {
+            let path = sandbox_config.resolve_path(source, head_template)?;
+            self.head_template = Some(fs::read_to_string(&path).with_context(|| {
+                format!(
+                    "Failed to parse the issue head template: `{}`",
+                    path.display(),
+                )
+            })?);
+        }
+        Ok(())
+    }
 }
 
 impl Default for Theme {
@@ -44,11 +127,16 @@ impl Default for Theme {
             link_color: Self::default_link_color(),
             secondary_color: Self::default_secondary_color(),
             background_image: None,
+            dark_mode: false,
             head_template: None,
             footer_template: None,
             article_extend_template: None,
             default_cover: None,
             default_avatar: None,
+            blocks: BTreeMap::new(),
+            shortcodes: BTreeMap::new(),
+            package: None,
+            loaded_package: None,
         }
     }
 }
@@ -61,6 +149,7 @@ impl std::fmt::Debug for Theme {
             .field("link_color", &self.link_color)
             .field("secondary_color", &self.secondary_color)
             .field("background_image", &self.background_image)
+            .field("dark_mode", &self.dark_mode)
             .field("head_template", &self.head_template.is_some())
             .field("footer_template", &self.footer_template.is_some())
             .field(
@@ -69,11 +158,95 @@ impl std::fmt::Debug for Theme {
             )
             .field("default_cover", &self.default_cover)
             .field("default_avatar", &self.default_avatar)
+            .field("blocks", &self.blocks.keys().collect::<Vec<_>>())
+            .field("shortcodes", &self.shortcodes.keys().collect::<Vec<_>>())
+            .field("package", &self.package)
             .finish()
     }
 }
 
 impl Theme {
+    /// The theme with the values of `theme_override` applied.
+    pub fn with_override(&self, theme_override: &ThemeOverride) -> Theme {
+        let mut theme = self.clone();
+        for (value, overridden) in [
+            (&mut theme.primary_color, &theme_override.primary_color),
+            (&mut theme.main_color, &theme_override.main_color),
+            (&mut theme.link_color, &theme_override.link_color),
+            (&mut theme.secondary_color, &theme_override.secondary_color),
+        ] {
+            if let Some(overridden) = overridden {
+                value.clone_from(overridden);
+            }
+        }
+        if theme_override.background_image.is_some() {
+            theme.background_image = theme_override.background_image.clone();
+        }
+        if theme_override.head_template.is_some() {
+            theme.head_template = theme_override.head_template.clone();
+        }
+        theme
+    }
+
+    /// Deserialize the `theme` of the root `zine.toml`, either the `[theme]` table
+    /// or the theme package shorthand, e.g. `theme = "themes/paper"`.
+    pub fn deserialize_table_or_package<'de, D>(deserializer: D) -> Result<Theme, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum TableOrPackage {
+            Package(String),
+            Table(Box<Theme>),
+        }
+
+        Ok(match TableOrPackage::deserialize(deserializer)? {
+            TableOrPackage::Package(package) => Theme {
+                package: Some(package),
+                ..Default::default()
+            },
+            TableOrPackage::Table(theme) => *theme,
+        })
+    }
+
+    /// Load the theme `package` of the project `source` directory if any,
+    /// the git packages are cloned into the cache directory once.
+    pub fn load_package(&mut self, source: &Path, sandbox_config: &SandboxConfig) -> Result<()> {
+        let Some(package) = self.package.as_deref() else {
+            return Ok(());
+        };
+        let dir = if is_git_url(package) {
+            ensure!(
+                !sandbox_config.enforced,
+                "The git theme package `{package}` is disallowed in the sandbox mode"
+            );
+            clone_package(source, package)?
+        } else {
+            sandbox_config.resolve_path(source, package)?
+        };
+        self.loaded_package = Some(
+            ThemePackage::load(&dir, sandbox_config)
+                .with_context(|| format!("Failed to load the theme package `{package}`"))?,
+        );
+        Ok(())
+    }
+
+    /// Load the fenced block templates and the shortcode templates of the project
+    /// `source` directory, the symlinks are skipped in the sandbox mode.
+    pub fn load_templates(&mut self, source: &Path, sandbox_config: &SandboxConfig) -> Result<()> {
+        self.blocks = read_templates(source, "blocks", "block", sandbox_config)?
+            .into_iter()
+            .map(|(name, template)| (format!("blocks/{name}"), template))
+            .collect();
+        self.shortcodes =
+            read_templates(source, "templates/shortcodes", "shortcode", sandbox_config)?
+                .into_iter()
+                .map(|(name, template)| (format!("shortcodes/{name}"), template))
+                .collect();
+        Ok(())
+    }
+
     const DEFAULT_PRIMARY_COLOR: &'static str = "#2563eb";
     const DEFAULT_MAIN_COLOR: &'static str = "#ffffff";
     const DEFAULT_LINK_COLOR: &'static str = "#2563eb";
@@ -138,6 +311,227 @@ impl Entity for Theme {
                 })?,
             );
         }
+
         Ok(())
     }
 }
+
+impl ThemePackage {
+    /// Load the theme package in the `dir` directory.
+    ///
+    /// In the sandbox mode, the files linking to outside of the package are disallowed.
+    pub fn load(dir: &Path, sandbox_config: &SandboxConfig) -> Result<Self> {
+        let manifest_file = dir.join(THEME_MANIFEST_FILE);
+        ensure!(
+            manifest_file.exists(),
+            "`{}` not found, is it a theme package?",
+            manifest_file.display()
+        );
+        let manifest_file = sandbox_config.resolve_project_path(dir, THEME_MANIFEST_FILE)?;
+        let manifest = toml::from_str::<ThemeManifest>(&fs::read_to_string(&manifest_file)?)
+            .with_context(|| format!("Failed to parse `{}`", manifest_file.display()))?;
+        if let Some(required) = manifest.min_zine_version.as_deref() {
+            let current = env!("CARGO_PKG_VERSION");
+            match site::version_at_least(current, required) {
+                Some(true) => {}
+                Some(false) => bail!(
+                    "The theme `{}` requires zine {required} or later, the current version is {current}",
+                    manifest.name
+                ),
+                None => bail!("Invalid `min_zine_version` of the theme: `{required}`, expect like `0.14`"),
+            }
+        }
+
+        let mut templates = BTreeMap::new();
+        let templates_dir = dir.join("templates");
+        if templates_dir.is_dir() {
+            for entry in sandbox_config.walk_dir(&templates_dir) {
+                let entry = entry?;
+                let path = entry.path();
+                // The symlinks are regular files only if followed.
+                if !entry.file_type().is_file()
+                    || !matches!(path.extension(), Some(ext) if ext == "jinja")
+                {
+                    continue;
+                }
+                let name = path
+                    .strip_prefix(&templates_dir)?
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let template = fs::read_to_string(path).with_context(|| {
+                    format!("Failed to parse the theme template: `{}`", path.display())
+                })?;
+                templates.insert(name, template);
+            }
+        }
+
+        let static_dir = dir.join("static");
+        Ok(ThemePackage {
+            manifest,
+            templates,
+            static_dir: static_dir.is_dir().then_some(static_dir),
+        })
+    }
+}
+
+// Whether the theme `package` is a git url rather than a local directory.
+fn is_git_url(package: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "git@"]
+        .iter()
+        .any(|prefix| package.starts_with(prefix))
+}
+
+// Clone the git theme `package` into the cache directory of `source` if missing,
+// an optional branch or tag follows the `#`, e.g. `https://github.com/zineland/paper.git#v1`.
+fn clone_package(source: &Path, package: &str) -> Result<PathBuf> {
+    let (url, reference) = match package.rsplit_once('#') {
+        Some((url, reference)) if !reference.is_empty() => (url, Some(reference)),
+        _ => (package.trim_end_matches('#'), None),
+    };
+    let dir = source
+        .join(THEME_CACHE_DIR)
+        .join(&snapshot::hash(package.as_bytes())[..16]);
+    if dir.join(THEME_MANIFEST_FILE).exists() {
+        return Ok(dir);
+    }
+
+    println!("Cloning the theme package `{package}`...");
+    if dir.exists() {
+        // Remove the broken clone.
+        fs::remove_dir_all(&dir)?;
+    }
+    let mut command = process::Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(reference) = reference {
+        command.args(["--branch", reference]);
+    }
+    let status = command
+        .arg(url)
+        .arg(&dir)
+        .status()
+        .with_context(|| "Failed to run `git clone`".to_string())?;
+    ensure!(
+        status.success(),
+        "Failed to clone the theme package `{url}`"
+    );
+    Ok(dir)
+}
+
+// Read the `*.jinja` templates of the `dir` directory in the project `source` directory,
+// keyed by the file name, e.g. `recipe.jinja`.
+fn read_templates(
+    source: &Path,
+    dir: &str,
+    kind: &str,
+    sandbox_config: &SandboxConfig,
+) -> Result<BTreeMap<String, String>> {
+    let mut templates = BTreeMap::new();
+    if !source.join(dir).is_dir() {
+        return Ok(templates);
+    }
+    let templates_dir = sandbox_config.resolve_project_path(source, dir)?;
+    for entry in sandbox_config.walk_dir(&templates_dir).max_depth(1) {
+        let entry = entry?;
+        let path = entry.path();
+        // The symlinks are regular files only if followed.
+        if !entry.file_type().is_file() || !matches!(path.extension(), Some(ext) if ext == "jinja")
+        {
+            continue;
+        }
+        let template = fs::read_to_string(path).with_context(|| {
+            format!("Failed to parse the {kind} template: `{}`", path.display())
+        })?;
+        templates.insert(entry.file_name().to_string_lossy().into_owned(), template);
+    }
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use tempfile::tempdir;
+    use test_case::test_case;
+
+    use crate::entity::SandboxConfig;
+
+    use super::{is_git_url, Theme, ThemePackage};
+
+    #[test_case("themes/paper", false)]
+    #[test_case("https://github.com/zineland/paper.git#v1", true)]
+    #[test_case("git@github.com:zineland/paper.git", true)]
+    fn test_is_git_url(package: &str, expected: bool) {
+        assert_eq!(is_git_url(package), expected);
+    }
+
+    #[test]
+    fn test_deserialize_theme_package() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "Theme::deserialize_table_or_package")]
+            theme: Theme,
+        }
+
+        let config = toml::from_str::<Config>(r#"theme = "themes/paper""#).unwrap();
+        assert_eq!(config.theme.package.as_deref(), Some("themes/paper"));
+        assert_eq!(config.theme.primary_color, Theme::DEFAULT_PRIMARY_COLOR);
+
+        let config = toml::from_str::<Config>(
+            "[theme]\npackage = \"themes/paper\"\nprimary_color = \"#000\"",
+        )
+        .unwrap();
+        assert_eq!(config.theme.package.as_deref(), Some("themes/paper"));
+        assert_eq!(config.theme.primary_color, "#000");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_load_package_symlinks() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("templates/shortcodes")).unwrap();
+        fs::create_dir_all(dir.join("blocks")).unwrap();
+        fs::write(dir.join("theme.toml"), "name = \"paper\"").unwrap();
+        for template in [
+            "templates/base",
+            "blocks/recipe",
+            "templates/shortcodes/youtube",
+        ] {
+            fs::write(dir.join(format!("{template}.jinja")), "base").unwrap();
+            std::os::unix::fs::symlink(
+                env::current_dir().unwrap().join("Cargo.toml"),
+                dir.join(format!("{template}-secret.jinja")),
+            )
+            .unwrap();
+        }
+
+        let package = ThemePackage::load(dir, &SandboxConfig::default()).unwrap();
+        assert!(package.templates.contains_key("base-secret.jinja"));
+        let mut theme = Theme::default();
+        theme
+            .load_templates(dir, &SandboxConfig::default())
+            .unwrap();
+        assert!(theme.blocks.contains_key("blocks/recipe-secret.jinja"));
+        assert!(theme
+            .shortcodes
+            .contains_key("shortcodes/youtube-secret.jinja"));
+
+        let mut sandbox_config = SandboxConfig::default();
+        sandbox_config.enforce();
+        let package = ThemePackage::load(dir, &sandbox_config).unwrap();
+        assert!(package.templates.contains_key("base.jinja"));
+        assert!(!package.templates.contains_key("base-secret.jinja"));
+        let mut theme = Theme::default();
+        theme.load_templates(dir, &sandbox_config).unwrap();
+        assert_eq!(
+            theme.blocks.keys().collect::<Vec<_>>(),
+            ["blocks/recipe.jinja"]
+        );
+        assert_eq!(
+            theme.shortcodes.keys().collect::<Vec<_>>(),
+            ["shortcodes/youtube.jinja"]
+        );
+    }
+}
@@ -1,28 +1,82 @@
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, fs, path::Path};
 
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use minijinja::Environment;
 use serde::{Deserialize, Serialize};
 
-use crate::engine;
+use crate::{data, engine};
 
-use super::Entity;
-use genkit::{html::Meta, Context};
+use super::{Entity, Pagination};
+use genkit::{html::Meta, markdown, Context};
+
+// The directory of the topic content files, e.g. `topics/rust.md`.
+static TOPIC_CONTENT_DIR: &str = "topics";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Topic {
     #[serde(skip_deserializing, default)]
     pub id: String,
+    /// The url slug, which is the lowercased id normalized by the slug policy.
+    #[serde(skip_deserializing, default)]
+    pub slug: String,
     name: Option<String>,
     description: Option<String>,
+    /// The landing content of the topic page (parsed from the `topics/<id>.md` file).
+    #[serde(skip_deserializing)]
+    pub content: Option<String>,
+}
+
+impl Topic {
+    pub(super) fn new(id: &str) -> Self {
+        Topic {
+            id: id.to_owned(),
+            slug: String::new(),
+            name: None,
+            description: None,
+            content: None,
+        }
+    }
+
+    /// The display name of the topic, fallback to the id.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
 }
 
 impl Entity for Topic {
-    fn parse(&mut self, _source: &Path) -> Result<()> {
+    fn parse(&mut self, source: &Path) -> Result<()> {
+        self.slug = data::read()
+            .get_site()
+            .slug_strategy
+            .apply(&self.id.to_lowercase());
+        super::slug::normalize_slug("topic", &mut self.slug);
         // Fallback to capitalized id if missing.
         if self.name.is_none() {
             self.name = Some(genkit::helpers::capitalize(&self.id));
         }
+
+        let content_file = format!("{TOPIC_CONTENT_DIR}/{}.md", self.id);
+        if source.join(&content_file).exists() {
+            let content_path = data::read()
+                .get_sandbox_config()
+                .resolve_project_path(source, &content_file)?;
+            let content = fs::read_to_string(&content_path).with_context(|| {
+                format!(
+                    "Failed to read topic content from {}",
+                    content_path.display()
+                )
+            })?;
+            let zine_data = data::read();
+            let content = super::preprocess(
+                zine_data.get_markdown_preprocessors(),
+                content,
+                &content_path,
+            )?;
+            zine_data
+                .get_lint_config()
+                .check_markers(&content_path, &content)?;
+            self.content = Some(content);
+        }
         Ok(())
     }
 
@@ -30,19 +84,28 @@ impl Entity for Topic {
         context.insert(
             "meta",
             &Meta {
-                title: Cow::Borrowed(self.name.as_deref().unwrap_or(&self.id)),
-                description: Cow::Borrowed(self.description.as_deref().unwrap_or("")),
-                url: Some(format!("/topic/{}", self.id.to_lowercase()).into()),
+                title: Cow::Borrowed(self.name()),
+                description: match (self.description.as_deref(), self.content.as_deref()) {
+                    (Some(description), _) => Cow::Borrowed(description),
+                    (None, Some(content)) => Cow::Owned(markdown::extract_description(content)),
+                    (None, None) => Cow::Borrowed(""),
+                },
+                url: Some(format!("/topic/{}", self.slug).into()),
                 image: None,
             },
         );
         context.insert("topic", &self);
-        engine::render(
-            env,
-            "topic.jinja",
-            context,
-            dest.join(self.id.to_lowercase()),
-        )?;
+        // The paginated pages are rendered into `topic/<slug>/page/<n>`.
+        let page_dir = context
+            .get("pagination")
+            .and_then(|pagination| pagination.get("current"))
+            .and_then(|current| current.as_u64())
+            .and_then(|current| Pagination::page_dir(current as usize));
+        let dest = match page_dir {
+            Some(page_dir) => dest.join(&self.slug).join(page_dir),
+            None => dest.join(&self.slug),
+        };
+        engine::render(env, "topic.jinja", context, dest)?;
         Ok(())
     }
 }
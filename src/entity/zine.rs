@@ -1,8 +1,14 @@
-use crate::{data, engine, error::ZineError, feed::FeedEntry};
-use anyhow::{Context as _, Result};
+use crate::{
+    changelog::ChangelogEntry,
+    data, engine,
+    error::ZineError,
+    feed::{FeedEntry, FeedLink, FeedSeries, SitemapAlternate, SitemapEntry},
+    i18n, media, output, permalink,
+};
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
 use genkit::{
-    entity::MarkdownConfig,
     helpers::{self, capitalize},
+    html::Meta,
     Context, Entity,
 };
 use minijinja::{context, Environment};
@@ -13,22 +19,38 @@ use rayon::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     fs,
     path::{Component, Path},
 };
+use time::Date;
 use walkdir::WalkDir;
 
-use super::{Author, Issue, List, MetaArticle, Page, Site, Theme, Topic};
+use super::{
+    author::validate_author_id, lint, load_glossary, normalize_author_id, render_calendar, site,
+    slug, Article, ArticleSource, Author, BuildConfig, CommentsConfig, CspMode, DeployConfig,
+    Event, FormsConfig, GlossaryTerm, ImagesConfig, Issue, LintConfig, List, MediaConfig,
+    MetaArticle, Page, Pagination, Preprocessor, RelatedArticle, RelatedConfig, SandboxConfig,
+    Series, SeriesNavigation, SeriesPart, Site, SlugConfig, SocialCardsConfig, Theme, Topic,
+    ZineMarkdownConfig,
+};
+
+// The count of top topics in the retrospective of the previous issue.
+const RETROSPECTIVE_TOPICS: usize = 3;
+// The preview tokens file in the `.zine` directory.
+const PREVIEW_TOKENS_FILE: &str = "preview-tokens.json";
+// The page rendered as the `404.html`, relative to the `pages` directory.
+const NOT_FOUND_PAGE: &str = "404.md";
 
 /// The root zine entity config.
 ///
 /// It parsed from the root directory's `zine.toml`.
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct Zine {
     pub site: Site,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "Theme::deserialize_table_or_package")]
     pub theme: Theme,
     #[serde(default)]
     pub authors: BTreeMap<String, Author>,
@@ -37,11 +59,59 @@ pub struct Zine {
     pub issues: Vec<Issue>,
     #[serde(default)]
     pub topics: BTreeMap<String, Topic>,
+    /// The multi-part article series, rendered in the `/series/<slug>/` pages.
+    #[serde(default)]
+    pub series: BTreeMap<String, Series>,
+    /// The events and announcements, rendered in the `/events/` page and the `/events.ics` calendar.
+    #[serde(default)]
+    #[serde(rename = "event")]
+    pub events: Vec<Event>,
+    /// The old url path and new url pairs, rendered as the redirect pages,
+    /// e.g. `"/issue-1/old-slug" = "/issue-1/new-slug"`.
+    #[serde(default)]
+    pub redirects: BTreeMap<String, String>,
     #[serde(skip)]
     pub pages: Vec<Page>,
+    /// The `pages/404.md` page, rendered as the `404.html` rather than a regular page.
+    #[serde(skip)]
+    pub not_found_page: Option<Page>,
+    /// The glossary terms of the root `glossary.toml`, rendered in the `/glossary/` page.
+    #[serde(skip)]
+    pub glossary: Vec<GlossaryTerm>,
     #[serde(default)]
     #[serde(rename = "markdown")]
-    pub markdown_config: MarkdownConfig,
+    pub markdown_config: ZineMarkdownConfig,
+    #[serde(default)]
+    #[serde(rename = "build")]
+    pub build_config: BuildConfig,
+    #[serde(default)]
+    #[serde(rename = "media")]
+    pub media_config: MediaConfig,
+    #[serde(default)]
+    #[serde(rename = "lint")]
+    pub lint_config: LintConfig,
+    #[serde(default)]
+    #[serde(rename = "slug")]
+    pub slug_config: SlugConfig,
+    #[serde(default)]
+    #[serde(rename = "sandbox")]
+    pub sandbox_config: SandboxConfig,
+    #[serde(default)]
+    #[serde(rename = "related")]
+    pub related_config: RelatedConfig,
+    #[serde(rename = "forms")]
+    pub forms_config: Option<FormsConfig>,
+    /// The comment system embedded in the article pages.
+    #[serde(rename = "comments")]
+    pub comments_config: Option<CommentsConfig>,
+    #[serde(rename = "images")]
+    pub images_config: Option<ImagesConfig>,
+    /// The generated OpenGraph and Twitter card images of articles.
+    #[serde(rename = "social_cards")]
+    pub social_cards_config: Option<SocialCardsConfig>,
+    /// The deploy target of `zine publish`.
+    #[serde(rename = "deploy")]
+    pub deploy_config: Option<DeployConfig>,
 }
 
 impl std::fmt::Debug for Zine {
@@ -59,18 +129,80 @@ impl std::fmt::Debug for Zine {
 #[derive(Serialize)]
 struct ArticleRef<'a> {
     article: &'a MetaArticle,
+    topics: &'a Vec<String>,
+    issue_title: &'a String,
+    issue_slug: &'a String,
+}
+
+impl ArticleRef<'_> {
+    // The url path of this article.
+    fn url(&self) -> String {
+        match self.article.path.as_ref() {
+            Some(path) => path.clone(),
+            None => format!("/{}/{}", self.issue_slug, self.article.slug),
+        }
+    }
+}
+
+// The `authors/<id>/articles.json` file of an author, for client side "load more".
+#[derive(Serialize)]
+struct AuthorArticles<'a> {
+    author: &'a str,
+    total: usize,
+    // The topic and article count pairs of this author.
+    topics: BTreeMap<&'a str, usize>,
+    articles: Vec<AuthorArticle<'a>>,
+}
+
+#[derive(Serialize)]
+struct AuthorArticle<'a> {
+    title: &'a String,
+    url: String,
+    cover: &'a Option<String>,
+    #[serde(with = "genkit::helpers::serde_date")]
+    pub_date: Date,
+    topics: &'a Vec<String>,
     issue_title: &'a String,
     issue_slug: &'a String,
 }
 
+impl<'a> AuthorArticles<'a> {
+    fn new(author: &'a str, articles: &[ArticleRef<'a>]) -> Self {
+        let mut topics = BTreeMap::new();
+        for topic in articles.iter().flat_map(|item| item.topics) {
+            *topics.entry(topic.as_str()).or_default() += 1;
+        }
+        AuthorArticles {
+            author,
+            total: articles.len(),
+            topics,
+            articles: articles
+                .iter()
+                .map(|item| AuthorArticle {
+                    title: &item.article.title,
+                    url: item.url(),
+                    cover: &item.article.cover,
+                    pub_date: item.article.pub_date,
+                    topics: item.topics,
+                    issue_title: item.issue_title,
+                    issue_slug: item.issue_slug,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl Zine {
     /// Parse Zine instance from the root zine.toml file.
     pub fn parse_from_toml<P: AsRef<Path>>(source: P) -> Result<Zine> {
         let source = source.as_ref().join(crate::ZINE_FILE);
         let content = fs::read_to_string(&source)
             .with_context(|| format!("Failed to read `{}`", source.display()))?;
+        // Check the version before parsing, the newer config may not be
+        // parsed by the older zine.
+        check_min_zine_version(&content)?;
 
-        Ok(toml::from_str::<Zine>(&content).map_err(|err| {
+        let mut zine = toml::from_str::<Zine>(&content).map_err(|err| {
             let value = toml::from_str::<toml::Value>(&content)
                 .unwrap_or_else(|_| panic!("Parse `{}` failed", source.display()));
             if value.get("site").is_some() {
@@ -78,7 +210,35 @@ impl Zine {
             } else {
                 ZineError::NotRootTomlFile
             }
-        })?)
+        })?;
+        if SandboxConfig::is_sandbox_mode() {
+            zine.enforce_sandbox()?;
+        }
+        Ok(zine)
+    }
+
+    // Disable everything reaching outside of the project in the sandbox mode: the files
+    // outside of the project, the external fetches and the command pre-processors.
+    fn enforce_sandbox(&mut self) -> Result<()> {
+        self.sandbox_config.enforce();
+        if self
+            .markdown_config
+            .preprocess
+            .iter()
+            .any(|preprocessor| matches!(preprocessor, Preprocessor::Command { .. }))
+        {
+            bail!("The command pre-processors of [[markdown.preprocess]] are disallowed in the sandbox mode");
+        }
+        if self.media_config.localize_remote {
+            println!("Warning: the `localize_remote` of [media] is disabled in the sandbox mode.");
+            self.media_config.localize_remote = false;
+        }
+        if self.lint_config.stale_canonical.take().is_some() {
+            println!(
+                "Warning: the `stale_canonical` check of [lint] is disabled in the sandbox mode."
+            );
+        }
+        Ok(())
     }
 
     /// Parsing issue entities from dir.
@@ -92,9 +252,15 @@ impl Zine {
             fs::create_dir_all(&content_dir)?;
         }
 
-        for entry in WalkDir::new(&content_dir).contents_first(true).into_iter() {
+        // The symlinks are only followed out of the sandbox mode.
+        let follow_links = self.sandbox_config.follow_links();
+        for entry in WalkDir::new(&content_dir)
+            .follow_root_links(follow_links)
+            .contents_first(true)
+            .into_iter()
+        {
             let entry = entry?;
-            if entry.file_name() != crate::ZINE_FILE {
+            if entry.file_name() != crate::ZINE_FILE || (entry.path_is_symlink() && !follow_links) {
                 continue;
             }
             let content = fs::read_to_string(entry.path()).with_context(|| {
@@ -128,6 +294,54 @@ impl Zine {
         Ok(())
     }
 
+    // Assign preview tokens to the unpublished issues which need preview.
+    //
+    // The tokens are persisted in the `.zine/preview-tokens.json` keyed by issue slug,
+    // so the preview links keep stable across builds. Unlike `zine-data.json`,
+    // the file is never published along with the site.
+    fn assign_preview_tokens(&mut self, source: &Path) -> Result<()> {
+        if !self.issues.iter().any(|issue| issue.preview) {
+            return Ok(());
+        }
+
+        let tokens_file = source.join(crate::ZINE_STATE_DIR).join(PREVIEW_TOKENS_FILE);
+        let mut tokens = if tokens_file.exists() {
+            serde_json::from_str::<BTreeMap<String, String>>(&fs::read_to_string(&tokens_file)?)
+                .with_context(|| format!("Failed to parse `{}`", tokens_file.display()))?
+        } else {
+            BTreeMap::new()
+        };
+
+        let mut changed = false;
+        for issue in self.issues.iter_mut().filter(|issue| issue.preview) {
+            if !tokens.contains_key(&issue.slug) {
+                tokens.insert(issue.slug.clone(), random_token()?);
+                changed = true;
+            }
+            issue.preview_token = tokens.get(&issue.slug).cloned();
+        }
+
+        // The new tokens are not persisted in a dry run.
+        if changed && !output::is_dry_run() {
+            fs::create_dir_all(source.join(crate::ZINE_STATE_DIR))?;
+            fs::write(&tokens_file, serde_json::to_string_pretty(&tokens)?)?;
+        }
+        Ok(())
+    }
+
+    /// Get the localized zine of the `locale` declared in `[site.i18n.<locale>]`,
+    /// which is rendered under the `/<locale>/` root with the same url paths.
+    pub fn localized(&self, locale: &str) -> Option<Zine> {
+        let mut zine = self.clone();
+        zine.site = self.site.localized(locale)?;
+        // The redirects are only written into the original site.
+        zine.redirects.clear();
+        zine.issues
+            .iter_mut()
+            .for_each(|issue| issue.localize(locale));
+        Some(zine)
+    }
+
     pub fn get_issue_by_number(&self, number: u32) -> Option<&Issue> {
         self.issues.iter().find(|issue| issue.number == number)
     }
@@ -151,6 +365,7 @@ impl Zine {
                         if article.is_author(author_id) {
                             Some(ArticleRef {
                                 article: &article.meta,
+                                topics: &article.topics,
                                 issue_title: &issue.title,
                                 issue_slug: &issue.slug,
                             })
@@ -178,6 +393,7 @@ impl Zine {
                         if article.topics.iter().any(|t| t == topic) {
                             Some(ArticleRef {
                                 article: &article.meta,
+                                topics: &article.topics,
                                 issue_title: &issue.title,
                                 issue_slug: &issue.slug,
                             })
@@ -192,263 +408,746 @@ impl Zine {
         items
     }
 
-    // Get author list.
-    fn authors(&self) -> Vec<Author> {
-        self.authors.values().cloned().collect()
-    }
-
-    fn all_articles(&self) -> Vec<(String, MetaArticle)> {
-        self.issues
-            .par_iter()
-            .flat_map(|issue| {
-                issue
-                    .articles()
-                    .iter()
-                    .map(|article| (issue.slug.clone(), article.meta.clone()))
-                    .collect::<Vec<_>>()
-            })
-            .collect()
-    }
-
-    /// Get latest `limit` number of articles in all issues.
-    /// Sort by date in descending order.
-    pub fn latest_feed_entries(&self, limit: usize) -> Vec<FeedEntry> {
-        let mut entries = self
-            .issues
-            .par_iter()
-            .flat_map(|issue| {
-                let mut entries = issue
-                    .articles()
-                    .iter()
-                    .map(|article| FeedEntry {
-                        title: &article.meta.title,
-                        url: if let Some(path) = article.meta.path.as_ref() {
-                            format!("{}{}", self.site.url, path)
-                        } else {
-                            format!("{}/{}/{}", self.site.url, issue.slug, article.meta.slug)
-                        },
-                        content: &article.markdown,
-                        author: &article.meta.author,
-                        date: Some(article.meta.pub_date),
-                    })
-                    .collect::<Vec<_>>();
-
-                // Add issue intro article into feed
-                if issue.need_publish() {
-                    if let Some(content) = issue.intro.as_ref() {
-                        entries.push(FeedEntry {
-                            title: &issue.title,
-                            url: format!("{}/{}", self.site.url, issue.slug),
-                            content,
-                            author: &None,
-                            date: issue.pub_date,
-                        })
-                    }
-                }
-                entries
-            })
-            .collect::<Vec<_>>();
-
-        // Sort by date in descending order.
-        entries.par_sort_unstable_by(|a, b| match (a.date, b.date) {
-            (Some(a_date), Some(b_date)) => b_date.cmp(&a_date),
-            _ => Ordering::Equal,
-        });
-        entries.into_iter().take(limit).collect()
+    // Normalize the author ids declared in `[authors]` table, see [`normalize_author_id`].
+    fn normalize_author_ids(&mut self) -> Result<()> {
+        let mut authors = BTreeMap::new();
+        for (id, mut author) in std::mem::take(&mut self.authors) {
+            // Fallback to capitalized id if missing, keep the original case of id.
+            if author.name.is_none() {
+                author.name = Some(capitalize(&id));
+            }
+            let normalized_id = normalize_author_id(&id);
+            validate_author_id(&normalized_id).with_context(|| {
+                format!("Invalid author `{id}` in [authors] of root `zine.toml`")
+            })?;
+            if authors.insert(normalized_id, author).is_some() {
+                bail!("Duplicate author `{id}` in [authors] of root `zine.toml`, the author id is case-insensitive");
+            }
+        }
+        self.authors = authors;
+        Ok(())
     }
 
-    /// Get `sitemap.xml` entries.
-    pub fn sitemap_entries(&self) -> Vec<String> {
-        let base_url = &self.site.url;
-        // Sitemap URL must begin with the protocol (such as http)
-        // and end with a trailing slash.
-        // https://www.sitemaps.org/protocol.html
-        let mut entries = vec![format!("{}/", base_url)];
-
-        // Issues and articles
+    // The output paths (with their sources) of issues, articles and pages,
+    // which are chosen by users. Only the published ones if `published_only` is true.
+    fn user_output_paths(&self, published_only: bool) -> Vec<(String, String)> {
+        let mut paths = vec![];
         for issue in &self.issues {
-            entries.push(format!("{}/{}/", base_url, issue.slug));
-            let articles = issue
-                .articles()
-                .into_iter()
-                .par_bridge()
+            if published_only && !issue.need_publish() {
+                continue;
+            }
+            paths.push((issue.slug.clone(), format!("issue `{}`", issue.dir)));
+            for download in &issue.downloads {
+                paths.push((
+                    download.url.clone(),
+                    format!("download `{}/downloads/{}`", issue.dir, download.name),
+                ));
+            }
+            for (locale, translation) in &issue.i18n {
+                paths.push((
+                    translation.slug.clone(),
+                    format!("`{locale}` translation of issue `{}`", issue.dir),
+                ));
+            }
+            for article in issue
+                .articles
+                .iter()
+                .filter(|article| {
+                    !published_only || article.need_publish() || article.tombstone_url().is_some()
+                })
                 .flat_map(|article| {
                     let mut articles = vec![article];
                     // including translation articles
                     articles.extend(article.i18n.values());
                     articles
                 })
-                .map(|article| {
-                    if let Some(path) = article.meta.path.as_ref() {
-                        format!("{}{}", base_url, path)
-                    } else {
-                        format!("{}/{}/{}", base_url, issue.slug, article.meta.slug)
-                    }
-                });
-            entries.par_extend(articles);
+            {
+                let path = match article.meta.path.as_ref() {
+                    Some(path) => path.clone(),
+                    None => format!("{}/{}", issue.slug, article.meta.slug),
+                };
+                paths.push((
+                    path,
+                    format!("article `{}/{}`", issue.dir, article.meta.file),
+                ));
+            }
         }
-
-        // Authors
-        entries.push(format!("{}/authors/", base_url));
-        entries.par_extend(
-            self.authors
-                .par_iter()
-                .map(|(id, _)| format!("{}/@{}/", base_url, id.to_lowercase())),
-        );
-
-        // Topics
-        if !self.topics.is_empty() {
-            entries.push(format!("{}/topics/", base_url));
-            entries.par_extend(
-                self.topics
-                    .par_iter()
-                    .map(|(id, _)| format!("{}/topic/{}/", base_url, id.to_lowercase())),
-            );
+        for page in self
+            .pages
+            .iter()
+            .filter(|page| !published_only || page.need_publish() || page.archive_url().is_some())
+        {
+            paths.push((
+                page.slug(),
+                format!("page `pages/{}`", page.file_path.display()),
+            ));
         }
-
-        // Pages
-        entries.par_extend(
-            self.pages
-                .par_iter()
-                .map(|page| format!("{}/{}/", base_url, page.slug())),
-        );
-        entries
+        for from in self.redirects.keys() {
+            paths.push((from.clone(), format!("redirect `{from}`")));
+        }
+        paths
     }
-}
-
-impl Entity for Zine {
-    fn parse(&mut self, source: &Path) -> Result<()> {
-        self.theme.parse(source)?;
 
-        self.topics.par_iter_mut().try_for_each(|(id, topic)| {
-            topic.id = id.clone();
-            topic.parse(source)
-        })?;
+    /// The url paths of the published issues, articles, pages, authors, topics
+    /// and redirects, e.g. `/issue-1/hello`. See [`permalink::check_registry`].
+    ///
+    /// [`permalink::check_registry`]: crate::permalink::check_registry
+    pub fn published_paths(&self) -> BTreeSet<String> {
+        self.user_output_paths(true)
+            .into_iter()
+            .chain(self.entity_output_paths())
+            .map(|(path, _)| format!("/{}", path.trim_matches('/')))
+            .collect()
+    }
 
-        {
-            let mut zine_data = data::write();
-            zine_data
-                .set_theme(self.theme.clone())
-                .set_site(self.site.clone())
-                .set_topics(self.topics.keys().cloned().collect());
+    // The output paths (with their sources) of author and topic pages,
+    // which are generated under their own prefix.
+    fn entity_output_paths(&self) -> Vec<(String, String)> {
+        let mut paths = vec![];
+        for id in self.authors.keys() {
+            paths.push((format!("@{id}"), format!("author `{id}`")));
         }
+        for topic in self.topics.values() {
+            paths.push((
+                format!("topic/{}", topic.slug),
+                format!("topic `{}`", topic.id),
+            ));
+        }
+        for series in self.series.values() {
+            paths.push((
+                format!("series/{}", series.slug),
+                format!("series `{}`", series.id),
+            ));
+        }
+        paths
+    }
 
-        self.parse_issue_from_dir(source)?;
-
-        self.issues.parse(source)?;
-        // Sort all issues by number.
-        self.issues.par_sort_unstable_by_key(|s| s.number);
-
-        if self.authors.is_empty() {
-            println!("Warning: no author specified in [authors] of root `zine.toml`.");
-        } else {
-            self.authors.par_iter_mut().try_for_each(|(id, author)| {
-                author.id = id.clone();
-                // Fallback to default zine avatar if neccessary.
-                if author.avatar.is_none()
-                    || matches!(&author.avatar, Some(avatar) if avatar.is_empty())
-                {
-                    author.avatar = self.theme.default_avatar.clone();
-                }
-
-                // Fallback to capitalized id if missing.
-                if author.name.is_none() {
-                    author.name = Some(capitalize(&author.id));
+    /// Get the earliest date after `today` when the build output changes by itself
+    /// with the source of that date, for scheduling the next build: an article or page
+    /// expires, the announcement starts or ends, or an event is no longer upcoming.
+    ///
+    /// The `pub_date` isn't a change, the future-dated articles are built already.
+    pub fn next_change_date(&self, today: Date) -> Option<(Date, String)> {
+        let mut dates = vec![];
+        for issue in &self.issues {
+            for article in issue
+                .articles
+                .iter()
+                .flat_map(|article| std::iter::once(article).chain(article.i18n.values()))
+            {
+                if let Some(expire_date) = article.meta.expire_date {
+                    dates.push((
+                        expire_date,
+                        format!("article `{}/{}` expires", issue.dir, article.meta.file),
+                    ));
                 }
-                author.parse(source)
-            })?;
+            }
+        }
+        for page in &self.pages {
+            if let Some(expire_date) = page.expire_date() {
+                dates.push((
+                    expire_date,
+                    format!("page `pages/{}` expires", page.file_path.display()),
+                ));
+            }
+        }
+        if let Some(announcement) = &self.site.announcement {
+            if let Some(start_date) = announcement.start_date {
+                dates.push((start_date, String::from("announcement starts")));
+            }
+            // The announcement is shown until the end of the `end_date`.
+            if let Some(end_date) = announcement.end_date.and_then(Date::next_day) {
+                dates.push((end_date, String::from("announcement ends")));
+            }
         }
+        for event in &self.events {
+            if let Some(date) = event.date.next_day() {
+                dates.push((date, format!("event `{}` passes", event.title)));
+            }
+        }
+        earliest_date_after(dates, today)
+    }
 
-        // Parse pages
-        let page_dir = source.join("pages");
-        if page_dir.exists() {
-            // Parallelize pages dir walk
-            self.pages = WalkDir::new(&page_dir)
+    /// Get all the output paths of a build with their sources, sorted by path.
+    pub fn output_paths(&self) -> Vec<(String, String)> {
+        let mut paths = vec![
+            (String::new(), String::from("home page")),
+            (String::from("feed.xml"), String::from("atom feed")),
+            (String::from("sitemap.xml"), String::from("sitemap")),
+            (String::from("feeds.opml"), String::from("feed list")),
+            (String::from("404.html"), String::from("not found page")),
+            (String::from("authors"), String::from("author list")),
+            (String::from("static"), String::from("static directory")),
+        ];
+        if !self.topics.is_empty() {
+            paths.push((String::from("topics"), String::from("topic list")));
+        }
+        if self.site.whats_new {
+            paths.push((String::from("whats-new"), String::from("changelog")));
+        }
+        if self.site.json_feed {
+            paths.push((String::from("feed.json"), String::from("json feed")));
+        }
+        if self.has_colophon() {
+            paths.push((String::from("colophon"), String::from("colophon")));
+        }
+        if !self.events.is_empty() {
+            paths.push((String::from("events"), String::from("event list")));
+            paths.push((String::from("events.ics"), String::from("event calendar")));
+        }
+        if !self.glossary.is_empty() {
+            paths.push((String::from("glossary"), String::from("glossary")));
+        }
+        if self.has_csp_headers() {
+            paths.push((
+                String::from(crate::csp::HEADERS_FILE),
+                String::from("content security policy"),
+            ));
+        }
+        paths.extend(
+            crate::export::output_paths(&self.build_config.exporters)
                 .into_iter()
-                .par_bridge()
-                .try_fold_with(vec![], |mut pages, entry| {
-                    let entry = entry?;
-                    let path = entry.path();
-                    if path.is_file() {
-                        let markdown = fs::read_to_string(path).with_context(|| {
-                            format!("Failed to read markdown file of `{}`", path.display())
-                        })?;
-                        pages.push(Page {
-                            markdown,
-                            file_path: path.strip_prefix(&page_dir)?.to_owned(),
-                        });
-                    }
-                    anyhow::Ok(pages)
-                })
-                .try_reduce_with(|mut pages, chuncks| {
-                    pages.par_extend(chuncks);
-                    anyhow::Ok(pages)
-                })
-                .transpose()?
-                .unwrap_or_default();
+                .map(|path| (path, String::from("exporter"))),
+        );
+        paths.extend(self.user_output_paths(true));
+        paths.extend(self.entity_output_paths());
+        for (id, author) in &self.authors {
+            paths.push((
+                format!("authors/{id}/articles.json"),
+                format!("author `{id}`"),
+            ));
+            if author.public_contact {
+                paths.push((format!("@{id}/vcard.vcf"), format!("author `{id}`")));
+            }
+            if !self
+                .author_feed_entries(id, self.site.feeds.author_entries)
+                .is_empty()
+            {
+                paths.push((format!("@{id}/feed.xml"), format!("author `{id}`")));
+            }
+        }
+        for topic in self.topics.values() {
+            if !self
+                .topic_feed_entries(&topic.id, self.site.feeds.topic_entries)
+                .is_empty()
+            {
+                paths.push((
+                    format!("topic/{}/feed.xml", topic.slug),
+                    format!("topic `{}`", topic.id),
+                ));
+            }
+        }
+        for (path, _) in &mut paths {
+            *path = format!("/{}", path.trim_matches('/'));
+        }
+        paths.sort();
+        paths
+    }
+
+    // Check the series of all articles are declared in the `[series]` table.
+    fn check_article_series(&self) -> Result<()> {
+        for issue in &self.issues {
+            for article in issue
+                .articles
+                .iter()
+                .flat_map(|article| std::iter::once(article).chain(article.i18n.values()))
+            {
+                if let Some(id) = article.meta.series.as_deref() {
+                    ensure!(
+                        self.series.contains_key(id),
+                        "the series `{id}` of article `{}` is invalid, please declare it in [series] of the root `zine.toml`",
+                        article.meta.title
+                    );
+                }
+            }
         }
         Ok(())
     }
 
-    fn render(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
-        context.insert("site", &self.site);
+    // Whether to write the `_headers` file of the content security policy.
+    fn has_csp_headers(&self) -> bool {
+        self.build_config
+            .csp
+            .as_ref()
+            .is_some_and(|csp| csp.mode == CspMode::Headers)
+    }
 
-        // Render all authors pages.
-        let authors = self.authors();
-        let mut author_list = List::author_list();
-        authors.iter().try_for_each(|author| {
+    // Check all the generated output paths are unique and not reserved by zine.
+    fn check_output_paths(&self) -> Result<()> {
+        let paths = self.user_output_paths(false);
+        let generated = self.entity_output_paths();
+
+        let mut reserved = vec![
+            "static",
+            "topic",
+            "topics",
+            "series",
+            "authors",
+            "preview",
+            "index.html",
+            "feed.xml",
+            "sitemap.xml",
+            "feeds.opml",
+            "404.html",
+        ];
+        if self.site.whats_new {
+            reserved.push("whats-new");
+        }
+        if self.site.json_feed {
+            reserved.push("feed.json");
+        }
+        if self.site.versioned_archive {
+            reserved.push("v");
+        }
+        // The localized sites are rendered under the `/<locale>/` roots.
+        reserved.extend(self.site.i18n.keys().map(String::as_str));
+        if self.has_colophon() {
+            reserved.push("colophon");
+        }
+        if !self.events.is_empty() {
+            reserved.extend(["events", "events.ics"]);
+        }
+        if !self.glossary.is_empty() {
+            reserved.push("glossary");
+        }
+        if self.build_config.serve_changes {
+            reserved.push("_changes");
+        }
+        if engine::drafts_mode() {
+            reserved.push(engine::DRAFTS_DIR);
+        }
+        if self.has_csp_headers() {
+            reserved.push(crate::csp::HEADERS_FILE);
+        }
+        check_output_path_conflicts(&paths, &generated, &reserved)
+    }
+
+    // Report the near-identical articles, see the `duplicate_threshold` of [`LintConfig`].
+    fn check_duplicate_articles(&self) -> Result<()> {
+        let Some(threshold) = self.lint_config.duplicate_threshold else {
+            return Ok(());
+        };
+        let texts = self
+            .issues
+            .iter()
+            .flat_map(|issue| {
+                issue.articles().into_iter().map(|article| {
+                    (
+                        format!("{}/{}", issue.dir, article.meta.file),
+                        crate::markdown::plain_text(&article.markdown),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let pairs = lint::find_duplicates(&texts, threshold);
+        if pairs.is_empty() {
+            return Ok(());
+        }
+
+        let summary = pairs
+            .iter()
+            .map(|pair| {
+                format!(
+                    "  {} <-> {} ({:.1}%)",
+                    pair.first,
+                    pair.second,
+                    pair.similarity * 100.0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let message = format!(
+            "Found {} pairs of near-identical articles:\n{summary}",
+            pairs.len()
+        );
+        if self.lint_config.duplicate_articles.is_error() {
+            bail!(message);
+        }
+        println!("Warning: {message}");
+        Ok(())
+    }
+
+    // Get the translations older than their source article, the source
+    // and the translation file pairs.
+    fn stale_translations(&self) -> Vec<(String, String)> {
+        let mut stale = vec![];
+        for issue in &self.issues {
+            for article in issue.articles() {
+                let mut translations = article
+                    .i18n
+                    .values()
+                    .filter(|translation| {
+                        translation.meta.last_modified() < article.meta.last_modified()
+                    })
+                    .map(|translation| {
+                        (
+                            format!("{}/{}", issue.dir, article.meta.file),
+                            format!("{}/{}", issue.dir, translation.meta.file),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                translations.sort();
+                stale.extend(translations);
+            }
+        }
+        stale
+    }
+
+    // Report the stale translations and canonical urls,
+    // see the `stale_translation` and `stale_canonical` of [`LintConfig`].
+    fn check_stale_articles(&self) -> Result<()> {
+        if let Some(severity) = self.lint_config.stale_translation {
+            for (source, translation) in self.stale_translations() {
+                let message = format!(
+                    "the translation `{translation}` is older than its source `{source}`, please update it or bump its `updated_date`"
+                );
+                ensure!(!severity.is_error(), "{message}");
+                println!("Warning: {message}");
+            }
+        }
+
+        let Some(severity) = self.lint_config.stale_canonical else {
+            return Ok(());
+        };
+        // Skip the slow HTTP requests in `zine serve` mode.
+        if !matches!(genkit::current_mode(), genkit::Mode::Build) {
+            return Ok(());
+        }
+        #[cfg(not(feature = "url-check"))]
+        {
+            let _ = severity;
+            println!("Warning: the `stale_canonical` check of [lint] requires the `url-check` feature of zine.");
+            Ok(())
+        }
+        #[cfg(feature = "url-check")]
+        self.report_stale_canonicals(severity)
+    }
+
+    // Request the canonical urls of all articles and report the failed ones.
+    #[cfg(feature = "url-check")]
+    fn report_stale_canonicals(&self, severity: super::build::Severity) -> Result<()> {
+        let mut failures = self
+            .issues
+            .iter()
+            .flat_map(|issue| issue.articles())
+            .flat_map(|article| std::iter::once(article).chain(article.i18n.values()))
+            .filter_map(|article| Some((article.meta.title.as_str(), article.canonical()?)))
+            .collect::<Vec<_>>()
+            .par_iter()
+            .filter_map(|(title, url)| {
+                lint::check_url(url)
+                    .err()
+                    .map(|reason| format!("  {title}: {url} ({reason})"))
+            })
+            .collect::<Vec<_>>();
+        if failures.is_empty() {
+            return Ok(());
+        }
+        failures.sort();
+        let message = format!(
+            "Found {} canonical urls no longer resolve:\n{}",
+            failures.len(),
+            failures.join("\n")
+        );
+        ensure!(!severity.is_error(), "{message}");
+        println!("Warning: {message}");
+        Ok(())
+    }
+
+    // Load the external article data files of `[build] article_data`,
+    // the missing files are skipped since they are usually produced by a backend.
+    fn load_article_data(&self, source: &Path) -> Result<Vec<serde_json::Value>> {
+        let mut data = vec![];
+        for file in &self.build_config.article_data {
+            let path = source.join(file);
+            if !path.exists() {
+                println!("Warning: the article data file `{file}` not found, skipped.");
+                continue;
+            }
+            if self.sandbox_config.enforced {
+                self.sandbox_config.resolve_path(source, file)?;
+            }
+            let content = fs::read_to_string(&path)?;
+            data.push(serde_json::from_str(&content).with_context(|| {
+                format!("Failed to parse the article data file `{}`", path.display())
+            })?);
+        }
+        Ok(data)
+    }
+
+    // Add the topics registered automatically while parsing articles, see
+    // the `auto_register_topics` of [`BuildConfig`].
+    fn register_new_topics(&mut self, source: &Path) -> Result<()> {
+        let topics = data::read().get_topics().to_vec();
+        for id in topics {
+            if !self.topics.contains_key(&id) {
+                println!("Auto registered topic `{id}`, please declare it in the root `zine.toml`");
+                let mut topic = Topic::new(&id);
+                topic.parse(source)?;
+                self.topics.insert(id, topic);
+            }
+        }
+        Ok(())
+    }
+
+    // Set the authors and articles data, which are required by rendering.
+    pub(crate) fn prepare_render_data(&self) {
+        let mut zine_data = data::write();
+        zine_data
+            .set_authors(self.authors())
+            .set_articles(self.all_articles())
+            .set_issues(
+                self.issues
+                    .iter()
+                    .filter(|issue| issue.need_publish())
+                    .cloned()
+                    .collect(),
+            )
+            .set_topic_entities(self.topics.values().cloned().collect())
+            .set_related_articles(self.related_articles())
+            .set_series_navigations(self.series_navigations());
+    }
+
+    // Get the published parts of each series, ordered by the publish date,
+    // keyed by the series id.
+    fn series_parts(&self) -> BTreeMap<&str, Vec<SeriesPart>> {
+        let mut parts = BTreeMap::<&str, Vec<SeriesPart>>::new();
+        for issue in &self.issues {
+            for article in issue.articles() {
+                let Some(id) = article.meta.series.as_deref() else {
+                    continue;
+                };
+                parts.entry(id).or_default().push(SeriesPart {
+                    title: article.meta.title.clone(),
+                    url: match article.meta.path.as_ref() {
+                        Some(path) => path.clone(),
+                        None => format!("/{}/{}", issue.slug, article.meta.slug),
+                    },
+                    pub_date: article.meta.pub_date,
+                    issue_title: issue.title.clone(),
+                    part: 0,
+                });
+            }
+        }
+        for parts in parts.values_mut() {
+            // The issues are sorted by number, keep that order for the same date.
+            parts.sort_by_key(|part| part.pub_date);
+            for (index, part) in parts.iter_mut().enumerate() {
+                part.part = index + 1;
+            }
+        }
+        parts
+    }
+
+    // Get the series navigation of each article in a series, keyed by the article path.
+    fn series_navigations(&self) -> HashMap<String, SeriesNavigation> {
+        let mut navigations = HashMap::new();
+        for (id, parts) in self.series_parts() {
+            let Some(series) = self.series.get(id) else {
+                continue;
+            };
+            for (index, part) in parts.iter().enumerate() {
+                navigations.insert(part.url.clone(), series.navigation(&parts, index));
+            }
+        }
+        navigations
+    }
+
+    // Render the landing pages of all series.
+    fn render_series(&self, env: &Environment, context: &Context, dest: &Path) -> Result<()> {
+        let mut parts = self.series_parts();
+        for series in self.series.values() {
+            let mut context = context.clone();
+            context.insert(
+                "parts",
+                &parts.remove(series.id.as_str()).unwrap_or_default(),
+            );
+            series.render(env, context, &dest.join("series"))?;
+        }
+        Ok(())
+    }
+
+    // Compute the related articles of each published article by the topic, author and
+    // issue overlap, keyed by the article path, see [`RelatedConfig`].
+    //
+    // The articles excluded from the search indexes are never recommended.
+    fn related_articles(&self) -> HashMap<String, Vec<RelatedArticle>> {
+        let config = &self.related_config;
+        if config.count == 0 {
+            return HashMap::new();
+        }
+        let articles = self
+            .issues
+            .iter()
+            .flat_map(|issue| {
+                issue
+                    .articles()
+                    .into_iter()
+                    .map(move |article| (issue, article))
+            })
+            .collect::<Vec<_>>();
+        let url = |issue: &Issue, article: &Article| match article.meta.path.as_ref() {
+            Some(path) => path.clone(),
+            None => format!("/{}/{}", issue.slug, article.meta.slug),
+        };
+        articles
+            .par_iter()
+            .map(|(issue, article)| {
+                let mut related = articles
+                    .iter()
+                    .filter(|(_, other)| {
+                        !std::ptr::eq(*other, *article) && other.meta.visibility().index
+                    })
+                    .filter_map(|(other_issue, other)| {
+                        let score = config.score(article, other, issue.slug == other_issue.slug);
+                        (score > 0).then_some((score, *other_issue, *other))
+                    })
+                    .collect::<Vec<_>>();
+                // The higher score first, then the newer one.
+                related.sort_by(|(a_score, _, a), (b_score, _, b)| {
+                    b_score
+                        .cmp(a_score)
+                        .then_with(|| b.meta.pub_date.cmp(&a.meta.pub_date))
+                });
+                let related = related
+                    .into_iter()
+                    .take(config.count)
+                    .map(|(score, other_issue, other)| RelatedArticle {
+                        title: other.meta.title.clone(),
+                        url: url(other_issue, other),
+                        cover: other.meta.cover.clone(),
+                        pub_date: other.meta.pub_date,
+                        topics: other.topics.clone(),
+                        issue_title: other_issue.title.clone(),
+                        score,
+                    })
+                    .collect();
+                (url(issue, article), related)
+            })
+            .collect()
+    }
+
+    // Render the author pages filtered by `predicate`, and the author list page.
+    fn render_authors(
+        &self,
+        env: &Environment,
+        context: &Context,
+        dest: &Path,
+        predicate: impl Fn(&Author) -> bool,
+    ) -> Result<()> {
+        let mut author_list = List::author_list();
+        self.authors().iter().try_for_each(|author| {
             let articles = self.get_articles_by_author(&author.id);
             author_list.push_author(author, articles.len());
+            if !predicate(author) {
+                return anyhow::Ok(());
+            }
 
-            let mut context = context.clone();
-            context.insert("articles", &articles);
-            author
-                .render(env, context, dest)
-                .expect("Failed to render author page");
+            let base_url = format!("/@{}", author.id);
+            let feed_entries = self.author_feed_entries(&author.id, self.site.feeds.author_entries);
+            if !feed_entries.is_empty() {
+                let author_name = author.name.as_deref().unwrap_or(&author.id);
+                engine::render_atom_feed(
+                    env,
+                    context! {
+                        site => &self.site,
+                        entries => &feed_entries,
+                        generator_version => env!("CARGO_PKG_VERSION"),
+                        feed_title => format!("{} - {}", self.site.name, author_name),
+                        feed_path => format!("{base_url}/feed.xml"),
+                    },
+                    dest.join(&base_url[1..]),
+                )?;
+            }
+            let total = articles.len();
+            let page_size = match self.site.author_page_size {
+                0 => self.site.page_size,
+                page_size => page_size,
+            };
+            for (articles, pagination) in Pagination::paginate(&articles, page_size, &base_url) {
+                let mut context = context.clone();
+                if !feed_entries.is_empty() {
+                    context.insert("feed_url", &format!("{base_url}/feed.xml"));
+                }
+                context.insert("articles", &articles);
+                context.insert("article_count", &total);
+                context.insert("pagination", &pagination);
+                author
+                    .render(env, context, dest)
+                    .expect("Failed to render author page");
+            }
 
+            output::write(
+                &dest.join("authors").join(&author.id).join("articles.json"),
+                serde_json::to_vec(&AuthorArticles::new(&author.id, &articles))?,
+            )?;
             anyhow::Ok(())
         })?;
         // Render author list page.
         author_list
             .render(env, context.clone(), dest)
             .expect("Failed to render author list page");
+        Ok(())
+    }
 
-        {
-            let mut zine_data = data::write();
-            zine_data
-                .set_authors(authors)
-                .set_articles(self.all_articles());
-        }
-
-        // Render all issues pages.
-        self.issues
-            .render(env, context.clone(), dest)
-            .expect("Failed to render issues");
-
-        // Render all topic pages
+    // Render the topic pages filtered by `predicate`, and the topic list page.
+    fn render_topics(
+        &self,
+        env: &Environment,
+        context: &Context,
+        dest: &Path,
+        predicate: impl Fn(&Topic) -> bool,
+    ) -> Result<()> {
         let topic_dest = dest.join("topic");
         let mut topic_list = List::topic_list();
         self.topics
             .values()
             .try_for_each(|topic| {
-                let mut context = context.clone();
                 let articles = self.get_articles_by_topic(&topic.id);
                 topic_list.push_topic(topic, articles.len());
-                context.insert("articles", &articles);
-                topic.render(env, context, &topic_dest)
+                if !predicate(topic) {
+                    return Ok(());
+                }
+
+                let base_url = format!("/topic/{}", topic.slug);
+                let feed_entries =
+                    self.topic_feed_entries(&topic.id, self.site.feeds.topic_entries);
+                if !feed_entries.is_empty() {
+                    engine::render_atom_feed(
+                        env,
+                        context! {
+                            site => &self.site,
+                            entries => &feed_entries,
+                            generator_version => env!("CARGO_PKG_VERSION"),
+                            feed_title => format!("{} - #{}", self.site.name, topic.name()),
+                            feed_path => format!("{base_url}/feed.xml"),
+                        },
+                        topic_dest.join(&topic.slug),
+                    )?;
+                }
+                let total = articles.len();
+                Pagination::paginate(&articles, self.site.page_size, &base_url)
+                    .into_iter()
+                    .try_for_each(|(articles, pagination)| {
+                        let mut context = context.clone();
+                        if !feed_entries.is_empty() {
+                            context.insert("feed_url", &format!("{base_url}/feed.xml"));
+                        }
+                        context.insert("articles", &articles);
+                        context.insert("article_count", &total);
+                        context.insert("pagination", &pagination);
+                        topic.render(env, context, &topic_dest)
+                    })
             })
             .expect("Failed to render topic pages");
         // Render topic list page
         topic_list
             .render(env, context.clone(), dest)
             .expect("Failed to render topic list page");
+        Ok(())
+    }
 
-        // Render other pages.
-        self.pages
-            .render(env, context.clone(), dest)
-            .expect("Failed to render pages");
-
-        // Render home page.
+    fn render_home(&self, env: &Environment, mut context: Context, dest: &Path) {
         let issues = self
             .issues
             .par_iter()
@@ -458,13 +1157,1214 @@ impl Entity for Zine {
                     slug => issue.slug,
                     title => issue.title,
                     number => issue.number,
+                    accent_color => issue.accent_color,
                     pub_date => issue.pub_date.as_ref().map(helpers::format_date),
                     articles => issue.featured_articles(),
+                    summary => issue.summary(self.site.home_summary_sentences),
+                    recent_articles => issue.recent_articles(self.site.home_recent_articles),
                 }
             })
             .collect::<Vec<_>>();
         context.insert("issues", &issues);
         engine::render(env, "index.jinja", context, dest).expect("Failed to render home page");
+    }
+
+    // Get the latest published issue before the `issue`.
+    fn previous_issue(&self, issue: &Issue) -> Option<&Issue> {
+        self.issues
+            .iter()
+            .filter(|previous| previous.need_publish() && previous.number < issue.number)
+            .max_by_key(|previous| previous.number)
+    }
+
+    // Render the `issue` with the retrospective of its previous issue.
+    fn render_issue(
+        &self,
+        env: &Environment,
+        context: &Context,
+        dest: &Path,
+        issue: &Issue,
+    ) -> Result<()> {
+        let mut context = context.clone();
+        if let Some(previous) = self.previous_issue(issue) {
+            context.insert(
+                "previous_issue",
+                &previous.retrospective(RETROSPECTIVE_TOPICS),
+            );
+        }
+        issue.render(env, context, dest)
+    }
+
+    // Whether to render the `/colophon/` page, which requires the site
+    // or any published issue has a colophon.
+    fn has_colophon(&self) -> bool {
+        self.site.colophon.is_some()
+            || self
+                .issues
+                .iter()
+                .any(|issue| issue.need_publish() && issue.colophon.is_some())
+    }
+
+    // Render the `/colophon/` page of the site and issues colophons.
+    fn render_colophon(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
+        if !self.has_colophon() {
+            return Ok(());
+        }
+        let issues = self
+            .issues
+            .iter()
+            .filter(|issue| issue.need_publish())
+            .filter_map(|issue| {
+                Some(context! {
+                    slug => issue.slug,
+                    title => issue.title,
+                    number => issue.number,
+                    colophon => issue.colophon.as_ref()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        let title = env.render_str(r#"{{ fluent("colophon") }}"#, context! {})?;
+        context.insert(
+            "meta",
+            &Meta {
+                title: Cow::Owned(title),
+                description: Cow::Owned(String::new()),
+                url: Some(Cow::Borrowed("colophon")),
+                image: None,
+            },
+        );
+        context.insert("colophon", &self.site.colophon);
+        context.insert("issues", &issues);
+        engine::render(env, "colophon.jinja", context, dest.join("colophon"))
+    }
+
+    // The upcoming events since `today`, the soonest first.
+    fn upcoming_events(&self, today: Date) -> Vec<&Event> {
+        let mut events = self
+            .events
+            .iter()
+            .filter(|event| event.is_upcoming(today))
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.date);
+        events
+    }
+
+    // Render the `/events/` page and the `/events.ics` calendar.
+    fn render_events(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
+        if self.events.is_empty() {
+            return Ok(());
+        }
+        let now = engine::frozen_now();
+        let mut past = self
+            .events
+            .iter()
+            .filter(|event| !event.is_upcoming(now.date()))
+            .collect::<Vec<_>>();
+        // The latest past event first.
+        past.sort_by_key(|event| std::cmp::Reverse(event.date));
+
+        let title = env.render_str(r#"{{ fluent("events") }}"#, context! {})?;
+        context.insert(
+            "meta",
+            &Meta {
+                title: Cow::Owned(title),
+                description: Cow::Owned(String::new()),
+                url: Some(Cow::Borrowed("events")),
+                image: None,
+            },
+        );
+        context.insert("past_events", &past);
+        engine::render(env, "events.jinja", context, dest.join("events"))?;
+
+        let mut events = self.events.clone();
+        events.sort_by_key(|event| event.date);
+        output::write(
+            &dest.join("events.ics"),
+            render_calendar(&events, &self.site, now),
+        )
+    }
+
+    // Render the `/glossary/` page of the glossary terms.
+    fn render_glossary(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
+        if self.glossary.is_empty() {
+            return Ok(());
+        }
+        let title = env.render_str(r#"{{ fluent("glossary") }}"#, context! {})?;
+        context.insert(
+            "meta",
+            &Meta {
+                title: Cow::Owned(title),
+                description: Cow::Owned(String::new()),
+                url: Some(Cow::Borrowed("glossary")),
+                image: None,
+            },
+        );
+        context.insert("terms", &self.glossary);
+        engine::render(env, "glossary.jinja", context, dest.join("glossary"))
+    }
+
+    /// Render only the issue `slug_or_number` into an existing build of `dest`,
+    /// along with the pages it affects: the home page, the colophon page, and the
+    /// author and topic pages of its articles. The feeds are rendered by the generator as usual.
+    pub fn render_only(
+        &self,
+        env: &Environment,
+        mut context: Context,
+        dest: &Path,
+        slug_or_number: &str,
+    ) -> Result<()> {
+        let issue = self
+            .issues
+            .iter()
+            .find(|issue| {
+                issue.slug == slug_or_number
+                    || slug_or_number.parse::<u32>().ok() == Some(issue.number)
+            })
+            .with_context(|| format!("Issue `{slug_or_number}` not found"))?;
+        ensure!(
+            dest.join("index.html").exists(),
+            "No existing build found in `{}`, please run a full build first.",
+            dest.display()
+        );
+
+        context.insert("site", &self.site);
+        context.insert(
+            "upcoming_events",
+            &self.upcoming_events(engine::frozen_now().date()),
+        );
+        self.prepare_render_data();
+
+        let articles = issue
+            .articles()
+            .into_iter()
+            .flat_map(|article| {
+                let mut articles = vec![article];
+                // including translation articles
+                articles.extend(article.i18n.values());
+                articles
+            })
+            .collect::<Vec<_>>();
+        self.render_issue(env, &context, dest, issue)?;
+        self.render_authors(env, &context, dest, |author| {
+            articles.iter().any(|article| article.is_author(&author.id))
+        })?;
+        self.render_topics(env, &context, dest, |topic| {
+            articles
+                .iter()
+                .any(|article| article.topics.contains(&topic.id))
+        })?;
+        self.render_colophon(env, context.clone(), dest)?;
+        self.render_home(env, context, dest);
+
+        println!(
+            "Rendered issue `{}` and its {} article(s) only.",
+            issue.slug,
+            articles.len()
+        );
+        Ok(())
+    }
+
+    // Compute the contribution badges of authors, see [`BadgeConfig`](super::BadgeConfig).
+    fn assign_author_badges(&mut self) {
+        let Some(config) = self.site.badges.as_ref() else {
+            return;
+        };
+        let badges = self
+            .authors
+            .values()
+            .map(|author| {
+                let articles = self.get_articles_by_author(&author.id);
+                let first_year = articles
+                    .iter()
+                    .map(|article| article.article.pub_date)
+                    .filter(|pub_date| *pub_date != Date::MIN)
+                    .map(|pub_date| pub_date.year())
+                    .min();
+                config.badges(articles.len(), first_year, author.editor)
+            })
+            .collect::<Vec<_>>();
+        for (author, badges) in self.authors.values_mut().zip(badges) {
+            author.badges = badges;
+        }
+    }
+
+    // Get author list.
+    fn authors(&self) -> Vec<Author> {
+        self.authors.values().cloned().collect()
+    }
+
+    fn all_articles(&self) -> Vec<(String, ArticleSource)> {
+        self.issues
+            .par_iter()
+            .flat_map(|issue| {
+                issue
+                    .articles()
+                    .iter()
+                    .map(|article| (issue.slug.clone(), article.source()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Get latest `limit` number of articles in all issues.
+    /// Sort by date in descending order.
+    pub fn latest_feed_entries(&self, limit: usize) -> Vec<FeedEntry> {
+        self.feed_entries(limit, true, |_| true)
+    }
+
+    /// Get latest `limit` number of articles of the topic `id`.
+    pub fn topic_feed_entries(&self, id: &str, limit: usize) -> Vec<FeedEntry> {
+        self.feed_entries(limit, false, |article| {
+            article.topics.iter().any(|topic| topic == id)
+        })
+    }
+
+    /// Get latest `limit` number of articles written or translated by the author `id`.
+    pub fn author_feed_entries(&self, id: &str, limit: usize) -> Vec<FeedEntry> {
+        self.feed_entries(limit, false, |article| article.is_author(id))
+    }
+
+    // Get latest `limit` number of articles matched by `predicate`, and the issue
+    // intro articles if `with_intros`.
+    fn feed_entries(
+        &self,
+        limit: usize,
+        with_intros: bool,
+        predicate: impl Fn(&Article) -> bool + Sync,
+    ) -> Vec<FeedEntry> {
+        let series_navigations = self.series_navigations();
+        let mut entries = self
+            .issues
+            .par_iter()
+            .flat_map(|issue| {
+                let mut entries = issue
+                    .articles()
+                    .iter()
+                    .filter(|article| article.meta.visibility().feed && predicate(article))
+                    .map(|article| {
+                        let path = match article.meta.path.as_ref() {
+                            Some(path) => path.clone(),
+                            None => format!("/{}/{}", issue.slug, article.meta.slug),
+                        };
+                        FeedEntry {
+                            title: &article.meta.title,
+                            url: format!("{}{}", self.site.url, path),
+                            content: &article.markdown,
+                            summary: article.meta.teaser.as_ref(),
+                            author: &article.meta.author,
+                            translator: &article.meta.translator,
+                            date: Some(article.meta.pub_date),
+                            series: series_navigations.get(&path).map(|navigation| FeedSeries {
+                                name: navigation.name.clone(),
+                                url: format!("{}{}", self.site.url, navigation.url),
+                                part: navigation.part,
+                                total: navigation.total,
+                            }),
+                            reading_time: Some(article.meta.reading_time),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                // Add issue intro article into feed
+                if with_intros && issue.need_publish() {
+                    if let Some(content) = issue.intro.as_ref() {
+                        entries.push(FeedEntry {
+                            title: &issue.title,
+                            url: format!("{}/{}", self.site.url, issue.slug),
+                            content,
+                            summary: None,
+                            author: &None,
+                            translator: &None,
+                            date: issue.pub_date,
+                            series: None,
+                            reading_time: None,
+                        })
+                    }
+                }
+                entries
+            })
+            .collect::<Vec<_>>();
+
+        // Sort by date in descending order.
+        entries.par_sort_unstable_by(|a, b| match (a.date, b.date) {
+            (Some(a_date), Some(b_date)) => b_date.cmp(&a_date),
+            _ => Ordering::Equal,
+        });
+        entries.into_iter().take(limit).collect()
+    }
+
+    /// Get the computed facts of this site, such as the first publication year
+    /// and the number of published issues.
+    pub fn site_facts(&self) -> minijinja::value::Value {
+        let issues = self
+            .issues
+            .iter()
+            .filter(|issue| issue.need_publish())
+            .collect::<Vec<_>>();
+        let first_year = issues
+            .iter()
+            .flat_map(|issue| {
+                issue.pub_date.into_iter().chain(
+                    issue
+                        .articles()
+                        .into_iter()
+                        .map(|article| article.meta.pub_date),
+                )
+            })
+            .map(|date| date.year())
+            .min();
+        context! {
+            first_year,
+            issue_count => issues.len(),
+            article_count => issues.iter().map(|issue| issue.articles().len()).sum::<usize>(),
+        }
+    }
+
+    /// Get all feed links of this site.
+    pub fn feed_links(&self) -> Vec<FeedLink> {
+        let base_url = &self.site.url;
+        let mut links = vec![FeedLink {
+            title: self.site.name.clone(),
+            url: format!("{}/feed.xml", base_url),
+            html_url: format!("{}/", base_url),
+        }];
+        if self.site.whats_new {
+            links.push(FeedLink {
+                title: format!("{} - What's new", self.site.name),
+                url: format!("{}/whats-new/feed.xml", base_url),
+                html_url: format!("{}/whats-new/", base_url),
+            });
+        }
+        for topic in self.topics.values() {
+            if !self
+                .topic_feed_entries(&topic.id, self.site.feeds.topic_entries)
+                .is_empty()
+            {
+                links.push(FeedLink {
+                    title: format!("{} - #{}", self.site.name, topic.name()),
+                    url: format!("{}/topic/{}/feed.xml", base_url, topic.slug),
+                    html_url: format!("{}/topic/{}/", base_url, topic.slug),
+                });
+            }
+        }
+        for author in self.authors.values() {
+            if !self
+                .author_feed_entries(&author.id, self.site.feeds.author_entries)
+                .is_empty()
+            {
+                links.push(FeedLink {
+                    title: format!(
+                        "{} - {}",
+                        self.site.name,
+                        author.name.as_deref().unwrap_or(&author.id)
+                    ),
+                    url: format!("{}/@{}/feed.xml", base_url, author.id),
+                    html_url: format!("{}/@{}/", base_url, author.id),
+                });
+            }
+        }
+        links
+    }
+
+    /// Get the feed entries of the articles in `changelog`, the latest first.
+    pub fn changelog_feed_entries<'a>(
+        &'a self,
+        changelog: &'a [ChangelogEntry],
+        limit: usize,
+    ) -> Vec<FeedEntry<'a>> {
+        changelog
+            .iter()
+            .filter_map(|entry| {
+                let article = self.issues.iter().find_map(|issue| {
+                    issue.articles().into_iter().find(|article| {
+                        article.meta.path.as_ref() == Some(&entry.path)
+                            || (article.meta.path.is_none()
+                                && entry.path == format!("/{}/{}", issue.slug, article.meta.slug))
+                    })
+                })?;
+                if !article.meta.visibility().feed {
+                    return None;
+                }
+                Some(FeedEntry {
+                    title: &article.meta.title,
+                    url: format!("{}{}", self.site.url, entry.path),
+                    content: &article.markdown,
+                    summary: article.meta.teaser.as_ref(),
+                    author: &article.meta.author,
+                    translator: &article.meta.translator,
+                    date: Some(entry.date),
+                    series: None,
+                    reading_time: Some(article.meta.reading_time),
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Get `sitemap.xml` entries.
+    pub fn sitemap_entries(&self) -> Vec<SitemapEntry> {
+        let base_url = &self.site.url;
+        // Sitemap URL must begin with the protocol (such as http)
+        // and end with a trailing slash.
+        // https://www.sitemaps.org/protocol.html
+        let mut entries: Vec<SitemapEntry> = vec![format!("{}/", base_url).into()];
+
+        // Issues and articles
+        for issue in &self.issues {
+            entries.push(format!("{}/{}/", base_url, issue.slug).into());
+            entries.extend(
+                issue
+                    .i18n
+                    .values()
+                    .map(|translation| format!("{}/{}/", base_url, translation.slug).into()),
+            );
+            let article_url = |article: &Article| {
+                if let Some(path) = article.meta.path.as_ref() {
+                    format!("{}{}", base_url, path)
+                } else {
+                    format!("{}/{}/{}", base_url, issue.slug, article.meta.slug)
+                }
+            };
+            let articles = issue
+                .articles()
+                .into_iter()
+                .filter(|article| article.meta.visibility().index)
+                .par_bridge()
+                .flat_map(|article| {
+                    let translations = article
+                        .i18n
+                        .iter()
+                        .filter(|(_, translation)| translation.meta.visibility().index)
+                        .collect::<Vec<_>>();
+                    if translations.is_empty() {
+                        return vec![SitemapEntry::from(article_url(article))];
+                    }
+
+                    // All the localized versions (including translation articles)
+                    // list each other as alternates.
+                    let mut alternates = vec![SitemapAlternate {
+                        hreflang: i18n::hreflang(&self.site.locale),
+                        href: article_url(article),
+                    }];
+                    alternates.extend(translations.into_iter().map(|(locale, translation)| {
+                        SitemapAlternate {
+                            hreflang: i18n::hreflang(locale),
+                            href: article_url(translation),
+                        }
+                    }));
+                    alternates.push(SitemapAlternate {
+                        hreflang: String::from("x-default"),
+                        href: article_url(article),
+                    });
+                    alternates
+                        .iter()
+                        .filter(|alternate| alternate.hreflang != "x-default")
+                        .map(|alternate| SitemapEntry {
+                            loc: alternate.href.clone(),
+                            alternates: alternates.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                });
+            entries.par_extend(articles);
+        }
+
+        // Authors
+        entries.push(format!("{}/authors/", base_url).into());
+        entries.par_extend(
+            self.authors
+                .par_iter()
+                .map(|(id, _)| format!("{}/@{}/", base_url, id.to_lowercase()).into()),
+        );
+
+        // Topics
+        if !self.topics.is_empty() {
+            entries.push(format!("{}/topics/", base_url).into());
+            entries.par_extend(
+                self.topics
+                    .par_iter()
+                    .map(|(_, topic)| format!("{}/topic/{}/", base_url, topic.slug).into()),
+            );
+        }
+        entries.extend(
+            self.series
+                .values()
+                .map(|series| format!("{}/series/{}/", base_url, series.slug).into()),
+        );
+
+        if self.site.whats_new {
+            entries.push(format!("{}/whats-new/", base_url).into());
+        }
+
+        if self.has_colophon() {
+            entries.push(format!("{}/colophon/", base_url).into());
+        }
+
+        if !self.events.is_empty() {
+            entries.push(format!("{}/events/", base_url).into());
+        }
+
+        if !self.glossary.is_empty() {
+            entries.push(format!("{}/glossary/", base_url).into());
+        }
+
+        // Pages
+        entries.par_extend(
+            self.pages
+                .par_iter()
+                .filter(|page| page.need_publish())
+                .map(|page| format!("{}/{}/", base_url, page.slug()).into()),
+        );
+        entries
+    }
+}
+
+// Check the output `paths` and `generated` paths (with their owner) don't collide
+// with each other, and the `paths` don't use the top level `reserved` names of zine.
+fn check_output_path_conflicts(
+    paths: &[(String, String)],
+    generated: &[(String, String)],
+    reserved: &[&str],
+) -> Result<()> {
+    for (path, owner) in paths {
+        let path = path.trim_matches('/');
+        let top_level = path.split('/').next().unwrap_or_default();
+        if path.is_empty() {
+            bail!("The output path of {owner} collides with the home page");
+        }
+        if reserved.contains(&top_level) {
+            bail!(
+                "The output path `/{path}` of {owner} collides with the `/{top_level}` generated by zine, please choose another slug or path"
+            );
+        }
+    }
+
+    let mut outputs = BTreeMap::new();
+    for (path, owner) in paths.iter().chain(generated) {
+        let path = path.trim_matches('/');
+        if let Some(other) = outputs.insert(path, owner) {
+            bail!("The output path `/{path}` of {owner} collides with {other}");
+        }
+    }
+    Ok(())
+}
+
+// Generate a random hex token with 128 bits from the OS random source.
+fn random_token() -> Result<String> {
+    let mut bytes = [0_u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|err| anyhow!("Failed to generate a random token: {err}"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+impl Entity for Zine {
+    fn parse(&mut self, source: &Path) -> Result<()> {
+        ensure!(
+            !self.site.i18n.contains_key(&self.site.locale),
+            "`[site.i18n.{0}]` duplicates the site locale `{0}`",
+            self.site.locale
+        );
+        // Check the custom templates are inside the project directory.
+        for template in [
+            &self.theme.head_template,
+            &self.theme.footer_template,
+            &self.theme.article_extend_template,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.sandbox_config.resolve_path(source, template)?;
+        }
+        self.theme.load_package(source, &self.sandbox_config)?;
+        self.theme.parse(source)?;
+        self.theme.load_templates(source, &self.sandbox_config)?;
+        if let Some(forms_config) = self.forms_config.as_mut() {
+            forms_config.parse(source)?;
+        }
+        if let Some(comments_config) = self.comments_config.as_mut() {
+            comments_config.parse(source)?;
+        }
+        if let Some(images_config) = self.images_config.as_mut() {
+            images_config.parse(source, &self.sandbox_config)?;
+        }
+        if let Some(social_cards_config) = self.social_cards_config.as_mut() {
+            social_cards_config.parse(source, &self.sandbox_config)?;
+            social_cards_config
+                .background
+                .get_or_insert_with(|| self.theme.primary_color.clone());
+        }
+
+        self.markdown_config
+            .preprocess
+            .iter_mut()
+            .for_each(|preprocessor| preprocessor.set_root(source));
+        let article_data = self.load_article_data(source)?;
+        self.glossary = load_glossary(source, &self.slug_config, &self.sandbox_config)?;
+
+        {
+            let mut zine_data = data::write();
+            zine_data
+                .set_theme(self.theme.clone())
+                .set_site(self.site.clone())
+                .set_build_config(self.build_config.clone())
+                .set_lint_config(self.lint_config.clone())
+                .set_sandbox_config(self.sandbox_config.clone())
+                .set_slug_config(self.slug_config.clone())
+                .set_markdown_preprocessors(self.markdown_config.preprocess.clone())
+                .set_number_headings(self.markdown_config.number_headings)
+                .set_toc(self.markdown_config.toc)
+                .set_toc_depth(self.markdown_config.toc_depth)
+                .set_glossary(self.glossary.clone())
+                .set_images_config(self.images_config.clone())
+                .set_social_cards_config(self.social_cards_config.clone())
+                .set_article_data(article_data)
+                .set_topics(self.topics.keys().cloned().collect());
+        }
+
+        self.topics.par_iter_mut().try_for_each(|(id, topic)| {
+            topic.id = id.clone();
+            topic.parse(source)
+        })?;
+
+        self.parse_issue_from_dir(source)?;
+
+        self.issues.parse(source)?;
+        self.register_new_topics(source)?;
+        for (id, series) in &mut self.series {
+            series.id = id.clone();
+            series.parse(source)?;
+        }
+        self.check_article_series()?;
+        slug::report_slug_changes();
+        self.assign_preview_tokens(source)?;
+        if self.media_config.localize_remote {
+            self.issues
+                .par_iter_mut()
+                .for_each(|issue| issue.localize_media(source));
+        }
+        // Sort all issues by number.
+        self.issues.par_sort_unstable_by_key(|s| s.number);
+
+        if self.authors.is_empty() {
+            println!("Warning: no author specified in [authors] of root `zine.toml`.");
+        } else {
+            self.normalize_author_ids()?;
+            self.authors.par_iter_mut().try_for_each(|(id, author)| {
+                author.id = id.clone();
+                // Fallback to default zine avatar if neccessary.
+                if author.avatar.is_none()
+                    || matches!(&author.avatar, Some(avatar) if avatar.is_empty())
+                {
+                    author.avatar = self.theme.default_avatar.clone();
+                }
+
+                if self.media_config.localize_remote {
+                    media::localize(source, &mut author.avatar);
+                }
+
+                author.parse(source)
+            })?;
+            self.assign_author_badges();
+        }
+
+        // Parse pages
+        let page_dir = source.join("pages");
+        if page_dir.exists() {
+            // Parallelize pages dir walk
+            self.pages = self
+                .sandbox_config
+                .walk_dir(&page_dir)
+                .into_iter()
+                .par_bridge()
+                .try_fold_with(vec![], |mut pages, entry| {
+                    let entry = entry?;
+                    let path = entry.path();
+                    // The symlinks are regular files only if followed.
+                    if entry.file_type().is_file() {
+                        let markdown = fs::read_to_string(path).with_context(|| {
+                            format!("Failed to read markdown file of `{}`", path.display())
+                        })?;
+                        pages.push(Page {
+                            markdown,
+                            file_path: path.strip_prefix(&page_dir)?.to_owned(),
+                        });
+                    }
+                    anyhow::Ok(pages)
+                })
+                .try_reduce_with(|mut pages, chuncks| {
+                    pages.par_extend(chuncks);
+                    anyhow::Ok(pages)
+                })
+                .transpose()?
+                .unwrap_or_default();
+            if let Some(index) = self
+                .pages
+                .iter()
+                .position(|page| page.file_path == Path::new(NOT_FOUND_PAGE))
+            {
+                self.not_found_page = Some(self.pages.remove(index));
+            }
+        }
+        self.check_duplicate_articles()?;
+        self.check_stale_articles()?;
+        self.check_output_paths()
+    }
+
+    fn render(&self, env: &Environment, mut context: Context, dest: &Path) -> Result<()> {
+        context.insert("site", &self.site);
+        context.insert(
+            "upcoming_events",
+            &self.upcoming_events(engine::frozen_now().date()),
+        );
+        self.prepare_render_data();
+
+        // Render all authors pages.
+        self.render_authors(env, &context, dest, |_| true)?;
+
+        // Render all issues pages, one by one in the low memory mode.
+        if self.build_config.low_memory {
+            for issue in &self.issues {
+                self.render_issue(env, &context, dest, issue)?;
+            }
+        } else {
+            self.issues
+                .par_iter()
+                .try_for_each(|issue| self.render_issue(env, &context, dest, issue))?;
+        }
+
+        // Render all topic pages
+        self.render_topics(env, &context, dest, |_| true)?;
+        self.render_series(env, &context, dest)?;
+
+        // Render other pages.
+        self.pages
+            .render(env, context.clone(), dest)
+            .expect("Failed to render pages");
+
+        self.render_colophon(env, context.clone(), dest)?;
+        self.render_events(env, context.clone(), dest)?;
+        self.render_glossary(env, context.clone(), dest)?;
+        permalink::write_redirects(&self.redirects, &self.site.url, dest)?;
+
+        // Render home page.
+        self.render_home(env, context, dest);
         Ok(())
     }
 }
+
+// Get the earliest date after `today` of the `dates` with their sources.
+fn earliest_date_after(dates: Vec<(Date, String)>, today: Date) -> Option<(Date, String)> {
+    dates
+        .into_iter()
+        .filter(|(date, _)| *date > today)
+        .min_by_key(|(date, _)| *date)
+}
+
+// Check the current zine version against the `min_zine_version` of `[site]` table.
+fn check_min_zine_version(content: &str) -> Result<()> {
+    let Ok(value) = toml::from_str::<toml::Value>(content) else {
+        return Ok(());
+    };
+    let Some(required) = value
+        .get("site")
+        .and_then(|site| site.get("min_zine_version"))
+        .and_then(|version| version.as_str())
+    else {
+        return Ok(());
+    };
+    let current = env!("CARGO_PKG_VERSION");
+    match site::version_at_least(current, required) {
+        Some(true) => Ok(()),
+        Some(false) => Err(ZineError::OutdatedZineVersion {
+            required: required.to_owned(),
+            current: current.to_owned(),
+        }
+        .into()),
+        None => bail!("Invalid `min_zine_version` of [site]: `{required}`, expect like `0.14`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use test_case::test_case;
+    use time::{Date, Month};
+
+    use crate::fixture::{self, ArticleBuilder, IssueBuilder, ZineBuilder};
+
+    use super::{check_output_path_conflicts, earliest_date_after};
+
+    const RESERVED: &[&str] = &["static", "topic", "topics", "authors"];
+
+    fn paths(paths: &[&str]) -> Vec<(String, String)> {
+        paths
+            .iter()
+            .map(|path| (path.to_string(), format!("`{path}`")))
+            .collect()
+    }
+
+    #[test_case(&["issue-1", "issue-1/hello", "/hello", "about"], true; "ok")]
+    #[test_case(&["static"], false; "reserved page")]
+    #[test_case(&["/topics/rust"], false; "reserved path")]
+    #[test_case(&["/"], false; "home page")]
+    #[test_case(&["issue-1/hello", "/issue-1/hello/"], false; "duplicate")]
+    #[test_case(&["@alice"], false; "author page")]
+    #[test_case(&["topic/rust"], false; "topic page")]
+    fn test_check_output_path_conflicts(user_paths: &[&str], ok: bool) {
+        let generated = paths(&["@alice", "topic/rust"]);
+        assert_eq!(
+            check_output_path_conflicts(&paths(user_paths), &generated, RESERVED).is_ok(),
+            ok
+        );
+    }
+
+    #[test]
+    fn test_earliest_date_after() {
+        let date = |day| Date::from_calendar_date(2023, Month::May, day).unwrap();
+        let today = date(10);
+        let dates = vec![
+            (date(1), String::from("past")),
+            (date(10), String::from("today")),
+            (date(20), String::from("later")),
+            (date(12), String::from("next")),
+        ];
+        assert_eq!(
+            earliest_date_after(dates, today),
+            Some((date(12), String::from("next")))
+        );
+        assert_eq!(
+            earliest_date_after(vec![(date(1), String::from("past"))], today),
+            None
+        );
+    }
+
+    #[test]
+    fn test_next_change_date() {
+        let _data = fixture::lock();
+        let date = |day| Date::from_calendar_date(2023, Month::May, day).unwrap();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .site(
+                "announcement",
+                json!({ "text": "Sale", "start_date": "2023-05-01", "end_date": "2023-05-20" }),
+            )
+            .config(
+                "event",
+                json!([{ "title": "Meetup", "date": "2023-05-16" }]),
+            )
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .set("pub_date", "2023-05-12")
+                    .article(ArticleBuilder::new("future", "Future").pub_date("2023-05-11"))
+                    .article(ArticleBuilder::new("sale", "Sale").set("expire_date", "2023-05-15")),
+            )
+            .build();
+        // The publish dates are skipped.
+        assert_eq!(
+            zine.next_change_date(date(10)),
+            Some((date(15), String::from("article `issue-1/sale.md` expires")))
+        );
+        assert_eq!(
+            zine.next_change_date(date(15)),
+            Some((date(17), String::from("event `Meetup` passes")))
+        );
+        assert_eq!(
+            zine.next_change_date(date(17)),
+            Some((date(21), String::from("announcement ends")))
+        );
+        assert_eq!(zine.next_change_date(date(21)), None);
+    }
+
+    #[test]
+    fn test_stale_translations() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(
+                IssueBuilder::new(1, "issue-1").article(
+                    ArticleBuilder::new("hello", "Hello")
+                        .set("updated_date", "2023-03-01")
+                        .set(
+                            "i18n",
+                            json!({
+                                "zh": { "file": "hello.zh.md", "title": "你好", "pub_date": "2023-02-01" },
+                                "ja": { "file": "hello.ja.md", "title": "こんにちは", "updated_date": "2023-03-02" },
+                            }),
+                        ),
+                ),
+            )
+            .build();
+        assert_eq!(
+            zine.stale_translations(),
+            vec![(
+                String::from("issue-1/hello.md"),
+                String::from("issue-1/hello.zh.md")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_article_visibility() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(ArticleBuilder::new("hello", "Hello"))
+                    .article(ArticleBuilder::new("draft", "Draft").set("noindex", true))
+                    .article(
+                        ArticleBuilder::new("notice", "Notice").set("exclude_from_feed", true),
+                    ),
+            )
+            .build();
+        let feed = zine
+            .latest_feed_entries(10)
+            .into_iter()
+            .map(|entry| entry.title.as_str())
+            .collect::<Vec<_>>();
+        assert!(feed.contains(&"Draft"));
+        assert!(!feed.contains(&"Notice"));
+        let sitemap = zine
+            .sitemap_entries()
+            .into_iter()
+            .map(|entry| entry.loc)
+            .collect::<Vec<_>>();
+        assert!(sitemap.contains(&String::from("https://zine.example/issue-1/hello")));
+        assert!(sitemap.contains(&String::from("https://zine.example/issue-1/notice")));
+        assert!(!sitemap.contains(&String::from("https://zine.example/issue-1/draft")));
+    }
+
+    #[test]
+    fn test_topic_and_author_feed_entries() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(
+                        ArticleBuilder::new("a", "A")
+                            .topics(&["rust"])
+                            .author("alice"),
+                    )
+                    .article(ArticleBuilder::new("b", "B").topics(&["web"]).author("bob"))
+                    .article(
+                        ArticleBuilder::new("c", "C")
+                            .topics(&["rust"])
+                            .author("bob")
+                            .set("exclude_from_feed", true),
+                    ),
+            )
+            .build();
+        let topic_feed = zine.topic_feed_entries("rust", 10);
+        assert_eq!(topic_feed.len(), 1);
+        assert_eq!(topic_feed[0].title, "A");
+        let author_feed = zine.author_feed_entries("bob", 10);
+        assert_eq!(author_feed.len(), 1);
+        assert_eq!(author_feed[0].title, "B");
+        assert!(zine.author_feed_entries("alice", 0).is_empty());
+    }
+
+    #[test]
+    fn test_related_articles() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(
+                        ArticleBuilder::new("a", "A")
+                            .topics(&["rust"])
+                            .author("alice"),
+                    )
+                    .article(ArticleBuilder::new("b", "B").topics(&["web"]).author("bob"))
+                    .article(
+                        ArticleBuilder::new("c", "C")
+                            .topics(&["rust"])
+                            .author("bob")
+                            .pub_date("2023-02-01"),
+                    ),
+            )
+            .issue(
+                IssueBuilder::new(2, "issue-2")
+                    .article(
+                        ArticleBuilder::new("d", "D")
+                            .topics(&["rust"])
+                            .author("alice")
+                            .set("noindex", true),
+                    )
+                    .article(
+                        ArticleBuilder::new("e", "E")
+                            .topics(&["rust", "web"])
+                            .author("bob"),
+                    ),
+            )
+            .build();
+        let related = zine.related_articles();
+        let titles = |url: &str| {
+            related[url]
+                .iter()
+                .map(|article| article.title.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles("/issue-1/a"), vec!["C", "E", "B"]);
+        assert_eq!(related["/issue-1/a"][0].score, 4);
+        // The tied articles are ordered by the newer first.
+        assert_eq!(titles("/issue-2/e"), vec!["C", "B", "A"]);
+    }
+
+    #[test]
+    fn test_series_navigations() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .config("series", json!({ "async": { "name": "Async Rust" } }))
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(
+                        ArticleBuilder::new("part-2", "Part 2")
+                            .set("series", "async")
+                            .pub_date("2023-01-08"),
+                    )
+                    .article(ArticleBuilder::new("part-1", "Part 1").set("series", "async"))
+                    .article(ArticleBuilder::new("other", "Other")),
+            )
+            .issue(
+                IssueBuilder::new(2, "issue-2").article(
+                    ArticleBuilder::new("part-3", "Part 3")
+                        .set("series", "async")
+                        .pub_date("2023-02-01"),
+                ),
+            )
+            .build();
+        let navigations = zine.series_navigations();
+        assert_eq!(navigations.len(), 3);
+
+        let navigation = &navigations["/issue-1/part-2"];
+        assert_eq!(navigation.name, "Async Rust");
+        assert_eq!(navigation.url, "/series/async");
+        assert_eq!((navigation.part, navigation.total), (2, 3));
+        assert_eq!(navigation.previous.as_ref().unwrap().url, "/issue-1/part-1");
+        assert_eq!(navigation.next.as_ref().unwrap().url, "/issue-2/part-3");
+
+        let navigation = &navigations["/issue-2/part-3"];
+        assert_eq!(navigation.part, 3);
+        assert!(navigation.next.is_none());
+    }
+
+    #[test_case(json!({ "async": { "name": "Async Rust" } }), true; "declared")]
+    #[test_case(json!({}), false; "missing series")]
+    fn test_check_article_series(series: serde_json::Value, ok: bool) {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .config("series", series)
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(ArticleBuilder::new("part-1", "Part 1").set("series", "async")),
+            )
+            .build();
+        assert_eq!(zine.check_article_series().is_ok(), ok);
+    }
+
+    #[test]
+    fn test_series_parts() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .config("series", json!({ "async": {} }))
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(ArticleBuilder::new("part-1", "Part 1").set("series", "async"))
+                    .article(
+                        ArticleBuilder::new("draft", "Draft")
+                            .set("series", "async")
+                            .set("draft", true)
+                            .pub_date("2023-01-05"),
+                    )
+                    .article(
+                        ArticleBuilder::new("part-2", "Part 2")
+                            .set("series", "async")
+                            .pub_date("2023-01-08"),
+                    ),
+            )
+            .issue(
+                IssueBuilder::new(2, "issue-2").article(
+                    ArticleBuilder::new("part-3", "Part 3")
+                        .set("series", "async")
+                        .pub_date("2023-01-08"),
+                ),
+            )
+            .build();
+        // The unpublished draft leaves no missing part, the parts of the same date
+        // are never duplicated and keep the issue order.
+        let parts = zine
+            .series_parts()
+            .remove("async")
+            .unwrap()
+            .into_iter()
+            .map(|part| (part.part, part.url))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            [
+                (1, String::from("/issue-1/part-1")),
+                (2, String::from("/issue-1/part-2")),
+                (3, String::from("/issue-2/part-3")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_previous_issue() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(ArticleBuilder::new("a", "A").topics(&["rust", "web"]))
+                    .article(ArticleBuilder::new("b", "B").topics(&["web"]))
+                    .article(ArticleBuilder::new("c", "C").topics(&["async"]))
+                    .article(ArticleBuilder::new("d", "D").set("publish", false)),
+            )
+            .issue(IssueBuilder::new(2, "issue-2").set("publish", false))
+            .issue(IssueBuilder::new(3, "issue-3"))
+            .build();
+        assert!(zine.previous_issue(&zine.issues[0]).is_none());
+        let previous = zine.previous_issue(&zine.issues[2]).unwrap();
+        assert_eq!(previous.number, 1);
+        let retrospective = previous.retrospective(2);
+        assert_eq!(retrospective.article_count, 3);
+        assert_eq!(retrospective.top_topics, vec!["web", "async"]);
+    }
+    #[test]
+    fn test_upcoming_events() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .config(
+                "event",
+                json!([
+                    { "title": "Meetup 3", "date": "2023-03-01" },
+                    { "title": "Meetup 1", "date": "2023-01-01" },
+                    { "title": "Meetup 2", "date": "2023-02-01" },
+                ]),
+            )
+            .build();
+        let today = Date::from_calendar_date(2023, Month::February, 1).unwrap();
+        let titles = zine
+            .upcoming_events(today)
+            .into_iter()
+            .map(|event| event.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Meetup 2", "Meetup 3"]);
+        assert!(zine
+            .output_paths()
+            .contains(&(String::from("/events.ics"), String::from("event calendar"))));
+    }
+    #[test]
+    fn test_published_paths() {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .article(ArticleBuilder::new("hello", "Hello"))
+                    .article(ArticleBuilder::new("draft", "Draft").set("publish", false)),
+            )
+            .config("redirects", json!({ "/issue-1/hi/": "/issue-1/hello" }))
+            .build();
+        let paths = zine.published_paths();
+        assert!(paths.contains("/issue-1"));
+        assert!(paths.contains("/issue-1/hello"));
+        assert!(paths.contains("/issue-1/hi"));
+        assert!(!paths.contains("/issue-1/draft"));
+    }
+}
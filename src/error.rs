@@ -6,4 +6,6 @@ pub enum ZineError {
     InvalidRootTomlFile(#[from] toml::de::Error),
     #[error("Not a root `zine.toml`, maybe it a `zine.toml` for issue?")]
     NotRootTomlFile,
+    #[error("This zine requires zine {required} or later, but the current version is {current}. Please upgrade zine, e.g. `cargo install zine`")]
+    OutdatedZineVersion { required: String, current: String },
 }
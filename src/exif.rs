@@ -0,0 +1,313 @@
+//! Find and strip the privacy-sensitive metadata of JPEG and PNG images,
+//! e.g. the GPS location, camera serial number and editing history.
+
+const JPEG_SIGNATURE: &[u8] = &[0xFF, 0xD8];
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+// The JPEG markers.
+const APP0: u8 = 0xE0;
+const APP1: u8 = 0xE1;
+const APP13: u8 = 0xED;
+const SOS: u8 = 0xDA;
+const EOI: u8 = 0xD9;
+
+// The TIFF tags.
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_GPS_LATITUDE: u16 = 0x0002;
+
+// The PNG chunks of metadata, the `eXIf` chunk is the EXIF in TIFF format.
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Whether the extension of `path` is an image format supported by this module.
+pub fn is_supported(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .map_or(false, |ext| matches!(ext.as_str(), "jpg" | "jpeg" | "png"))
+}
+
+/// Whether the JPEG or PNG `image` has the GPS location in its EXIF.
+pub fn has_location(image: &[u8]) -> bool {
+    exif_payloads(image).into_iter().any(|payload| {
+        let Some(tiff) = Tiff::new(payload) else {
+            return false;
+        };
+        tiff.ifd0_entry(TAG_GPS_IFD)
+            .and_then(|entry| tiff.u32(entry + 8))
+            .map_or(false, |gps_ifd| {
+                tiff.entries(gps_ifd as usize)
+                    .any(|(tag, _)| tag == TAG_GPS_LATITUDE)
+            })
+    })
+}
+
+/// Strip the metadata of the JPEG or PNG `image`, returns `None` if the image
+/// isn't a JPEG or PNG, or is malformed.
+///
+/// The EXIF, XMP and IPTC segments of JPEG are dropped, except the orientation
+/// is kept in a minimal EXIF segment, otherwise the rotated photos are displayed sideways.
+/// The ICC color profile is kept as well.
+pub fn strip_metadata(image: &[u8]) -> Option<Vec<u8>> {
+    if image.starts_with(JPEG_SIGNATURE) {
+        strip_jpeg(image)
+    } else if image.starts_with(PNG_SIGNATURE) {
+        strip_png(image)
+    } else {
+        None
+    }
+}
+
+// The JPEG segments after the SOI marker, in the marker and whole segment pairs,
+// the last one is the SOS segment along with the remaining image data.
+fn jpeg_segments(image: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut segments = vec![];
+    let mut offset = JPEG_SIGNATURE.len();
+    while offset < image.len() {
+        if image[offset] != 0xFF {
+            return None;
+        }
+        let marker = *image.get(offset + 1)?;
+        match marker {
+            // The fill bytes.
+            0xFF => offset += 1,
+            SOS | EOI => {
+                segments.push((marker, &image[offset..]));
+                break;
+            }
+            // The standalone markers without length.
+            0x01 | 0xD0..=0xD7 => {
+                segments.push((marker, &image[offset..offset + 2]));
+                offset += 2;
+            }
+            _ => {
+                let length = u16::from_be_bytes([*image.get(offset + 2)?, *image.get(offset + 3)?]);
+                let end = offset + 2 + length as usize;
+                segments.push((marker, image.get(offset..end)?));
+                offset = end;
+            }
+        }
+    }
+    Some(segments)
+}
+
+// The PNG chunks after the signature, in the type and whole chunk pairs.
+fn png_chunks(image: &[u8]) -> Option<Vec<(&[u8], &[u8])>> {
+    let mut chunks = vec![];
+    let mut offset = PNG_SIGNATURE.len();
+    while offset < image.len() {
+        let length = u32::from_be_bytes(image.get(offset..offset + 4)?.try_into().ok()?);
+        // The length, type, data and CRC.
+        let end = offset.checked_add(12 + length as usize)?;
+        chunks.push((image.get(offset + 4..offset + 8)?, image.get(offset..end)?));
+        offset = end;
+    }
+    Some(chunks)
+}
+
+// The TIFF payloads of the EXIF segments or chunks.
+fn exif_payloads(image: &[u8]) -> Vec<&[u8]> {
+    if image.starts_with(JPEG_SIGNATURE) {
+        jpeg_segments(image)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(marker, _)| *marker == APP1)
+            // The marker and length, then the EXIF header.
+            .filter_map(|(_, segment)| segment.get(4..)?.strip_prefix(EXIF_HEADER))
+            .collect()
+    } else if image.starts_with(PNG_SIGNATURE) {
+        png_chunks(image)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(kind, _)| *kind == b"eXIf")
+            .filter_map(|(_, chunk)| chunk.get(8..chunk.len() - 4))
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+fn strip_jpeg(image: &[u8]) -> Option<Vec<u8>> {
+    let orientation = exif_payloads(image).into_iter().find_map(|payload| {
+        let tiff = Tiff::new(payload)?;
+        tiff.u16(tiff.ifd0_entry(TAG_ORIENTATION)? + 8)
+    });
+    let segments = jpeg_segments(image)?
+        .into_iter()
+        .filter(|(marker, _)| !matches!(*marker, APP1 | APP13))
+        .collect::<Vec<_>>();
+    // The EXIF segment follows the APP0 JFIF segment, if any.
+    let exif_index = usize::from(matches!(segments.first(), Some((APP0, _))));
+    let mut stripped = JPEG_SIGNATURE.to_vec();
+    for (index, (_, segment)) in segments.into_iter().enumerate() {
+        if index == exif_index {
+            if let Some(orientation) = orientation.filter(|&orientation| orientation != 1) {
+                stripped.extend(orientation_segment(orientation));
+            }
+        }
+        stripped.extend_from_slice(segment);
+    }
+    Some(stripped)
+}
+
+// The minimal EXIF segment only contains the orientation tag.
+fn orientation_segment(orientation: u16) -> Vec<u8> {
+    let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+    // One entry of IFD0: the SHORT (3) orientation, then no next IFD.
+    tiff.extend_from_slice(&1_u16.to_be_bytes());
+    tiff.extend_from_slice(&TAG_ORIENTATION.to_be_bytes());
+    tiff.extend_from_slice(&3_u16.to_be_bytes());
+    tiff.extend_from_slice(&1_u32.to_be_bytes());
+    tiff.extend_from_slice(&orientation.to_be_bytes());
+    tiff.extend_from_slice(&[0, 0]);
+    tiff.extend_from_slice(&0_u32.to_be_bytes());
+
+    let mut segment = vec![0xFF, APP1];
+    let length = 2 + EXIF_HEADER.len() + tiff.len();
+    segment.extend_from_slice(&(length as u16).to_be_bytes());
+    segment.extend_from_slice(EXIF_HEADER);
+    segment.extend_from_slice(&tiff);
+    segment
+}
+
+fn strip_png(image: &[u8]) -> Option<Vec<u8>> {
+    let mut stripped = PNG_SIGNATURE.to_vec();
+    for (kind, chunk) in png_chunks(image)? {
+        if !PNG_METADATA_CHUNKS.iter().any(|metadata| kind == *metadata) {
+            stripped.extend_from_slice(chunk);
+        }
+    }
+    Some(stripped)
+}
+
+// A minimal TIFF reader to look up the tags of EXIF.
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let big_endian = match data.get(..2)? {
+            b"MM" => true,
+            b"II" => false,
+            _ => return None,
+        };
+        Some(Tiff { data, big_endian })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    // The tag and offset of the entries of the IFD at `offset`.
+    fn entries(&self, offset: usize) -> impl Iterator<Item = (u16, usize)> + '_ {
+        let count = self.u16(offset).unwrap_or_default() as usize;
+        (0..count).map_while(move |index| {
+            let entry = offset + 2 + index * 12;
+            Some((self.u16(entry)?, entry))
+        })
+    }
+
+    // The offset of the `tag` entry of IFD0.
+    fn ifd0_entry(&self, tag: u16) -> Option<usize> {
+        let ifd0 = self.u32(4)? as usize;
+        self.entries(ifd0)
+            .find(|(entry_tag, _)| *entry_tag == tag)
+            .map(|(_, entry)| entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_location, strip_metadata, Tiff, EXIF_HEADER, PNG_SIGNATURE};
+
+    // A little-endian EXIF with the orientation and a GPS IFD containing the latitude.
+    fn exif() -> Vec<u8> {
+        let mut tiff = b"II\x2a\0\x08\0\0\0".to_vec();
+        // IFD0 at 8: two entries, then the next IFD offset.
+        tiff.extend_from_slice(&2_u16.to_le_bytes());
+        tiff.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+        tiff.extend_from_slice(&[0x25, 0x88, 4, 0, 1, 0, 0, 0, 38, 0, 0, 0]);
+        tiff.extend_from_slice(&0_u32.to_le_bytes());
+        // The GPS IFD at 38: one latitude reference entry.
+        tiff.extend_from_slice(&1_u16.to_le_bytes());
+        tiff.extend_from_slice(&[0x02, 0x00, 5, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        tiff.extend_from_slice(&0_u32.to_le_bytes());
+        tiff
+    }
+
+    fn jpeg() -> Vec<u8> {
+        let mut app1 = EXIF_HEADER.to_vec();
+        app1.extend(exif());
+        let mut image = vec![0xFF, 0xD8];
+        image.extend_from_slice(&[0xFF, 0xE0, 0, 4, b'J', b'F']);
+        image.extend_from_slice(&[0xFF, 0xE1]);
+        image.extend_from_slice(&(app1.len() as u16 + 2).to_be_bytes());
+        image.extend(app1);
+        image.extend_from_slice(&[0xFF, 0xDA, 0, 2, 1, 2, 3, 0xFF, 0xD9]);
+        image
+    }
+
+    fn png_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        // The CRC isn't checked.
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    #[test]
+    fn test_jpeg_metadata() {
+        let image = jpeg();
+        assert!(has_location(&image));
+
+        let stripped = strip_metadata(&image).unwrap();
+        assert!(!has_location(&stripped));
+        // The JFIF segment, then the orientation only EXIF.
+        assert_eq!(&stripped[2..8], &[0xFF, 0xE0, 0, 4, b'J', b'F']);
+        let tiff = Tiff::new(&stripped[18..]).unwrap();
+        assert_eq!(tiff.u16(tiff.ifd0_entry(0x0112).unwrap() + 8), Some(6));
+        assert!(stripped.ends_with(&[0xFF, 0xDA, 0, 2, 1, 2, 3, 0xFF, 0xD9]));
+    }
+
+    #[test]
+    fn test_png_metadata() {
+        let mut image = PNG_SIGNATURE.to_vec();
+        image.extend(png_chunk(b"IHDR", &[0; 13]));
+        image.extend(png_chunk(b"eXIf", &exif()));
+        image.extend(png_chunk(b"tEXt", b"Author\0Alice"));
+        image.extend(png_chunk(b"IEND", &[]));
+        assert!(has_location(&image));
+
+        let stripped = strip_metadata(&image).unwrap();
+        assert!(!has_location(&stripped));
+        let mut expected = PNG_SIGNATURE.to_vec();
+        expected.extend(png_chunk(b"IHDR", &[0; 13]));
+        expected.extend(png_chunk(b"IEND", &[]));
+        assert_eq!(stripped, expected);
+    }
+
+    #[test]
+    fn test_unsupported_image() {
+        assert!(!has_location(b"GIF89a"));
+        assert_eq!(strip_metadata(b"GIF89a"), None);
+        // The truncated JPEG segment.
+        assert_eq!(strip_metadata(&[0xFF, 0xD8, 0xFF, 0xE1, 0, 100]), None);
+    }
+}
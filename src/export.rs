@@ -0,0 +1,245 @@
+use std::path::Path;
+
+use anyhow::Result;
+use genkit::helpers;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{
+    entity::{ExportFormat, ExporterConfig, Zine},
+    markdown, output,
+};
+
+/// A searchable article record of the exporters.
+#[derive(Debug, Serialize)]
+pub struct ExportRecord {
+    /// The unique id derived from the url path, e.g. `issue-1-hello`.
+    pub id: String,
+    pub title: String,
+    /// The absolute url of the article.
+    pub url: String,
+    pub issue: String,
+    pub locale: String,
+    /// The author names.
+    pub authors: Vec<String>,
+    pub topics: Vec<String>,
+    /// The publish date. Format like YYYY-MM-DD.
+    pub pub_date: String,
+    /// The unix timestamp of the publish date, for sorting.
+    pub timestamp: i64,
+    /// The plain text content, truncated to the `content_length` of the exporter.
+    pub content: String,
+}
+
+/// Run all the exporters of `zine` into the `dest` directory.
+pub fn export(zine: &Zine, dest: &Path) -> Result<()> {
+    for exporter in &zine.build_config.exporters {
+        let records = export_records(zine, exporter.content_length);
+        let output = dest.join(exporter.output.trim_start_matches('/'));
+        output::write(&output, render_records(&records, exporter.format)?)?;
+        if exporter.format == ExportFormat::Typesense {
+            output::write(
+                &output.with_file_name(schema_file_name(exporter)),
+                serde_json::to_vec_pretty(&typesense_schema(exporter))?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The output paths of the exporters, relative to the build directory.
+pub fn output_paths(exporters: &[ExporterConfig]) -> Vec<String> {
+    let mut paths = vec![];
+    for exporter in exporters {
+        let output = exporter.output.trim_start_matches('/');
+        paths.push(output.to_owned());
+        if exporter.format == ExportFormat::Typesense {
+            let schema = Path::new(output).with_file_name(schema_file_name(exporter));
+            paths.push(schema.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    paths
+}
+
+// The schema file of the Typesense exporter, e.g. `articles.schema.json`.
+fn schema_file_name(exporter: &ExporterConfig) -> String {
+    let stem = Path::new(&exporter.output)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    format!("{stem}.schema.json")
+}
+
+// Collect the records of all published articles, including the translations.
+fn export_records(zine: &Zine, content_length: usize) -> Vec<ExportRecord> {
+    let site_url = zine.site.url.trim_end_matches('/');
+    let mut records = vec![];
+    for issue in zine.issues.iter().filter(|issue| issue.need_publish()) {
+        for article in issue.articles() {
+            let translations = article
+                .i18n
+                .iter()
+                .map(|(locale, article)| (locale.as_str(), article));
+            for (locale, article) in
+                std::iter::once((zine.site.locale.as_str(), article)).chain(translations)
+            {
+                if !article.meta.visibility().index {
+                    continue;
+                }
+                let path = article
+                    .meta
+                    .path
+                    .clone()
+                    .unwrap_or_else(|| format!("/{}/{}", issue.slug, article.meta.slug));
+                let authors = article
+                    .meta
+                    .author
+                    .as_ref()
+                    .map(|author| author.ids())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|id| {
+                        zine.authors
+                            .get(id)
+                            .and_then(|author| author.name.clone())
+                            .unwrap_or_else(|| id.to_owned())
+                    })
+                    .collect();
+                let pub_date = article.meta.pub_date;
+                records.push(ExportRecord {
+                    id: record_id(&path),
+                    title: article.meta.title.clone(),
+                    url: format!("{site_url}{path}"),
+                    issue: issue.title.clone(),
+                    locale: locale.to_owned(),
+                    authors,
+                    topics: article.topics.clone(),
+                    pub_date: helpers::format_date(&pub_date),
+                    timestamp: pub_date.midnight().assume_utc().unix_timestamp(),
+                    content: markdown::plain_text(&article.markdown)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .chars()
+                        .take(content_length)
+                        .collect(),
+                });
+            }
+        }
+    }
+    records
+}
+
+// The record id of the url `path`, which only contains the alphanumerics, `-` and `_`,
+// as required by Meilisearch.
+fn record_id(path: &str) -> String {
+    path.trim_matches('/')
+        .chars()
+        .map(|ch| match ch {
+            '/' => '-',
+            ch if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' => ch,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Render the `records` in the `format`.
+pub fn render_records(records: &[ExportRecord], format: ExportFormat) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Algolia => {
+            let records = records
+                .iter()
+                .map(|record| {
+                    let mut value = serde_json::to_value(record)?;
+                    if let Value::Object(map) = &mut value {
+                        if let Some(id) = map.remove("id") {
+                            map.insert("objectID".into(), id);
+                        }
+                    }
+                    Ok(value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(serde_json::to_vec(&records)?)
+        }
+        ExportFormat::Meilisearch | ExportFormat::Typesense => {
+            let mut buf = vec![];
+            for record in records {
+                serde_json::to_writer(&mut buf, record)?;
+                buf.push(b'\n');
+            }
+            Ok(buf)
+        }
+    }
+}
+
+// The Typesense collection schema of the records, named by the output file stem.
+fn typesense_schema(exporter: &ExporterConfig) -> Value {
+    let name = Path::new(&exporter.output)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    json!({
+        "name": name,
+        "fields": [
+            { "name": "title", "type": "string" },
+            { "name": "url", "type": "string", "index": false, "optional": true },
+            { "name": "issue", "type": "string", "facet": true },
+            { "name": "locale", "type": "string", "facet": true },
+            { "name": "authors", "type": "string[]", "facet": true },
+            { "name": "topics", "type": "string[]", "facet": true },
+            { "name": "pub_date", "type": "string", "index": false, "optional": true },
+            { "name": "timestamp", "type": "int64" },
+            { "name": "content", "type": "string" },
+        ],
+        "default_sorting_field": "timestamp",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use test_case::test_case;
+
+    use crate::entity::ExportFormat;
+
+    use super::{record_id, render_records, ExportRecord};
+
+    fn record() -> ExportRecord {
+        ExportRecord {
+            id: record_id("/issue-1/hello"),
+            title: String::from("Hello"),
+            url: String::from("https://zine.example/issue-1/hello"),
+            issue: String::from("Issue 1"),
+            locale: String::from("en"),
+            authors: vec![String::from("Alice")],
+            topics: vec![String::from("rust")],
+            pub_date: String::from("2023-01-02"),
+            timestamp: 1672617600,
+            content: String::from("Hello zine"),
+        }
+    }
+
+    #[test_case("/issue-1/hello", "issue-1-hello")]
+    #[test_case("/about/", "about")]
+    #[test_case("/blog/2023.01 新年", "blog-2023_01___")]
+    fn test_record_id(path: &str, expected: &str) {
+        assert_eq!(record_id(path), expected);
+    }
+
+    #[test]
+    fn test_render_records() {
+        let records = [record(), record()];
+        let algolia: Value =
+            serde_json::from_slice(&render_records(&records, ExportFormat::Algolia).unwrap())
+                .unwrap();
+        assert_eq!(algolia[0]["objectID"], "issue-1-hello");
+        assert!(algolia[0].get("id").is_none());
+
+        let documents = render_records(&records, ExportFormat::Meilisearch).unwrap();
+        let lines = String::from_utf8(documents).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        let document: Value = serde_json::from_str(lines.lines().next().unwrap()).unwrap();
+        assert_eq!(document["id"], "issue-1-hello");
+        assert_eq!(document["authors"][0], "Alice");
+    }
+}
@@ -8,7 +8,56 @@ pub struct FeedEntry<'a> {
     pub title: &'a String,
     pub url: String,
     pub content: &'a String,
+    /// The teaser of the content, if any.
+    pub summary: Option<&'a String>,
     pub author: &'a Option<AuthorId>,
+    pub translator: &'a Option<AuthorId>,
     #[serde(with = "genkit::helpers::serde_date::options")]
     pub date: Option<Date>,
+    /// The series of the article, if any.
+    pub series: Option<FeedSeries>,
+    /// The estimated reading time of the article in minutes.
+    pub reading_time: Option<usize>,
+}
+
+/// The series info of a feed entry, e.g. part 2 of 4.
+#[derive(Serialize)]
+pub struct FeedSeries {
+    pub name: String,
+    pub url: String,
+    pub part: usize,
+    pub total: usize,
+}
+
+/// A url entry of the `sitemap.xml` file.
+#[derive(Serialize)]
+pub struct SitemapEntry {
+    pub loc: String,
+    /// The localized versions of this url, including itself.
+    pub alternates: Vec<SitemapAlternate>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SitemapAlternate {
+    pub hreflang: String,
+    pub href: String,
+}
+
+impl From<String> for SitemapEntry {
+    fn from(loc: String) -> Self {
+        SitemapEntry {
+            loc,
+            alternates: Vec::new(),
+        }
+    }
+}
+
+/// A feed link of this site, listed in the `feeds.opml` file.
+#[derive(Serialize)]
+pub struct FeedLink {
+    pub title: String,
+    /// The absolute url of the feed file.
+    pub url: String,
+    /// The absolute url of the page this feed belongs to.
+    pub html_url: String,
 }
@@ -0,0 +1,326 @@
+//! In-memory builders of the zine entities for unit tests, which are
+//! enabled by the `test-fixtures` feature outside of zine.
+//!
+//! The builders produce the same entities as parsing a zine directory,
+//! without touching the filesystem, then [`render`] renders the templates
+//! against them, e.g.
+//!
+//! ```no_run
+//! use zine::fixture::{self, ArticleBuilder, AuthorBuilder, IssueBuilder, ZineBuilder};
+//!
+//! let _data = fixture::lock();
+//! let zine = ZineBuilder::new("Rust Magazine")
+//!     .author(AuthorBuilder::new("alice").name("Alice"))
+//!     .issue(
+//!         IssueBuilder::new(1, "issue-1").article(
+//!             ArticleBuilder::new("hello", "Hello")
+//!                 .author("alice")
+//!                 .markdown("Hello **zine**"),
+//!         ),
+//!     )
+//!     .build();
+//! let html = fixture::render(&zine, "index.jinja", serde_json::json!({ "site": &zine.site }))?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+use std::path::Path;
+
+use anyhow::Result;
+use genkit::Generator;
+use minijinja::Environment;
+use parking_lot::{const_mutex, Mutex, MutexGuard};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    data,
+    engine::ZineGenerator,
+    entity::{Article, Author, Issue, Zine},
+};
+
+// Serialize the tests using the fixtures, see [`lock`].
+static FIXTURES: Mutex<()> = const_mutex(());
+
+/// Lock the global data for the fixtures of a test, hold the guard while building
+/// and rendering them, since the concurrent tests share the same global data.
+pub fn lock() -> MutexGuard<'static, ()> {
+    FIXTURES.lock()
+}
+
+/// Render the builtin or theme `template` with the `context` against the `zine`,
+/// the same environment as `zine build` renders the pages.
+pub fn render(zine: &Zine, template: &str, context: impl Serialize) -> Result<String> {
+    let env = ZineGenerator.on_extend_environment(Path::new("."), Environment::new(), zine);
+    // The builtin templates are loaded from the zine source in debug mode.
+    #[cfg(debug_assertions)]
+    let env = {
+        let mut env = env;
+        env.set_loader(minijinja::path_loader(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates"
+        )));
+        env
+    };
+    Ok(env.get_template(template)?.render(context)?)
+}
+
+// Deserialize the `value` as the entity `T`, the same as declared in `zine.toml`.
+fn from_value<T: DeserializeOwned>(value: Map<String, Value>) -> T {
+    serde_json::from_value(Value::Object(value)).expect("Invalid fixture")
+}
+
+/// The builder of [`Zine`].
+pub struct ZineBuilder {
+    value: Map<String, Value>,
+    authors: Vec<AuthorBuilder>,
+    issues: Vec<IssueBuilder>,
+}
+
+impl ZineBuilder {
+    pub fn new(name: &str) -> Self {
+        let mut value = Map::new();
+        value.insert(
+            "site".into(),
+            json!({ "name": name, "url": "https://zine.example" }),
+        );
+        ZineBuilder {
+            value,
+            authors: Vec::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Set the `key` of the `[site]` table.
+    pub fn site(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.value["site"][key] = value.into();
+        self
+    }
+
+    /// Set the root table `key`, such as `theme` or `build`.
+    pub fn config(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.value.insert(key.into(), value.into());
+        self
+    }
+
+    /// Declare the topics in the `[topics]` table.
+    pub fn topics(mut self, topics: &[&str]) -> Self {
+        let topics = topics
+            .iter()
+            .map(|topic| (topic.to_string(), json!({})))
+            .collect::<Map<_, _>>();
+        self.value.insert("topics".into(), Value::Object(topics));
+        self
+    }
+
+    pub fn author(mut self, author: AuthorBuilder) -> Self {
+        self.authors.push(author);
+        self
+    }
+
+    pub fn issue(mut self, issue: IssueBuilder) -> Self {
+        self.issues.push(issue);
+        self
+    }
+
+    /// Build the [`Zine`] and load it into the global data, like [`Zine::parse`] does,
+    /// see [`lock`].
+    pub fn build(self) -> Zine {
+        let mut zine: Zine = from_value(self.value);
+        for author in self.authors {
+            let id = author.id.clone();
+            zine.authors.insert(id, author.build());
+        }
+        zine.issues = self.issues.into_iter().map(IssueBuilder::build).collect();
+        for (id, topic) in &mut zine.topics {
+            topic.id = id.clone();
+            topic.slug = zine.slug_config.normalize(&id.to_lowercase());
+        }
+        for (id, series) in &mut zine.series {
+            series.id = id.clone();
+            series.slug = zine.slug_config.normalize(&id.to_lowercase());
+        }
+
+        data::load();
+        data::write()
+            .set_theme(zine.theme.clone())
+            .set_sandbox_config(zine.sandbox_config.clone())
+            .set_site(zine.site.clone())
+            .set_build_config(zine.build_config.clone())
+            .set_lint_config(zine.lint_config.clone())
+            .set_slug_config(zine.slug_config.clone())
+            .set_topics(zine.topics.keys().cloned().collect());
+        zine.prepare_render_data();
+        zine
+    }
+}
+
+/// The builder of [`Author`].
+pub struct AuthorBuilder {
+    id: String,
+    value: Map<String, Value>,
+}
+
+impl AuthorBuilder {
+    pub fn new(id: &str) -> Self {
+        AuthorBuilder {
+            id: id.to_owned(),
+            value: Map::new(),
+        }
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        self.set("name", name)
+    }
+
+    pub fn bio(self, bio: &str) -> Self {
+        self.set("bio", bio)
+    }
+
+    /// Set any field of the `[authors.<id>]` table.
+    pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.value.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn build(self) -> Author {
+        let mut author: Author = from_value(self.value);
+        author.id = self.id;
+        author
+    }
+}
+
+/// The builder of [`Issue`], which is published by default.
+pub struct IssueBuilder {
+    value: Map<String, Value>,
+    articles: Vec<ArticleBuilder>,
+}
+
+impl IssueBuilder {
+    pub fn new(number: u32, slug: &str) -> Self {
+        let mut value = Map::new();
+        value.insert("number".into(), number.into());
+        value.insert("slug".into(), slug.into());
+        value.insert("title".into(), format!("Issue {number}").into());
+        value.insert("publish".into(), true.into());
+        IssueBuilder {
+            value,
+            articles: Vec::new(),
+        }
+    }
+
+    /// Set any field of the issue `zine.toml`.
+    pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.value.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn article(mut self, article: ArticleBuilder) -> Self {
+        self.articles.push(article);
+        self
+    }
+
+    pub fn build(mut self) -> Issue {
+        let articles = self
+            .articles
+            .into_iter()
+            .map(|article| Value::Object(article.value))
+            .collect();
+        self.value.insert("article".into(), Value::Array(articles));
+        let mut issue: Issue = from_value(self.value);
+        issue.dir = issue.slug.clone();
+        issue
+    }
+}
+
+/// The builder of [`Article`], which is published by default.
+pub struct ArticleBuilder {
+    value: Map<String, Value>,
+}
+
+impl ArticleBuilder {
+    pub fn new(slug: &str, title: &str) -> Self {
+        let mut value = Map::new();
+        value.insert("file".into(), format!("{slug}.md").into());
+        value.insert("slug".into(), slug.into());
+        value.insert("title".into(), title.into());
+        value.insert("pub_date".into(), "2023-01-01".into());
+        value.insert("publish".into(), true.into());
+        ArticleBuilder { value }
+    }
+
+    pub fn author(self, author: &str) -> Self {
+        self.set("author", author)
+    }
+
+    pub fn topics(self, topics: &[&str]) -> Self {
+        self.set("topic", topics.to_vec())
+    }
+
+    pub fn pub_date(self, pub_date: &str) -> Self {
+        self.set("pub_date", pub_date)
+    }
+
+    /// Set the markdown content, which is read from the article file normally.
+    pub fn markdown(self, markdown: &str) -> Self {
+        self.set("markdown", markdown)
+    }
+
+    /// Set any field of the `[[article]]` table.
+    pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.value.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn build(self) -> Article {
+        from_value(self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{ArticleBuilder, AuthorBuilder, IssueBuilder, ZineBuilder};
+
+    #[test]
+    fn test_build_zine() {
+        let zine = ZineBuilder::new("Rust Magazine")
+            .site("description", "A magazine about Rust")
+            .config("theme", json!({ "primary_color": "#000000" }))
+            .topics(&["rust"])
+            .author(AuthorBuilder::new("alice").name("Alice").bio("Rustacean"))
+            .issue(
+                IssueBuilder::new(1, "issue-1")
+                    .set("pub_date", "2023-01-01")
+                    .article(
+                        ArticleBuilder::new("hello", "Hello")
+                            .author("alice")
+                            .topics(&["rust"])
+                            .pub_date("2023-01-02")
+                            .markdown("Hello **zine**"),
+                    )
+                    .article(ArticleBuilder::new("about", "About").set("path", "/about")),
+            )
+            .build();
+
+        assert_eq!(
+            zine.site.description.as_deref(),
+            Some("A magazine about Rust")
+        );
+        assert_eq!(zine.theme.primary_color, "#000000");
+        assert_eq!(zine.authors["alice"].id, "alice");
+        assert_eq!(zine.topics["rust"].slug, "rust");
+        let paths = zine
+            .output_paths()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        assert!(paths.contains(&String::from("/issue-1/hello")));
+        assert!(paths.contains(&String::from("/about")));
+        assert!(paths.contains(&String::from("/@alice")));
+
+        let article = ArticleBuilder::new("hello", "Hello")
+            .markdown("Hello **zine**")
+            .build();
+        assert_eq!(article.markdown, "Hello **zine**");
+    }
+}
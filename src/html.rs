@@ -1,16 +1,37 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 
 use genkit::helpers;
-use lol_html::{element, html_content::Element, HtmlRewriter, Settings};
+use lol_html::{
+    element,
+    html_content::{ContentType, Element},
+    rewrite_str, HtmlRewriter, RewriteStrSettings, Settings,
+};
+
+use crate::images::ResponsiveImage;
 
 /// Rewrite root path URL in `raw_html` with `site_url` and `cdn_url`.
+///
+/// The `/static` asset URLs are appended with the `cache_bust_version`
+/// query string if present, e.g. `/static/zine.css?v=3f2a9c1-1700000000`.
 pub fn rewrite_html_base_url(
     raw_html: &[u8],
     site_url: Option<&str>,
     cdn_url: Option<&str>,
+    cache_bust_version: Option<&str>,
 ) -> Result<Vec<u8>> {
     let rewrite_url_in_attr = |el: &mut Element, attr_name: &str| {
-        if let Some(attr) = el.get_attribute(attr_name) {
+        if let Some(mut attr) = el.get_attribute(attr_name) {
+            // Links and meta tags aren't the assets of this page.
+            if let Some(version) = cache_bust_version.filter(|_| {
+                attr.starts_with("/static/") && !matches!(el.tag_name().as_str(), "a" | "meta")
+            }) {
+                let separator = if attr.contains('?') { '&' } else { '?' };
+                attr = format!("{attr}{separator}v={version}");
+                el.set_attribute(attr_name, &attr)
+                    .expect("Set attribute failed");
+            }
             let dest_url =
                 if let (Some(attr), Some(cdn_url)) = (attr.strip_prefix("/static"), cdn_url) {
                     format!("{}{}", &cdn_url, attr)
@@ -96,19 +117,320 @@ pub fn rewrite_html_base_url(
     Ok(html)
 }
 
+/// Get the asset URLs in `html` which are busted by the `v` query string,
+/// see [`rewrite_html_base_url`].
+pub fn cache_busted_urls(html: &str) -> Result<Vec<String>> {
+    let mut urls = vec![];
+    rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!(
+                "link[rel=stylesheet][href], script[src], iframe[src], img[src], audio[src], video[src]",
+                |el| {
+                    let attr = if el.tag_name() == "link" { "href" } else { "src" };
+                    if let Some(url) = el.get_attribute(attr) {
+                        let busted = url
+                            .split_once('?')
+                            .map(|(_, query)| query.split('&').any(|pair| pair.starts_with("v=")))
+                            .unwrap_or_default();
+                        if busted {
+                            urls.push(url);
+                        }
+                    }
+                    Ok(())
+                }
+            )],
+            ..RewriteStrSettings::default()
+        },
+    )?;
+    Ok(urls)
+}
+
+/// Fill the empty `alt` attribute of images in `html` with the image title (the caption),
+/// or the humanized file name if the title is missing.
+pub fn fill_image_alt(html: &str) -> Result<String> {
+    Ok(rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!("img", |el| {
+                if el
+                    .get_attribute("alt")
+                    .map(|alt| !alt.trim().is_empty())
+                    .unwrap_or_default()
+                {
+                    return Ok(());
+                }
+
+                let fallback = el
+                    .get_attribute("title")
+                    .filter(|title| !title.trim().is_empty())
+                    .or_else(|| image_name(&el.get_attribute("src")?));
+                if let Some(alt) = fallback {
+                    el.set_attribute("alt", &alt)?;
+                }
+                Ok(())
+            })],
+            ..RewriteStrSettings::default()
+        },
+    )?)
+}
+
+/// Add the `srcset` and `sizes` attributes to the images in `html` resolved by `resolve`,
+/// the images with extra formats are wrapped in a `<picture>` with their `<source>`.
+///
+/// The images which have the `srcset` attribute already are kept untouched.
+pub fn responsive_images(
+    html: &str,
+    resolve: impl Fn(&str) -> Option<ResponsiveImage>,
+) -> Result<String> {
+    Ok(rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!("img[src]", |el| {
+                if el.has_attribute("srcset") {
+                    return Ok(());
+                }
+                let Some(image) = el.get_attribute("src").and_then(|src| resolve(&src)) else {
+                    return Ok(());
+                };
+                el.set_attribute("srcset", &image.srcset)?;
+                el.set_attribute("sizes", &image.sizes)?;
+                if !image.sources.is_empty() {
+                    let sources = image
+                        .sources
+                        .iter()
+                        .map(|source| {
+                            format!(
+                                r#"<source type="{}" srcset="{}" sizes="{}">"#,
+                                source.r#type,
+                                escape_attr(&source.srcset),
+                                escape_attr(&image.sizes)
+                            )
+                        })
+                        .collect::<String>();
+                    el.before(&format!("<picture>{sources}"), ContentType::Html);
+                    el.after("</picture>", ContentType::Html);
+                }
+                Ok(())
+            })],
+            ..RewriteStrSettings::default()
+        },
+    )?)
+}
+
+/// Escape the `value` to be quoted as an html attribute value.
+pub(crate) fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Prefix the headings in `html` with the hierarchical numbers, e.g. `2.1.3`,
+/// returns the numbered html and the numbers keyed by the heading ids.
+///
+/// The numbers are relative to the enclosing headings rather than the heading levels,
+/// so a skipped level (e.g. a `<h4>` under a `<h2>`) doesn't produce a `0` number.
+pub fn number_headings(html: &str) -> Result<(String, HashMap<String, String>)> {
+    // The enclosing heading levels and their counts.
+    let mut levels: Vec<(u8, usize)> = vec![];
+    let mut numbers = HashMap::new();
+    let html = rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!("h1, h2, h3, h4, h5, h6", |el| {
+                let level = el.tag_name()[1..].parse::<u8>().unwrap_or_default();
+                while matches!(levels.last(), Some((last, _)) if *last > level) {
+                    levels.pop();
+                }
+                match levels.last_mut() {
+                    Some((last, count)) if *last == level => *count += 1,
+                    _ => levels.push((level, 1)),
+                }
+                let number = levels
+                    .iter()
+                    .map(|(_, count)| count.to_string())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                el.prepend(
+                    &format!(r#"<span class="heading-number">{number}</span> "#),
+                    ContentType::Html,
+                );
+                if let Some(id) = el.get_attribute("id") {
+                    numbers.insert(id, number);
+                }
+                Ok(())
+            })],
+            ..RewriteStrSettings::default()
+        },
+    )?;
+    Ok((html, numbers))
+}
+
+// Humanize the file name of image url, e.g. `/static/black-cat_2.png` -> `black cat 2`.
+fn image_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    let stem = file_name
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .unwrap_or(file_name);
+    let name = stem
+        .split(['-', '_'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+/// Rebase the root path URLs of this site in `html` under the `prefix` path, e.g. `/v/3`,
+/// so an archived page refers to the archived pages and assets rather than the current ones.
+pub fn rebase_html_urls(html: &str, site_url: &str, prefix: &str) -> Result<String> {
+    let site_url = site_url.trim_end_matches('/');
+    let rebase_attr = |el: &mut Element, attr_name: &str| {
+        if let Some(url) = el
+            .get_attribute(attr_name)
+            .and_then(|url| rebase_url(&url, site_url, prefix))
+        {
+            el.set_attribute(attr_name, &url)
+                .expect("Set attribute failed");
+        }
+    };
+    Ok(rewrite_str(
+        html,
+        RewriteStrSettings {
+            element_content_handlers: vec![
+                element!("a[href], link[href]", |el| {
+                    rebase_attr(el, "href");
+                    Ok(())
+                }),
+                element!(
+                    "script[src], iframe[src], img[src], audio[src], video[src], source[src]",
+                    |el| {
+                        rebase_attr(el, "src");
+                        Ok(())
+                    }
+                ),
+            ],
+            ..RewriteStrSettings::default()
+        },
+    )?)
+}
+
+fn rebase_url(url: &str, site_url: &str, prefix: &str) -> Option<String> {
+    let (base, path) = match url.strip_prefix(site_url) {
+        Some(path) if site_url.is_empty() => ("", path),
+        Some(path) if path.is_empty() => (site_url, "/"),
+        Some(path) if path.starts_with('/') => (site_url, path),
+        _ if url.starts_with('/') && !url.starts_with("//") => ("", url),
+        _ => return None,
+    };
+    if path.starts_with('/') && !path.starts_with("//") && !path.starts_with(&format!("{prefix}/"))
+    {
+        Some(format!("{base}{prefix}{path}"))
+    } else {
+        None
+    }
+}
+
+// HTML elements which never have a closing tag.
+static VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Truncate the rendered `html` after the first `paragraphs` paragraphs,
+/// the unclosed tags (e.g. a blockquote) are closed so the excerpt is still well-formed.
+pub fn truncate_html(html: &str, paragraphs: usize) -> String {
+    if paragraphs == 0 {
+        return String::new();
+    }
+
+    let mut open_tags: Vec<String> = vec![];
+    let mut count = 0;
+    let mut pos = 0;
+    while let Some(start) = html[pos..].find('<').map(|i| pos + i) {
+        let Some(end) = html[start..].find('>').map(|i| start + i + 1) else {
+            break;
+        };
+        pos = end;
+
+        let tag = &html[start + 1..end - 1];
+        if tag.starts_with('!') || tag.starts_with('?') {
+            // Comment, doctype or processing instruction.
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim().to_ascii_lowercase();
+            if let Some(index) = open_tags.iter().rposition(|tag| *tag == name) {
+                open_tags.truncate(index);
+            }
+            if name == "p" {
+                count += 1;
+                if count == paragraphs {
+                    let mut excerpt = html[..end].to_owned();
+                    for tag in open_tags.iter().rev() {
+                        excerpt.push_str(&format!("</{tag}>"));
+                    }
+                    return excerpt;
+                }
+            }
+            continue;
+        }
+
+        let name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if !tag.ends_with('/') && !VOID_ELEMENTS.contains(&name.as_str()) {
+            open_tags.push(name);
+        }
+    }
+
+    // Not enough paragraphs, keep the whole html.
+    html.to_owned()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::rewrite_html_base_url;
+    use super::{
+        cache_busted_urls, fill_image_alt, number_headings, rebase_url, responsive_images,
+        rewrite_html_base_url, truncate_html,
+    };
+    use crate::images::{ImageSource, ResponsiveImage};
     use test_case::test_case;
 
     const SITE_URL: &str = "https://github.com";
     const CDN_URL: &str = "https://cdn-example.net";
 
+    #[test]
+    fn test_responsive_images() {
+        let html = r#"<p><img src="/static/a.png" alt="a"><img src="/static/b.png" srcset="/static/b.png 1x"><img src="/static/c.png"></p>"#;
+        let responsive = responsive_images(html, |src| {
+            (src == "/static/a.png").then(|| ResponsiveImage {
+                srcset: String::from("/static/_images/a-480.png 480w, /static/a.png 1000w"),
+                sizes: String::from("100vw"),
+                sources: vec![ImageSource {
+                    r#type: "image/webp",
+                    srcset: String::from("/static/_images/a-480.webp 480w"),
+                }],
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            responsive,
+            r#"<p><picture><source type="image/webp" srcset="/static/_images/a-480.webp 480w" sizes="100vw"><img src="/static/a.png" alt="a" srcset="/static/_images/a-480.png 480w, /static/a.png 1000w" sizes="100vw"></picture><img src="/static/b.png" srcset="/static/b.png 1x"><img src="/static/c.png"></p>"#
+        );
+    }
+
     #[test_case(r#"<body><div class="bg-primary text-main" style="background-image: url('/test.png');"></div></body>"#)]
     fn test_rewrite_background_image_url(html: &str) {
         assert_eq!(
             String::from_utf8_lossy(
-                &rewrite_html_base_url(html.as_bytes(), Some(SITE_URL), Some(CDN_URL)).unwrap()
+                &rewrite_html_base_url(html.as_bytes(), Some(SITE_URL), Some(CDN_URL), None)
+                    .unwrap()
             ),
             html.replace("/test.png", &format!("{}/test.png", SITE_URL))
         );
@@ -119,7 +441,8 @@ mod tests {
     fn test_rewrite_cdn_background_image_url(html: &str) {
         assert_eq!(
             String::from_utf8_lossy(
-                &rewrite_html_base_url(html.as_bytes(), Some(SITE_URL), Some(CDN_URL)).unwrap()
+                &rewrite_html_base_url(html.as_bytes(), Some(SITE_URL), Some(CDN_URL), None)
+                    .unwrap()
             ),
             html.replace("/static/test.png", &format!("{}/test.png", CDN_URL))
         );
@@ -140,7 +463,8 @@ mod tests {
                 &rewrite_html_base_url(
                     html.replace("{}", path).as_bytes(),
                     Some(SITE_URL),
-                    Some(CDN_URL)
+                    Some(CDN_URL),
+                    None
                 )
                 .unwrap()
             ),
@@ -164,7 +488,8 @@ mod tests {
                 &rewrite_html_base_url(
                     html.replace("{}", &whole_url).as_bytes(),
                     Some(SITE_URL),
-                    Some(CDN_URL)
+                    Some(CDN_URL),
+                    None
                 )
                 .unwrap()
             ),
@@ -185,7 +510,8 @@ mod tests {
                 &rewrite_html_base_url(
                     html.replace("{}", path).as_bytes(),
                     Some(SITE_URL),
-                    Some(CDN_URL)
+                    Some(CDN_URL),
+                    None
                 )
                 .unwrap()
             ),
@@ -205,7 +531,8 @@ mod tests {
                 &rewrite_html_base_url(
                     html.replace("{}", path).as_bytes(),
                     Some(SITE_URL),
-                    Some(CDN_URL)
+                    Some(CDN_URL),
+                    None
                 )
                 .unwrap()
             ),
@@ -226,7 +553,8 @@ mod tests {
                 &rewrite_html_base_url(
                     html.replace("{}", &whole_url).as_bytes(),
                     Some(SITE_URL),
-                    Some(CDN_URL)
+                    Some(CDN_URL),
+                    None
                 )
                 .unwrap()
             ),
@@ -246,11 +574,101 @@ mod tests {
                 &rewrite_html_base_url(
                     html.replace("{}", path).as_bytes(),
                     Some(SITE_URL),
-                    Some(CDN_URL)
+                    Some(CDN_URL),
+                    None
                 )
                 .unwrap()
             ),
             html.replace("{}", path)
         );
     }
+
+    #[test_case("<p>a</p><p>b</p><p>c</p>", 2, "<p>a</p><p>b</p>"; "top level")]
+    #[test_case("<p>a <em>b</em><br></p><p>c</p>", 1, "<p>a <em>b</em><br></p>"; "inline")]
+    #[test_case("<blockquote><p>a</p><p>b</p></blockquote>", 1, "<blockquote><p>a</p></blockquote>"; "nested")]
+    #[test_case("<h1>t</h1><!-- <p> --><p>a</p><p>b</p>", 1, "<h1>t</h1><!-- <p> --><p>a</p>"; "comment")]
+    #[test_case("<p>a</p>", 2, "<p>a</p>"; "not enough")]
+    #[test_case("<p>a</p>", 0, ""; "zero")]
+    fn test_truncate_html(html: &str, paragraphs: usize, expected: &str) {
+        assert_eq!(truncate_html(html, paragraphs), expected);
+    }
+
+    #[test_case(r#"<img src="/a.png" alt="A cat">"#, r#"<img src="/a.png" alt="A cat">"#; "keep")]
+    #[test_case(r#"<img src="/a.png" alt="" title="A cat">"#, r#"<img src="/a.png" alt="A cat" title="A cat">"#; "title")]
+    #[test_case(r#"<img src="/static/black-cat_2.png?v=1" alt="">"#, r#"<img src="/static/black-cat_2.png?v=1" alt="black cat 2">"#; "file name")]
+    #[test_case(r#"<img src="/static/">"#, r#"<img src="/static/">"#; "no name")]
+    fn test_fill_image_alt(html: &str, expected: &str) {
+        assert_eq!(fill_image_alt(html).unwrap(), expected);
+    }
+
+    #[test_case("<link rel=\"stylesheet\" href=\"{}\">", "/static/zine.css"; "link")]
+    #[test_case("<script src=\"{}\"></script>", "/static/zine.js"; "script")]
+    #[test_case("<img src=\"{}\">", "/static/zine.png?size=64"; "img")]
+    fn test_rewrite_cache_bust_url(html: &str, path: &str) {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        assert_eq!(
+            String::from_utf8_lossy(
+                &rewrite_html_base_url(
+                    html.replace("{}", path).as_bytes(),
+                    None,
+                    None,
+                    Some("abc")
+                )
+                .unwrap()
+            ),
+            html.replace("{}", &format!("{path}{separator}v=abc"))
+        );
+    }
+
+    #[test_case("<a href=\"/static/zine.css\"></a>"; "a")]
+    #[test_case("<link rel=\"stylesheet\" href=\"/zine.css\">"; "not static")]
+    fn test_not_rewrite_cache_bust_url(html: &str) {
+        assert_eq!(
+            String::from_utf8_lossy(
+                &rewrite_html_base_url(html.as_bytes(), None, None, Some("abc")).unwrap()
+            ),
+            html
+        );
+    }
+
+    #[test]
+    fn test_cache_busted_urls() {
+        let html = r#"<link rel="stylesheet" href="/static/zine.css?v=abc">
+            <script src="https://cdn.zine.dev/zine.js?lang=en&v=abc"></script>
+            <img src="/static/logo.png"><img src="/static/cat.png?w=100">
+            <a href="/static/guide.pdf?v=abc">Guide</a>"#;
+        assert_eq!(
+            cache_busted_urls(html).unwrap(),
+            vec![
+                "/static/zine.css?v=abc",
+                "https://cdn.zine.dev/zine.js?lang=en&v=abc"
+            ]
+        );
+    }
+
+    #[test_case("https://zine.dev/issue-1/", Some("https://zine.dev/v/3/issue-1/"); "absolute")]
+    #[test_case("https://zine.dev", Some("https://zine.dev/v/3/"); "root")]
+    #[test_case("/static/zine.css", Some("/v/3/static/zine.css"); "root path")]
+    #[test_case("https://zine.dev/v/3/static/zine.css", None; "rebased")]
+    #[test_case("https://zine.dev.org/a", None; "other site")]
+    #[test_case("//cdn.net/a.png", None; "protocol relative")]
+    #[test_case("#more", None; "anchor")]
+    fn test_rebase_url(url: &str, expected: Option<&str>) {
+        assert_eq!(
+            rebase_url(url, "https://zine.dev", "/v/3").as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_number_headings() {
+        let (html, numbers) = number_headings(
+            r#"<h2 id="a">A</h2><h3 id="b">B</h3><h3 id="c">C</h3><h5 id="d">D</h5><h2 id="e">E</h2><h4>F</h4>"#,
+        )
+        .unwrap();
+        assert!(html.starts_with(r#"<h2 id="a"><span class="heading-number">1</span> A</h2>"#));
+        assert!(html.ends_with(r#"<h4><span class="heading-number">2.1</span> F</h4>"#));
+        let numbers = ["a", "b", "c", "d", "e"].map(|id| numbers[id].as_str());
+        assert_eq!(numbers, ["1", "1.1", "1.2", "1.2.1", "2"]);
+    }
 }
@@ -13,3 +13,8 @@ static LOCALES: [(&str, &str); 7] = [
 pub fn get_locale_name(locale: &str) -> Option<&'static str> {
     HashMap::from(LOCALES).get(locale).copied()
 }
+
+/// Get the `hreflang` attribute value of `locale`, e.g. `zh_CN` -> `zh-CN`.
+pub fn hreflang(locale: &str) -> String {
+    locale.replace('_', "-")
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufWriter, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Context as _, Result};
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder, webp::WebPQuality},
+    imageops::FilterType,
+    DynamicImage, ImageEncoder,
+};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use serde::Serialize;
+
+use crate::{entity::ImagesConfig, media, output, snapshot};
+
+// The directory (relative to the zine root) to cache the resized images.
+static IMAGE_CACHE_DIR: &str = ".zine-cache/images";
+// The url prefix of the resized images.
+static IMAGE_URL_PREFIX: &str = "/static/_images";
+// The extensions of the images which can be resized.
+static RESIZABLE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+// The variants to write, keyed by the file name.
+static VARIANTS: OnceCell<Mutex<BTreeMap<String, Variant>>> = OnceCell::new();
+
+// A resized or converted variant of a source image.
+#[derive(Clone, Debug)]
+struct Variant {
+    source: PathBuf,
+    width: u32,
+    // The extension of the variant, which decides the encoding format.
+    extension: String,
+}
+
+/// The `srcset` of a local image, rendered as the `<img>` attributes,
+/// or a `<picture>` if there are extra formats.
+#[derive(Clone, Debug, Serialize)]
+pub struct ResponsiveImage {
+    /// The `srcset` of the original format, including the original image.
+    pub srcset: String,
+    pub sizes: String,
+    /// The `<source>` of the extra formats, in the order of the `formats` config.
+    pub sources: Vec<ImageSource>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ImageSource {
+    /// The mime type, e.g. `image/webp`.
+    pub r#type: &'static str,
+    pub srcset: String,
+}
+
+/// Forget the registered variants, which is called at the start of each build,
+/// so a `zine serve` rebuild never writes the variants of the removed images.
+pub fn reset_variants() {
+    if let Some(variants) = VARIANTS.get() {
+        variants.lock().clear();
+    }
+}
+
+/// Resolve the responsive image of the local image `url`, e.g. `/static/cover.png`,
+/// and register the variants to write by [`write_variants`].
+///
+/// Return `None` if the image isn't a local image in the `static` directory,
+/// or it's not larger than any of the `widths` in config.
+pub fn responsive_image(url: &str, config: &ImagesConfig) -> Option<ResponsiveImage> {
+    let path = url.split(['?', '#']).next()?;
+    let relative_path = path.strip_prefix('/')?;
+    if !relative_path.starts_with("static/")
+        || Path::new(relative_path)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let extension = media::url_extension(path)?;
+    if !RESIZABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+
+    let source = match config
+        .sandbox_config
+        .resolve_project_path(&config.root, relative_path)
+    {
+        Ok(source) => source,
+        Err(err) => {
+            println!("Warning: failed to read the image `{url}`: {err}");
+            return None;
+        }
+    };
+    let (width, _) = match image::image_dimensions(&source) {
+        Ok(dimensions) => dimensions,
+        Err(err) => {
+            println!("Warning: failed to read the image `{url}`: {err}");
+            return None;
+        }
+    };
+    let widths = config
+        .widths
+        .iter()
+        .copied()
+        .filter(|resized| *resized < width)
+        .collect::<Vec<_>>();
+    if widths.is_empty() {
+        return None;
+    }
+
+    let content = fs::read(&source).ok()?;
+    let stem = Path::new(relative_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("image");
+    let prefix = format!("{stem}-{}", &snapshot::hash(&content)[..12]);
+    let variants = VARIANTS.get_or_init(|| Mutex::new(BTreeMap::new()));
+    let register = |width: u32, extension: &str| {
+        let name = format!("{prefix}-{width}.{extension}");
+        variants.lock().insert(
+            name.clone(),
+            Variant {
+                source: source.clone(),
+                width,
+                extension: extension.to_owned(),
+            },
+        );
+        format!("{IMAGE_URL_PREFIX}/{name} {width}w")
+    };
+
+    let mut srcset = widths
+        .iter()
+        .map(|width| register(*width, &extension))
+        .collect::<Vec<_>>();
+    srcset.push(format!("{path} {width}w"));
+    let sources = config
+        .formats
+        .iter()
+        .filter(|format| format.extension() != extension)
+        .map(|format| ImageSource {
+            r#type: format.mime_type(),
+            srcset: widths
+                .iter()
+                .chain([&width])
+                .map(|width| register(*width, format.extension()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        })
+        .collect();
+    Some(ResponsiveImage {
+        srcset: srcset.join(", "),
+        sizes: config.sizes.clone(),
+        sources,
+    })
+}
+
+/// Write all the registered variants into the `static/_images` directory of `dest`.
+///
+/// The variants are cached in the `.zine-cache/images` directory, which are
+/// only encoded once since the file names have the content hash of the source image.
+pub fn write_variants(dest: &Path, config: &ImagesConfig) -> Result<()> {
+    let Some(variants) = VARIANTS.get() else {
+        return Ok(());
+    };
+    let variants = variants.lock().clone();
+    let dry_run = output::is_dry_run();
+    let cache_dir = config.root.join(IMAGE_CACHE_DIR);
+    let images_dir = dest.join(IMAGE_URL_PREFIX.trim_start_matches('/'));
+    variants.par_iter().try_for_each(|(name, variant)| {
+        let cache_file = cache_dir.join(name);
+        // The variants are only recorded in a dry run.
+        if !cache_file.exists() && !dry_run {
+            if let Err(err) = encode_variant(variant, &cache_file, config.quality) {
+                println!(
+                    "Warning: failed to resize the image `{}`: {err:#}",
+                    variant.source.display()
+                );
+                return Ok(());
+            }
+        }
+        output::copy(&cache_file, &images_dir.join(name))
+    })
+}
+
+// Resize the source image of `variant` and encode it into the `file`.
+fn encode_variant(variant: &Variant, file: &Path, quality: u8) -> Result<()> {
+    let image = image::open(&variant.source)
+        .with_context(|| format!("Failed to decode `{}`", variant.source.display()))?;
+    let image = if variant.width < image.width() {
+        image.resize(variant.width, u32::MAX, FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first to avoid leaving a broken file.
+    let temp_file = file.with_extension("encoding");
+    let mut writer = BufWriter::new(fs::File::create(&temp_file)?);
+    encode(&image, &variant.extension, quality, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(temp_file, file)?;
+    Ok(())
+}
+
+fn encode(image: &DynamicImage, extension: &str, quality: u8, writer: impl Write) -> Result<()> {
+    // The encoders only support the 8-bit RGB(A) images, and JPEG has no alpha channel.
+    let image = if image.color().has_alpha() && !matches!(extension, "jpg" | "jpeg") {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+    let (data, width, height, color) = (
+        image.as_bytes(),
+        image.width(),
+        image.height(),
+        image.color(),
+    );
+    match extension {
+        "jpg" | "jpeg" => JpegEncoder::new_with_quality(writer, quality)
+            .write_image(data, width, height, color)?,
+        "png" => PngEncoder::new(writer).write_image(data, width, height, color)?,
+        "webp" => WebPEncoder::new_with_quality(writer, WebPQuality::lossy(quality))
+            .encode(data, width, height, color)?,
+        #[cfg(feature = "avif")]
+        "avif" => image::codecs::avif::AvifEncoder::new_with_speed_quality(writer, 6, quality)
+            .write_image(data, width, height, color)?,
+        _ => bail!("Unsupported image format `{extension}`"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+    use tempfile::tempdir;
+
+    use crate::entity::{ImageFormat, ImagesConfig, SandboxConfig};
+
+    use super::responsive_image;
+
+    #[test]
+    fn test_responsive_image() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().to_owned();
+        std::fs::create_dir_all(root.join("static")).unwrap();
+        RgbImage::from_pixel(1000, 500, Rgb([255, 0, 0]))
+            .save(root.join("static/red.png"))
+            .unwrap();
+        let config = ImagesConfig {
+            widths: vec![480, 960, 1440],
+            formats: vec![ImageFormat::Webp],
+            sizes: String::from("100vw"),
+            quality: 80,
+            root,
+            sandbox_config: SandboxConfig::default(),
+        };
+
+        let image = responsive_image("/static/red.png", &config).unwrap();
+        let srcset = image.srcset.split(", ").collect::<Vec<_>>();
+        assert_eq!(srcset.len(), 3);
+        assert!(
+            srcset[0].starts_with("/static/_images/red-") && srcset[0].ends_with("-480.png 480w")
+        );
+        assert_eq!(srcset[2], "/static/red.png 1000w");
+        assert_eq!(image.sources.len(), 1);
+        assert_eq!(image.sources[0].r#type, "image/webp");
+        assert!(image.sources[0].srcset.ends_with("-1000.webp 1000w"));
+
+        assert!(responsive_image("/static/missing.png", &config).is_none());
+        assert!(responsive_image("/static/../zine.toml", &config).is_none());
+        assert!(responsive_image("https://example.com/red.png", &config).is_none());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_responsive_image_outside_project() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("project");
+        let outside = dir.path().join("outside.png");
+        std::fs::create_dir_all(root.join("static")).unwrap();
+        RgbImage::from_pixel(1000, 500, Rgb([255, 0, 0]))
+            .save(&outside)
+            .unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("static/linked.png")).unwrap();
+        let mut config = ImagesConfig {
+            widths: vec![480],
+            formats: vec![],
+            sizes: String::from("100vw"),
+            quality: 80,
+            root,
+            sandbox_config: SandboxConfig::default(),
+        };
+        assert!(responsive_image("/static/linked.png", &config).is_some());
+
+        config.sandbox_config.enforce();
+        assert!(responsive_image("/static/linked.png", &config).is_none());
+    }
+}
@@ -0,0 +1,308 @@
+//! The library behind the `zine` command, which exposes the in-memory `fixture`
+//! builders with the `test-fixtures` feature, for the theme developers to render
+//! templates against synthetic content.
+use anyhow::{anyhow, Context as _, Result};
+use clap::{Arg, ArgAction, Command};
+use engine::ZineGenerator;
+use genkit::Genkit;
+use markdown::ZineMarkdownVisitor;
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+pub use entity::{Article, Author, Issue, Zine};
+use error::ZineError;
+use walkdir::WalkDir;
+
+mod archive;
+mod assets;
+mod changelog;
+mod changes;
+mod cmd;
+mod code_blocks;
+mod crosspost;
+mod csp;
+mod data;
+mod date;
+mod engine;
+mod entity;
+mod error;
+mod exif;
+mod export;
+mod feed;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixture;
+mod html;
+mod i18n;
+mod images;
+mod lint;
+mod locales;
+mod markdown;
+mod media;
+mod output;
+mod permalink;
+mod snapshot;
+mod social_card;
+mod watch;
+
+// The convention name of zine config file.
+static ZINE_FILE: &str = "zine.toml";
+// The convention name of zine markdown directory.
+static ZINE_CONTENT_DIR: &str = "content";
+// The convention name of introduction file for zine issue.
+static ZINE_INTRO_FILE: &str = "intro.md";
+// The convention name of zine data file.
+static ZINE_DATA_FILE: &str = "zine-data.json";
+// The directory of the state files owned by zine, which are never published.
+static ZINE_STATE_DIR: &str = ".zine";
+pub static ZINE_BANNER: &str = r"
+
+███████╗██╗███╗   ██╗███████╗
+╚══███╔╝██║████╗  ██║██╔════╝
+  ███╔╝ ██║██╔██╗ ██║█████╗  
+ ███╔╝  ██║██║╚██╗██║██╔══╝  
+███████╗██║██║ ╚████║███████╗
+╚══════╝╚═╝╚═╝  ╚═══╝╚══════╝
+                             
+";
+
+// Find the root zine file in current dir and try to parse it
+fn parse_root_zine_file<P: AsRef<Path>>(path: P) -> Result<Option<Zine>> {
+    // Find the name in current dir
+    if WalkDir::new(&path).max_depth(1).into_iter().any(|entry| {
+        let entry = entry.as_ref().unwrap();
+        entry.file_name() == crate::ZINE_FILE
+    }) {
+        // Try to parse the root zine.toml as Zine instance
+        return Ok(Some(Zine::parse_from_toml(path)?));
+    }
+
+    Ok(None)
+}
+
+/// Locate folder contains the root `zine.toml`, and return path info and Zine instance.
+pub fn locate_root_zine_folder<P: AsRef<Path>>(path: P) -> Result<Option<(PathBuf, Zine)>> {
+    match parse_root_zine_file(&path) {
+        Ok(Some(zine)) => return Ok(Some((path.as_ref().to_path_buf(), zine))),
+        Err(err) => match err.downcast::<ZineError>() {
+            // Found a root zine.toml, but it has invalid format
+            Ok(inner_err @ ZineError::InvalidRootTomlFile(_)) => return Err(anyhow!(inner_err)),
+            // Found a root zine.toml, but it requires a newer zine
+            Ok(inner_err @ ZineError::OutdatedZineVersion { .. }) => {
+                return Err(anyhow!(inner_err))
+            }
+            // Found a zine.toml, but it isn't a root zine.toml
+            Ok(ZineError::NotRootTomlFile) => {}
+            // No zine.toml file found
+            _ => {}
+        },
+        _ => {}
+    }
+
+    match path.as_ref().parent() {
+        Some(parent_path) => locate_root_zine_folder(parent_path),
+        None => Ok(None),
+    }
+}
+
+// Whether the global `--<name>` flag is in the command line `args`, see [`global_arg`].
+fn global_flag(args: impl IntoIterator<Item = OsString>, name: &str) -> bool {
+    let flag = format!("--{name}");
+    args.into_iter()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == flag.as_str())
+}
+
+// Get the subcommand name from the command line `args`, e.g. `build`,
+// the values of the global arguments are skipped.
+fn subcommand_name(args: impl IntoIterator<Item = OsString>) -> Option<OsString> {
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if ["source", "threads", "only", "archive"]
+            .iter()
+            .any(|name| arg == format!("--{name}").as_str())
+        {
+            args.next();
+        } else if !arg.to_string_lossy().starts_with('-') {
+            return Some(arg);
+        }
+    }
+    None
+}
+
+// Get the value of the global `--<name>` argument from the command line `args`.
+//
+// The global arguments are handled before the commands parse, since the
+// `build` and `serve` commands are parsed by genkit.
+fn global_arg(args: impl IntoIterator<Item = OsString>, name: &str) -> Option<OsString> {
+    let flag = format!("--{name}");
+    let prefix = format!("--{name}=");
+    let mut value = None;
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == flag.as_str() {
+            value = args.next();
+        } else if let Some(arg) = arg.to_str().and_then(|arg| arg.strip_prefix(&prefix)) {
+            value = Some(OsString::from(arg));
+        }
+    }
+    value
+}
+
+/// Run the `zine` command with the command line arguments.
+pub async fn run() -> Result<()> {
+    // The archive path is relative to the current directory rather than the `--source`.
+    if let Some(archive) = global_arg(env::args_os(), "archive").map(PathBuf::from) {
+        env::set_var(output::BUILD_ARCHIVE_ENV, env::current_dir()?.join(archive));
+    }
+    // Run all commands in the `--source` directory, the same as running
+    // them in that directory.
+    if let Some(source) = global_arg(env::args_os(), "source").map(PathBuf::from) {
+        env::set_current_dir(&source)
+            .with_context(|| format!("Failed to change to the source `{}`", source.display()))?;
+    }
+    // The render thread pool is built after the root `zine.toml` is loaded.
+    if let Some(threads) = global_arg(env::args_os(), "threads") {
+        env::set_var(engine::THREADS_ENV, threads);
+    }
+    if let Some(issue) = global_arg(env::args_os(), "only") {
+        env::set_var(engine::BUILD_ONLY_ENV, issue);
+    }
+    // The `publish` and `deploy` commands have their own `--dry-run` flag.
+    let build = subcommand_name(env::args_os()).is_some_and(|name| name == "build");
+    if build && global_flag(env::args_os(), "dry-run") {
+        env::set_var(engine::BUILD_DRY_RUN_ENV, "1");
+    }
+    if global_flag(env::args_os(), "drafts") {
+        env::set_var(engine::DRAFTS_ENV, "1");
+    }
+    if global_flag(env::args_os(), "sandbox") {
+        env::set_var(entity::SANDBOX_ENV, "1");
+    }
+
+    let command = Command::new(clap::crate_name!())
+        .about(clap::crate_description!())
+        .version(clap::crate_version!())
+        .arg(
+            Arg::new("zine_source")
+                .long("source")
+                .global(true)
+                .value_name("PATH")
+                .help("The zine project directory to run the command in, default to the current directory"),
+        )
+        .arg(
+            Arg::new("zine_threads")
+                .long("threads")
+                .global(true)
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .help("The threads to render the site, default to the `threads` of `[build]` table or the CPU count"),
+        )
+        .arg(
+            Arg::new("zine_only")
+                .long("only")
+                .global(true)
+                .value_name("ISSUE")
+                .help("Build only the issue of this slug or number into the existing build directory in `zine build`"),
+        )
+        .arg(
+            Arg::new("zine_archive")
+                .long("archive")
+                .global(true)
+                .value_name("FILE")
+                .help("Write the built site into this tar (or .tar.gz) archive rather than the build directory in `zine build`"),
+        )
+        .arg(
+            Arg::new("zine_drafts")
+                .long("drafts")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Render the draft articles under the unlisted `/drafts/` prefix in `zine serve`"),
+        )
+        .arg(
+            Arg::new("zine_sandbox")
+                .long("sandbox")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Build the untrusted project safely, no file outside of the project is read, no external fetch or command is run"),
+        );
+    // Only registered for `zine build`, otherwise it duplicates the `--dry-run`
+    // flag of the other commands.
+    let command = if build {
+        command.arg(
+            Arg::new("zine_dry_run")
+                .long("dry-run")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the files `zine build` would write with their sources, without writing anything"),
+        )
+    } else {
+        command
+    };
+    let genkit = Genkit::with_command(command, ZineGenerator)
+        .markdown_visitor(ZineMarkdownVisitor)
+        .data_filename(ZINE_DATA_FILE)
+        .banner(ZINE_BANNER)
+        .add_command(cmd::ExportCmd)
+        .add_command(cmd::LintCmd)
+        .add_command(cmd::NewCmd)
+        .add_command(cmd::NextPublishCmd)
+        .add_command(cmd::PublishCmd)
+        .add_command(cmd::SnapshotCmd)
+        .add_command(cmd::VersionCmd);
+    #[cfg(feature = "s3")]
+    let genkit = genkit.add_command(cmd::DeployCmd);
+    genkit.run().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use test_case::test_case;
+
+    use super::{global_arg, global_flag, subcommand_name};
+
+    #[test_case(&["zine", "build"], None)]
+    #[test_case(&["zine", "--source", "site", "build"], Some("site"))]
+    #[test_case(&["zine", "new", "--issue", "--source=site"], Some("site"))]
+    #[test_case(&["zine", "serve", "--", "--source", "site"], None)]
+    #[test_case(&["zine", "build", "--sources", "site"], None)]
+    fn test_source_arg(args: &[&str], expected: Option<&str>) {
+        assert_eq!(
+            global_arg(args.iter().map(Into::into), "source").map(PathBuf::from),
+            expected.map(PathBuf::from)
+        );
+    }
+
+    #[test_case(&["zine", "build", "--sandbox"], true)]
+    #[test_case(&["zine", "--sandbox", "serve"], true)]
+    #[test_case(&["zine", "build", "--", "--sandbox"], false)]
+    #[test_case(&["zine", "build", "--sandboxed"], false)]
+    fn test_sandbox_flag(args: &[&str], expected: bool) {
+        assert_eq!(
+            global_flag(args.iter().map(Into::into), "sandbox"),
+            expected
+        );
+    }
+
+    #[test_case(&["zine", "build", "--dry-run"], Some("build"))]
+    #[test_case(&["zine", "--source", "site", "--threads", "4", "build"], Some("build"))]
+    #[test_case(&["zine", "--sandbox", "publish", "--dry-run"], Some("publish"))]
+    #[test_case(&["zine", "--source=site"], None)]
+    #[test_case(&["zine", "--", "build"], None)]
+    fn test_subcommand_name(args: &[&str], expected: Option<&str>) {
+        assert_eq!(
+            subcommand_name(args.iter().map(Into::into)),
+            expected.map(Into::into)
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+
+use crate::{
+    engine,
+    entity::{normalize_author_id, validate_author_id, Zine},
+    markdown::{self, Reference},
+};
+
+/// A problem found by `zine lint`, with the file and line context.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Problem {
+    pub file: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+/// Check the markdown files of the `zine` in `source` for the broken internal links,
+/// the missing static files, the unknown author mentions and the undeclared topics.
+pub fn lint(source: &Path, zine: &Zine) -> Result<Vec<Problem>> {
+    let linter = Linter::new(source, zine);
+    let mut problems = vec![];
+    for file in linter.markdown_files() {
+        let markdown = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read `{}`", file.display()))?;
+        for (line, reference) in markdown::references(&markdown) {
+            let message = match &reference {
+                Reference::Link(url) | Reference::Image(url) => linter.check_url(url),
+                Reference::Code(code) => linter.check_code(code),
+            };
+            if let Some(message) = message {
+                problems.push(Problem {
+                    file: file.clone(),
+                    line,
+                    message,
+                });
+            }
+        }
+    }
+    problems.extend(linter.check_topics());
+    problems.sort();
+    Ok(problems)
+}
+
+struct Linter<'a> {
+    source: &'a Path,
+    zine: &'a Zine,
+    // The url paths of the build without the leading and trailing slashes.
+    paths: BTreeSet<String>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(source: &'a Path, zine: &'a Zine) -> Self {
+        let paths = zine
+            .output_paths()
+            .into_iter()
+            .map(|(path, _)| path.trim_matches('/').to_owned())
+            .collect();
+        Linter {
+            source,
+            zine,
+            paths,
+        }
+    }
+
+    // The markdown files of the issue intros, articles (including translations) and pages.
+    fn markdown_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![];
+        for issue in &self.zine.issues {
+            let dir = self.source.join(crate::ZINE_CONTENT_DIR).join(&issue.dir);
+            let intro = dir.join(crate::ZINE_INTRO_FILE);
+            if intro.exists() {
+                files.push(intro);
+            }
+            for article in &issue.articles {
+                files.push(dir.join(&article.meta.file));
+                files.extend(
+                    article
+                        .i18n
+                        .values()
+                        .map(|translation| dir.join(&translation.meta.file)),
+                );
+            }
+        }
+        for page in self.zine.pages.iter().chain(&self.zine.not_found_page) {
+            files.push(self.source.join("pages").join(&page.file_path));
+        }
+        files
+    }
+
+    // Check the link or image `url`, return the problem if it's broken.
+    fn check_url(&self, url: &str) -> Option<String> {
+        let site_url = self.zine.site.url.trim_end_matches('/');
+        let path = match url.strip_prefix(site_url) {
+            Some(path) if !site_url.is_empty() && (path.is_empty() || path.starts_with('/')) => {
+                path
+            }
+            _ if url.starts_with('/') && !url.starts_with("//") => url,
+            // The external and relative urls.
+            _ => return None,
+        };
+        let path = path
+            .split(['#', '?'])
+            .next()
+            .unwrap_or_default()
+            .trim_matches('/');
+        if let Some(file) = path.strip_prefix("static/") {
+            let exists = self.source.join(path).is_file()
+                || engine::is_builtin_static(file)
+                || self
+                    .zine
+                    .theme
+                    .loaded_package
+                    .as_ref()
+                    .and_then(|package| package.static_dir.as_ref())
+                    .is_some_and(|dir| dir.join(file).is_file());
+            return (!exists).then(|| format!("missing static file `{url}`"));
+        }
+        (!self.is_known_path(path)).then(|| format!("broken internal link `{url}`"))
+    }
+
+    fn is_known_path(&self, path: &str) -> bool {
+        // The paginated pages, e.g. `/issue-1/page/2`.
+        let path = match path.rsplit_once("page/") {
+            Some((base, number)) if number.parse::<usize>().is_ok() => base.trim_end_matches('/'),
+            _ => path,
+        };
+        // The previous builds of the versioned archive.
+        self.paths.contains(path) || (self.zine.site.versioned_archive && path.starts_with("v/"))
+    }
+
+    // Check the inline `code` which mentions an author, article or topic.
+    fn check_code(&self, code: &str) -> Option<String> {
+        if let Some(id) = code.strip_prefix('@') {
+            let id = normalize_author_id(id);
+            if validate_author_id(&id).is_ok() && !self.zine.authors.contains_key(&id) {
+                return Some(format!("unknown author `{code}`"));
+            }
+        } else if let Some(topic) = code.strip_prefix('#') {
+            let is_topic_like = !topic.is_empty()
+                && topic
+                    .chars()
+                    .all(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_'));
+            if is_topic_like && !self.is_declared_topic(topic) {
+                return Some(format!("undeclared topic `{code}`"));
+            }
+        } else if code.starts_with('/') && !code.contains(char::is_whitespace) {
+            // Only the codes under an issue are article mentions, e.g. not `/usr/bin`.
+            let first_segment = code.trim_start_matches('/').split('/').next();
+            let mentions_issue = self
+                .zine
+                .issues
+                .iter()
+                .any(|issue| Some(issue.slug.as_str()) == first_segment);
+            if mentions_issue && !self.is_known_path(code.trim_matches('/')) {
+                return Some(format!("broken article mention `{code}`"));
+            }
+        }
+        None
+    }
+
+    fn is_declared_topic(&self, topic: &str) -> bool {
+        self.zine
+            .topics
+            .keys()
+            .any(|id| id.eq_ignore_ascii_case(topic))
+    }
+
+    // Check the topics of the articles, which are declared in the issue `zine.toml`.
+    fn check_topics(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+        for issue in &self.zine.issues {
+            let file = self
+                .source
+                .join(crate::ZINE_CONTENT_DIR)
+                .join(&issue.dir)
+                .join(crate::ZINE_FILE);
+            let mut toml = None;
+            for article in &issue.articles {
+                for topic in article
+                    .topics
+                    .iter()
+                    .filter(|topic| !self.is_declared_topic(topic))
+                {
+                    let toml =
+                        toml.get_or_insert_with(|| fs::read_to_string(&file).unwrap_or_default());
+                    let needle = format!("\"{topic}\"");
+                    let line = toml
+                        .lines()
+                        .position(|line| line.contains(&needle))
+                        .map_or(1, |index| index + 1);
+                    problems.push(Problem {
+                        file: file.clone(),
+                        line,
+                        message: format!(
+                            "undeclared topic `{topic}` of article `{}`",
+                            article.meta.title
+                        ),
+                    });
+                }
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use test_case::test_case;
+
+    use crate::fixture::{self, ArticleBuilder, AuthorBuilder, IssueBuilder, ZineBuilder};
+
+    use super::Linter;
+
+    #[test_case("/issue-1/hello", None)]
+    #[test_case("https://zine.example/issue-1/hello#intro", None; "site url")]
+    #[test_case("/issue-1/page/2", None; "pagination")]
+    #[test_case("https://github.com/zineland/zine", None; "external")]
+    #[test_case("/issue-1/missing", Some("broken internal link `/issue-1/missing`"))]
+    #[test_case(
+        "/static/missing.png",
+        Some("missing static file `/static/missing.png`")
+    )]
+    fn test_check_url(url: &str, expected: Option<&str>) {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(IssueBuilder::new(1, "issue-1").article(ArticleBuilder::new("hello", "Hello")))
+            .build();
+        let linter = Linter::new(Path::new("/zine-lint"), &zine);
+        assert_eq!(linter.check_url(url).as_deref(), expected);
+    }
+
+    #[test_case("@alice", None)]
+    #[test_case("@Bob", Some("unknown author `@Bob`"))]
+    #[test_case("#Rust", None)]
+    #[test_case("#web", Some("undeclared topic `#web`"))]
+    #[test_case("#include <stdio.h>", None; "not a topic")]
+    #[test_case("/issue-1/hello", None)]
+    #[test_case("/issue-1/missing", Some("broken article mention `/issue-1/missing`"))]
+    #[test_case("/usr/bin", None; "not a mention")]
+    fn test_check_code(code: &str, expected: Option<&str>) {
+        let _data = fixture::lock();
+        let zine = ZineBuilder::new("Rust Magazine")
+            .author(AuthorBuilder::new("alice"))
+            .topics(&["rust"])
+            .issue(IssueBuilder::new(1, "issue-1").article(ArticleBuilder::new("hello", "Hello")))
+            .build();
+        let linter = Linter::new(Path::new("/zine-lint"), &zine);
+        assert_eq!(linter.check_code(code).as_deref(), expected);
+    }
+}
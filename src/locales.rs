@@ -3,6 +3,8 @@ use std::{fs, path::Path};
 use fluent::{bundle::FluentBundle, FluentArgs, FluentResource, FluentValue};
 use intl_memoizer::concurrent::IntlLangMemoizer;
 
+use crate::entity::SandboxConfig;
+
 static FLUENT_EN: &str = include_str!("../locales/en.ftl");
 static FLUENT_ZH_CN: &str = include_str!("../locales/zh.ftl");
 
@@ -15,7 +17,7 @@ pub struct FluentLoader {
 }
 
 impl FluentLoader {
-    pub fn new(source: &Path, mut locale: &str) -> Self {
+    pub fn new(source: &Path, mut locale: &str, sandbox_config: &SandboxConfig) -> Self {
         let resource = match locale {
             "en" => FluentResource::try_new(FLUENT_EN.to_owned()),
             "zh" => FluentResource::try_new(FLUENT_ZH_CN.to_owned()),
@@ -24,7 +26,9 @@ impl FluentLoader {
                 let file = format!("locales/{}.ftl", locale);
                 let path = source.join(&file);
                 if path.exists() {
-                    let translation = fs::read_to_string(path)
+                    let translation = sandbox_config
+                        .resolve_project_path(source, &file)
+                        .and_then(|path| fs::read_to_string(path).map_err(Into::into))
                         .unwrap_or_else(|err| panic!("{file} read failed: {}", err));
                     FluentResource::try_new(translation)
                 } else {
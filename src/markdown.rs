@@ -1,4 +1,8 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use anyhow::Result;
 use genkit::{CodeBlock, MarkdownVisitor};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
 
 use crate::{
     code_blocks::{AuthorCode, InlineLink},
@@ -30,7 +34,8 @@ impl MarkdownVisitor for ZineMarkdownVisitor {
         } else if let Some(topic) = code.strip_prefix('#') {
             let data = data::read();
             if data.is_valid_topic(topic) {
-                let html = format!(r#"<a href="/topic/{topic}">#{topic}</a>"#);
+                let slug = data.get_topic_slug(topic);
+                let html = format!(r#"<a href="/topic/{slug}">#{topic}</a>"#);
                 return Some(html);
             }
         }
@@ -38,3 +43,496 @@ impl MarkdownVisitor for ZineMarkdownVisitor {
         None
     }
 }
+
+/// Get the teaser of the `markdown`, which is the content before
+/// the `<!-- more -->` marker line, if any.
+pub fn split_teaser(markdown: &str) -> Option<&str> {
+    // The marker is a top-level html block, so the markers
+    // inside the code blocks, lists or paragraphs are ignored.
+    let mut depth = 0;
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            Event::Html(html) if depth == 0 && is_more_marker(&html) => {
+                return Some(markdown[..range.start].trim_end());
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Check whether the `line` is the `<!-- more -->` marker.
+pub fn is_more_marker(line: &str) -> bool {
+    line.trim()
+        .strip_prefix("<!--")
+        .and_then(|rest| rest.strip_suffix("-->"))
+        .map(|inner| inner.trim().eq_ignore_ascii_case("more"))
+        .unwrap_or_default()
+}
+
+/// Get the value of the `<!-- name: value -->` directive line in the `markdown`, if any.
+pub fn comment_directive<'a>(markdown: &'a str, name: &str) -> Option<&'a str> {
+    markdown.lines().find_map(|line| {
+        let (key, value) = line
+            .trim()
+            .strip_prefix("<!--")?
+            .strip_suffix("-->")?
+            .split_once(':')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+/// Get the urls of images which have no alt text in the `markdown`.
+pub fn images_without_alt(markdown: &str) -> Vec<String> {
+    let mut images = vec![];
+    // The url and alt text of the image being visited.
+    let mut current: Option<(String, String)> = None;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Image(_, url, _)) => current = Some((url.to_string(), String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, alt)) = current.as_mut() {
+                    alt.push_str(&text);
+                }
+            }
+            Event::End(Tag::Image(..)) => {
+                if let Some((url, alt)) = current.take() {
+                    if alt.trim().is_empty() {
+                        images.push(url);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    images
+}
+
+/// Get the distinct lowercase names of the raw HTML tags in the `markdown`,
+/// e.g. `iframe` of `<iframe src="..."></iframe>`. The closing tags and comments are ignored.
+pub fn raw_html_tags(markdown: &str) -> Vec<String> {
+    let mut tags = Vec::<String>::new();
+    for event in Parser::new(markdown) {
+        let Event::Html(html) = event else {
+            continue;
+        };
+        for part in html.split('<').skip(1) {
+            let name = part
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+                .collect::<String>()
+                .to_ascii_lowercase();
+            // The closing tags, comments and declarations have no leading name.
+            if !name.is_empty() && !tags.contains(&name) {
+                tags.push(name);
+            }
+        }
+    }
+    tags
+}
+
+/// Find the `markers` (e.g. `TODO`) in the text of `markdown`, the code is excluded.
+///
+/// The markers are matched case-insensitively as whole words,
+/// return the 1-based line number and marker pairs.
+pub fn find_markers(markdown: &str, markers: &[String]) -> Vec<(usize, String)> {
+    let markers = markers
+        .iter()
+        .filter(|marker| !marker.is_empty())
+        .map(|marker| (marker, marker.to_lowercase()))
+        .collect::<Vec<_>>();
+    let mut found = Vec::new();
+    let mut in_code = false;
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        let text = match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code = true;
+                continue;
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code = false;
+                continue;
+            }
+            Event::Text(text) if !in_code => text.to_lowercase(),
+            _ => continue,
+        };
+        let line = markdown[..range.start].matches('\n').count() + 1;
+        for (marker, needle) in &markers {
+            let mut start = 0;
+            while let Some(index) = text[start..].find(needle.as_str()) {
+                let (begin, end) = (start + index, start + index + needle.len());
+                let is_word = !text[..begin]
+                    .chars()
+                    .next_back()
+                    .map_or(false, char::is_alphanumeric)
+                    && !text[end..]
+                        .chars()
+                        .next()
+                        .map_or(false, char::is_alphanumeric);
+                if is_word {
+                    found.push((
+                        line + text[..begin].matches('\n').count(),
+                        marker.to_string(),
+                    ));
+                }
+                start = end;
+            }
+        }
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// A url or inline code referenced by the markdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reference {
+    /// The url of a link.
+    Link(String),
+    /// The url of an image.
+    Image(String),
+    /// The inline code, which may mention an author, article or topic, e.g. `` `@alice` ``.
+    Code(String),
+}
+
+/// Get the links, images and inline codes of the `markdown` with their line numbers.
+pub fn references(markdown: &str) -> Vec<(usize, Reference)> {
+    Parser::new(markdown)
+        .into_offset_iter()
+        .filter_map(|(event, range)| {
+            let reference = match event {
+                Event::Start(Tag::Link(_, url, _)) => Reference::Link(url.to_string()),
+                Event::Start(Tag::Image(_, url, _)) => Reference::Image(url.to_string()),
+                Event::Code(code) => Reference::Code(code.to_string()),
+                _ => return None,
+            };
+            let line = markdown[..range.start].matches('\n').count() + 1;
+            Some((line, reference))
+        })
+        .collect()
+}
+
+/// Get the plain text of the `markdown`, without any markup.
+pub fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(content) | Event::Code(content) => text.push_str(&content),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push('\n'),
+            _ => {}
+        }
+    }
+    text
+}
+
+// The reading speeds, in words and CJK characters per minute.
+const WORDS_PER_MINUTE: usize = 200;
+const CJK_CHARS_PER_MINUTE: usize = 350;
+
+/// The word count and estimated reading time of a text, see [`reading_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadingStats {
+    pub word_count: usize,
+    /// The estimated reading time in minutes, at least 1 minute for any non-empty text.
+    pub reading_time: usize,
+}
+
+/// Count the words of the plain `text` and estimate the reading time.
+///
+/// Each CJK character counts as a word, which is read faster than the other words.
+pub fn reading_stats(text: &str) -> ReadingStats {
+    let (mut words, mut cjk_chars) = (0, 0);
+    let mut in_word = false;
+    for ch in text.chars() {
+        if is_cjk(ch) {
+            cjk_chars += 1;
+            in_word = false;
+        } else if ch.is_alphanumeric() {
+            if !in_word {
+                words += 1;
+            }
+            in_word = true;
+        } else if !(in_word && matches!(ch, '\'' | '’' | '-')) {
+            // The apostrophes and hyphens inside a word, e.g. `don't`, `well-known`.
+            in_word = false;
+        }
+    }
+    let seconds = words * 60 / WORDS_PER_MINUTE + cjk_chars * 60 / CJK_CHARS_PER_MINUTE;
+    let word_count = words + cjk_chars;
+    ReadingStats {
+        word_count,
+        reading_time: if word_count == 0 {
+            0
+        } else {
+            seconds.div_ceil(60).max(1)
+        },
+    }
+}
+
+// Whether the `ch` is a Chinese or Japanese character, which is a word by itself.
+fn is_cjk(ch: char) -> bool {
+    matches!(ch,
+        '\u{3040}'..='\u{30ff}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4dbf}' // CJK Unified Ideographs Extension A
+        | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+        | '\u{f900}'..='\u{faff}' // CJK Compatibility Ideographs
+    )
+}
+
+/// Get the first `count` sentences of the plain `text`, the whitespaces are collapsed.
+pub fn first_sentences(text: &str, count: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut end = 0;
+    let mut sentences = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        if sentences == count {
+            break;
+        }
+        let next = chars.peek().map(|(_, next)| *next);
+        let is_end = match ch {
+            // The CJK punctuations end a sentence without a following space.
+            '。' | '！' | '？' => true,
+            '.' | '!' | '?' => next.map_or(true, char::is_whitespace),
+            _ => false,
+        };
+        if is_end || next.is_none() {
+            sentences += 1;
+            end = index + ch.len_utf8();
+        }
+    }
+    text[..end].to_owned()
+}
+
+/// Whether the fenced block `name` can be handled by a `blocks/<name>.jinja` template.
+pub fn is_block_name(name: &str) -> bool {
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Replace the fenced blocks of `markdown` with the html returned by `render`,
+/// which receives the block name, options and body, e.g. `recipe`,
+/// `{"servings": "2"}` and the body of a ```` ```recipe servings=2 ```` block.
+///
+/// The block is kept as is if `render` returns `None`. The html is kept as a single
+/// html block of markdown, whose blank lines are closed with an empty comment,
+/// so the html is never parsed as markdown, e.g. the indented lines as code.
+pub fn replace_fenced_blocks<'a, F>(markdown: &'a str, mut render: F) -> Result<Cow<'a, str>>
+where
+    F: FnMut(&str, &BTreeMap<String, String>, &str) -> Option<Result<String>>,
+{
+    let mut replaced = String::new();
+    let mut offset = 0;
+    // The name, options, body and start offset of the fenced block being visited.
+    let mut current: Option<(String, BTreeMap<String, String>, String, usize)> = None;
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                if let Some((name, options)) = parse_block_info(&info) {
+                    current = Some((name, options, String::new(), range.start));
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, _, body, _)) = current.as_mut() {
+                    body.push_str(&text);
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                let Some((name, options, body, start)) = current.take() else {
+                    continue;
+                };
+                if let Some(html) = render(&name, &options, &body).transpose()? {
+                    replaced.push_str(&markdown[offset..start]);
+                    replaced.push_str("\n\n");
+                    for line in html.lines() {
+                        replaced.push_str(line);
+                        // The html block of markdown ends at the first blank line.
+                        if line.trim().is_empty() {
+                            replaced.push_str("<!-- -->");
+                        }
+                        replaced.push('\n');
+                    }
+                    replaced.push('\n');
+                    offset = range.end;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if offset == 0 {
+        Ok(Cow::Borrowed(markdown))
+    } else {
+        replaced.push_str(&markdown[offset..]);
+        Ok(Cow::Owned(replaced))
+    }
+}
+
+// Parse the info string of a fenced block into the name and options, either
+// `recipe servings=2 vegan` or the shortcode style `youtube, id: abc, start: 10`.
+fn parse_block_info(info: &str) -> Option<(String, BTreeMap<String, String>)> {
+    let info = info.trim();
+    let end = info
+        .find(|c: char| c.is_whitespace() || c == ',')
+        .unwrap_or(info.len());
+    let (name, rest) = info.split_at(end);
+    if name.is_empty() {
+        return None;
+    }
+    let options = match rest.trim_start().strip_prefix(',') {
+        Some(rest) => rest
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| match part.split_once([':', '=']) {
+                Some((key, value)) => (
+                    key.trim().to_owned(),
+                    value.trim().trim_matches('"').to_owned(),
+                ),
+                None => (part.to_owned(), String::from("true")),
+            })
+            .collect(),
+        None => parse_block_options(rest.split_whitespace()),
+    };
+    Some((name.to_owned(), options))
+}
+
+// Parse the `key=value` options of a fenced block, a bare `key` means `key=true`.
+fn parse_block_options<'a>(parts: impl Iterator<Item = &'a str>) -> BTreeMap<String, String> {
+    parts
+        .map(|part| match part.split_once('=') {
+            Some((key, value)) => (key.to_owned(), value.trim_matches('"').to_owned()),
+            None => (part.to_owned(), String::from("true")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{
+        comment_directive, find_markers, images_without_alt, raw_html_tags, reading_stats,
+        references, replace_fenced_blocks, split_teaser, ReadingStats, Reference,
+    };
+
+    #[test_case("![cat](/cat.png)", &[])]
+    #[test_case("![](/cat.png)", &["/cat.png"])]
+    #[test_case("![ ](/cat.png \"A cat\")", &["/cat.png"])]
+    #[test_case("![**cat**](/cat.png) and ![](/dog.png)", &["/dog.png"])]
+    #[test_case("`![](/cat.png)`", &[])]
+    fn test_images_without_alt(markdown: &str, expected: &[&str]) {
+        assert_eq!(images_without_alt(markdown), expected);
+    }
+
+    #[test_case("Plain **text** and `<code>`", &[])]
+    #[test_case("<!-- comment -->\n\nText", &[])]
+    #[test_case("<iframe src=\"/video\"></iframe>\n\nText <sup>1</sup>", &["iframe", "sup"])]
+    #[test_case("<DIV>\n<style>p {}</style>\n</DIV>", &["div", "style"])]
+    fn test_raw_html_tags(markdown: &str, expected: &[&str]) {
+        assert_eq!(raw_html_tags(markdown), expected);
+    }
+
+    #[test_case("Done.", &[])]
+    #[test_case("Intro\n\nTODO: add *more*\nfixme", &[(3, "TODO"), (4, "FIXME")])]
+    #[test_case("Lorem ipsum dolor\n\nA todolist and `TODO`", &[(1, "lorem ipsum")])]
+    #[test_case("```\n// TODO\n```\n\nText", &[])]
+    fn test_find_markers(markdown: &str, expected: &[(usize, &str)]) {
+        let markers = ["TODO", "FIXME", "lorem ipsum"].map(String::from);
+        let expected = expected
+            .iter()
+            .map(|(line, marker)| (*line, marker.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(find_markers(markdown, &markers), expected);
+    }
+
+    #[test_case("", 0, 0; "empty")]
+    #[test_case("Hello, world! Don't panic.", 4, 1)]
+    #[test_case("你好，世界", 4, 1; "chinese")]
+    #[test_case("Rust 是一门语言", 6, 1; "mixed")]
+    fn test_reading_stats(text: &str, word_count: usize, reading_time: usize) {
+        assert_eq!(
+            reading_stats(text),
+            ReadingStats {
+                word_count,
+                reading_time
+            }
+        );
+    }
+
+    #[test]
+    fn test_reading_time() {
+        assert_eq!(reading_stats(&"word ".repeat(1000)).reading_time, 5);
+        assert_eq!(reading_stats(&"字".repeat(700)).reading_time, 2);
+    }
+
+    #[test]
+    fn test_references() {
+        let markdown = "Hi `@alice`,\n\nsee [the post](/issue-1/hello#intro)\nand ![cat](/static/cat.png).\n\n```\n`@bob`\n```";
+        assert_eq!(
+            references(markdown),
+            vec![
+                (1, Reference::Code(String::from("@alice"))),
+                (3, Reference::Link(String::from("/issue-1/hello#intro"))),
+                (4, Reference::Image(String::from("/static/cat.png"))),
+            ]
+        );
+    }
+
+    #[test_case("Intro\n\n<!-- more -->\n\nRest", Some("Intro"))]
+    #[test_case("Intro\n<!--more-->\nRest", Some("Intro"))]
+    #[test_case("Intro\n  <!-- MORE -->  \nRest", Some("Intro"))]
+    #[test_case("Intro <!-- more --> Rest", None)]
+    #[test_case("Intro\n\nRest", None)]
+    #[test_case("Intro\n\n```html\n<!-- more -->\n```\n\nRest", None)]
+    #[test_case("Intro\n\n    <!-- more -->\n\nRest", None)]
+    #[test_case(
+        "```\n<!-- more -->\n```\n\nIntro\n\n<!-- more -->\n\nRest",
+        Some("```\n<!-- more -->\n```\n\nIntro")
+    )]
+    fn test_split_teaser(markdown: &str, teaser: Option<&str>) {
+        assert_eq!(split_teaser(markdown), teaser);
+    }
+
+    #[test_case("One. Two!  Three?", 2, "One. Two!")]
+    #[test_case("Version 1.0 is out. Try\nit now.", 1, "Version 1.0 is out.")]
+    #[test_case("第一句。第二句！第三句", 2, "第一句。第二句！")]
+    #[test_case("No punctuation", 3, "No punctuation")]
+    #[test_case("One. Two.", 0, "")]
+    fn test_first_sentences(text: &str, count: usize, expected: &str) {
+        assert_eq!(first_sentences(text, count), expected);
+    }
+
+    #[test_case("<!-- expire_date: 2024-01-31 -->\n# Event", Some("2024-01-31"))]
+    #[test_case("# Event\n<!--expire_date:2024-01-31-->", Some("2024-01-31"))]
+    #[test_case("<!-- archive_url: https://archive.example -->", None)]
+    #[test_case("Text <!-- expire_date: 2024-01-31 --> text", None)]
+    fn test_comment_directive(markdown: &str, expected: Option<&str>) {
+        assert_eq!(comment_directive(markdown, "expire_date"), expected);
+    }
+
+    #[test_case("Intro\n\n```rust\nfn main() {}\n```\n", "Intro\n\n```rust\nfn main() {}\n```\n"; "unknown block")]
+    #[test_case(
+        "Intro\n\n```recipe servings=2 vegan\nFlour\n\n    Eggs\n```\nRest",
+        "Intro\n\n\n\n<div>recipe {\"servings\": \"2\", \"vegan\": \"true\"}</div>\n<pre>\nFlour\n<!-- -->\n    Eggs\n</pre>\n\n\nRest";
+        "template block"
+    )]
+    #[test_case(
+        "```recipe, servings: 2, title: \"Egg tart\", vegan\n```",
+        "\n\n<div>recipe {\"servings\": \"2\", \"title\": \"Egg tart\", \"vegan\": \"true\"}</div>\n<pre>\n</pre>\n\n";
+        "shortcode options"
+    )]
+    fn test_replace_fenced_blocks(markdown: &str, expected: &str) {
+        let replaced = replace_fenced_blocks(markdown, |name, options, body| {
+            (name == "recipe").then(|| {
+                Ok(format!(
+                    "<div>{name} {options:?}</div>\n<pre>\n{body}</pre>"
+                ))
+            })
+        })
+        .unwrap();
+        assert_eq!(replaced, expected);
+    }
+}
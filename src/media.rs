@@ -0,0 +1,160 @@
+use std::{collections::HashMap, fs, io::Read, path::Path, thread};
+
+use anyhow::{anyhow, Result};
+use genkit::helpers;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+use crate::{exif, output};
+
+// The directory (relative to the zine root) to cache the downloaded media files.
+static MEDIA_CACHE_DIR: &str = ".zine-cache/media";
+// The url prefix of localized media files.
+static MEDIA_URL_PREFIX: &str = "/static/media";
+
+// Remote url and localized url pairs.
+static LOCALIZED_URLS: OnceCell<Mutex<HashMap<String, String>>> = OnceCell::new();
+
+/// Download the remote media `url` into the cache directory,
+/// then rewrite the `url` to the local path.
+///
+/// The `url` keeps untouched if it isn't a remote url or the download failed.
+pub fn localize(source: &Path, url: &mut Option<String>) {
+    let Some(remote_url) = url.as_deref() else {
+        return;
+    };
+    if !remote_url.starts_with("http://") && !remote_url.starts_with("https://") {
+        return;
+    }
+
+    let localized_urls = LOCALIZED_URLS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(local_url) = { localized_urls.lock().get(remote_url).cloned() } {
+        *url = Some(local_url);
+        return;
+    }
+
+    let file_name = cache_file_name(remote_url);
+    let cache_file = source.join(MEDIA_CACHE_DIR).join(&file_name);
+    if !cache_file.exists() {
+        // Nothing is downloaded in a dry run.
+        if output::is_dry_run() {
+            return;
+        }
+        if let Err(err) = download(remote_url, &cache_file) {
+            println!("Warning: failed to download `{remote_url}`: {err}");
+            return;
+        }
+    }
+
+    let local_url = format!("{MEDIA_URL_PREFIX}/{file_name}");
+    localized_urls
+        .lock()
+        .insert(remote_url.to_owned(), local_url.clone());
+    *url = Some(local_url);
+}
+
+/// Copy all cached media files into the `static/media` directory of `dest`.
+pub fn copy_localized_media(source: &Path, dest: &Path, strip_metadata: bool) -> Result<()> {
+    let cache_dir = source.join(MEDIA_CACHE_DIR);
+    if !cache_dir.exists() {
+        return Ok(());
+    }
+
+    let media_dir = dest.join(MEDIA_URL_PREFIX.trim_start_matches('/'));
+    for entry in fs::read_dir(cache_dir)? {
+        let path = entry?.path();
+        if let Some(file_name) = path.file_name() {
+            if strip_metadata {
+                copy_stripped(&path, &media_dir.join(file_name))?;
+            } else {
+                output::copy(&path, &media_dir.join(file_name))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Copy the image `from` into `to`, with the metadata stripped if it's a JPEG or PNG.
+pub fn copy_stripped(from: &Path, to: &Path) -> Result<()> {
+    let image = fs::read(from)?;
+    match exif::strip_metadata(&image) {
+        Some(stripped) => output::write(to, stripped),
+        None => output::copy(from, to),
+    }
+}
+
+/// Download the remote `url` into the `file`, the parent directories are created if missing.
+pub fn download(url: &str, file: &Path) -> Result<()> {
+    let owned_url = url.to_owned();
+    // Run in a standalone thread, since we may be in either
+    // a rayon thread or a tokio runtime thread.
+    let bytes = thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(async {
+                let mut reader = helpers::fetch_url(&owned_url).await?;
+                let mut bytes = vec![];
+                reader.read_to_end(&mut bytes)?;
+                anyhow::Ok(bytes)
+            })
+    })
+    .join()
+    .map_err(|_| anyhow!("download thread panicked"))??;
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write to a temporary file first to avoid leaving a broken file.
+    let temp_file = file.with_extension("download");
+    fs::write(&temp_file, bytes)?;
+    fs::rename(temp_file, file)?;
+    Ok(())
+}
+
+// The cache file name is the hash of url, with the original extension if any.
+fn cache_file_name(url: &str) -> String {
+    // FNV-1a hash, which is stable across builds.
+    let hash = url.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    match url_extension(url) {
+        Some(ext) => format!("{hash:016x}.{ext}"),
+        None => format!("{hash:016x}"),
+    }
+}
+
+/// The lowercase file extension of the `url` path, if any, e.g. `png`.
+pub fn url_extension(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match path
+        .rsplit_once('/')
+        .and_then(|(_, name)| name.rsplit_once('.'))
+    {
+        Some((_, ext))
+            if !ext.is_empty() && ext.len() <= 5 && ext.chars().all(char::is_alphanumeric) =>
+        {
+            Some(ext.to_lowercase())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::cache_file_name;
+
+    #[test_case("https://example.com/avatar.png", Some("png"))]
+    #[test_case("https://example.com/avatar.JPG?size=64", Some("jpg"))]
+    #[test_case("https://example.com/a.b/avatar", None)]
+    #[test_case("https://example.com/avatar", None)]
+    #[test_case("https://example.com/u/123?v=4", None)]
+    fn test_cache_file_name(url: &str, ext: Option<&str>) {
+        let name = cache_file_name(url);
+        assert_eq!(name.split_once('.').map(|(_, ext)| ext), ext);
+        assert_eq!(name, cache_file_name(url));
+    }
+}
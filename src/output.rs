@@ -0,0 +1,304 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context as _, Result};
+use flate2::{write::GzEncoder, Compression};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use walkdir::WalkDir;
+
+/// The environment variable to write the built site into a tar archive
+/// rather than the dest directory, e.g. `ZINE_BUILD_ARCHIVE=site.tar.gz zine build`,
+/// which is set by the global `--archive` argument.
+pub static BUILD_ARCHIVE_ENV: &str = "ZINE_BUILD_ARCHIVE";
+
+// The current output backend and the dest directory it writes for.
+static OUTPUT: Lazy<RwLock<Option<(PathBuf, Box<dyn Output>)>>> = Lazy::new(|| RwLock::new(None));
+// Whether the current build is a dry run, see [`set_dry_run`].
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// The output backend which the generated site is written into.
+pub trait Output: Send + Sync {
+    /// Write `content` into the `path` relative to the site root.
+    fn write(&self, path: &Path, content: &[u8]) -> Result<()>;
+
+    /// Copy the file `from` into the `path` relative to the site root.
+    fn copy(&self, from: &Path, path: &Path) -> Result<()> {
+        self.write(path, &fs::read(from)?)
+    }
+
+    /// Whether the files are written into the dest directory.
+    fn is_dir(&self) -> bool {
+        false
+    }
+
+    /// Flush all the written files.
+    fn finish(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// The recorded paths if the files are recorded rather than written.
+    fn recorded_paths(&self) -> Option<Vec<PathBuf>> {
+        None
+    }
+}
+
+/// Write files into a directory, which is the default output.
+pub struct DirOutput {
+    root: PathBuf,
+}
+
+impl Output for DirOutput {
+    fn write(&self, path: &Path, content: &[u8]) -> Result<()> {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write `{}`", path.display()))
+    }
+
+    fn copy(&self, from: &Path, path: &Path) -> Result<()> {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, &path).with_context(|| format!("Failed to copy `{}`", from.display()))?;
+        Ok(())
+    }
+
+    fn is_dir(&self) -> bool {
+        true
+    }
+}
+
+/// Stream files into a tar archive, which is gzip compressed
+/// if the file name ends with `.gz` or `.tgz`.
+pub struct TarOutput {
+    builder: Mutex<Option<tar::Builder<ArchiveWriter>>>,
+    // The modification time of all files, in unix timestamp.
+    mtime: u64,
+}
+
+enum ArchiveWriter {
+    Plain(fs::File),
+    Gzip(GzEncoder<fs::File>),
+}
+
+impl Write for ArchiveWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            ArchiveWriter::Plain(file) => file.write(buf),
+            ArchiveWriter::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ArchiveWriter::Plain(file) => file.flush(),
+            ArchiveWriter::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+impl TarOutput {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create `{}`", path.display()))?;
+        let name = path.to_string_lossy();
+        let writer = if name.ends_with(".gz") || name.ends_with(".tgz") {
+            ArchiveWriter::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            ArchiveWriter::Plain(file)
+        };
+        Ok(TarOutput {
+            builder: Mutex::new(Some(tar::Builder::new(writer))),
+            mtime: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl Output for TarOutput {
+    fn write(&self, path: &Path, content: &[u8]) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(self.mtime);
+        header.set_cksum();
+        self.builder
+            .lock()
+            .as_mut()
+            .context("The archive is already finished")?
+            .append_data(&mut header, path, content)?;
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        if let Some(builder) = self.builder.lock().take() {
+            match builder.into_inner()? {
+                ArchiveWriter::Plain(mut file) => file.flush()?,
+                ArchiveWriter::Gzip(encoder) => encoder.finish()?.flush()?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Record the paths of the files without writing anything, which is
+/// the output of `zine build --dry-run`.
+#[derive(Default)]
+pub struct DryRunOutput {
+    paths: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl Output for DryRunOutput {
+    fn write(&self, path: &Path, _content: &[u8]) -> Result<()> {
+        self.paths.lock().insert(path.to_owned());
+        Ok(())
+    }
+
+    fn copy(&self, _from: &Path, path: &Path) -> Result<()> {
+        self.paths.lock().insert(path.to_owned());
+        Ok(())
+    }
+
+    fn recorded_paths(&self) -> Option<Vec<PathBuf>> {
+        Some(self.paths.lock().iter().cloned().collect())
+    }
+}
+
+/// Set the output backend of `dest`, which is the tar archive
+/// in the [`BUILD_ARCHIVE_ENV`] variable if `allow_archive` is true, otherwise the `dest` directory.
+pub fn init(dest: &Path, allow_archive: bool) -> Result<()> {
+    let output: Box<dyn Output> = match std::env::var_os(BUILD_ARCHIVE_ENV) {
+        Some(archive) if allow_archive && !archive.is_empty() => {
+            Box::new(TarOutput::create(Path::new(&archive))?)
+        }
+        _ => Box::new(DirOutput {
+            root: dest.to_owned(),
+        }),
+    };
+    *OUTPUT.write() = Some((dest.to_owned(), output));
+    Ok(())
+}
+
+/// Set the dry-run output backend of `dest`, which records the paths only.
+pub fn init_dry_run(dest: &Path) {
+    set_dry_run(true);
+    *OUTPUT.write() = Some((dest.to_owned(), Box::<DryRunOutput>::default()));
+}
+
+/// Finish the output backend, the tar archive is completed after this call.
+pub fn finish() -> Result<()> {
+    match OUTPUT.write().take() {
+        Some((_, output)) => output.finish(),
+        None => Ok(()),
+    }
+}
+
+/// Whether the site is written into the dest directory, the default output.
+pub fn is_dir() -> bool {
+    OUTPUT
+        .read()
+        .as_ref()
+        .map(|(_, output)| output.is_dir())
+        .unwrap_or(true)
+}
+
+/// The paths relative to the dest directory which are recorded by the dry-run output,
+/// or `None` if the files are really written.
+pub fn recorded_paths() -> Option<Vec<PathBuf>> {
+    OUTPUT
+        .read()
+        .as_ref()
+        .and_then(|(_, output)| output.recorded_paths())
+}
+
+/// Mark the current build as a dry run or not before the entities are parsed,
+/// so the state files are untouched while parsing too, see [`is_dry_run`].
+pub fn set_dry_run(dry_run: bool) {
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+/// Whether nothing is written, see [`DryRunOutput`].
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Write `content` into the file `path`, which is inside the dest directory.
+///
+/// The file outside the dest directory is written directly, except in a dry run.
+pub fn write(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let output = OUTPUT.read();
+    match output.as_ref() {
+        Some((dest, output)) if path.starts_with(dest) => {
+            output.write(path.strip_prefix(dest)?, content.as_ref())
+        }
+        _ if is_dry_run() => Ok(()),
+        _ => DirOutput {
+            root: PathBuf::new(),
+        }
+        .write(path, content.as_ref()),
+    }
+}
+
+/// Copy the file `from` into the file `to`, which is inside the dest directory.
+pub fn copy(from: &Path, to: &Path) -> Result<()> {
+    let output = OUTPUT.read();
+    match output.as_ref() {
+        Some((dest, output)) if to.starts_with(dest) => output.copy(from, to.strip_prefix(dest)?),
+        _ if is_dry_run() => Ok(()),
+        _ => DirOutput {
+            root: PathBuf::new(),
+        }
+        .copy(from, to),
+    }
+}
+
+/// Copy all the files of directory `from` into the directory `to`.
+pub fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            copy(entry.path(), &to.join(entry.path().strip_prefix(from)?))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{DryRunOutput, Output};
+
+    #[test]
+    fn test_dry_run_output() {
+        let output = DryRunOutput::default();
+        output
+            .write(Path::new("issue-1/index.html"), b"<h1>Issue 1</h1>")
+            .unwrap();
+        // Nothing is read from the missing file.
+        output
+            .copy(Path::new("missing.png"), Path::new("static/logo.png"))
+            .unwrap();
+        output.write(Path::new("index.html"), b"").unwrap();
+        output.write(Path::new("index.html"), b"").unwrap();
+        assert_eq!(
+            output.recorded_paths(),
+            Some(vec![
+                PathBuf::from("index.html"),
+                PathBuf::from("issue-1/index.html"),
+                PathBuf::from("static/logo.png"),
+            ])
+        );
+    }
+}
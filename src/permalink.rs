@@ -0,0 +1,151 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{bail, Context as _, Result};
+
+use crate::{entity::Zine, output};
+
+// The published url registry file in the `.zine` directory.
+static REGISTRY_FILE: &str = "permalinks.json";
+// The registry field of `zine-data.json` in the previous versions.
+static LEGACY_REGISTRY_FIELD: &str = "published_urls";
+
+/// Check all the registered urls in `.zine/permalinks.json` are still published or redirected,
+/// then register the currently published urls of `zine`.
+///
+/// The registry is committed along with the source, so the permalinks keep stable
+/// across refactors, e.g. renaming an issue directory or changing the slug policy.
+pub fn check_registry(source: &Path, zine: &Zine) -> Result<()> {
+    let registry_file = source.join(crate::ZINE_STATE_DIR).join(REGISTRY_FILE);
+    let registry = if registry_file.exists() {
+        serde_json::from_str::<BTreeSet<String>>(&fs::read_to_string(&registry_file)?)
+            .with_context(|| format!("Failed to parse `{}`", registry_file.display()))?
+    } else {
+        legacy_registry(source)
+    };
+
+    let published = zine.published_paths();
+    let missing = registry.difference(&published).collect::<Vec<_>>();
+    if !missing.is_empty() {
+        let list = missing
+            .iter()
+            .map(|url| format!("  - {url}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        bail!(
+            "The following published url(s) disappeared, please restore them \
+            or redirect them in the `[redirects]` table of `zine.toml`:\n{list}"
+        );
+    }
+
+    if !published.is_subset(&registry) || !registry_file.exists() {
+        let registry = registry.union(&published).collect::<BTreeSet<_>>();
+        fs::create_dir_all(source.join(crate::ZINE_STATE_DIR))?;
+        fs::write(&registry_file, serde_json::to_string_pretty(&registry)?)?;
+    }
+    Ok(())
+}
+
+// Read the registry of the previous versions from the `published_urls` of `zine-data.json`,
+// which is moved into `.zine/permalinks.json` at the next build.
+fn legacy_registry(source: &Path) -> BTreeSet<String> {
+    fs::read_to_string(source.join(crate::ZINE_DATA_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|data| serde_json::from_value(data.get(LEGACY_REGISTRY_FIELD)?.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Write the redirect pages of the old url path and new url pairs into `dest`,
+/// the root-relative new urls are resolved against the `site_url`.
+pub fn write_redirects(
+    redirects: &BTreeMap<String, String>,
+    site_url: &str,
+    dest: &Path,
+) -> Result<()> {
+    let site_url = site_url.trim_end_matches('/');
+    for (from, to) in redirects {
+        let to = if to.starts_with('/') {
+            format!("{site_url}{to}")
+        } else {
+            to.clone()
+        };
+        output::write(
+            &dest.join(from.trim_matches('/')).join("index.html"),
+            redirect_html(&to),
+        )?;
+    }
+    Ok(())
+}
+
+// The redirect page to the absolute `url`, which isn't indexed by search engines.
+fn redirect_html(url: &str) -> String {
+    let url = url
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Redirecting…</title>
+<link rel="canonical" href="{url}">
+<meta name="robots" content="noindex">
+<meta http-equiv="refresh" content="0; url={url}">
+</head>
+<body><a href="{url}">{url}</a></body>
+</html>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::fixture::{self, ArticleBuilder, IssueBuilder, ZineBuilder};
+
+    use super::{check_registry, redirect_html};
+
+    #[test]
+    fn test_redirect_html() {
+        let html = redirect_html("https://zine.example/a?b=1&c=\"2\"");
+        assert!(html.contains(
+            r#"<meta http-equiv="refresh" content="0; url=https://zine.example/a?b=1&amp;c=&quot;2&quot;">"#
+        ));
+        assert!(html.contains(r#"<meta name="robots" content="noindex">"#));
+    }
+
+    #[test]
+    fn test_check_registry() {
+        let _data = fixture::lock();
+        let source = std::env::temp_dir().join("zine-permalink-registry");
+        let _ = fs::remove_dir_all(&source);
+        fs::create_dir_all(&source).unwrap();
+        // The registry of the previous versions is moved out of `zine-data.json`.
+        let data = r#"{ "published_urls": ["/issue-1/hello"] }"#;
+        fs::write(source.join(crate::ZINE_DATA_FILE), data).unwrap();
+
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(IssueBuilder::new(1, "issue-1").article(ArticleBuilder::new("hello", "Hello")))
+            .build();
+        check_registry(&source, &zine).unwrap();
+        let registry = fs::read_to_string(source.join(".zine/permalinks.json")).unwrap();
+        assert!(registry.contains("/issue-1/hello"));
+        assert_eq!(
+            fs::read_to_string(source.join(crate::ZINE_DATA_FILE)).unwrap(),
+            data
+        );
+
+        let zine = ZineBuilder::new("Rust Magazine")
+            .issue(IssueBuilder::new(1, "issue-1").article(ArticleBuilder::new("world", "World")))
+            .build();
+        let err = check_registry(&source, &zine).unwrap_err();
+        assert!(err.to_string().contains("/issue-1/hello"));
+    }
+}
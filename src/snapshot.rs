@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use time::format_description::well_known::Rfc3339;
+use walkdir::WalkDir;
+
+use crate::engine;
+
+/// The name of manifest file in the snapshot archive.
+pub static MANIFEST_FILE: &str = "manifest.json";
+
+/// The manifest of a site snapshot.
+///
+/// The format is versioned, any incompatible change must bump the
+/// [`SnapshotManifest::FORMAT_VERSION`], so the old snapshots can still be verified.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub format_version: u32,
+    /// The zine version which creates this snapshot.
+    pub zine_version: String,
+    /// The creation time in RFC 3339 format, which is frozen to the `SOURCE_DATE_EPOCH` if present.
+    pub created_at: String,
+    /// The sha256 hash of the root `zine.toml`.
+    pub config_hash: String,
+    /// The raw content of the root `zine.toml`.
+    pub config: String,
+    /// The file path (relative to the build directory) and its sha256 hash pairs.
+    pub files: BTreeMap<String, String>,
+}
+
+impl SnapshotManifest {
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Create the manifest of `build_dir`, with the root `zine.toml` content.
+    pub fn new(build_dir: &Path, config: String) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for entry in WalkDir::new(build_dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry
+                .path()
+                .strip_prefix(build_dir)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let content = fs::read(entry.path())
+                .with_context(|| format!("Failed to read `{}`", entry.path().display()))?;
+            files.insert(path, hash(&content));
+        }
+
+        Ok(SnapshotManifest {
+            format_version: Self::FORMAT_VERSION,
+            zine_version: env!("CARGO_PKG_VERSION").to_owned(),
+            created_at: engine::frozen_now().format(&Rfc3339)?,
+            config_hash: hash(config.as_bytes()),
+            config,
+            files,
+        })
+    }
+
+    /// The content address of this snapshot, which is the hash of the root `zine.toml`
+    /// and the build files only, so the same content always has the same address
+    /// regardless of when and by which zine version it's created.
+    pub fn content_address(&self) -> String {
+        let mut content = format!("{}\n", self.config_hash);
+        for (path, hash) in &self.files {
+            content.push_str(&format!("{path}\0{hash}\n"));
+        }
+        hash(content.as_bytes())
+    }
+}
+
+/// Get the hex-encoded sha256 hash of `content`.
+pub fn hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::SnapshotManifest;
+
+    #[test]
+    fn test_content_address() {
+        let build_dir = std::env::temp_dir().join("zine-snapshot-address");
+        fs::create_dir_all(build_dir.join("issue-1")).unwrap();
+        fs::write(build_dir.join("index.html"), "<h1>Zine</h1>").unwrap();
+        fs::write(build_dir.join("issue-1/index.html"), "<h1>Issue 1</h1>").unwrap();
+        let config = String::from("[site]\nname = \"Zine\"");
+
+        let first = SnapshotManifest::new(&build_dir, config.clone()).unwrap();
+        let mut second = SnapshotManifest::new(&build_dir, config).unwrap();
+        second.created_at = String::from("2000-01-01T00:00:00Z");
+        second.zine_version = String::from("0.0.0");
+        assert_eq!(first.content_address(), second.content_address());
+
+        fs::write(build_dir.join("index.html"), "<h1>Zine!</h1>").unwrap();
+        let changed = SnapshotManifest::new(&build_dir, first.config.clone()).unwrap();
+        assert_ne!(first.content_address(), changed.content_address());
+    }
+}
@@ -0,0 +1,303 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use anyhow::{Context as _, Result};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{entity::SocialCardsConfig, output, snapshot};
+
+// The directory (relative to the zine root) to cache the rendered cards.
+static CARD_CACHE_DIR: &str = ".zine-cache/og";
+// The url prefix of the cards.
+static CARD_URL_PREFIX: &str = "/static/og";
+// The recommended size of the OpenGraph images.
+const CARD_WIDTH: u32 = 1200;
+const CARD_HEIGHT: u32 = 630;
+const CARD_PADDING: f32 = 80.0;
+const TITLE_SIZE: f32 = 72.0;
+const TITLE_MAX_LINES: usize = 4;
+const SUBTITLE_SIZE: f32 = 36.0;
+// The fallback background color if the theme color isn't a hex color.
+const DEFAULT_BACKGROUND: Rgba<u8> = Rgba([37, 99, 235, 255]);
+
+// The cards to write, keyed by the file name.
+static CARDS: OnceCell<Mutex<BTreeMap<String, SocialCard>>> = OnceCell::new();
+
+/// The text of a social card.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SocialCard {
+    pub title: String,
+    /// The author names, joined by commas.
+    pub authors: String,
+    pub issue: String,
+    pub site: String,
+}
+
+/// Register the social `card` to write by [`write_cards`], return the card url,
+/// e.g. `/static/og/<hash>.png`.
+pub fn card_url(card: SocialCard, config: &SocialCardsConfig) -> String {
+    // The file name changes with the text, colors and font, so the cache never goes stale.
+    let key = [
+        card.title.as_str(),
+        card.authors.as_str(),
+        card.issue.as_str(),
+        card.site.as_str(),
+        config.background.as_deref().unwrap_or_default(),
+        config.text_color.as_str(),
+        config.font.as_str(),
+    ]
+    .join("\n");
+    let name = format!("{}.png", &snapshot::hash(key.as_bytes())[..16]);
+    CARDS
+        .get_or_init(|| Mutex::new(BTreeMap::new()))
+        .lock()
+        .insert(name.clone(), card);
+    format!("{CARD_URL_PREFIX}/{name}")
+}
+
+/// Write all the registered cards into the `static/og` directory of `dest`.
+///
+/// The cards are cached in the `.zine-cache/og` directory of the zine `root`.
+pub fn write_cards(root: &Path, dest: &Path, config: &SocialCardsConfig) -> Result<()> {
+    let Some(cards) = CARDS.get() else {
+        return Ok(());
+    };
+    let cards = cards.lock().clone();
+    if cards.is_empty() {
+        return Ok(());
+    }
+    let dry_run = output::is_dry_run();
+    let font = FontVec::try_from_vec(config.font_data.to_vec())
+        .with_context(|| format!("Invalid font of [social_cards]: `{}`", config.font))?;
+    let cache_dir = root.join(CARD_CACHE_DIR);
+    let cards_dir = dest.join(CARD_URL_PREFIX.trim_start_matches('/'));
+    cards.par_iter().try_for_each(|(name, card)| {
+        let cache_file = cache_dir.join(name);
+        // The cards are only recorded in a dry run.
+        if !cache_file.exists() && !dry_run {
+            fs::create_dir_all(&cache_dir)?;
+            render_card(card, config, &font)
+                .save(&cache_file)
+                .with_context(|| format!("Failed to write the social card `{name}`"))?;
+        }
+        output::copy(&cache_file, &cards_dir.join(name))
+    })
+}
+
+// Render the `card` with the site and issue name on the top, the wrapped title
+// in the middle and the authors on the bottom.
+fn render_card(card: &SocialCard, config: &SocialCardsConfig, font: &FontVec) -> RgbaImage {
+    let background = config
+        .background
+        .as_deref()
+        .and_then(parse_color)
+        .unwrap_or(DEFAULT_BACKGROUND);
+    let text_color = parse_color(&config.text_color).unwrap_or(Rgba([255, 255, 255, 255]));
+    let mut subtitle_color = text_color;
+    subtitle_color.0[3] = 200;
+    let mut image = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, background);
+    let max_width = CARD_WIDTH as f32 - CARD_PADDING * 2.0;
+
+    let header = [card.site.as_str(), card.issue.as_str()]
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" · ");
+    let header = wrap_text(font, SUBTITLE_SIZE, &header, max_width, 1);
+    if let Some(header) = header.first() {
+        draw_text(
+            &mut image,
+            font,
+            SUBTITLE_SIZE,
+            subtitle_color,
+            (CARD_PADDING, CARD_PADDING),
+            header,
+        );
+    }
+
+    let lines = wrap_text(font, TITLE_SIZE, &card.title, max_width, TITLE_MAX_LINES);
+    let line_height = TITLE_SIZE * 1.25;
+    // Center the title vertically.
+    let mut y = (CARD_HEIGHT as f32 - line_height * lines.len() as f32) / 2.0;
+    for line in &lines {
+        draw_text(
+            &mut image,
+            font,
+            TITLE_SIZE,
+            text_color,
+            (CARD_PADDING, y),
+            line,
+        );
+        y += line_height;
+    }
+
+    let authors = wrap_text(font, SUBTITLE_SIZE, &card.authors, max_width, 1);
+    if let Some(authors) = authors.first() {
+        draw_text(
+            &mut image,
+            font,
+            SUBTITLE_SIZE,
+            subtitle_color,
+            (
+                CARD_PADDING,
+                CARD_HEIGHT as f32 - CARD_PADDING - SUBTITLE_SIZE,
+            ),
+            authors,
+        );
+    }
+    image
+}
+
+// The advance width of the `text` in the font `size`.
+fn text_width(font: &FontVec, size: f32, text: &str) -> f32 {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for ch in text.chars() {
+        let id = font.glyph_id(ch);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+// Wrap the `text` into at most `max_lines` lines not wider than `max_width`,
+// breaking at the whitespaces if possible, otherwise at any character (e.g. CJK).
+// The last line ends with an ellipsis if the text is truncated.
+fn wrap_text(
+    font: &FontVec,
+    size: f32,
+    text: &str,
+    max_width: f32,
+    max_lines: usize,
+) -> Vec<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for ch in text.chars() {
+        line.push(ch);
+        if text_width(font, size, &line) <= max_width {
+            continue;
+        }
+        line.pop();
+        // Keep the last word in the next line, unless the line ends at a whitespace.
+        let rest = match line.rfind(' ').filter(|_| ch != ' ') {
+            Some(index) => {
+                let rest = line[index + 1..].to_owned();
+                line.truncate(index);
+                rest
+            }
+            None => String::new(),
+        };
+        lines.push(std::mem::take(&mut line));
+        line = rest;
+        if ch != ' ' {
+            line.push(ch);
+        }
+        if lines.len() == max_lines {
+            break;
+        }
+    }
+    if lines.len() < max_lines {
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    } else if let Some(last) = lines.last_mut() {
+        // Truncated, replace the tail with an ellipsis.
+        while !last.is_empty() && text_width(font, size, &format!("{last}…")) > max_width {
+            last.pop();
+        }
+        *last = format!("{}…", last.trim_end());
+    }
+    lines
+}
+
+// Draw the single line `text` whose top left corner is at `position`.
+fn draw_text(
+    image: &mut RgbaImage,
+    font: &FontVec,
+    size: f32,
+    color: Rgba<u8>,
+    position: (f32, f32),
+    text: &str,
+) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let (mut x, y) = position;
+    let baseline = y + scaled.ascent();
+    let mut previous = None;
+    for ch in text.chars() {
+        let id = scaled.glyph_id(ch);
+        if let Some(previous) = previous {
+            x += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(size, point(x, baseline));
+        x += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= image.width() as i64 || py >= image.height() as i64 {
+                return;
+            }
+            let pixel = image.get_pixel_mut(px as u32, py as u32);
+            let alpha = coverage.clamp(0.0, 1.0) * color.0[3] as f32 / 255.0;
+            for channel in 0..3 {
+                let blended =
+                    pixel.0[channel] as f32 * (1.0 - alpha) + color.0[channel] as f32 * alpha;
+                pixel.0[channel] = blended.round() as u8;
+            }
+        });
+    }
+}
+
+/// Parse the hex color, e.g. `#1e293b` or `#fff`.
+pub fn parse_color(color: &str) -> Option<Rgba<u8>> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |index: usize, len: usize| {
+        let value = u8::from_str_radix(&hex[index * len..(index + 1) * len], 16).ok()?;
+        // Expand the short form, e.g. `f` to `ff`.
+        Some(if len == 1 { value * 17 } else { value })
+    };
+    let len = match hex.len() {
+        3 => 1,
+        6 => 2,
+        _ => return None,
+    };
+    Some(Rgba([
+        channel(0, len)?,
+        channel(1, len)?,
+        channel(2, len)?,
+        255,
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+    use test_case::test_case;
+
+    use super::parse_color;
+
+    #[test_case("#1e293b", Some([30, 41, 59, 255]))]
+    #[test_case("#FFF", Some([255, 255, 255, 255]))]
+    #[test_case("1e293b", None)]
+    #[test_case("#1e29", None)]
+    #[test_case("#gggggg", None)]
+    fn test_parse_color(color: &str, expected: Option<[u8; 4]>) {
+        assert_eq!(parse_color(color), expected.map(Rgba));
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use parking_lot::{const_mutex, Mutex};
+use walkdir::WalkDir;
+
+use crate::assets;
+
+// The size and modification time of a watched file.
+type FileStamp = (u64, Option<Duration>);
+
+// The stamps of the watched source files at the last rendering.
+static LAST_SNAPSHOT: Mutex<Option<HashMap<PathBuf, FileStamp>>> = const_mutex(None);
+// The watched tree found by the last walk, see [`WatchedTree`].
+static WATCHED_TREE: Mutex<Option<WatchedTree>> = const_mutex(None);
+
+// The prefixes and suffixes of temporary file names written by editors,
+// e.g. the `.#hello.md` lock file of emacs and the `.hello.md.swp` of vim.
+const TEMPORARY_PREFIXES: &[&str] = &[".#", "#"];
+const TEMPORARY_SUFFIXES: &[&str] = &["~", ".swp", ".swx", ".swo", ".tmp", ".bak"];
+// The file names written by the operating systems.
+const SYSTEM_FILES: &[&str] = &[".DS_Store", "Thumbs.db"];
+// The directories of version control and the zine state and caches,
+// which are written by the builds themselves.
+const IGNORED_DIRS: &[&str] = &[".git", ".hg", ".svn", crate::ZINE_STATE_DIR, ".zine-cache"];
+
+// The watched directories and files found by a walk, which is reused until any
+// directory is modified, i.e. an entry is added, removed or renamed in it.
+struct WatchedTree {
+    // The directories and their modification times.
+    dirs: Vec<(PathBuf, Option<Duration>)>,
+    files: Vec<PathBuf>,
+}
+
+/// The minimal rebuild of the changed source files in `zine serve` mode.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rebuild {
+    /// No watched file changed, e.g. only an editor swap file changed.
+    Nothing,
+    /// Copy the changed static assets only.
+    Static,
+    /// Render only the issues of these slugs, e.g. an article markdown changed.
+    ///
+    /// The references from the other issues are refreshed in the next full rebuild.
+    Issues(BTreeSet<String>),
+    /// Render the whole site, e.g. a template or the root `zine.toml` changed.
+    Full,
+}
+
+/// Get the minimal rebuild of the watched files of `source` changed since the last call,
+/// the first call always returns [`Rebuild::Full`].
+///
+/// The `issues` are the issue directory and slug pairs. The files ignored by [`is_ignored`]
+/// and the `dest` directory are not watched.
+pub fn rebuild(source: &Path, dest: &Path, ignores: &[String], issues: &[(&str, &str)]) -> Rebuild {
+    let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_owned());
+    let dest = fs::canonicalize(dest).unwrap_or_else(|_| dest.to_owned());
+    let roots = [
+        Some(source.clone()),
+        // The builtin templates are loaded from the disk in debug build.
+        cfg!(debug_assertions).then(|| PathBuf::from("templates")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    let snapshot = {
+        let mut tree = WATCHED_TREE.lock();
+        if !tree.as_ref().is_some_and(WatchedTree::is_fresh) {
+            *tree = Some(WatchedTree::walk(&roots, &dest, ignores));
+        }
+        tree.as_ref()
+            .map(WatchedTree::stamp_files)
+            .unwrap_or_default()
+    };
+
+    let Some(last) = LAST_SNAPSHOT.lock().replace(snapshot.clone()) else {
+        return Rebuild::Full;
+    };
+    let changed = snapshot
+        .iter()
+        .filter(|(path, stamp)| last.get(*path) != Some(stamp))
+        .map(|(path, _)| path)
+        .chain(last.keys().filter(|path| !snapshot.contains_key(*path)))
+        .map(|path| path.strip_prefix(&source).unwrap_or(path))
+        .collect::<Vec<_>>();
+    classify_changes(&changed, issues)
+}
+
+// Classify the `changed` paths relative to the source directory into the minimal rebuild.
+fn classify_changes(changed: &[&Path], issues: &[(&str, &str)]) -> Rebuild {
+    let mut rebuild = Rebuild::Nothing;
+    for path in changed {
+        let current = if path.starts_with("static") {
+            Rebuild::Static
+        } else if let Some((_, slug)) = issues
+            .iter()
+            .find(|(dir, _)| path.starts_with(Path::new(crate::ZINE_CONTENT_DIR).join(dir)))
+        {
+            Rebuild::Issues(BTreeSet::from([slug.to_string()]))
+        } else {
+            // The root `zine.toml`, templates, pages, locales and so on.
+            return Rebuild::Full;
+        };
+        rebuild = match (rebuild, current) {
+            (Rebuild::Nothing, current) => current,
+            (Rebuild::Static, Rebuild::Static) => Rebuild::Static,
+            (Rebuild::Issues(mut slugs), Rebuild::Issues(current)) => {
+                slugs.extend(current);
+                Rebuild::Issues(slugs)
+            }
+            // Both static assets and issues changed.
+            _ => return Rebuild::Full,
+        };
+    }
+    rebuild
+}
+
+/// Whether the `path` relative to the source directory is ignored by watching:
+/// the editor temporary files, the system files, the version control and zine
+/// state directories, and the files matching the `ignores` globs.
+///
+/// Other hidden files are watched, e.g. the `static/.well-known` directory.
+pub fn is_ignored(path: &Path, ignores: &[String]) -> bool {
+    let is_ignored_dir = path
+        .components()
+        .any(|component| IGNORED_DIRS.iter().any(|dir| component.as_os_str() == *dir));
+    let is_temporary = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| {
+            SYSTEM_FILES.iter().any(|file| name == *file)
+                || TEMPORARY_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+                || TEMPORARY_SUFFIXES
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix))
+        });
+    is_ignored_dir || is_temporary || assets::is_excluded(path, ignores)
+}
+
+impl WatchedTree {
+    // Walk the watched directories and files of the `roots`.
+    fn walk(roots: &[PathBuf], dest: &Path, ignores: &[String]) -> Self {
+        let mut tree = WatchedTree {
+            dirs: vec![],
+            files: vec![],
+        };
+        for root in roots {
+            let walker = WalkDir::new(root)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(|entry| {
+                    let path = entry.path();
+                    path == root
+                        || !(path.starts_with(dest)
+                            || is_ignored(path.strip_prefix(root).unwrap_or(path), ignores))
+                });
+            for entry in walker.flatten() {
+                if entry.file_type().is_dir() {
+                    let modified = entry
+                        .metadata()
+                        .ok()
+                        .and_then(|metadata| modified(&metadata));
+                    tree.dirs.push((entry.into_path(), modified));
+                } else if entry.file_type().is_file() {
+                    tree.files.push(entry.into_path());
+                }
+            }
+        }
+        tree
+    }
+
+    // Whether no directory is modified since the walk.
+    fn is_fresh(&self) -> bool {
+        self.dirs.iter().all(|(dir, last)| {
+            fs::metadata(dir)
+                .ok()
+                .map(|metadata| modified(&metadata))
+                .is_some_and(|modified| modified == *last)
+        })
+    }
+
+    // Stamp the size and modification time of all the watched files.
+    fn stamp_files(&self) -> HashMap<PathBuf, FileStamp> {
+        self.files
+            .iter()
+            .filter_map(|file| {
+                let metadata = fs::symlink_metadata(file).ok()?;
+                Some((file.clone(), (metadata.len(), modified(&metadata))))
+            })
+            .collect()
+    }
+}
+
+// The modification time of the `metadata`, in the duration since the unix epoch.
+fn modified(metadata: &fs::Metadata) -> Option<Duration> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, env, fs, path::Path};
+
+    use test_case::test_case;
+
+    use super::{classify_changes, is_ignored, Rebuild, WatchedTree};
+
+    #[test_case("content/issue-1/.hello.md.swp", true; "vim swap")]
+    #[test_case("content/issue-1/hello.md~", true; "backup")]
+    #[test_case(".git/index", true; "git")]
+    #[test_case("content/#hello.md#", true; "emacs autosave")]
+    #[test_case("content/.#hello.md", true; "emacs lock")]
+    #[test_case("static/.DS_Store", true; "system file")]
+    #[test_case(".zine/permalinks.json", true; "zine state")]
+    #[test_case(".zine-cache/images/cat-480.webp", true; "zine cache")]
+    #[test_case("static/.well-known/security.txt", false; "well known")]
+    #[test_case(".github/workflows/deploy.yml", false; "hidden dir")]
+    #[test_case("drafts/hello.md", true; "user glob")]
+    #[test_case("notes.psd", true; "user file glob")]
+    #[test_case("content/issue-1/hello.md", false; "article")]
+    #[test_case("zine.toml", false; "config")]
+    fn test_is_ignored(path: &str, ignored: bool) {
+        let ignores = [String::from("drafts/*"), String::from("*.psd")];
+        assert_eq!(is_ignored(Path::new(path), &ignores), ignored);
+    }
+
+    #[test]
+    fn test_classify_changes() {
+        let issues = [("issue-1", "issue-1"), ("issue-10", "special")];
+        let classify = |paths: &[&str]| {
+            let paths = paths.iter().map(Path::new).collect::<Vec<_>>();
+            classify_changes(&paths, &issues)
+        };
+        assert_eq!(classify(&[]), Rebuild::Nothing);
+        assert_eq!(classify(&["static/cover.png"]), Rebuild::Static);
+        assert_eq!(
+            classify(&["content/issue-10/hello.md", "content/issue-1/zine.toml"]),
+            Rebuild::Issues(BTreeSet::from([
+                String::from("issue-1"),
+                String::from("special")
+            ]))
+        );
+        assert_eq!(
+            classify(&["content/issue-1/hello.md", "zine.toml"]),
+            Rebuild::Full
+        );
+        assert_eq!(
+            classify(&["content/issue-1/a.md", "static/a.png"]),
+            Rebuild::Full
+        );
+        assert_eq!(classify(&["templates/article.jinja"]), Rebuild::Full);
+    }
+
+    #[test]
+    fn test_watched_tree() {
+        let root = env::temp_dir().join("zine-watched-tree");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("content/issue-1")).unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::write(root.join("zine.toml"), "").unwrap();
+        fs::write(root.join("content/issue-1/hello.md"), "Hello").unwrap();
+        fs::write(root.join("build/index.html"), "").unwrap();
+
+        let tree = WatchedTree::walk(&[root.clone()], &root.join("build"), &[]);
+        assert_eq!(
+            tree.files,
+            [
+                root.join("content/issue-1/hello.md"),
+                root.join("zine.toml")
+            ]
+        );
+        assert!(tree.is_fresh());
+        // A changed file is stamped without walking again.
+        fs::write(root.join("content/issue-1/hello.md"), "Hello zine").unwrap();
+        assert!(tree.is_fresh());
+        assert_eq!(
+            tree.stamp_files()[&root.join("content/issue-1/hello.md")].0,
+            10
+        );
+        // A new file modifies its directory.
+        fs::write(root.join("content/issue-1/world.md"), "World").unwrap();
+        assert!(!tree.is_fresh());
+    }
+}